@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use repeater::crud::DB;
+use repeater::parser::{ScanOptions, register_all_cards};
+use repeater::stats::DEFAULT_HORIZON_DAYS;
+use repeater::utils::get_data_dir;
+use std::hint::black_box;
+use tokio::runtime::Runtime;
+
+fn bench_collection_stats(c: &mut Criterion) {
+    let rt = Runtime::new().expect("failed to build Tokio runtime");
+    let data_dir = get_data_dir(None).expect("failed to find the data directory");
+    let db: Arc<DB> = Arc::new(rt.block_on(DB::new(&data_dir)).expect("failed to init DB"));
+    let paths = vec![PathBuf::from("test_data")];
+    let (cards, _) = rt
+        .block_on(register_all_cards(
+            db.as_ref(),
+            paths,
+            ScanOptions::default(),
+        ))
+        .expect("failed to register cards");
+
+    c.bench_function("collection_stats", |b| {
+        b.to_async(&rt).iter(|| {
+            let db = Arc::clone(&db);
+            let cards = &cards;
+            async move {
+                let stats = db
+                    .collection_stats(cards, DEFAULT_HORIZON_DAYS)
+                    .await
+                    .expect("failed to collect stats");
+                black_box(stats);
+            }
+        });
+    });
+}
+
+criterion_group!(benches, bench_collection_stats);
+criterion_main!(benches);
@@ -3,13 +3,15 @@ use std::sync::Arc;
 
 use criterion::{Criterion, criterion_group, criterion_main};
 use repeater::crud::DB;
-use repeater::parser::register_all_cards;
+use repeater::parser::{ScanOptions, register_all_cards};
+use repeater::utils::get_data_dir;
 use std::hint::black_box;
 use tokio::runtime::Runtime;
 
 fn bench_register_all_cards(c: &mut Criterion) {
     let rt = Runtime::new().expect("failed to build Tokio runtime");
-    let db: Arc<DB> = Arc::new(rt.block_on(DB::new()).expect("failed to init DB"));
+    let data_dir = get_data_dir(None).expect("failed to find the data directory");
+    let db: Arc<DB> = Arc::new(rt.block_on(DB::new(&data_dir)).expect("failed to init DB"));
     let paths = vec![PathBuf::from("test_data")];
 
     c.bench_function("register_all_cards", |b| {
@@ -17,7 +19,7 @@ fn bench_register_all_cards(c: &mut Criterion) {
             let db = Arc::clone(&db);
             let paths = paths.clone();
             async move {
-                let (cards, stats) = register_all_cards(db.as_ref(), paths)
+                let (cards, stats) = register_all_cards(db.as_ref(), paths, ScanOptions::default())
                     .await
                     .expect("failed to register cards");
                 black_box(cards);
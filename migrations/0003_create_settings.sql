@@ -0,0 +1,7 @@
+-- Create the settings table.
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE IF NOT EXISTS settings (
+    id INTEGER PRIMARY KEY,
+    desired_retention REAL
+) STRICT;
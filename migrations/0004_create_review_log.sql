@@ -0,0 +1,12 @@
+-- Create the review log table.
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE IF NOT EXISTS review_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    card_hash TEXT NOT NULL,
+    grade INTEGER NOT NULL,
+    reviewed_at TEXT NOT NULL,
+    elapsed_days INTEGER NOT NULL
+) STRICT;
+
+CREATE INDEX IF NOT EXISTS idx_review_log_card_hash ON review_log(card_hash, reviewed_at);
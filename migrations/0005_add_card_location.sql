@@ -0,0 +1,8 @@
+-- Remember where each card was last seen so edited cards can keep their history.
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE cards ADD COLUMN file_path TEXT;
+ALTER TABLE cards ADD COLUMN start_line INTEGER;
+ALTER TABLE cards ADD COLUMN end_line INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_cards_location ON cards(file_path, start_line, end_line);
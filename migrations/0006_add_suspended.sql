@@ -0,0 +1,4 @@
+-- Let cards be suspended so they stay out of drills until unsuspended.
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE cards ADD COLUMN suspended INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Store configurable learning steps as a space-separated list like "1m 10m 1d".
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE settings ADD COLUMN learning_steps TEXT;
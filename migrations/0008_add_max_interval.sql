@@ -0,0 +1,4 @@
+-- Optional upper bound on scheduled intervals, in days. NULL means no cap.
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE settings ADD COLUMN max_interval_days INTEGER;
@@ -0,0 +1,4 @@
+-- Let interval fuzz be turned off. NULL keeps the default (on).
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE settings ADD COLUMN fuzz_intervals INTEGER;
@@ -0,0 +1,10 @@
+-- Remember LLM output per card so repeat drills don't pay for the same request.
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE IF NOT EXISTS llm_cache (
+    card_hash TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    result TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (card_hash, kind)
+) STRICT;
@@ -0,0 +1,4 @@
+-- Let a collection choose how cards end. NULL keeps the default (`---`).
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE settings ADD COLUMN card_separator TEXT;
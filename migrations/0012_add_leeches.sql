@@ -0,0 +1,14 @@
+-- Count each card's failed reviews and flag cards that keep failing (leeches).
+-- NULL settings keep the defaults (8 failures, no suspension).
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE cards ADD COLUMN lapses INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE cards ADD COLUMN leech INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE settings ADD COLUMN leech_threshold INTEGER;
+ALTER TABLE settings ADD COLUMN suspend_leeches INTEGER;
+
+UPDATE cards SET lapses = (
+    SELECT COUNT(*) FROM review_log
+    WHERE review_log.card_hash = cards.card_hash AND review_log.grade = 1
+);
+UPDATE cards SET leech = 1 WHERE lapses >= 8;
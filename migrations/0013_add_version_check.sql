@@ -0,0 +1,4 @@
+-- Let the GitHub release check in `check` be turned off. NULL keeps the default (on).
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE settings ADD COLUMN version_check INTEGER;
@@ -0,0 +1,4 @@
+-- Hour after local midnight when a new study day begins. NULL keeps the default (midnight).
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE settings ADD COLUMN day_start_hour INTEGER;
@@ -0,0 +1,11 @@
+-- Remember what each deck file held when it was last parsed, so unchanged
+-- files skip parsing and registration on the next scan.
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE IF NOT EXISTS file_index (
+    file_path TEXT PRIMARY KEY,
+    modified_ns INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    card_separator TEXT NOT NULL,
+    cards TEXT NOT NULL
+) STRICT;
@@ -0,0 +1,5 @@
+-- Keep each card's text next to its hash so rows can be shown without the
+-- markdown they came from, e.g. once the card has been edited or deleted.
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE cards ADD COLUMN content TEXT;
@@ -0,0 +1,4 @@
+-- Keys bound to drill actions, as `action=key,key` pairs. NULL keeps the default keys.
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE settings ADD COLUMN keymap TEXT;
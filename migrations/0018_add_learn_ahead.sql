@@ -0,0 +1,4 @@
+-- How far ahead, in seconds, cards count as due now. NULL keeps the default (20 minutes).
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE settings ADD COLUMN learn_ahead_secs INTEGER;
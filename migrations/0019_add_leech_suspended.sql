@@ -0,0 +1,5 @@
+-- Remember which suspensions leech detection made, so undoing the failure
+-- that made a leech lifts only those and leaves hand-suspended cards alone.
+PRAGMA foreign_keys = ON;
+
+ALTER TABLE cards ADD COLUMN leech_suspended INTEGER NOT NULL DEFAULT 0;
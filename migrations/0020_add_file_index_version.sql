@@ -0,0 +1,6 @@
+-- Tag each file index entry with the parser version that wrote it, so a
+-- parser change re-parses every deck instead of reusing stale cards.
+PRAGMA foreign_keys = ON;
+
+DELETE FROM file_index;
+ALTER TABLE file_index ADD COLUMN parser_version INTEGER NOT NULL DEFAULT 0;
@@ -1,17 +1,25 @@
 use std::path::PathBuf;
 
 use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
 
 use crate::llm::drill_preprocessor::AIStatus;
+use crate::parser::get_hash;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Card {
     pub file_path: PathBuf,
-    #[allow(dead_code)]
     pub file_card_range: (usize, usize),
     pub content: CardContent,
     pub card_hash: String,
     pub ai_status: AIStatus,
+    pub tags: Vec<String>,
+    pub direction: CardDirection,
+    /// Deck name from the file's frontmatter, overriding the folder name.
+    pub deck: Option<String>,
+    /// Set by `suspended: true` in the frontmatter; the card is suspended when
+    /// first registered and later unsuspends stick.
+    pub suspended_by_default: bool,
 }
 
 impl Card {
@@ -27,11 +35,82 @@ impl Card {
             content,
             card_hash,
             ai_status: AIStatus::NoNeed,
+            tags: Vec::new(),
+            direction: CardDirection::Forward,
+            deck: None,
+            suspended_by_default: false,
         }
     }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Builds the answer-to-question twin of a basic card. The reverse card
+    /// gets its own hash so FSRS schedules each direction independently.
+    pub fn reversed(&self) -> Option<Card> {
+        if self.direction == CardDirection::Reverse {
+            return None;
+        }
+        let CardContent::Basic { question, answer } = &self.content else {
+            return None;
+        };
+        let card_hash = get_hash(&format!("{} reverse", self.card_hash))?;
+        let mut card = Card::new(
+            self.file_path.clone(),
+            self.file_card_range,
+            CardContent::Basic {
+                question: answer.clone(),
+                answer: question.clone(),
+            },
+            card_hash,
+        )
+        .with_tags(self.tags.clone());
+        card.direction = CardDirection::Reverse;
+        card.deck = self.deck.clone();
+        card.suspended_by_default = self.suspended_by_default;
+        Some(card)
+    }
+
+    /// This card's hash plus its reverse card's, which exists in the database
+    /// once `drill --both-ways` has shown it.
+    pub fn hashes_with_reverse(&self) -> impl Iterator<Item = String> + use<> {
+        std::iter::once(self.card_hash.clone()).chain(self.reversed().map(|card| card.card_hash))
+    }
+
+    /// The card as it would be written in a deck, e.g. `Q: ...\nA: ...`.
+    pub fn markdown(&self) -> String {
+        match &self.content {
+            CardContent::Basic { question, answer } => format!("Q: {question}\nA: {answer}"),
+            CardContent::Cloze { text, .. } => format!("C: {text}"),
+            CardContent::TypeAnswer { question, answer } => format!("T: {question}\nA: {answer}"),
+        }
+    }
+
+    /// Tags are matched case-insensitively and without the leading `#`.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim_start_matches('#');
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Decks are matched case-insensitively against the name set by frontmatter
+    /// or an `@deck:` line.
+    pub fn in_deck(&self, deck: &str) -> bool {
+        self.deck
+            .as_deref()
+            .is_some_and(|name| name.eq_ignore_ascii_case(deck.trim()))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CardDirection {
+    #[default]
+    Forward,
+    Reverse,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum CardContent {
     Basic {
         question: String,
@@ -39,14 +118,22 @@ pub enum CardContent {
     },
     Cloze {
         text: String,
-        cloze_range: Option<ClozeRange>,
+        /// Every blank in `text`, in order. Empty when the card has no blank yet.
+        cloze_ranges: Vec<ClozeRange>,
+    },
+    /// A `T:`/`A:` card, drilled by typing the answer and graded from it.
+    TypeAnswer {
+        question: String,
+        answer: String,
     },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ClozeRange {
     pub start: usize,
     pub end: usize,
+    /// Text after the `|` in `[answer|hint]`, shown inside the masked blank.
+    pub hint: Option<String>,
 }
 
 impl ClozeRange {
@@ -59,7 +146,16 @@ impl ClozeRange {
             bail!("Invalid cloze range: range must be at least length 1");
         }
 
-        Ok(Self { start, end })
+        Ok(Self {
+            start,
+            end,
+            hint: None,
+        })
+    }
+
+    pub fn with_hint(mut self, hint: Option<String>) -> Self {
+        self.hint = hint;
+        self
     }
 }
 
@@ -1,6 +1,8 @@
 use crate::{crud::DB, palette::Palette};
 
 use std::{
+    env,
+    ffi::OsString,
     io::{self, Write},
     time::Duration,
 };
@@ -9,6 +11,9 @@ use anyhow::Result;
 use serde::Deserialize;
 
 const TIMEOUT: u64 = 900;
+/// Set to anything but `0` to skip the release check without touching the
+/// saved setting.
+pub const NO_VERSION_CHECK_ENV: &str = "REPEATER_NO_VERSION_CHECK";
 pub const ONE_DAY: Duration = Duration::from_secs(60 * 60 * 24);
 pub const ONE_WEEK: Duration = Duration::from_secs(60 * 60 * 24 * 7);
 
@@ -45,6 +50,21 @@ fn should_notify(now: chrono::DateTime<chrono::Utc>, stats: &VersionUpdateStats)
     true
 }
 
+fn disabled_by_env(value: Option<OsString>) -> bool {
+    value.is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Whether `check` may ask GitHub for the latest release: on unless
+/// `REPEATER_NO_VERSION_CHECK` is set or `config --version-check off` was run.
+pub async fn version_check_enabled(db: &DB) -> bool {
+    if disabled_by_env(env::var_os(NO_VERSION_CHECK_ENV)) {
+        return false;
+    }
+    db.get_settings()
+        .await
+        .map_or(true, |settings| settings.version_check)
+}
+
 pub async fn check_version(db: DB) -> Option<VersionNotification> {
     let now = chrono::Utc::now();
     let version_update_stats = db.get_version_update_information().await.ok()?;
@@ -54,16 +74,18 @@ pub async fn check_version(db: DB) -> Option<VersionNotification> {
     }
 
     let current_version = env!("CARGO_PKG_VERSION");
-    let latest_release = get_latest().await.ok()?;
-
-    #[cfg(debug_assertions)]
-    {
-        let elapsed = chrono::Utc::now()
-            .signed_duration_since(now)
-            .num_milliseconds();
-
-        dbg!(elapsed, "ms");
-    }
+    let latest_release = get_latest()
+        .await
+        .inspect_err(|err| tracing::debug!("Version check failed: {err:#}"))
+        .ok()?;
+    let elapsed_ms = chrono::Utc::now()
+        .signed_duration_since(now)
+        .num_milliseconds();
+    tracing::debug!(
+        elapsed_ms,
+        latest = %latest_release.tag_name,
+        "Checked for a new release"
+    );
 
     db.update_last_version_check_at().await.ok();
 
@@ -136,6 +158,15 @@ mod tests {
         assert_eq!(normalize_version("v1.0.0"), "1.0.0");
         assert_eq!(normalize_version("1.0.0"), "1.0.0");
     }
+    #[test]
+    fn environment_variable_disables_the_check_unless_zero() {
+        assert!(!disabled_by_env(None));
+        assert!(!disabled_by_env(Some(OsString::new())));
+        assert!(!disabled_by_env(Some(OsString::from("0"))));
+        assert!(disabled_by_env(Some(OsString::from("1"))));
+        assert!(disabled_by_env(Some(OsString::from("true"))));
+    }
+
     #[test]
     fn should_notify_when_never_checked_or_prompted() {
         let now = chrono::Utc::now();
@@ -0,0 +1,181 @@
+//! Picks a blank for `C:` cards written without one, without calling an LLM.
+
+use std::ops::Range;
+
+use crate::cloze_utils::{cloze_ranges, find_cloze_ranges};
+
+/// Words too common to be worth hiding.
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "are", "as", "at", "be", "been", "but",
+    "by", "can", "do", "does", "for", "from", "had", "has", "have", "he", "her", "his", "how",
+    "if", "in", "into", "is", "it", "its", "not", "of", "on", "or", "she", "so", "than", "that",
+    "the", "their", "them", "then", "there", "these", "they", "this", "to", "was", "we", "were",
+    "what", "when", "where", "which", "who", "why", "will", "with", "you",
+];
+
+/// `text` with one span wrapped in `[...]`: the longest name (a run of
+/// capitalized words) or number, else the last word that isn't a stop word.
+/// Words inside links and inline code are never picked. Returns `None` when
+/// there is no word to hide.
+pub fn suggest_cloze(text: &str) -> Option<String> {
+    let words = words(text);
+    let pick = longest_name_or_number(text, &words)
+        .or_else(|| {
+            words
+                .iter()
+                .rev()
+                .find(|word| !is_stop_word(&text[(*word).clone()]))
+                .cloned()
+        })
+        .or_else(|| words.last().cloned())?;
+
+    let suggestion = format!(
+        "{}[{}]{}",
+        &text[..pick.start],
+        &text[pick.clone()],
+        &text[pick.end..]
+    );
+    cloze_ranges(&suggestion)
+        .is_ok_and(|ranges| !ranges.is_empty())
+        .then_some(suggestion)
+}
+
+/// Byte ranges of the words in `text`, skipping brackets and backtick spans.
+fn words(text: &str) -> Vec<Range<usize>> {
+    // Brackets, plus the `(url)` right after a link's text.
+    let mut skipped: Vec<Range<usize>> = find_cloze_ranges(text)
+        .into_iter()
+        .map(|(start, end)| match text[end..].strip_prefix('(') {
+            Some(rest) => start..rest.find(')').map_or(text.len(), |close| end + close + 2),
+            None => start..end,
+        })
+        .collect();
+    let mut ticks = text.match_indices('`').map(|(idx, _)| idx);
+    while let (Some(open), Some(close)) = (ticks.next(), ticks.next()) {
+        skipped.push(open..close + 1);
+    }
+
+    let mut words = Vec::new();
+    let mut start = None;
+    for (idx, ch) in text.char_indices().chain([(text.len(), ' ')]) {
+        let in_word = ch.is_alphanumeric() || (start.is_some() && matches!(ch, '\'' | '-'));
+        match (in_word, start) {
+            (true, None) => start = Some(idx),
+            (false, Some(word_start)) => {
+                let word = word_start..idx;
+                let trimmed =
+                    word_start..word_start + text[word].trim_end_matches(['\'', '-']).len();
+                if !skipped
+                    .iter()
+                    .any(|skip| skip.start <= trimmed.start && trimmed.end <= skip.end)
+                {
+                    words.push(trimmed);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// The longest run of capitalized words joined by single spaces, or number.
+/// Capitalized stop words (`The`, `In`) don't count, and neither does a
+/// single capitalized word that merely starts a sentence.
+fn longest_name_or_number(text: &str, words: &[Range<usize>]) -> Option<Range<usize>> {
+    let is_name = |word: &Range<usize>| {
+        let word = &text[word.clone()];
+        word.starts_with(char::is_uppercase) && !is_stop_word(word)
+    };
+    let is_number =
+        |word: &Range<usize>| text[word.clone()].starts_with(|ch: char| ch.is_ascii_digit());
+    let is_proper_name = |run: &Range<usize>| {
+        let starts_sentence = text[..run.start]
+            .trim_end()
+            .chars()
+            .last()
+            .is_none_or(|ch| matches!(ch, '.' | '!' | '?' | ':'));
+        !starts_sentence || text[run.clone()].contains(' ')
+    };
+
+    let mut candidates: Vec<Range<usize>> = Vec::new();
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    let mut run: Option<Range<usize>> = None;
+    for word in words {
+        if is_name(word) {
+            run = match run {
+                Some(current) if &text[current.end..word.start] == " " => {
+                    Some(current.start..word.end)
+                }
+                Some(current) => {
+                    runs.push(current);
+                    Some(word.clone())
+                }
+                None => Some(word.clone()),
+            };
+            continue;
+        }
+        runs.extend(run.take());
+        if is_number(word) {
+            candidates.push(word.clone());
+        }
+    }
+    runs.extend(run);
+    candidates.extend(runs.into_iter().filter(is_proper_name));
+    candidates.sort_by_key(|span| span.start);
+
+    // Ties go to the later span, which is usually the fact being stated.
+    candidates
+        .into_iter()
+        .max_by_key(|span| text[span.clone()].chars().count())
+}
+
+fn is_stop_word(word: &str) -> bool {
+    STOP_WORDS.contains(&word.to_lowercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_names_and_numbers() {
+        assert_eq!(
+            suggest_cloze("Speech is produced in Broca's area").as_deref(),
+            Some("Speech is produced in [Broca's] area")
+        );
+        assert_eq!(
+            suggest_cloze("The Treaty of Versailles was signed in 1919").as_deref(),
+            Some("The Treaty of [Versailles] was signed in 1919")
+        );
+        assert_eq!(
+            suggest_cloze("Water boils at 100 degrees Celsius").as_deref(),
+            Some("Water boils at 100 degrees [Celsius]")
+        );
+        assert_eq!(
+            suggest_cloze("Water boils at 100 degrees").as_deref(),
+            Some("Water boils at [100] degrees")
+        );
+        assert_eq!(
+            suggest_cloze("Paris is the capital of France").as_deref(),
+            Some("Paris is the capital of [France]")
+        );
+        assert_eq!(
+            suggest_cloze("The speed of light in New York City").as_deref(),
+            Some("The speed of light in [New York City]")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_last_content_word() {
+        assert_eq!(
+            suggest_cloze("mitochondria produce energy for the cell").as_deref(),
+            Some("mitochondria produce energy for the [cell]")
+        );
+        assert_eq!(
+            suggest_cloze("see [the docs](https://x.io) or run `cargo test` here").as_deref(),
+            Some("see [the docs](https://x.io) or run `cargo test` [here]")
+        );
+        assert_eq!(suggest_cloze("... !!"), None);
+    }
+}
@@ -1,3 +1,5 @@
+use anyhow::{Result, bail};
+
 use crate::card::ClozeRange;
 
 pub fn find_cloze_ranges(text: &str) -> Vec<(usize, usize)> {
@@ -20,15 +22,82 @@ pub fn find_cloze_ranges(text: &str) -> Vec<(usize, usize)> {
     ranges
 }
 
-pub fn mask_cloze_text(text: &str, range: &ClozeRange) -> String {
-    let start = range.start;
-    let end = range.end;
-    let hidden_section = &text[start..end];
+/// The blanks of a cloze card. Markdown links and images (`[text](url)`,
+/// `![](media/x.png)`) use brackets too but are never treated as blanks.
+/// A blank written as `[answer|hint]` carries a hint.
+pub fn cloze_ranges(text: &str) -> Result<Vec<ClozeRange>> {
+    let mut ranges = Vec::new();
+    for (start, end) in find_cloze_ranges(text) {
+        if text[end..].starts_with('(') {
+            continue;
+        }
+        let (answer, hint) = split_hint(&text[start..end]);
+        if answer.trim().is_empty() {
+            bail!("Invalid cloze range: blank has a hint but no answer");
+        }
+        ranges.push(ClozeRange::new(start, end)?.with_hint(hint.map(str::to_string)));
+    }
+    Ok(ranges)
+}
+
+/// Splits the inside of `[answer|hint]` into its answer and trimmed hint.
+fn split_hint(hidden_section: &str) -> (&str, Option<&str>) {
     let core = hidden_section.trim_start_matches('[').trim_end_matches(']');
-    let placeholder = "_".repeat(core.chars().count().max(3));
+    match core.rsplit_once('|') {
+        Some((answer, hint)) => {
+            let hint = hint.trim();
+            (answer, (!hint.is_empty()).then_some(hint))
+        }
+        None => (core, None),
+    }
+}
 
-    let masked = format!("{}[{}]{}", &text[..start], placeholder, &text[end..]);
-    masked
+/// The code inside a blank written entirely as inline code, like
+/// `` [`us-east-2`] ``.
+fn inline_code(answer: &str) -> Option<&str> {
+    let code = answer.trim().strip_prefix('`')?.strip_suffix('`')?;
+    (!code.is_empty() && !code.contains('`')).then_some(code)
+}
+
+/// Replaces every blank with underscores, followed by its hint if it has one.
+/// A blank that is inline code stays inline code, sized to the code alone.
+/// `ranges` must be sorted and must not overlap, which is what
+/// [`cloze_ranges`] returns.
+pub fn mask_cloze_text(text: &str, ranges: &[ClozeRange]) -> String {
+    rewrite_blanks(text, ranges, |answer, hint| {
+        let code = inline_code(answer);
+        let width = code.unwrap_or(answer.trim()).chars().count().max(3);
+        let placeholder = match code {
+            Some(_) => format!("`{}`", "_".repeat(width)),
+            None => "_".repeat(width),
+        };
+        match hint {
+            Some(hint) => format!("[{placeholder} ({hint})]"),
+            None => format!("[{placeholder}]"),
+        }
+    })
+}
+
+/// The cloze text with every blank's hint removed, for showing the answer.
+pub fn reveal_cloze_text(text: &str, ranges: &[ClozeRange]) -> String {
+    rewrite_blanks(text, ranges, |answer, _| format!("[{}]", answer.trim()))
+}
+
+fn rewrite_blanks(
+    text: &str,
+    ranges: &[ClozeRange],
+    render: impl Fn(&str, Option<&str>) -> String,
+) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for range in ranges {
+        let (answer, _) = split_hint(&text[range.start..range.end]);
+        out.push_str(&text[cursor..range.start]);
+        out.push_str(&render(answer, range.hint.as_deref()));
+        cursor = range.end;
+    }
+    out.push_str(&text[cursor..]);
+    out
 }
 
 #[cfg(test)]
@@ -48,7 +117,7 @@ mod tests {
             .transpose()
             .unwrap()
             .unwrap();
-        let masked = mask_cloze_text(text, &range);
+        let masked = mask_cloze_text(text, &[range]);
         assert_eq!(masked, "Capital of 日本 is [___]");
 
         let text = "Capital of 日本 is [longer text is in this bracket]";
@@ -60,10 +129,57 @@ mod tests {
             .transpose()
             .unwrap()
             .unwrap();
-        let masked = mask_cloze_text(text, &range);
+        let masked = mask_cloze_text(text, &[range]);
         assert_eq!(
             masked,
             "Capital of 日本 is [______________________________]"
         );
     }
+
+    #[test]
+    fn inline_code_blanks_stay_code_and_ignore_backticks_in_width() {
+        let text = "Region: [`us-east-2`] in [`東京`]";
+        let ranges = cloze_ranges(text).unwrap();
+        assert_eq!(
+            mask_cloze_text(text, &ranges),
+            "Region: [`_________`] in [`___`]"
+        );
+        assert_eq!(reveal_cloze_text(text, &ranges), text);
+
+        let text = "Run [`cargo test`|command] or [`a` and `b`]";
+        let ranges = cloze_ranges(text).unwrap();
+        assert_eq!(
+            mask_cloze_text(text, &ranges),
+            "Run [`__________` (command)] or [___________]"
+        );
+    }
+
+    #[test]
+    fn masks_every_blank_but_not_links() {
+        let text = "[Paris] is the capital of [France] ![](media/map.png) [source](https://x.y)";
+        let ranges = cloze_ranges(text).unwrap();
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(
+            mask_cloze_text(text, &ranges),
+            "[_____] is the capital of [______] ![](media/map.png) [source](https://x.y)"
+        );
+        assert!(cloze_ranges("no blanks here").unwrap().is_empty());
+    }
+
+    #[test]
+    fn hints_are_shown_in_the_blank_and_dropped_on_reveal() {
+        let text = "[Tokyo|city] is in [Japan]";
+        let ranges = cloze_ranges(text).unwrap();
+        assert_eq!(ranges[0].hint.as_deref(), Some("city"));
+        assert_eq!(ranges[1].hint, None);
+        assert_eq!(
+            mask_cloze_text(text, &ranges),
+            "[_____ (city)] is in [_____]"
+        );
+        assert_eq!(reveal_cloze_text(text, &ranges), "[Tokyo] is in [Japan]");
+
+        assert!(cloze_ranges("[|hint only]").is_err());
+        let blank_hint = cloze_ranges("[Tokyo| ]").unwrap();
+        assert_eq!(blank_hint[0].hint, None);
+    }
 }
@@ -0,0 +1,125 @@
+use crate::commands::config::{describe_separator, format_day_start, format_max_interval, on_off};
+use crate::crud::{DB, Snapshot};
+use crate::palette::Palette;
+use crate::settings::{
+    format_learning_steps, format_step, fsrs_params_path, write_fsrs_parameters,
+};
+use crate::utils::pluralize;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+pub async fn backup(db: &DB, path: &Path) -> Result<()> {
+    let snapshot = db.export_snapshot().await?;
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {}", parent.display()))?;
+    }
+    let contents = serde_json::to_string_pretty(&snapshot)? + "\n";
+    fs::write(path, contents)
+        .with_context(|| format!("failed to write backup to {}", path.display()))?;
+
+    println!(
+        "{} {} {} {}",
+        Palette::paint(Palette::SUCCESS, "Backed up"),
+        Palette::paint(Palette::INFO, pluralize("card", snapshot.cards.len())),
+        Palette::dim(format!(
+            "and {} to",
+            pluralize("review", snapshot.reviews.len())
+        )),
+        Palette::paint(Palette::ACCENT, path.display()),
+    );
+    Ok(())
+}
+
+pub async fn restore(db: &DB, path: &Path) -> Result<()> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read backup at {}", path.display()))?;
+    let snapshot: Snapshot = serde_json::from_str(&contents)
+        .with_context(|| format!("{} is not a repeater backup", path.display()))?;
+
+    // checked before anything is written, so a bad backup changes nothing
+    let settings = snapshot.settings()?;
+    let old_separator = db.get_settings().await?.card_separator;
+
+    let report = db.import_snapshot(&snapshot).await?;
+    println!(
+        "{} {} {} {} {}",
+        Palette::paint(Palette::SUCCESS, "Restored from"),
+        Palette::paint(Palette::ACCENT, path.display()),
+        Palette::paint(Palette::INFO, format!("{} added,", report.added)),
+        Palette::paint(Palette::INFO, format!("{} updated,", report.updated)),
+        Palette::dim(format!("{} kept as is", report.unchanged)),
+    );
+    println!(
+        "{}",
+        Palette::dim(format!(
+            "{} added to the review log.",
+            pluralize("review", report.reviews_added)
+        ))
+    );
+    if let Some(retention) = settings.desired_retention {
+        print_setting("Desired retention set to", format!("{:.2}", retention));
+    }
+    if let Some(steps) = &settings.learning_steps {
+        print_setting("Learning steps set to", format_learning_steps(steps));
+    }
+    if let Some(days) = settings.max_interval_days {
+        print_setting("Maximum interval set to", format_max_interval(Some(days)));
+    }
+    if let Some(enabled) = settings.fuzz_intervals {
+        print_setting("Interval fuzz turned", on_off(enabled));
+    }
+    if let Some(separator) = settings.card_separator {
+        if separator != old_separator {
+            eprintln!(
+                "{} cards will split at different lines, so any card whose text changes gets a new hash and starts over without its review history.",
+                Palette::paint(Palette::WARNING, "Warning:"),
+            );
+        }
+        print_setting("Cards now end at", describe_separator(separator));
+    }
+    if let Some(failures) = settings.leech_threshold {
+        print_setting(
+            "Cards become leeches after",
+            pluralize("failure", failures as usize),
+        );
+    }
+    if let Some(enabled) = settings.suspend_leeches {
+        print_setting("Suspending new leeches turned", on_off(enabled));
+    }
+    if let Some(enabled) = settings.version_check {
+        print_setting("Version check turned", on_off(enabled));
+    }
+    if let Some(hour) = settings.day_start_hour {
+        print_setting("New study days start at", format_day_start(hour));
+    }
+    if let Some(keymap) = &settings.keymap {
+        print_setting("Drill keys set to", keymap.format());
+    }
+    if let Some(window) = settings.learn_ahead {
+        print_setting("Cards count as due this far ahead:", format_step(window));
+    }
+    if let Some(parameters) = &settings.fsrs_parameters {
+        let target = fsrs_params_path(db.data_dir());
+        write_fsrs_parameters(&target, parameters)?;
+        println!(
+            "{} {}",
+            Palette::dim("Saved custom FSRS parameters to"),
+            Palette::paint(Palette::ACCENT, target.display()),
+        );
+    }
+    Ok(())
+}
+
+fn print_setting(label: &str, value: impl std::fmt::Display) {
+    println!(
+        "{} {}",
+        Palette::dim(label),
+        Palette::paint(Palette::INFO, value)
+    );
+}
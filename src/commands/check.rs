@@ -1,22 +1,31 @@
 use crate::{
-    check_version::{check_version, prompt_for_new_version},
+    card::{Card, CardContent},
+    check_version::{check_version, prompt_for_new_version, version_check_enabled},
+    commands::{
+        drill::{DrillOptions, drill_cards},
+        drill_session,
+    },
     crud::DB,
-    palette::Palette,
-    parser::{FileSearchStats, register_all_cards},
-    stats::{CardLifeCycle, CardStats, Histogram},
+    palette::{Palette, PaletteColor},
+    parser::{
+        DuplicateCard, FileSearchStats, ScanOptions, extract_media, register_all_cards,
+        report_skipped_files,
+    },
+    stats::{BrokenMedia, CardLifeCycle, CardStats, FORECAST_DAYS, Histogram, forecast_workload},
     tui::Theme,
-    utils::pluralize,
+    utils::{ask_yn, pluralize},
 };
 
 use std::{
     cmp,
+    collections::{BTreeMap, HashMap},
     io::{self},
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::Duration,
 };
 
 use anyhow::Result;
-use chrono::NaiveDate;
+use chrono::{Days, NaiveDate, Utc};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     execute,
@@ -29,26 +38,404 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Bar, BarChart, BarGroup, Paragraph, Wrap},
 };
+use serde::Serialize;
 
-pub async fn run(db: &DB, paths: Vec<PathBuf>, plain: bool) -> Result<usize> {
-    let version_check = tokio::spawn(check_version(db.clone()));
+/// Most file rows the dashboard shows before the list scrolls.
+const MAX_FILE_ROWS: usize = 10;
 
-    let (card_hashes, file_traversal_stats) = register_all_cards(db, paths).await?;
+/// Scroll position of the dashboard's file list.
+#[derive(Debug, Default)]
+struct DashboardState {
+    scroll: usize,
+    /// File rows that fit on screen, updated on every draw.
+    visible_rows: usize,
+}
+
+impl DashboardState {
+    fn scroll_by(&mut self, delta: isize, total: usize) {
+        let max_scroll = total.saturating_sub(self.visible_rows.max(1));
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max_scroll);
+    }
+
+    /// Scrolls the file list for arrow, page, Home, and End keys.
+    fn handle_key(&mut self, code: KeyCode, total: usize) {
+        let page = self.visible_rows.max(1) as isize;
+        let delta = match code {
+            KeyCode::Up => -1,
+            KeyCode::Down => 1,
+            KeyCode::PageUp => -page,
+            KeyCode::PageDown => page,
+            KeyCode::Home => isize::MIN,
+            KeyCode::End => isize::MAX,
+            _ => return,
+        };
+        self.scroll_by(delta, total);
+    }
+}
+
+/// Files and their card counts, busiest first.
+fn file_rows(stats: &CardStats) -> Vec<(String, usize)> {
+    let mut rows: Vec<(String, usize)> = stats
+        .file_paths
+        .iter()
+        .map(|(path, count)| (path.display().to_string(), *count))
+        .collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    rows
+}
+
+const FORECAST_NOTE: &str = "Assumes every card is passed the day it comes due.";
+
+/// How `check` shows the collection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CheckOutput {
+    /// The interactive TUI dashboard.
+    #[default]
+    Dashboard,
+    /// A text summary on stdout.
+    Plain,
+    /// A [`CheckSummary`] as JSON on stdout, for scripts and dashboards.
+    Json,
+}
+
+/// What `check --json` prints. Days are study days (`%Y-%m-%d`), and the
+/// histograms cover reviewed cards on a 0 to 1 scale.
+#[derive(Debug, Serialize)]
+struct CheckSummary {
+    cards: i64,
+    cards_in_db: i64,
+    orphaned_rows: usize,
+    suspended: i64,
+    leeches: i64,
+    broken_media: Vec<BrokenMediaSummary>,
+    lifecycles: BTreeMap<&'static str, i64>,
+    due_now: i64,
+    reviewed_today: usize,
+    upcoming: BTreeMap<String, usize>,
+    upcoming_month: i64,
+    forecast: Option<BTreeMap<String, usize>>,
+    difficulty: HistogramSummary,
+    retrievability: HistogramSummary,
+    files_searched: usize,
+    markdown_files: usize,
+    skipped_files: Vec<SkippedFileSummary>,
+    duplicate_cards: Vec<DuplicateCardSummary>,
+    files: Vec<FileSummary>,
+}
+
+#[derive(Debug, Serialize)]
+struct HistogramSummary {
+    mean: Option<f64>,
+    bins: [u32; 5],
+}
+
+#[derive(Debug, Serialize)]
+struct BrokenMediaSummary {
+    file: String,
+    line: usize,
+    media: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DuplicateCardSummary {
+    card_hash: String,
+    locations: Vec<CardLocationSummary>,
+}
+
+#[derive(Debug, Serialize)]
+struct CardLocationSummary {
+    file: String,
+    line: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct SkippedFileSummary {
+    path: String,
+    error: String,
+}
+
+#[derive(Debug, Serialize)]
+struct FileSummary {
+    path: String,
+    cards: usize,
+}
+
+impl HistogramSummary {
+    fn new(histogram: &Histogram<5>) -> Self {
+        Self {
+            mean: histogram.mean(),
+            bins: histogram.bins,
+        }
+    }
+}
+
+impl CheckSummary {
+    fn new(stats: &CardStats, file_traversal_stats: &FileSearchStats, horizon_days: u32) -> Self {
+        let lifecycles = [
+            CardLifeCycle::New,
+            CardLifeCycle::Young,
+            CardLifeCycle::Mature,
+        ]
+        .into_iter()
+        .map(|lifecycle| {
+            let count = stats.card_lifecycles.get(&lifecycle).copied();
+            (lifecycle.label(), count.unwrap_or(0))
+        })
+        .collect();
+        let fill = |counts| upcoming_days(counts, horizon_days, stats.today);
+        Self {
+            cards: stats.num_cards,
+            cards_in_db: stats.total_cards_in_db,
+            orphaned_rows: stats.orphans.len(),
+            suspended: stats.suspended_cards,
+            leeches: stats.leech_cards,
+            broken_media: stats
+                .broken_media
+                .iter()
+                .map(|broken| BrokenMediaSummary {
+                    file: broken.card_path.display().to_string(),
+                    line: broken.line,
+                    media: broken.media_path.display().to_string(),
+                })
+                .collect(),
+            lifecycles,
+            due_now: stats.due_cards,
+            reviewed_today: stats.reviewed_today,
+            upcoming: fill(&stats.upcoming).into_iter().collect(),
+            upcoming_month: stats.upcoming_month,
+            forecast: stats.forecast.as_ref().map(|forecast| {
+                upcoming_days(forecast, FORECAST_DAYS, stats.today)
+                    .into_iter()
+                    .collect()
+            }),
+            difficulty: HistogramSummary::new(&stats.difficulty_histogram),
+            retrievability: HistogramSummary::new(&stats.retrievability_histogram),
+            files_searched: file_traversal_stats.files_searched,
+            markdown_files: file_traversal_stats.markdown_files,
+            skipped_files: file_traversal_stats
+                .skipped_files
+                .iter()
+                .map(|skipped| SkippedFileSummary {
+                    path: skipped.path.display().to_string(),
+                    error: skipped.error.clone(),
+                })
+                .collect(),
+            duplicate_cards: file_traversal_stats
+                .duplicate_cards
+                .iter()
+                .map(|duplicate| DuplicateCardSummary {
+                    card_hash: duplicate.card_hash.clone(),
+                    locations: duplicate
+                        .locations
+                        .iter()
+                        .map(|(path, line)| CardLocationSummary {
+                            file: path.display().to_string(),
+                            line: *line,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            files: file_rows(stats)
+                .into_iter()
+                .map(|(path, cards)| FileSummary { path, cards })
+                .collect(),
+        }
+    }
+}
+
+pub async fn run(
+    db: &DB,
+    paths: Vec<PathBuf>,
+    output: CheckOutput,
+    migrate_edits: bool,
+    horizon_days: u32,
+    forecast: bool,
+    scan: ScanOptions,
+) -> Result<usize> {
+    // the update prompt would end up in the middle of the JSON
+    let version_check = (output != CheckOutput::Json && version_check_enabled(db).await)
+        .then(|| tokio::spawn(check_version(db.clone())));
+
+    let session_paths = drill_session::session_paths(&paths);
+    let roots = paths.clone();
+    let (card_hashes, file_traversal_stats) = register_all_cards(db, paths, scan).await?;
+    if output != CheckOutput::Json {
+        report_skipped_files(&file_traversal_stats);
+    }
+    if migrate_edits {
+        migrate_edited_cards(db, &card_hashes).await?;
+    }
     let count = card_hashes.len();
-    let crud_stats = db.collection_stats(&card_hashes).await?;
-    if let Some(notification) = version_check.await.ok().flatten() {
+    let mut crud_stats = db.collection_stats(&card_hashes, horizon_days).await?;
+    crud_stats.broken_media = find_broken_media(&card_hashes);
+    crud_stats.orphans = db.orphaned_cards(&card_hashes, &roots).await?;
+    if forecast {
+        let settings = db.get_settings().await?;
+        let performances = db.card_performances(&card_hashes).await?;
+        crud_stats.forecast = Some(forecast_workload(
+            &performances,
+            &settings,
+            Utc::now(),
+            FORECAST_DAYS,
+        )?);
+    }
+    if let Some(version_check) = version_check
+        && let Some(notification) = version_check.await.ok().flatten()
+    {
         prompt_for_new_version(db, &notification).await;
     }
 
-    if plain {
-        render_plain_summary(&crud_stats, &file_traversal_stats);
-    } else {
-        render_dashboard(&crud_stats, &file_traversal_stats)?;
+    match output {
+        CheckOutput::Plain => {
+            render_plain_summary(&crud_stats, &file_traversal_stats, horizon_days);
+        }
+        CheckOutput::Json => {
+            let summary = CheckSummary::new(&crud_stats, &file_traversal_stats, horizon_days);
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        }
+        CheckOutput::Dashboard => {
+            if render_dashboard(&crud_stats, &file_traversal_stats, horizon_days)? {
+                // the cards are registered already, so the drill skips a second walk
+                let options = DrillOptions {
+                    scan,
+                    ..DrillOptions::default()
+                };
+                drill_cards(db, &session_paths, card_hashes, options).await?;
+            }
+        }
     }
     Ok(count)
 }
 
-fn render_dashboard(crud_stats: &CardStats, file_traversal_stats: &FileSearchStats) -> Result<()> {
+/// Offers to carry review history over to cards whose text changed in place.
+/// Returns how many cards were migrated.
+pub async fn migrate_edited_cards(db: &DB, card_hashes: &HashMap<String, Card>) -> Result<usize> {
+    let edits = db.find_edited_cards(card_hashes).await?;
+    if edits.is_empty() {
+        return Ok(0);
+    }
+
+    let mut prompt = format!(
+        "Found {} edited in place since their last review:",
+        Palette::paint(Palette::WARNING, pluralize("card", edits.len()))
+    );
+    for (_, card) in &edits {
+        let (start, end) = card.file_card_range;
+        prompt.push_str(&format!(
+            "\n  {} {}",
+            Palette::paint(
+                Palette::ACCENT,
+                format!("{}:{}-{}", card.file_path.display(), start + 1, end)
+            ),
+            Palette::dim(card_preview(card)),
+        ));
+    }
+    prompt.push_str("\nKeep their review history instead of starting them over as new cards?");
+    if !ask_yn(prompt) {
+        return Ok(0);
+    }
+
+    let mut migrated = 0;
+    for (old_hash, card) in &edits {
+        if db.rekey_card(old_hash, &card.card_hash).await? {
+            migrated += 1;
+        }
+    }
+    println!(
+        "{} {}",
+        Palette::paint(Palette::SUCCESS, "Kept review history for"),
+        Palette::paint(Palette::INFO, pluralize("edited card", migrated)),
+    );
+    Ok(migrated)
+}
+
+/// Lists each card written in more than one place, with where every copy starts.
+fn print_duplicate_cards(duplicates: &[DuplicateCard]) {
+    let color = if duplicates.is_empty() {
+        Palette::INFO
+    } else {
+        Palette::WARNING
+    };
+    println!(
+        "{} {}",
+        Palette::dim("Duplicate cards:"),
+        Palette::paint(color, duplicates.len())
+    );
+    for duplicate in duplicates {
+        let locations: Vec<String> = duplicate
+            .locations
+            .iter()
+            .map(|(path, line)| format!("{}:{}", path.display(), line))
+            .collect();
+        println!(
+            "  {}",
+            Palette::paint(Palette::ACCENT, locations.join(", "))
+        );
+    }
+    if !duplicates.is_empty() {
+        println!(
+            "{}",
+            Palette::dim("Copies share one schedule and review history; keep one of each.")
+        );
+    }
+}
+
+/// Media links in `cards` whose file is missing, by card file and line.
+fn find_broken_media(cards: &HashMap<String, Card>) -> Vec<BrokenMedia> {
+    let mut broken: Vec<BrokenMedia> = cards
+        .values()
+        .flat_map(|card| {
+            let text = match &card.content {
+                CardContent::Basic { question, answer }
+                | CardContent::TypeAnswer { question, answer } => {
+                    format!("{question}\n\n{answer}")
+                }
+                CardContent::Cloze { text, .. } => text.clone(),
+            };
+            extract_media(&text, card.file_path.parent())
+                .into_iter()
+                // remote media can't be checked without a network call
+                .filter_map(|media| media.local_path().map(Path::to_path_buf))
+                .filter(|path| !path.is_file())
+                .map(|media_path| BrokenMedia {
+                    card_path: card.file_path.clone(),
+                    line: card.file_card_range.0 + 1,
+                    media_path,
+                })
+        })
+        .collect();
+    broken.sort_by(|a, b| {
+        (&a.card_path, a.line, &a.media_path).cmp(&(&b.card_path, b.line, &b.media_path))
+    });
+    broken.dedup();
+    broken
+}
+
+pub(crate) fn card_preview(card: &Card) -> String {
+    let text = match &card.content {
+        CardContent::Basic { question, .. } | CardContent::TypeAnswer { question, .. } => question,
+        CardContent::Cloze { text, .. } => text,
+    };
+    text_preview(text)
+}
+
+/// The first line of `text`, shortened to fit one terminal line.
+pub(crate) fn text_preview(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() > 60 {
+        format!("{}…", line.chars().take(59).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Shows the dashboard until the user leaves it, returning whether they
+/// pressed `Enter` to drill the due cards.
+fn render_dashboard(
+    crud_stats: &CardStats,
+    file_traversal_stats: &FileSearchStats,
+    horizon_days: u32,
+) -> Result<bool> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
@@ -56,7 +443,12 @@ fn render_dashboard(crud_stats: &CardStats, file_traversal_stats: &FileSearchSta
     let mut terminal = Terminal::new(backend)?;
     terminal.hide_cursor()?;
 
-    let draw_result = dashboard_loop(&mut terminal, crud_stats, file_traversal_stats);
+    let draw_result = dashboard_loop(
+        &mut terminal,
+        crud_stats,
+        file_traversal_stats,
+        horizon_days,
+    );
 
     terminal.show_cursor()?;
     disable_raw_mode()?;
@@ -65,13 +457,31 @@ fn render_dashboard(crud_stats: &CardStats, file_traversal_stats: &FileSearchSta
     draw_result
 }
 
-fn render_plain_summary(crud_stats: &CardStats, file_traversal_stats: &FileSearchStats) {
+fn render_plain_summary(
+    crud_stats: &CardStats,
+    file_traversal_stats: &FileSearchStats,
+    horizon_days: u32,
+) {
     println!("{}", Palette::paint(Palette::ACCENT, "Collection Summary"));
     println!(
         "{} {}",
         Palette::dim("Cards found:"),
         Palette::paint(Palette::INFO, crud_stats.num_cards)
     );
+    if crud_stats.suspended_cards > 0 {
+        println!(
+            "{} {}",
+            Palette::dim("Suspended:"),
+            Palette::paint(Palette::WARNING, crud_stats.suspended_cards)
+        );
+    }
+    if crud_stats.leech_cards > 0 {
+        println!(
+            "{} {}",
+            Palette::dim("Leeches:"),
+            Palette::paint(Palette::WARNING, crud_stats.leech_cards)
+        );
+    }
     println!(
         "{} {} {} {} {} {}",
         Palette::dim("New:"),
@@ -119,6 +529,45 @@ fn render_plain_summary(crud_stats: &CardStats, file_traversal_stats: &FileSearc
         Palette::dim("Total cards indexed in DB:"),
         Palette::paint(Palette::INFO, crud_stats.total_cards_in_db)
     );
+    let orphan_color = if crud_stats.orphans.is_empty() {
+        Palette::INFO
+    } else {
+        Palette::WARNING
+    };
+    println!(
+        "{} {}",
+        Palette::dim("Orphaned DB rows:"),
+        Palette::paint(orphan_color, crud_stats.orphans.len())
+    );
+    if !crud_stats.orphans.is_empty() {
+        println!(
+            "{}",
+            Palette::dim(
+                "Run `repeater prune` on the same paths to delete rows for cards no longer on disk."
+            )
+        );
+    }
+    let broken_color = if crud_stats.broken_media.is_empty() {
+        Palette::INFO
+    } else {
+        Palette::WARNING
+    };
+    println!(
+        "{} {}",
+        Palette::dim("Broken media:"),
+        Palette::paint(broken_color, crud_stats.broken_media.len())
+    );
+    for broken in &crud_stats.broken_media {
+        println!(
+            "  {} {}",
+            Palette::paint(
+                Palette::ACCENT,
+                format!("{}:{}", broken.card_path.display(), broken.line)
+            ),
+            Palette::dim(format!("missing {}", broken.media_path.display())),
+        );
+    }
+    print_duplicate_cards(&file_traversal_stats.duplicate_cards);
 
     println!("\n{}", Palette::paint(Palette::ACCENT, "Due Status"));
     let load_factor = if crud_stats.num_cards == 0 {
@@ -131,7 +580,7 @@ fn render_plain_summary(crud_stats: &CardStats, file_traversal_stats: &FileSearc
     } else {
         Palette::SUCCESS
     };
-    let upcoming_week_total: usize = crud_stats.upcoming_week.values().sum();
+    let upcoming_total: usize = crud_stats.upcoming.values().sum();
     println!(
         "{} {}",
         Palette::dim("Due load:"),
@@ -144,8 +593,13 @@ fn render_plain_summary(crud_stats: &CardStats, file_traversal_stats: &FileSearc
     );
     println!(
         "{} {}",
-        Palette::dim("Next 7 days:"),
-        Palette::paint(Palette::INFO, upcoming_week_total)
+        Palette::dim("Reviewed today:"),
+        Palette::paint(Palette::SUCCESS, crud_stats.reviewed_today)
+    );
+    println!(
+        "{} {}",
+        Palette::dim(format!("Next {}:", pluralize("day", horizon_days as usize))),
+        Palette::paint(Palette::INFO, upcoming_total)
     );
     println!(
         "{} {}",
@@ -155,25 +609,23 @@ fn render_plain_summary(crud_stats: &CardStats, file_traversal_stats: &FileSearc
 
     println!(
         "\n{}",
-        Palette::paint(Palette::ACCENT, "Next 7 Days Histogram")
+        Palette::paint(
+            Palette::ACCENT,
+            format!("Next {} Histogram", pluralize("Day", horizon_days as usize))
+        )
     );
-    if crud_stats.upcoming_week.is_empty() {
-        println!("{}", Palette::dim("You're clear for the next 7 days."));
-    } else {
-        let max_count = crud_stats
-            .upcoming_week
-            .values()
-            .max()
-            .copied()
-            .unwrap_or(0);
-        for (day, count) in &crud_stats.upcoming_week {
-            let label = format_upcoming_label(day);
-            println!(
-                "{} {}",
-                Palette::dim(format!("{label}:")),
-                format_bar(*count, max_count)
-            );
-        }
+    render_plain_day_histogram(&crud_stats.upcoming, horizon_days, crud_stats.today);
+
+    if let Some(forecast) = &crud_stats.forecast {
+        println!(
+            "\n{}",
+            Palette::paint(
+                Palette::ACCENT,
+                format!("Projected Load, Next {FORECAST_DAYS} Days (Estimate)")
+            )
+        );
+        println!("{}", Palette::dim(FORECAST_NOTE));
+        render_plain_day_histogram(forecast, FORECAST_DAYS, crud_stats.today);
     }
 
     println!(
@@ -189,11 +641,13 @@ fn render_plain_summary(crud_stats: &CardStats, file_traversal_stats: &FileSearc
             "Difficulty",
             "The higher the difficulty, the slower stability will increase.",
             &crud_stats.difficulty_histogram,
+            None,
         );
         render_plain_histogram(
             "Retrievability",
             "The probability of recalling a card today.",
             &crud_stats.retrievability_histogram,
+            Some(retrievability_color),
         );
     }
     println!(
@@ -207,7 +661,50 @@ fn render_plain_summary(crud_stats: &CardStats, file_traversal_stats: &FileSearc
     println!("{}", Palette::dim("Rerun command anytime to refresh data"));
 }
 
-fn render_plain_histogram(label: &str, description: &str, stats: &Histogram<5>) {
+fn render_plain_day_histogram(counts: &BTreeMap<String, usize>, days: u32, today: NaiveDate) {
+    if counts.is_empty() {
+        println!(
+            "{}",
+            Palette::dim(format!(
+                "You're clear for the next {}.",
+                pluralize("day", days as usize)
+            ))
+        );
+        return;
+    }
+    let days = upcoming_days(counts, days, today);
+    let max_count = days.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    for (day, count) in days {
+        let label = format_upcoming_label(&day);
+        println!(
+            "{} {}",
+            Palette::dim(format!("{label}:")),
+            format_bar(count, max_count, Palette::INFO)
+        );
+    }
+}
+
+/// Color for a histogram bin covering `low..high`, both in `0.0..=1.0`.
+type BinColor = fn(f64, f64) -> PaletteColor;
+
+/// Recall probability below which cards count as at risk.
+const AT_RISK_RETRIEVABILITY: f64 = 0.7;
+
+/// Bins whose midpoint falls below [`AT_RISK_RETRIEVABILITY`] are at risk.
+fn retrievability_color(low: f64, high: f64) -> PaletteColor {
+    if (low + high) / 2.0 < AT_RISK_RETRIEVABILITY {
+        Palette::DANGER
+    } else {
+        Palette::SUCCESS
+    }
+}
+
+fn render_plain_histogram(
+    label: &str,
+    description: &str,
+    stats: &Histogram<5>,
+    bin_color: Option<BinColor>,
+) {
     println!(
         "{} {}",
         Palette::dim("Card"),
@@ -229,15 +726,21 @@ fn render_plain_histogram(label: &str, description: &str, stats: &Histogram<5>)
     for (idx, count) in stats.bins.iter().enumerate() {
         let min_thresh = step_size * idx;
         let label = format!("{}%-{}%", min_thresh, min_thresh + step_size);
+        let color = bin_color.map_or(Palette::INFO, |color| {
+            color(
+                min_thresh as f64 / 100.0,
+                (min_thresh + step_size) as f64 / 100.0,
+            )
+        });
         println!(
             "{} {}",
             Palette::dim(format!("{label}:")),
-            format_bar(*count as usize, max_bin as usize)
+            format_bar(*count as usize, max_bin as usize, color)
         );
     }
 }
 
-fn format_bar(count: usize, max: usize) -> String {
+fn format_bar(count: usize, max: usize, color: PaletteColor) -> String {
     let width = 20usize;
     let filled = if max == 0 {
         0
@@ -249,7 +752,7 @@ fn format_bar(count: usize, max: usize) -> String {
     let remainder = "-".repeat(width - clamped);
     format!(
         "{} {}",
-        Palette::paint(Palette::INFO, bar + &remainder),
+        Palette::paint(color, bar + &remainder),
         Palette::dim(pluralize("card", count))
     )
 }
@@ -258,9 +761,21 @@ fn dashboard_loop(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     crud_stats: &CardStats,
     file_traversal_stats: &FileSearchStats,
-) -> Result<()> {
+    horizon_days: u32,
+) -> Result<bool> {
+    let files = file_rows(crud_stats);
+    let mut state = DashboardState::default();
     loop {
-        terminal.draw(|frame| draw_dashboard(frame, crud_stats, file_traversal_stats))?;
+        terminal.draw(|frame| {
+            draw_dashboard(
+                frame,
+                crud_stats,
+                file_traversal_stats,
+                horizon_days,
+                &files,
+                &mut state,
+            )
+        })?;
 
         if event::poll(Duration::from_millis(200))?
             && let Event::Key(key) = event::read()?
@@ -271,26 +786,34 @@ fn dashboard_loop(
             let exit_ctrl_c =
                 key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
             if key.code == KeyCode::Esc || exit_ctrl_c {
-                break;
+                return Ok(false);
             }
+            if key.code == KeyCode::Enter {
+                return Ok(true);
+            }
+            state.handle_key(key.code, files.len());
         }
     }
-    Ok(())
 }
 
 fn draw_dashboard(
     frame: &mut Frame<'_>,
     crud_stats: &CardStats,
     file_traversal_stats: &FileSearchStats,
+    horizon_days: u32,
+    files: &[(String, usize)],
+    state: &mut DashboardState,
 ) {
     let area = frame.area();
     frame.render_widget(Theme::backdrop(), area);
 
+    let files_height = files.len().clamp(1, MAX_FILE_ROWS) as u16 + 2;
     let rows = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(7),
             Constraint::Min(6),
+            Constraint::Length(files_height),
             Constraint::Length(3),
         ])
         .split(area);
@@ -304,18 +827,78 @@ fn draw_dashboard(
         collection_panel(crud_stats, file_traversal_stats),
         summary[0],
     );
-    frame.render_widget(due_panel(crud_stats), summary[1]);
+    frame.render_widget(due_panel(crud_stats, horizon_days), summary[1]);
 
     let mid = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
         .split(rows[1]);
 
-    render_upcoming_histogram(frame, mid[0], crud_stats);
+    let days_label = pluralize("day", horizon_days as usize);
+    let upcoming_title = format!("Next {days_label} histogram");
+    match &crud_stats.forecast {
+        Some(forecast) => {
+            let left = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(mid[0]);
+            render_day_histogram(
+                frame,
+                left[0],
+                upcoming_title,
+                None,
+                &crud_stats.upcoming,
+                horizon_days,
+                crud_stats.today,
+            );
+            render_day_histogram(
+                frame,
+                left[1],
+                format!("Projected load, next {FORECAST_DAYS} days (estimate)"),
+                Some(FORECAST_NOTE),
+                forecast,
+                FORECAST_DAYS,
+                crud_stats.today,
+            );
+        }
+        None => render_day_histogram(
+            frame,
+            mid[0],
+            upcoming_title,
+            None,
+            &crud_stats.upcoming,
+            horizon_days,
+            crud_stats.today,
+        ),
+    }
 
     render_fsrs_panel(frame, mid[1], crud_stats);
 
-    frame.render_widget(help_panel(crud_stats), rows[2]);
+    state.visible_rows = rows[2].height.saturating_sub(2) as usize;
+    state.scroll_by(0, files.len());
+    frame.render_widget(files_panel(files, state), rows[2]);
+
+    frame.render_widget(help_panel(crud_stats), rows[3]);
+}
+
+fn files_panel(files: &[(String, usize)], state: &DashboardState) -> Paragraph<'static> {
+    let shown = files.iter().skip(state.scroll).take(state.visible_rows);
+    let lines: Vec<Line<'static>> = shown
+        .map(|(path, count)| {
+            Line::from(vec![
+                Theme::label_span(format!("{count:>5}")),
+                Theme::bullet(),
+                Theme::span(path.clone()),
+            ])
+        })
+        .collect();
+    let title = if files.len() > state.visible_rows {
+        let last = cmp::min(state.scroll + state.visible_rows, files.len());
+        format!("Files {}-{last} of {}", state.scroll + 1, files.len())
+    } else {
+        "Files".to_string()
+    };
+    Paragraph::new(lines).block(Theme::panel(title))
 }
 
 fn collection_panel(
@@ -376,12 +959,32 @@ fn collection_panel(
             Theme::span("Total Cards Indexed in DB"),
             Theme::bullet(),
             Theme::label_span(format!("{}", crud_stats.total_cards_in_db)),
+            Theme::bullet(),
+            Theme::span("Orphaned"),
+            Theme::bullet(),
+            Theme::label_span(format!("{}", crud_stats.orphans.len())),
+            Theme::bullet(),
+            Theme::span("Suspended"),
+            Theme::bullet(),
+            Theme::label_span(format!("{}", crud_stats.suspended_cards)),
+            Theme::bullet(),
+            Theme::span("Leeches"),
+            Theme::bullet(),
+            Theme::label_span(format!("{}", crud_stats.leech_cards)),
+            Theme::bullet(),
+            Theme::span("Broken Media"),
+            Theme::bullet(),
+            Theme::label_span(format!("{}", crud_stats.broken_media.len())),
+            Theme::bullet(),
+            Theme::span("Duplicates"),
+            Theme::bullet(),
+            Theme::label_span(format!("{}", file_traversal_stats.duplicate_cards.len())),
         ]),
     ];
     Paragraph::new(lines).block(Theme::panel("Collection"))
 }
 
-fn due_panel(stats: &CardStats) -> Paragraph<'static> {
+fn due_panel(stats: &CardStats, horizon_days: u32) -> Paragraph<'static> {
     let load_factor = if stats.num_cards == 0 {
         0.0
     } else {
@@ -394,7 +997,7 @@ fn due_panel(stats: &CardStats) -> Paragraph<'static> {
     } else {
         Theme::emphasis()
     };
-    let upcoming_week_total: usize = stats.upcoming_week.values().sum();
+    let upcoming_total: usize = stats.upcoming.values().sum();
     let lines = vec![
         Line::from(vec![Span::styled("Focus", emphasis)]),
         Line::from(vec![
@@ -408,24 +1011,39 @@ fn due_panel(stats: &CardStats) -> Paragraph<'static> {
             Theme::span("  "),
         ]),
         Line::from(vec![
-            Theme::span("Next 7 days"),
+            Theme::span(format!("Next {}", pluralize("day", horizon_days as usize))),
             Theme::bullet(),
-            Theme::label_span(format!("{}", upcoming_week_total)),
+            Theme::label_span(format!("{}", upcoming_total)),
             Theme::bullet(),
             Theme::span("Next 30 days"),
             Theme::bullet(),
             Theme::label_span(format!("{}", stats.upcoming_month)),
         ]),
+        Line::from(vec![
+            Theme::span("Reviewed today"),
+            Theme::bullet(),
+            Theme::label_span(format!("{}", stats.reviewed_today)),
+        ]),
     ];
     Paragraph::new(lines).block(Theme::panel("Due Status"))
 }
 
-fn render_upcoming_histogram(frame: &mut Frame<'_>, area: Rect, stats: &CardStats) {
-    let block = Theme::panel_with_line(Theme::title_line("Next 7 days histogram"));
-    if stats.upcoming_week.is_empty() {
-        let empty = Paragraph::new(vec![Line::from(vec![Theme::span(
-            "You're clear for the next 7 days.",
-        )])])
+/// Bar chart with one bar per day for `days` days, starting today.
+fn render_day_histogram(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    title: String,
+    note: Option<&'static str>,
+    counts: &BTreeMap<String, usize>,
+    days: u32,
+    today: NaiveDate,
+) {
+    let block = Theme::panel_with_line(Theme::title_line(title));
+    if counts.is_empty() {
+        let empty = Paragraph::new(vec![Line::from(vec![Theme::span(format!(
+            "You're clear for the next {}.",
+            pluralize("day", days as usize)
+        ))])])
         .block(block);
         frame.render_widget(empty, area);
         return;
@@ -436,6 +1054,10 @@ fn render_upcoming_histogram(frame: &mut Frame<'_>, area: Rect, stats: &CardStat
     if inner.width == 0 || inner.height == 0 {
         inner = area;
     }
+    if let Some(note) = note {
+        let note = Paragraph::new(Line::from(vec![Theme::span(note)])).wrap(Wrap { trim: true });
+        frame.render_widget(note, inner);
+    }
     let mut chart_area = inner;
     let top_pad = cmp::min(3, chart_area.height);
     chart_area.y = chart_area.y.saturating_add(top_pad);
@@ -448,20 +1070,8 @@ fn render_upcoming_histogram(frame: &mut Frame<'_>, area: Rect, stats: &CardStat
         chart_area = inner;
     }
 
-    let bars: Vec<Bar<'static>> = stats
-        .upcoming_week
-        .iter()
-        .map(|(day, count)| {
-            let label = format_upcoming_label(day);
-            Bar::default()
-                .value(*count as u64)
-                .text_value(count.to_string())
-                .label(Line::from(vec![Theme::span(label)]))
-                .style(Theme::label())
-        })
-        .collect();
-
-    let len = bars.len() as u16;
+    let days = upcoming_days(counts, days, today);
+    let len = days.len() as u16;
     let denom = cmp::max(len, 1);
     let mut available = chart_area.width.saturating_sub(1).max(1);
     let mut bar_gap: u16 = if len > 1 { 1 } else { 0 };
@@ -473,6 +1083,18 @@ fn render_upcoming_histogram(frame: &mut Frame<'_>, area: Rect, stats: &CardStat
     available = available.saturating_sub(total_gap);
     let bar_width = cmp::max(1, cmp::min(available / denom, available));
 
+    let bars: Vec<Bar<'static>> = days
+        .into_iter()
+        .map(|(day, count)| {
+            let label = upcoming_bar_label(&day, bar_width);
+            Bar::default()
+                .value(count as u64)
+                .text_value(count.to_string())
+                .label(Line::from(vec![Theme::span(label)]))
+                .style(Theme::label())
+        })
+        .collect();
+
     let chart = BarChart::default()
         .data(BarGroup::default().bars(&bars))
         .bar_width(bar_width)
@@ -482,6 +1104,36 @@ fn render_upcoming_histogram(frame: &mut Frame<'_>, area: Rect, stats: &CardStat
     frame.render_widget(chart, chart_area);
 }
 
+/// Every day from `today` through the horizon with its due count, so quiet
+/// days show up between busy ones.
+fn upcoming_days(
+    counts: &BTreeMap<String, usize>,
+    days: u32,
+    today: NaiveDate,
+) -> Vec<(String, usize)> {
+    (0..u64::from(days))
+        .map(|offset| {
+            let day = (today + Days::new(offset)).format("%Y-%m-%d").to_string();
+            let count = counts.get(&day).copied().unwrap_or(0);
+            (day, count)
+        })
+        .collect()
+}
+
+/// Drops the weekday, then the whole label, once bars get too narrow for it.
+fn upcoming_bar_label(day: &str, bar_width: u16) -> String {
+    let label = format_upcoming_label(day);
+    if label.len() <= bar_width as usize {
+        return label;
+    }
+    match label.split_once(' ') {
+        Some((_, day_of_month)) if day_of_month.len() <= bar_width as usize => {
+            day_of_month.to_string()
+        }
+        _ => String::new(),
+    }
+}
+
 fn format_upcoming_label(day: &str) -> String {
     NaiveDate::parse_from_str(day, "%Y-%m-%d")
         .map(|date| date.format("%a %d").to_string())
@@ -494,6 +1146,7 @@ fn render_fsrs_histogram(
     histogram_stats: &Histogram<5>,
     title: &str,
     description: &str,
+    bin_color: Option<BinColor>,
 ) {
     let section_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -521,11 +1174,18 @@ fn render_fsrs_histogram(
         .map(|(i, count)| {
             let min_thresh = step_size * i;
             let label = format!("{}%-{}%", min_thresh, min_thresh + step_size);
+            let style = bin_color.map_or_else(Theme::label, |color| {
+                let color = color(
+                    min_thresh as f64 / 100.0,
+                    (min_thresh + step_size) as f64 / 100.0,
+                );
+                Theme::label().fg(color.tui())
+            });
             Bar::default()
                 .value(*count as u64)
                 .text_value(count.to_string())
                 .label(Line::from(vec![Theme::span(label)]))
-                .style(Theme::label())
+                .style(style)
         })
         .collect();
 
@@ -577,6 +1237,7 @@ fn render_fsrs_panel(frame: &mut Frame<'_>, area: Rect, stats: &CardStats) {
         &stats.difficulty_histogram,
         "Difficulty",
         "The higher the difficulty, the slower stability will increase.",
+        None,
     );
     render_fsrs_histogram(
         frame,
@@ -584,6 +1245,7 @@ fn render_fsrs_panel(frame: &mut Frame<'_>, area: Rect, stats: &CardStats) {
         &stats.retrievability_histogram,
         "Retrievability",
         "The probability of recalling a card today.",
+        Some(retrievability_color),
     );
 }
 
@@ -594,6 +1256,14 @@ fn help_panel(stats: &CardStats) -> Paragraph<'static> {
             Theme::span("/ "),
             Theme::key_chip("Ctrl+C"),
             Theme::span(" exit"),
+            Theme::bullet(),
+            Theme::key_chip("Enter"),
+            Theme::span(" drill due cards"),
+            Theme::bullet(),
+            Theme::key_chip("↑/↓"),
+            Theme::span("/ "),
+            Theme::key_chip("PgUp/PgDn"),
+            Theme::span(" scroll files"),
         ]),
         Line::from(vec![
             Theme::span("Snapshot covers"),
@@ -611,10 +1281,19 @@ fn help_panel(stats: &CardStats) -> Paragraph<'static> {
 
 #[cfg(test)]
 mod tests {
-    use crate::parser::FileSearchStats;
-    use crate::stats::CardStats;
+    use crate::card::{Card, CardContent};
+    use crate::parser::{DuplicateCard, FileSearchStats, SkippedFile};
+    use crate::stats::{CardLifeCycle, CardStats, DEFAULT_HORIZON_DAYS};
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
 
-    use super::{format_upcoming_label, render_plain_summary};
+    use crossterm::event::KeyCode;
+
+    use super::{
+        CheckSummary, DashboardState, file_rows, find_broken_media, format_upcoming_label,
+        render_plain_summary, retrievability_color, upcoming_bar_label, upcoming_days,
+    };
+    use crate::palette::Palette;
 
     #[test]
     fn format_upcoming_label_pretty_prints_dates() {
@@ -632,6 +1311,158 @@ mod tests {
     fn test_plain_summary() {
         let crud_stats = CardStats::default();
         let file_traversal_stats = FileSearchStats::default();
-        render_plain_summary(&crud_stats, &file_traversal_stats);
+        render_plain_summary(&crud_stats, &file_traversal_stats, DEFAULT_HORIZON_DAYS);
+    }
+
+    #[test]
+    fn retrievability_bins_below_the_threshold_are_at_risk() {
+        let color = |low: f64, high: f64| retrievability_color(low, high).tui();
+        assert_eq!(color(0.4, 0.6), Palette::DANGER.tui());
+        assert_eq!(color(0.6, 0.8), Palette::SUCCESS.tui());
+        assert_eq!(color(0.8, 1.0), Palette::SUCCESS.tui());
+    }
+
+    #[test]
+    fn file_list_scrolling_is_clamped_to_the_rows() {
+        let mut stats = CardStats::default();
+        for (idx, count) in [3, 9, 1, 5].into_iter().enumerate() {
+            stats
+                .file_paths
+                .insert(format!("deck/{idx}.md").into(), count);
+        }
+        let files = file_rows(&stats);
+        assert_eq!(
+            files.iter().map(|(_, count)| *count).collect::<Vec<_>>(),
+            vec![9, 5, 3, 1]
+        );
+
+        let mut state = DashboardState {
+            scroll: 0,
+            visible_rows: 3,
+        };
+        state.handle_key(KeyCode::Char('x'), files.len());
+        assert_eq!(state.scroll, 0);
+        state.handle_key(KeyCode::Up, files.len());
+        assert_eq!(state.scroll, 0);
+        state.handle_key(KeyCode::PageDown, files.len());
+        assert_eq!(state.scroll, 1);
+        state.handle_key(KeyCode::Down, files.len());
+        assert_eq!(state.scroll, 1);
+        state.handle_key(KeyCode::Home, files.len());
+        assert_eq!(state.scroll, 0);
+        state.handle_key(KeyCode::End, files.len());
+        assert_eq!(state.scroll, 1);
+
+        state.visible_rows = 10;
+        state.scroll_by(0, files.len());
+        assert_eq!(state.scroll, 0);
+    }
+
+    #[test]
+    fn upcoming_days_fill_quiet_days_through_the_horizon() {
+        let mut stats = CardStats::default();
+        stats.upcoming.insert("2024-12-25".into(), 3);
+        stats.upcoming.insert("2024-12-28".into(), 1);
+        let today = NaiveDate::from_ymd_opt(2024, 12, 24).unwrap();
+
+        let days = upcoming_days(&stats.upcoming, 10, today);
+        assert_eq!(days.len(), 10);
+        assert_eq!(days[0], ("2024-12-24".to_string(), 0));
+        assert_eq!(days[1], ("2024-12-25".to_string(), 3));
+        assert_eq!(days[4], ("2024-12-28".to_string(), 1));
+        assert_eq!(days[9], ("2025-01-02".to_string(), 0));
+    }
+
+    #[test]
+    fn json_summary_fills_days_and_names_lifecycles() {
+        let mut stats = CardStats {
+            num_cards: 4,
+            due_cards: 2,
+            today: NaiveDate::from_ymd_opt(2024, 12, 24).unwrap(),
+            ..Default::default()
+        };
+        stats.card_lifecycles.insert(CardLifeCycle::New, 3);
+        stats.card_lifecycles.insert(CardLifeCycle::Young, 1);
+        stats.upcoming.insert("2024-12-24".into(), 2);
+        stats.file_paths.insert("deck/a.md".into(), 4);
+        stats.difficulty_histogram.update(0.5);
+        let traversal = FileSearchStats {
+            files_searched: 5,
+            markdown_files: 2,
+            skipped_files: vec![SkippedFile {
+                path: "deck/b.md".into(),
+                error: "Invalid frontmatter".into(),
+            }],
+            duplicate_cards: vec![DuplicateCard {
+                card_hash: "abc".into(),
+                locations: vec![("deck/a.md".into(), 1), ("deck/c.md".into(), 7)],
+            }],
+        };
+
+        let summary = CheckSummary::new(&stats, &traversal, 3);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["cards"], 4);
+        assert_eq!(json["due_now"], 2);
+        assert_eq!(
+            json["lifecycles"],
+            serde_json::json!({"new": 3, "young": 1, "mature": 0})
+        );
+        assert_eq!(
+            json["upcoming"],
+            serde_json::json!({"2024-12-24": 2, "2024-12-25": 0, "2024-12-26": 0})
+        );
+        assert_eq!(json["forecast"], serde_json::Value::Null);
+        assert_eq!(json["difficulty"]["mean"], 0.5);
+        assert_eq!(json["retrievability"]["mean"], serde_json::Value::Null);
+        assert_eq!(
+            json["files"],
+            serde_json::json!([{"path": "deck/a.md", "cards": 4}])
+        );
+        assert_eq!(json["markdown_files"], 2);
+        assert_eq!(
+            json["skipped_files"],
+            serde_json::json!([{"path": "deck/b.md", "error": "Invalid frontmatter"}])
+        );
+        assert_eq!(
+            json["duplicate_cards"],
+            serde_json::json!([{
+                "card_hash": "abc",
+                "locations": [
+                    {"file": "deck/a.md", "line": 1},
+                    {"file": "deck/c.md", "line": 7}
+                ]
+            }])
+        );
+    }
+
+    #[test]
+    fn broken_media_is_resolved_against_the_card_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("media")).unwrap();
+        std::fs::write(dir.path().join("media/dog.jpg"), b"").unwrap();
+        let path = dir.path().join("deck.md");
+        let card = Card::new(
+            path.clone(),
+            (4, 6),
+            CardContent::Basic {
+                question: "![dog](media/dog.jpg)".into(),
+                answer: "[bark](media/bark.mp3) [docs](https://example.com)".into(),
+            },
+            "hash".into(),
+        );
+        let cards = HashMap::from([(card.card_hash.clone(), card)]);
+
+        let broken = find_broken_media(&cards);
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].card_path, path);
+        assert_eq!(broken[0].line, 5);
+        assert_eq!(broken[0].media_path, dir.path().join("media/bark.mp3"));
+    }
+
+    #[test]
+    fn upcoming_bar_label_shrinks_with_bar_width() {
+        assert_eq!(upcoming_bar_label("2024-12-25", 6), "Wed 25");
+        assert_eq!(upcoming_bar_label("2024-12-25", 3), "25");
+        assert_eq!(upcoming_bar_label("2024-12-25", 1), "");
     }
 }
@@ -0,0 +1,300 @@
+use crate::crud::DB;
+use crate::keymap::Keymap;
+use crate::palette::Palette;
+use crate::parser::CardSeparator;
+use crate::settings::{
+    format_learning_steps, format_step, fsrs_params_path, parse_learning_steps,
+    read_fsrs_parameters, write_fsrs_parameters,
+};
+use crate::utils::pluralize;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use fsrs::DEFAULT_PARAMETERS;
+
+#[derive(Clone, Debug, Default)]
+pub struct ConfigOptions {
+    pub retention: Option<f32>,
+    pub fsrs_params: Option<PathBuf>,
+    pub learning_steps: Option<String>,
+    /// `Some(0)` removes the cap.
+    pub max_interval: Option<u32>,
+    pub fuzz: Option<bool>,
+    pub card_separator: Option<CardSeparator>,
+    pub leech_threshold: Option<u32>,
+    pub suspend_leeches: Option<bool>,
+    pub version_check: Option<bool>,
+    pub day_start: Option<u32>,
+    pub learn_ahead: Option<Duration>,
+    /// Drill key bindings like `fail=j good=k`, or `default`.
+    pub keys: Option<String>,
+}
+
+pub async fn run(db: &DB, options: ConfigOptions) -> Result<()> {
+    let ConfigOptions {
+        retention,
+        fsrs_params,
+        learning_steps,
+        max_interval,
+        fuzz,
+        card_separator,
+        leech_threshold,
+        suspend_leeches,
+        version_check,
+        day_start,
+        learn_ahead,
+        keys,
+    } = options;
+    if retention.is_none()
+        && fsrs_params.is_none()
+        && learning_steps.is_none()
+        && max_interval.is_none()
+        && fuzz.is_none()
+        && card_separator.is_none()
+        && leech_threshold.is_none()
+        && suspend_leeches.is_none()
+        && version_check.is_none()
+        && day_start.is_none()
+        && learn_ahead.is_none()
+        && keys.is_none()
+    {
+        return print_settings(db).await;
+    }
+
+    if let Some(retention) = retention {
+        db.set_desired_retention(retention).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Desired retention set to"),
+            Palette::paint(Palette::INFO, format!("{:.2}", retention)),
+        );
+    }
+
+    if let Some(source) = fsrs_params {
+        let parameters = read_fsrs_parameters(&source)?
+            .with_context(|| format!("No FSRS parameters file at {}", source.display()))?;
+        let target = fsrs_params_path(db.data_dir());
+        write_fsrs_parameters(&target, &parameters)?;
+        println!(
+            "{} {} {}",
+            Palette::paint(Palette::SUCCESS, "Saved"),
+            Palette::paint(Palette::INFO, parameters.len()),
+            Palette::dim(format!("FSRS parameters to {}", target.display())),
+        );
+    }
+
+    if let Some(steps) = learning_steps {
+        let steps = parse_learning_steps(&steps)
+            .with_context(|| format!("Invalid learning steps \"{steps}\""))?;
+        db.set_learning_steps(&steps).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Learning steps set to"),
+            Palette::paint(Palette::INFO, format_learning_steps(&steps)),
+        );
+    }
+
+    if let Some(days) = max_interval {
+        let days = (days > 0).then_some(days);
+        db.set_max_interval(days).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Maximum interval set to"),
+            Palette::paint(Palette::INFO, format_max_interval(days)),
+        );
+    }
+
+    if let Some(enabled) = fuzz {
+        db.set_fuzz_intervals(enabled).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Interval fuzz turned"),
+            Palette::paint(Palette::INFO, on_off(enabled)),
+        );
+    }
+
+    if let Some(separator) = card_separator {
+        if separator != db.get_settings().await?.card_separator {
+            eprintln!(
+                "{} cards will split at different lines, so any card whose text changes gets a new hash and starts over without its review history.",
+                Palette::paint(Palette::WARNING, "Warning:"),
+            );
+        }
+        db.set_card_separator(separator).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Cards now end at"),
+            Palette::paint(Palette::INFO, describe_separator(separator)),
+        );
+    }
+
+    if let Some(failures) = leech_threshold {
+        db.set_leech_threshold(failures).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Cards become leeches after"),
+            Palette::paint(Palette::INFO, pluralize("failure", failures as usize)),
+        );
+    }
+
+    if let Some(enabled) = suspend_leeches {
+        db.set_suspend_leeches(enabled).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Suspending new leeches turned"),
+            Palette::paint(Palette::INFO, on_off(enabled)),
+        );
+    }
+
+    if let Some(hour) = day_start {
+        db.set_day_start_hour(hour).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "New study days start at"),
+            Palette::paint(Palette::INFO, format_day_start(hour)),
+        );
+    }
+
+    if let Some(window) = learn_ahead {
+        db.set_learn_ahead(window).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Cards count as due this far ahead:"),
+            Palette::paint(Palette::INFO, format_step(window)),
+        );
+    }
+
+    if let Some(enabled) = version_check {
+        db.set_version_check(enabled).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Version check turned"),
+            Palette::paint(Palette::INFO, on_off(enabled)),
+        );
+    }
+
+    if let Some(keys) = keys {
+        let keymap = if keys.trim().eq_ignore_ascii_case("default") {
+            db.set_keymap(None).await?;
+            Keymap::default()
+        } else {
+            let keymap = db
+                .get_settings()
+                .await?
+                .keymap
+                .rebind(&keys)
+                .with_context(|| format!("Invalid drill keys \"{keys}\""))?;
+            db.set_keymap(Some(&keymap)).await?;
+            keymap
+        };
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Drill keys set to"),
+            Palette::paint(Palette::INFO, keymap.format()),
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn describe_separator(separator: CardSeparator) -> &'static str {
+    match separator {
+        CardSeparator::Rule => "a --- line",
+        CardSeparator::BlankLine => "a blank line",
+    }
+}
+
+pub(crate) fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
+
+pub(crate) fn format_day_start(hour: u32) -> String {
+    format!("{hour:02}:00 local time")
+}
+
+pub(crate) fn format_max_interval(days: Option<u32>) -> String {
+    match days {
+        Some(days) => pluralize("day", days as usize),
+        None => String::from("none"),
+    }
+}
+
+async fn print_settings(db: &DB) -> Result<()> {
+    let settings = db.get_settings().await?;
+    println!(
+        "{} {}",
+        Palette::dim("Desired retention:"),
+        Palette::paint(Palette::INFO, format!("{:.2}", settings.desired_retention)),
+    );
+    let parameters = if settings.fsrs_parameters == DEFAULT_PARAMETERS {
+        String::from("default")
+    } else {
+        format!("custom ({})", fsrs_params_path(db.data_dir()).display())
+    };
+    println!(
+        "{} {}",
+        Palette::dim("FSRS parameters:"),
+        Palette::paint(Palette::INFO, parameters),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Learning steps:"),
+        Palette::paint(
+            Palette::INFO,
+            format_learning_steps(&settings.learning_steps)
+        ),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Maximum interval:"),
+        Palette::paint(
+            Palette::INFO,
+            format_max_interval(settings.max_interval_days)
+        ),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Interval fuzz:"),
+        Palette::paint(Palette::INFO, on_off(settings.fuzz_intervals)),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Card separator:"),
+        Palette::paint(Palette::INFO, settings.card_separator.name()),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Leech threshold:"),
+        Palette::paint(
+            Palette::INFO,
+            pluralize("failure", settings.leech_threshold as usize)
+        ),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Suspend leeches:"),
+        Palette::paint(Palette::INFO, on_off(settings.suspend_leeches)),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Day starts at:"),
+        Palette::paint(Palette::INFO, format_day_start(settings.day_start_hour)),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Learn ahead:"),
+        Palette::paint(Palette::INFO, format_step(settings.learn_ahead)),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Version check:"),
+        Palette::paint(Palette::INFO, on_off(settings.version_check)),
+    );
+    println!(
+        "{} {}",
+        Palette::dim("Drill keys:"),
+        Palette::paint(Palette::INFO, settings.keymap.format()),
+    );
+    Ok(())
+}
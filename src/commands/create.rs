@@ -1,8 +1,8 @@
 use crate::{
-    card::CardType,
+    card::{CardContent, CardType},
     crud::DB,
     palette::Palette,
-    parser::{cards_from_md, content_to_card},
+    parser::{CardSeparator, cards_from_md, content_to_card, content_to_cards},
     tui::Editor,
     tui::Theme,
     utils::ask_yn,
@@ -36,11 +36,39 @@ use ratatui::{
 
 const FLASH_SECS: f64 = 1.5;
 
-pub async fn run(db: &DB, card_path: PathBuf) -> Result<()> {
+/// A card given on the command line instead of typed into the editor.
+#[derive(Clone, Debug)]
+pub enum NewCard {
+    Basic { question: String, answer: String },
+    Cloze { text: String },
+}
+
+impl NewCard {
+    fn contents(&self) -> String {
+        match self {
+            NewCard::Basic { question, answer } => {
+                format!("Q: {}\nA: {}", question.trim(), answer.trim())
+            }
+            NewCard::Cloze { text } => format!("C: {}", text.trim()),
+        }
+    }
+}
+
+pub async fn run(db: &DB, card_path: PathBuf, new_card: Option<NewCard>) -> Result<()> {
     if !is_markdown(&card_path) {
         bail!("Card path must be a markdown file: {}", card_path.display());
     }
 
+    if let Some(card) = new_card {
+        add_card(db, &card_path, &card).await?;
+        println!(
+            "{} {}",
+            Palette::paint(Palette::SUCCESS, "Added card to"),
+            Palette::paint(Palette::ACCENT, card_path.display()),
+        );
+        return Ok(());
+    }
+
     let file_exists = card_path.is_file();
     if !file_exists {
         let should_create = ask_yn(format!(
@@ -68,13 +96,34 @@ fn create_file(path: &Path) -> Result<File> {
     Ok(file)
 }
 
+/// Appends a card without the editor, creating the deck if needed. Cloze
+/// cards must have at least one blank.
+async fn add_card(db: &DB, path: &Path, card: &NewCard) -> Result<()> {
+    let contents = card.contents();
+    if let NewCard::Cloze { .. } = card {
+        let parsed = content_to_card(path, &contents, 0, 0).context("Invalid card")?;
+        if let CardContent::Cloze { cloze_ranges, .. } = &parsed.content
+            && cloze_ranges.is_empty()
+        {
+            bail!("Cloze text needs at least one [blank]");
+        }
+    }
+    create_card_append_file(db, path, &contents).await
+}
+
+/// Validates `contents` as a card, refuses duplicates, then appends it to
+/// `path` and registers it.
 async fn create_card_append_file(db: &DB, path: &Path, contents: &str) -> Result<()> {
     let existing_len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
     let start_idx = existing_len as usize;
     let end_idx = start_idx + contents.len();
 
-    let card = content_to_card(path, contents, start_idx, end_idx).context("Invalid card")?;
-    let card_exists = db.card_exists(&card).await?;
+    let cards = content_to_cards(path, contents, start_idx, end_idx).context("Invalid card")?;
+    let separator = db.get_settings().await?.card_separator;
+    if file_has_card(path, &cards[0].card_hash, separator) {
+        bail!("This card is already written in {}.", path.display());
+    }
+    let card_exists = db.card_exists(&cards[0]).await?;
     if card_exists {
         bail!("This card already exists in the database.");
     }
@@ -85,11 +134,28 @@ async fn create_card_append_file(db: &DB, path: &Path, contents: &str) -> Result
     }
     writeln!(file, "{}", contents)?;
 
-    db.add_card(&card).await?;
+    db.add_cards_batch(&cards).await?;
 
     Ok(())
 }
 
+/// Whether the deck at `path` already holds a card with `card_hash`, catching
+/// re-entries that were never registered. A missing or unparsable deck counts
+/// as having none; the database check still applies.
+fn file_has_card(path: &Path, card_hash: &str, separator: CardSeparator) -> bool {
+    cards_from_md(path, separator)
+        .map(|cards| cards.iter().any(|card| card.card_hash == card_hash))
+        .unwrap_or(false)
+}
+
+/// Text on the system clipboard, or `None` when there is no clipboard to
+/// read (e.g. over SSH) so pasting quietly does nothing.
+fn clipboard_text() -> Option<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .ok()
+}
+
 async fn capture_cards(db: &DB, card_path: &Path) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -107,7 +173,8 @@ async fn capture_cards(db: &DB, card_path: &Path) -> Result<()> {
     let editor_result: Result<()> = async {
         let mut editor = Editor::new();
         let mut status: Option<String> = None;
-        let existing_cards = cards_from_md(card_path)?;
+        let separator = db.get_settings().await?.card_separator;
+        let existing_cards = cards_from_md(card_path, separator)?;
         let unique_hashes: HashSet<_> = existing_cards.into_iter().map(|c| c.card_hash).collect();
 
         let mut num_cards_in_collection = unique_hashes.len();
@@ -229,10 +296,19 @@ async fn capture_cards(db: &DB, card_path: &Path) -> Result<()> {
                     continue;
                 }
 
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
                 match key.code {
-                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    KeyCode::Char(c) if !ctrl => {
                         editor.insert_char(c);
                     }
+                    KeyCode::Char('v') if ctrl => {
+                        if let Some(text) = clipboard_text() {
+                            editor.insert_str(&text);
+                        }
+                    }
+                    KeyCode::Char('w') | KeyCode::Backspace if ctrl => editor.delete_word_back(),
+                    KeyCode::Left if ctrl => editor.move_word_left(),
+                    KeyCode::Right if ctrl => editor.move_word_right(),
                     KeyCode::Enter => editor.insert_newline(),
                     KeyCode::Tab => editor.insert_tab(),
                     KeyCode::Backspace => editor.backspace(),
@@ -274,17 +350,68 @@ async fn capture_cards(db: &DB, card_path: &Path) -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-
-    use std::env::temp_dir;
-
     use super::*;
 
     #[tokio::test]
     async fn test_card_create() {
         let db = DB::new_in_memory().await.unwrap();
         let content = "Q: what?\nA: yes\n\n";
-        let card_path = temp_dir().join("test.md");
+        let dir = tempfile::tempdir().unwrap();
+        let card_path = dir.path().join("test.md");
         let result = create_card_append_file(&db, &card_path, content).await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn adds_cards_from_arguments_and_refuses_duplicates() {
+        let db = DB::new_in_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let card_path = dir.path().join("decks/new.md");
+        let basic = NewCard::Basic {
+            question: "What powers the cell?".into(),
+            answer: "Mitochondria".into(),
+        };
+        let cloze = NewCard::Cloze {
+            text: "[Paris] is the capital of France".into(),
+        };
+
+        add_card(&db, &card_path, &basic).await.unwrap();
+        add_card(&db, &card_path, &cloze).await.unwrap();
+        assert!(add_card(&db, &card_path, &basic).await.is_err());
+        let no_blank = NewCard::Cloze {
+            text: "Paris is the capital of France".into(),
+        };
+        assert!(add_card(&db, &card_path, &no_blank).await.is_err());
+
+        let cards = cards_from_md(&card_path, CardSeparator::Rule).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&card_path).unwrap(),
+            "Q: What powers the cell?\nA: Mitochondria\n\nC: [Paris] is the capital of France\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_cards_already_written_in_the_file() {
+        let db = DB::new_in_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let card_path = dir.path().join("deck.md");
+        let original = "Q: What powers the cell?\nA: Mitochondria\n\nC: [Paris] is in France\n";
+        fs::write(&card_path, original).unwrap();
+
+        let err =
+            create_card_append_file(&db, &card_path, "Q: What powers the cell?\nA: Mitochondria")
+                .await
+                .unwrap_err();
+        assert!(err.to_string().contains("already written in"));
+        assert_eq!(fs::read_to_string(&card_path).unwrap(), original);
+
+        create_card_append_file(&db, &card_path, "Q: What stores genes?\nA: DNA")
+            .await
+            .unwrap();
+        let err = create_card_append_file(&db, &card_path, "Q: What stores genes?\nA: DNA")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already written in"));
+    }
 }
@@ -1,24 +1,36 @@
-use std::io;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::time::{Duration, Instant};
 
 use crate::card::{Card, CardContent};
-use crate::cloze_utils::mask_cloze_text;
-use crate::crud::DB;
-use crate::fsrs::{LEARN_AHEAD_THRESHOLD_MINS, ReviewStatus};
-use crate::llm::drill_preprocessor::{AIStatus, DrillPreprocessor};
-use crate::parser::register_all_cards;
-use crate::parser::render_markdown;
-use crate::parser::{Media, extract_media};
-use crate::tui::Theme;
-use crate::utils::pluralize;
-
-use anyhow::{Context, Result, anyhow};
+use crate::cloze_utils::{mask_cloze_text, reveal_cloze_text};
+use crate::commands::check::migrate_edited_cards;
+use crate::commands::drill_session;
+use crate::crud::{DB, DrillOrder, DueOptions};
+use crate::fsrs::{Performance, ReviewStatus};
+use crate::keymap::{DrillAction, Keymap};
+use crate::llm::drill_preprocessor::{AIStatus, DrillPreprocessor, does_card_need_cloze};
+use crate::palette::Palette;
+use crate::parser::{
+    CardSeparator, ScanOptions, forward_card_at, register_all_cards, report_skipped_files,
+};
+use crate::parser::{Media, MediaKind, extract_media};
+use crate::parser::{render_markdown, with_break_opportunities};
+use crate::settings::DEFAULT_LEARN_AHEAD;
+use crate::stats::CardLifeCycle;
+use crate::tui::{Editor, InlineImages, Theme};
+use crate::typed_answer::{DiffSegment, answer_matches, diff_answer};
+use crate::utils::{ask_yn, format_duration, pluralize};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::ValueEnum;
 use crossterm::event::KeyModifiers;
 use crossterm::{
     event::{
-        self, Event, KeyCode, KeyEventKind, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
-        PushKeyboardEnhancementFlags,
+        self, Event, KeyCode, KeyEvent, KeyEventKind, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
     },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
@@ -27,6 +39,7 @@ use ratatui::{
     Terminal,
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
+    style::Modifier,
     text::{Line, Span},
     widgets::{Paragraph, Wrap},
 };
@@ -35,33 +48,257 @@ use tokio::sync::mpsc;
 const MINUTES_PER_DAY: f64 = 24.0 * 60.0;
 const FLASH_SECS: f64 = 2.0;
 
-pub async fn run(
+/// Which reviewed cards come back later in the same session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum RedoPolicy {
+    /// Failed cards, and passed cards due again within the learn-ahead window.
+    #[default]
+    Soon,
+    /// Only failed cards. Short-interval passes wait for a later session.
+    AgainOnly,
+}
+
+impl RedoPolicy {
+    /// Whether a card graded `action`, due again in `show_again_days`, is
+    /// shown again this session.
+    fn requeues(self, action: ReviewStatus, show_again_days: f64, learn_ahead: Duration) -> bool {
+        let due_soon = show_again_days < learn_ahead.as_secs_f64() / 60.0 / MINUTES_PER_DAY;
+        action == ReviewStatus::Again || (self == RedoPolicy::Soon && due_soon)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DrillOptions {
+    pub card_limit: Option<usize>,
+    pub new_card_limit: Option<usize>,
+    pub per_file_limit: Option<usize>,
+    pub rephrase_questions: bool,
+    pub shuffle: bool,
+    pub order: DrillOrder,
+    pub tag: Option<String>,
+    pub deck: Option<String>,
+    pub both_ways: bool,
+    pub time_limit: Option<Duration>,
+    pub plain: bool,
+    pub type_answers: bool,
+    pub redo: RedoPolicy,
+    pub autoplay_audio: bool,
+    pub migrate_edits: bool,
+    pub write_ai_fixes: bool,
+    pub scan: ScanOptions,
+    pub only: Option<CardLifeCycle>,
+    /// Print how many cards a session would hold instead of drilling them.
+    pub count_only: bool,
+    /// Print the cards a session would hold, in order, instead of drilling them.
+    pub list_only: bool,
+}
+
+pub async fn run(db: &DB, paths: Vec<PathBuf>, options: DrillOptions) -> Result<()> {
+    let session_paths = drill_session::session_paths(&paths);
+    let (hash_cards, stats) = register_all_cards(db, paths, options.scan).await?;
+    report_skipped_files(&stats);
+    drill_cards(db, &session_paths, hash_cards, options).await
+}
+
+/// Drills the cards due among `hash_cards`, which must already be registered.
+/// `paths` are the drilled paths from [`drill_session::session_paths`], under
+/// which an interrupted session is saved.
+pub async fn drill_cards(
     db: &DB,
-    paths: Vec<PathBuf>,
-    card_limit: Option<usize>,
-    new_card_limit: Option<usize>,
-    rephrase_questions: bool,
-    shuffle: bool,
+    paths: &[PathBuf],
+    mut hash_cards: HashMap<String, Card>,
+    options: DrillOptions,
 ) -> Result<()> {
-    let (hash_cards, _) = register_all_cards(db, paths).await?;
-    let mut cards_due_today = db
-        .due_today(&hash_cards, card_limit, new_card_limit)
-        .await?;
+    let DrillOptions {
+        card_limit,
+        new_card_limit,
+        per_file_limit,
+        rephrase_questions,
+        shuffle,
+        order,
+        tag,
+        deck,
+        both_ways,
+        time_limit,
+        plain,
+        type_answers,
+        redo,
+        autoplay_audio,
+        migrate_edits,
+        write_ai_fixes,
+        scan: _,
+        only,
+        count_only,
+        list_only,
+    } = options;
+    if migrate_edits {
+        migrate_edited_cards(db, &hash_cards).await?;
+    }
+    if both_ways {
+        add_reverse_cards(db, &mut hash_cards).await?;
+    }
+    if let Some(tag) = &tag {
+        hash_cards.retain(|_, card| card.has_tag(tag));
+    }
+    if let Some(deck) = &deck {
+        hash_cards.retain(|_, card| card.in_deck(deck));
+    }
+    let due_options = DueOptions {
+        card_limit,
+        new_card_limit,
+        per_file_limit,
+        order,
+        shuffle,
+        only,
+    };
+    let mut cards_due_today = db.due_today(&hash_cards, due_options).await?;
 
-    if shuffle {
-        use rand::seq::SliceRandom;
-        cards_due_today.shuffle(&mut rand::rng());
+    if count_only {
+        let (reviews, new) = count_due(db, &cards_due_today).await?;
+        println!(
+            "{} due: {} to review, {} new",
+            pluralize("card", reviews + new),
+            reviews,
+            new
+        );
+        return Ok(());
     }
 
     if cards_due_today.is_empty() {
-        println!("All caught up—no cards due today.");
+        if let Some(tag) = &tag
+            && hash_cards.is_empty()
+        {
+            println!("No cards tagged #{}.", tag.trim_start_matches('#'));
+            return Ok(());
+        }
+        if let Some(deck) = &deck
+            && hash_cards.is_empty()
+        {
+            println!("No cards in deck {}.", deck.trim());
+            return Ok(());
+        }
+        match only {
+            Some(only) => println!("All caught up—no {} cards due today.", only.label()),
+            None => println!("All caught up—no cards due today."),
+        }
         return Ok(());
     }
 
-    let drill_preprocessor = DrillPreprocessor::new(&cards_due_today, rephrase_questions)?;
+    if list_only {
+        return write_checklist(&cards_due_today, &mut io::stdout().lock());
+    }
+
+    let data_dir = db.data_dir();
+    if let Some(saved) = drill_session::load(data_dir, paths) {
+        let all_due = DueOptions {
+            only,
+            ..DueOptions::default()
+        };
+        let resumable =
+            drill_session::resumable(&saved, &db.due_today(&hash_cards, all_due).await?);
+        if !resumable.is_empty()
+            && io::stdin().is_terminal()
+            && ask_yn(format!(
+                "Resume your last drill of these paths ({} left)?",
+                pluralize("card", resumable.len())
+            ))
+        {
+            cards_due_today = resumable;
+        }
+        drill_session::clear(data_dir)?;
+    }
+
+    let settings = db.get_settings().await?;
+    if plain {
+        // plain mode has no AI preprocessing, so cards still missing a cloze can't be drilled
+        let before = cards_due_today.len();
+        cards_due_today.retain(|card| !does_card_need_cloze(card));
+        let skipped = before - cards_due_today.len();
+        if skipped > 0 {
+            println!(
+                "{}",
+                Palette::dim(format!(
+                    "Skipping {} without a cloze deletion.",
+                    pluralize("card", skipped)
+                ))
+            );
+        }
+        let mut state = DrillState::new(db, cards_due_today)
+            .with_time_limit(time_limit)
+            .with_redo_policy(redo)
+            .with_learn_ahead(settings.learn_ahead);
+        plain_drill_loop(&mut state, io::stdin().lock(), &mut io::stdout()).await?;
+        return drill_session::finish(data_dir, paths, &state.unfinished_cards());
+    }
+
+    let drill_preprocessor =
+        DrillPreprocessor::new(db, &cards_due_today, rephrase_questions, write_ai_fixes).await?;
     drill_preprocessor.initialize_card_status(&mut cards_due_today);
-    start_drill_session(db, cards_due_today, drill_preprocessor).await?;
+    let mut state = DrillState::new(db, cards_due_today)
+        .with_time_limit(time_limit)
+        .with_typed_answers(type_answers)
+        .with_redo_policy(redo)
+        .with_learn_ahead(settings.learn_ahead)
+        .with_autoplay_audio(autoplay_audio)
+        .with_card_separator(settings.card_separator)
+        .with_keymap(settings.keymap);
+    start_drill_session(&mut state, drill_preprocessor).await?;
+
+    drill_session::finish(data_dir, paths, &state.unfinished_cards())
+}
+
+/// Prints `cards` as a numbered list of their questions, with clozes masked,
+/// and where each one lives.
+fn write_checklist<W: Write>(cards: &[Card], output: &mut W) -> Result<()> {
+    let width = cards.len().to_string().len();
+    for (idx, card) in cards.iter().enumerate() {
+        let prompt = match &card.content {
+            CardContent::Basic { question, .. } | CardContent::TypeAnswer { question, .. } => {
+                question.clone()
+            }
+            CardContent::Cloze { text, cloze_ranges } => mask_cloze_text(text, cloze_ranges),
+        };
+        let prompt = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+        writeln!(
+            output,
+            "{:>width$}. {}  {}",
+            idx + 1,
+            prompt,
+            Palette::dim(format!(
+                "{}:{}",
+                card.file_path.display(),
+                card.file_card_range.0 + 1
+            )),
+        )?;
+    }
+    Ok(())
+}
+
+/// Splits a session's cards into reviews and never-seen cards.
+async fn count_due(db: &DB, cards: &[Card]) -> Result<(usize, usize)> {
+    let by_hash: HashMap<String, Card> = cards
+        .iter()
+        .map(|card| (card.card_hash.clone(), card.clone()))
+        .collect();
+    let new = db
+        .card_performances(&by_hash)
+        .await?
+        .iter()
+        .filter(|(_, performance)| matches!(performance, Performance::New))
+        .count();
+    Ok((cards.len() - new, new))
+}
 
+async fn add_reverse_cards(db: &DB, hash_cards: &mut HashMap<String, Card>) -> Result<()> {
+    let reverse_cards: Vec<Card> = hash_cards
+        .values()
+        .filter_map(Card::reversed)
+        .filter(|card| !hash_cards.contains_key(&card.card_hash))
+        .collect();
+    db.add_cards_batch(&reverse_cards).await?;
+    for card in reverse_cards {
+        hash_cards.insert(card.card_hash.clone(), card);
+    }
     Ok(())
 }
 
@@ -72,7 +309,91 @@ struct DrillState<'a> {
     current_idx: usize,
     show_answer: bool,
     last_action: Option<LastAction>,
+    undo: Option<UndoEntry>,
+    last_undo_at: Option<Instant>,
+    /// Media on the visible part of the card, question side first.
     current_medias: Vec<Media>,
+    /// Where the answer side's media start in `current_medias`.
+    answer_media_start: usize,
+    /// `O` was pressed on a card with several media; the next key picks one.
+    choosing_media: bool,
+    /// Play a card's first audio file when its answer is revealed.
+    autoplay_audio: bool,
+    /// The revealed card's audio has been played, so later frames don't replay it.
+    audio_played: bool,
+    started_at: Instant,
+    time_limit: Option<Duration>,
+    in_redo_pass: bool,
+    redo_policy: RedoPolicy,
+    /// Cards due again within this long come back this session.
+    learn_ahead: Duration,
+    time_up: bool,
+    awaiting_redo_choice: bool,
+    tally: SessionTally,
+    /// Cards buried or suspended this session; they are not shown again.
+    buried: HashSet<String>,
+    edit_note: Option<(String, Instant)>,
+    /// Where the answer to a `T:` card, or a basic card in `--type` mode, is typed.
+    answer_input: Editor,
+    /// `--type`: basic cards take a typed answer too, not just `T:` cards.
+    type_basic_answers: bool,
+    typed_answer: Option<TypedAnswer>,
+    /// Why AI enhancement failed, by card hash.
+    ai_errors: HashMap<String, String>,
+    /// Cards that were waiting on AI enhancement when the session started.
+    ai_total: usize,
+    /// How cards end in the deck files, for re-reading a card after an edit.
+    card_separator: CardSeparator,
+    keymap: Keymap,
+}
+struct TypedAnswer {
+    typed: String,
+    correct: bool,
+}
+struct UndoEntry {
+    card: Card,
+    action: ReviewStatus,
+    previous_performance: Performance,
+    requeued: bool,
+}
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct SessionTally {
+    again: usize,
+    hard: usize,
+    good: usize,
+    easy: usize,
+    requeued: usize,
+    buried: usize,
+    suspended: usize,
+}
+impl SessionTally {
+    fn count_mut(&mut self, action: ReviewStatus) -> &mut usize {
+        match action {
+            ReviewStatus::Again => &mut self.again,
+            ReviewStatus::Hard => &mut self.hard,
+            ReviewStatus::Good => &mut self.good,
+            ReviewStatus::Easy => &mut self.easy,
+        }
+    }
+
+    fn record(&mut self, action: ReviewStatus, requeued: bool) {
+        *self.count_mut(action) += 1;
+        self.requeued += requeued as usize;
+    }
+
+    fn unrecord(&mut self, action: ReviewStatus, requeued: bool) {
+        let count = self.count_mut(action);
+        *count = count.saturating_sub(1);
+        self.requeued = self.requeued.saturating_sub(requeued as usize);
+    }
+
+    fn reviews(&self) -> usize {
+        self.again + self.hard + self.good + self.easy
+    }
+
+    fn passed(&self) -> usize {
+        self.hard + self.good + self.easy
+    }
 }
 struct LastAction {
     action: ReviewStatus,
@@ -99,6 +420,7 @@ impl LastAction {
 
 impl<'a> DrillState<'a> {
     fn new(db: &'a DB, cards: Vec<Card>) -> Self {
+        let ai_total = cards.iter().filter(|card| is_ai_pending(card)).count();
         Self {
             db,
             cards,
@@ -106,8 +428,131 @@ impl<'a> DrillState<'a> {
             current_idx: 0,
             show_answer: false,
             last_action: None,
+            undo: None,
+            last_undo_at: None,
             current_medias: Vec::new(),
+            answer_media_start: 0,
+            choosing_media: false,
+            autoplay_audio: false,
+            audio_played: false,
+            started_at: Instant::now(),
+            time_limit: None,
+            in_redo_pass: false,
+            redo_policy: RedoPolicy::default(),
+            learn_ahead: DEFAULT_LEARN_AHEAD,
+            time_up: false,
+            awaiting_redo_choice: false,
+            tally: SessionTally::default(),
+            buried: HashSet::new(),
+            edit_note: None,
+            answer_input: Editor::blank(),
+            type_basic_answers: false,
+            typed_answer: None,
+            ai_errors: HashMap::new(),
+            ai_total,
+            card_separator: CardSeparator::default(),
+            keymap: Keymap::default(),
+        }
+    }
+
+    fn with_time_limit(mut self, time_limit: Option<Duration>) -> Self {
+        self.time_limit = time_limit;
+        self
+    }
+
+    fn with_card_separator(mut self, separator: CardSeparator) -> Self {
+        self.card_separator = separator;
+        self
+    }
+
+    fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    fn with_redo_policy(mut self, policy: RedoPolicy) -> Self {
+        self.redo_policy = policy;
+        self
+    }
+
+    fn with_learn_ahead(mut self, window: Duration) -> Self {
+        self.learn_ahead = window;
+        self
+    }
+
+    fn with_autoplay_audio(mut self, enabled: bool) -> Self {
+        self.autoplay_audio = enabled;
+        self
+    }
+
+    /// The audio to play now that the answer is showing, at most once per reveal.
+    fn take_autoplay_audio(&mut self) -> Option<&Media> {
+        if !self.show_answer {
+            self.audio_played = false;
+            return None;
+        }
+        if !self.autoplay_audio || self.audio_played {
+            return None;
+        }
+        self.audio_played = true;
+        // the answer's own audio, e.g. a pronunciation, before any on the question
+        let is_audio = |media: &&Media| *media.kind() == MediaKind::Audio;
+        let answer_start = self.answer_media_start.min(self.current_medias.len());
+        self.current_medias[answer_start..]
+            .iter()
+            .find(is_audio)
+            .or_else(|| self.current_medias.iter().find(is_audio))
+    }
+
+    /// The media the open key plays: the only one on screen, or none once
+    /// several have switched the footer to the chooser.
+    fn media_to_open(&mut self) -> Option<&Media> {
+        match self.current_medias.as_slice() {
+            [] => None,
+            [media] => Some(media),
+            _ => {
+                self.choosing_media = true;
+                None
+            }
+        }
+    }
+
+    fn with_typed_answers(mut self, enabled: bool) -> Self {
+        self.type_basic_answers = enabled;
+        self
+    }
+
+    fn time_remaining(&self) -> Option<Duration> {
+        self.time_limit
+            .map(|limit| limit.saturating_sub(self.started_at.elapsed()))
+    }
+
+    fn remaining_cards(&self) -> usize {
+        self.cards.len().saturating_sub(self.current_idx) + self.redo_cards.len()
+    }
+
+    /// The cards not yet reviewed this session and those coming again, in the
+    /// order they would be shown, for saving an interrupted drill.
+    fn unfinished_cards(&self) -> Vec<Card> {
+        let mut seen = HashSet::new();
+        self.cards
+            .iter()
+            .skip(self.current_idx)
+            .chain(&self.redo_cards)
+            .filter(|card| !self.buried.contains(&card.card_hash))
+            .filter(|card| seen.insert(card.card_hash.clone()))
+            .cloned()
+            .collect()
+    }
+
+    /// Drops cards not yet seen this session once the time budget is spent.
+    /// Cards already in the redo queue stay so the user can choose to finish them.
+    fn wind_down(&mut self) {
+        self.time_up = true;
+        if !self.in_redo_pass {
+            self.cards.truncate(self.current_idx);
         }
+        self.awaiting_redo_choice = self.remaining_cards() > 0;
     }
 
     fn current_card(&mut self) -> Option<Card> {
@@ -115,30 +560,148 @@ impl<'a> DrillState<'a> {
             if self.redo_cards.is_empty() {
                 return None;
             }
-            self.cards = std::mem::take(&mut self.redo_cards);
+            let buried = &self.buried;
+            self.cards = std::mem::take(&mut self.redo_cards)
+                .into_iter()
+                .filter(|card| !buried.contains(&card.card_hash))
+                .collect();
             self.current_idx = 0;
+            self.in_redo_pass = true;
         }
         self.cards.get(self.current_idx).cloned()
     }
 
+    /// Takes the current card out of the session without rating it. A buried
+    /// card comes back next session; a suspended one stays out of drills until
+    /// `repeater unsuspend`.
+    async fn set_aside(&mut self, suspend: bool) -> Result<()> {
+        let card = self
+            .current_card()
+            .expect("card should exist when setting it aside");
+        if suspend {
+            self.db.suspend_card(&card).await?;
+            self.tally.suspended += 1;
+        } else {
+            self.tally.buried += 1;
+        }
+        self.cards.remove(self.current_idx);
+        self.redo_cards
+            .retain(|queued| queued.card_hash != card.card_hash);
+        self.buried.insert(card.card_hash);
+        // undoing across a set-aside card could bring it back
+        self.undo = None;
+        self.hide_answer();
+        Ok(())
+    }
+
+    /// Re-reads the file `before` came from after the user edited it and swaps
+    /// the card at the same line into the session. The edited card inherits
+    /// the old card's history. Returns whether the card changed.
+    async fn reload_edited_card(&mut self, before: &Card) -> Result<bool> {
+        let start = before.file_card_range.0;
+        let Some(after) = forward_card_at(&before.file_path, start, self.card_separator)? else {
+            bail!("no card left at line {}", start + 1);
+        };
+        if after.card_hash == before.card_hash {
+            return Ok(false);
+        }
+        self.db
+            .rekey_card(&before.card_hash, &after.card_hash)
+            .await?;
+        self.db.add_card(&after).await?;
+
+        let old_reverse = before.reversed().map(|card| card.card_hash);
+        let new_reverse = after.reversed();
+        for card in self.cards.iter_mut().chain(self.redo_cards.iter_mut()) {
+            if card.card_hash == before.card_hash {
+                *card = after.clone();
+            } else if old_reverse.as_ref() == Some(&card.card_hash)
+                && let Some(reverse) = &new_reverse
+            {
+                *card = reverse.clone();
+            }
+        }
+        // the undo entry still points at the old hash
+        self.undo = None;
+        Ok(true)
+    }
+
     fn reveal_answer(&mut self) {
         self.show_answer = true;
     }
 
+    fn hide_answer(&mut self) {
+        self.show_answer = false;
+        self.typed_answer = None;
+        self.answer_input.clear();
+    }
+
+    /// The answer `card` is checked against when it takes a typed answer:
+    /// always for `T:` cards, and for basic cards in `--type` mode.
+    fn expected_typed_answer<'c>(&self, card: &'c Card) -> Option<&'c str> {
+        match &card.content {
+            CardContent::TypeAnswer { answer, .. } => Some(answer),
+            CardContent::Basic { answer, .. } if self.type_basic_answers => Some(answer),
+            _ => None,
+        }
+    }
+
+    /// Whether the current card is waiting for its answer to be typed.
+    fn awaiting_typed_answer(&self) -> bool {
+        !self.show_answer
+            && self
+                .cards
+                .get(self.current_idx)
+                .and_then(|card| self.expected_typed_answer(card))
+                .is_some()
+    }
+
+    /// Checks the typed answer against the card's and reveals it.
+    fn submit_typed_answer(&mut self) {
+        let Some(answer) = self
+            .cards
+            .get(self.current_idx)
+            .and_then(|card| self.expected_typed_answer(card))
+        else {
+            return;
+        };
+        let typed = self.answer_input.content();
+        let correct = answer_matches(answer, &typed);
+        self.typed_answer = Some(TypedAnswer { typed, correct });
+        self.reveal_answer();
+    }
+
+    /// What `Space`/`Enter` grades once the answer is shown: a wrong typed
+    /// answer fails the card.
+    fn default_grade(&self) -> ReviewStatus {
+        match &self.typed_answer {
+            Some(typed) if !typed.correct => ReviewStatus::Again,
+            _ => ReviewStatus::Good,
+        }
+    }
+
     async fn handle_review(&mut self, action: ReviewStatus) -> Result<()> {
         let current_card = self
             .current_card()
             .expect("card should exist when handling review");
+        let previous_performance = self.db.get_card_performance(&current_card).await?;
         let show_again_duration = self
             .db
             .update_card_performance(&current_card, action, None)
             .await?;
-        if action == ReviewStatus::Fail
-            || show_again_duration
-                < (LEARN_AHEAD_THRESHOLD_MINS.num_minutes() as f64 / MINUTES_PER_DAY)
-        {
+        let requeued = self
+            .redo_policy
+            .requeues(action, show_again_duration, self.learn_ahead);
+        if requeued {
             self.redo_cards.push(current_card.clone());
         }
+        self.tally.record(action, requeued);
+        self.undo = Some(UndoEntry {
+            card: current_card,
+            action,
+            previous_performance,
+            requeued,
+        });
 
         self.last_action = Some(LastAction {
             action,
@@ -146,7 +709,53 @@ impl<'a> DrillState<'a> {
             last_reviewed_at: std::time::Instant::now(),
         });
         self.current_idx += 1;
-        self.show_answer = false;
+        self.hide_answer();
+        if !self.time_up && self.time_remaining() == Some(Duration::ZERO) {
+            self.wind_down();
+        }
+        Ok(())
+    }
+
+    async fn undo_last_review(&mut self) -> Result<()> {
+        let Some(undo) = self.undo.take() else {
+            return Ok(());
+        };
+        self.db
+            .restore_card_performance(&undo.card, &undo.previous_performance)
+            .await?;
+
+        if undo.requeued {
+            if let Some(pos) = self
+                .redo_cards
+                .iter()
+                .rposition(|card| card.card_hash == undo.card.card_hash)
+            {
+                self.redo_cards.remove(pos);
+            } else if let Some(pos) = self
+                .cards
+                .iter()
+                .rposition(|card| card.card_hash == undo.card.card_hash)
+                && pos >= self.current_idx
+            {
+                self.cards.remove(pos);
+            }
+        }
+
+        // The redo queue may already have been swapped in, in which case the
+        // reviewed card is no longer right behind the cursor.
+        let previous_idx = self.current_idx.checked_sub(1);
+        match previous_idx {
+            Some(idx) if self.cards[idx].card_hash == undo.card.card_hash => {
+                self.current_idx = idx;
+            }
+            _ => self.cards.insert(self.current_idx, undo.card),
+        }
+
+        self.hide_answer();
+        self.last_action = None;
+        self.last_undo_at = Some(Instant::now());
+        self.tally.unrecord(undo.action, undo.requeued);
+        self.awaiting_redo_choice = false;
         Ok(())
     }
 
@@ -155,53 +764,61 @@ impl<'a> DrillState<'a> {
     }
 
     fn apply_ai_update(&mut self, update: AiUpdate) {
+        let status = match &update.error {
+            Some(_) => AIStatus::AiFailed,
+            None => AIStatus::AiEnhanced,
+        };
         for card in self.cards.iter_mut().chain(self.redo_cards.iter_mut()) {
             if card.card_hash == update.card_hash {
                 *card = update.card.clone();
-                card.ai_status = AIStatus::AiEnhanced;
+                card.ai_status = status.clone();
             }
         }
+        if let Some(error) = update.error {
+            self.ai_errors.insert(update.card.card_hash, error);
+        }
     }
 
     fn current_ai_pending(&self) -> bool {
-        matches!(
-            self.cards
-                .get(self.current_idx)
-                .map(|card| card.ai_status.clone()),
-            Some(AIStatus::ClozeNeedDeletion | AIStatus::QuestionNeedRephrasing)
-        )
+        self.cards.get(self.current_idx).is_some_and(is_ai_pending)
+    }
+
+    /// How many of the cards needing AI enhancement are done, out of how
+    /// many, while any are still waiting.
+    fn ai_progress(&self) -> Option<(usize, usize)> {
+        let pending = self.cards.iter().filter(|card| is_ai_pending(card)).count();
+        (pending > 0).then(|| (self.ai_total.saturating_sub(pending), self.ai_total))
     }
 }
 
+fn is_ai_pending(card: &Card) -> bool {
+    matches!(
+        card.ai_status,
+        AIStatus::ClozeNeedDeletion | AIStatus::QuestionNeedRephrasing
+    )
+}
+
 #[derive(Clone, Debug)]
 struct AiUpdate {
     card_hash: String,
+    /// The enhanced card, or the original one when `error` is set.
     card: Card,
+    error: Option<String>,
 }
 
 async fn start_drill_session(
-    db: &DB,
-    cards: Vec<Card>,
+    state: &mut DrillState<'_>,
     drill_preprocessor: DrillPreprocessor,
 ) -> Result<()> {
-    enable_raw_mode().context("failed to enable raw mode")?;
-    let mut stdout = io::stdout();
-    execute!(
-        stdout,
-        EnterAlternateScreen,
-        PushKeyboardEnhancementFlags(
-            KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
-                | KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
-        )
-    )
-    .context("failed to configure terminal")?;
-    let backend = CrosstermBackend::new(stdout);
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend).context("failed to start terminal")?;
-    terminal.hide_cursor().context("failed to hide cursor")?;
+    enter_drill_screen(&mut terminal)?;
+    let mut inline_images = InlineImages::detect();
 
+    let keymap = state.keymap.clone();
     let (ai_updates_tx, mut ai_updates_rx) = mpsc::unbounded_channel();
     let mut ai_preprocess_handle = if drill_preprocessor.llm_required() {
-        let ai_cards = cards.clone();
+        let ai_cards = state.cards.clone();
         Some(tokio::spawn(async move {
             preprocess_cards_in_order(drill_preprocessor, ai_cards, ai_updates_tx).await
         }))
@@ -209,8 +826,6 @@ async fn start_drill_session(
         None
     };
 
-    let mut state = DrillState::new(db, cards);
-
     let loop_result: Result<()> = async {
         loop {
             if state.is_complete() {
@@ -224,25 +839,72 @@ async fn start_drill_session(
             if let Some(handle) = &mut ai_preprocess_handle
                 && handle.is_finished()
             {
-                let result = handle
+                handle
                     .await
                     .map_err(|err| anyhow!("AI preprocessing task failed: {err}"))?;
-                if let Err(err) = result {
-                    break Err(err);
-                }
                 ai_preprocess_handle = None;
             }
 
+            if state.awaiting_redo_choice {
+                terminal
+                    .draw(|frame| render_time_up(frame, state))
+                    .context("failed to render frame")?;
+                if let Some(images) = &mut inline_images {
+                    images.sync(&mut terminal, None)?;
+                }
+                if event::poll(Duration::from_millis(16))?
+                    && let Event::Key(key) = event::read()?
+                    && key.kind == KeyEventKind::Press
+                {
+                    if is_ctrl_c(&key) || keymap.matches(DrillAction::Exit, &key) {
+                        break Ok(());
+                    }
+                    if keymap.matches(DrillAction::Reveal, &key) {
+                        state.awaiting_redo_choice = false;
+                    }
+                }
+                continue;
+            }
+
+            let mut wanted_image = None;
             terminal
                 .draw(|frame| {
                     let card = state
                         .current_card()
                         .expect("card should exist while session is active");
+                    let ai_pending = state.current_ai_pending();
+                    let content = if ai_pending {
+                        "Enhancing this card with AI...\n\nPlease wait.".to_string()
+                    } else {
+                        format_card_text(&card, state.show_answer)
+                    };
+                    let markdown = with_break_opportunities(render_markdown(&content));
+                    (state.current_medias, state.answer_media_start) = if ai_pending {
+                        (Vec::new(), 0)
+                    } else {
+                        card_media(&card, state.show_answer)
+                    };
+                    let inline_image = inline_images.as_ref().and_then(|images| {
+                        state.current_medias.iter().find_map(|media| {
+                            media.local_path().filter(|path| {
+                                *media.kind() == MediaKind::Image && images.supports(path)
+                            })
+                        })
+                    });
+
                     let area = frame.area();
                     frame.render_widget(Theme::backdrop(), area);
+                    let answer_height = match state.expected_typed_answer(&card) {
+                        Some(_) => 4,
+                        None => 0,
+                    };
                     let chunks = Layout::default()
                         .direction(Direction::Vertical)
-                        .constraints([Constraint::Min(5), Constraint::Length(5)])
+                        .constraints([
+                            Constraint::Min(5),
+                            Constraint::Length(answer_height),
+                            Constraint::Length(5),
+                        ])
                         .split(area);
 
                     let mut header_vec = vec![
@@ -256,32 +918,92 @@ async fn start_drill_session(
                         Theme::bullet(),
                         Theme::span(card.file_path.display().to_string()),
                     ];
-                    if card.ai_status == AIStatus::AiEnhanced {
+                    if let Some(remaining) = state.time_remaining() {
+                        header_vec.push(Theme::bullet());
+                        if state.time_up {
+                            header_vec.push(Span::styled("Time's up", Theme::emphasis()));
+                        } else {
+                            header_vec
+                                .push(Theme::span(format!("{} left", format_duration(remaining))));
+                        }
+                    }
+                    match card.ai_status {
+                        AIStatus::AiEnhanced => {
+                            header_vec.push(Theme::bullet());
+                            header_vec.push(Theme::key_chip("AI enhanced"));
+                        }
+                        AIStatus::AiFailed => {
+                            header_vec.push(Theme::bullet());
+                            header_vec.push(Span::styled("AI failed", Theme::emphasis()));
+                        }
+                        AIStatus::ClozeSuggested => {
+                            header_vec.push(Theme::bullet());
+                            header_vec.push(Theme::key_chip("Suggested blank"));
+                        }
+                        _ => {}
+                    }
+                    if let Some((done, total)) = state.ai_progress() {
                         header_vec.push(Theme::bullet());
-                        header_vec.push(Theme::key_chip("AI enhanced"));
+                        header_vec.push(Theme::span(format!("AI enhancing: {done}/{total} done")));
                     }
                     let header_line = Line::from(header_vec);
 
-                    let ai_pending = state.current_ai_pending();
-                    let content = if ai_pending {
-                        "Enhancing this card with AI...\n\nPlease wait.".to_string()
-                    } else {
-                        format_card_text(&card, state.show_answer)
+                    let card_area = match inline_image {
+                        Some(image_path) => {
+                            let image_height = (chunks[0].height / 2).min(18);
+                            let card_chunks = Layout::default()
+                                .direction(Direction::Vertical)
+                                .constraints([Constraint::Min(5), Constraint::Length(image_height)])
+                                .split(chunks[0]);
+                            let file_name = image_path
+                                .file_name()
+                                .map(|name| name.to_string_lossy().to_string())
+                                .unwrap_or_else(|| "Image".to_string());
+                            let image_panel =
+                                Theme::panel_with_line(Theme::section_header(file_name));
+                            wanted_image =
+                                Some((image_path.to_path_buf(), image_panel.inner(card_chunks[1])));
+                            frame.render_widget(image_panel, card_chunks[1]);
+                            card_chunks[0]
+                        }
+                        None => chunks[0],
                     };
-                    let markdown = render_markdown(&content);
-                    state.current_medias = extract_media(&content, card.file_path.parent());
 
                     let card_widget = Paragraph::new(markdown)
                         .block(Theme::panel_with_line(header_line))
                         .wrap(Wrap { trim: false });
-                    frame.render_widget(card_widget, chunks[0]);
+                    frame.render_widget(card_widget, card_area);
+
+                    if answer_height > 0 {
+                        let answer_widget = Paragraph::new(typed_answer_lines(state, &card))
+                            .block(Theme::panel_with_line(Theme::section_header("Your answer")))
+                            .wrap(Wrap { trim: false });
+                        frame.render_widget(answer_widget, chunks[1]);
+                        if state.awaiting_typed_answer() && !ai_pending {
+                            let (_, cursor_col) = state.answer_input.cursor();
+                            let max_x = chunks[1].width.saturating_sub(2);
+                            frame.set_cursor_position((
+                                chunks[1].x + 1 + (cursor_col as u16).min(max_x),
+                                chunks[1].y + 1,
+                            ));
+                        }
+                    }
 
-                    let instructions = instructions_text(&state);
+                    let instructions = instructions_text(state);
                     let footer = Paragraph::new(instructions)
                         .block(Theme::panel_with_line(Theme::section_header("Controls")));
-                    frame.render_widget(footer, chunks[1]);
+                    frame.render_widget(footer, chunks[2]);
                 })
                 .context("failed to render frame")?;
+            if let Some(images) = &mut inline_images {
+                images.sync(&mut terminal, wanted_image)?;
+            }
+            // the media were just read from the revealed card, answer included
+            if let Some(audio) = state.take_autoplay_audio()
+                && let Err(err) = audio.play()
+            {
+                state.edit_note = Some((format!("Couldn't play audio: {err}"), Instant::now()));
+            }
 
             if event::poll(Duration::from_millis(16))?
                 && let Event::Key(key) = event::read()?
@@ -290,172 +1012,801 @@ async fn start_drill_session(
                     continue;
                 }
 
-                if key.code == KeyCode::Esc
-                    || (key.code == KeyCode::Char('c')
-                        && key.modifiers.contains(KeyModifiers::CONTROL))
-                {
-                    break Ok(());
+                // any key other than a listed digit, Esc included, closes the list
+                if state.choosing_media {
+                    state.choosing_media = false;
+                    if let Some(idx) = media_choice(key.code, state.current_medias.len())
+                        && let Err(err) = state.current_medias[idx].play()
+                    {
+                        state.edit_note =
+                            Some((format!("Couldn't open media: {err}"), Instant::now()));
+                    }
+                    continue;
                 }
+
                 let ai_pending = state.current_ai_pending();
-                match key.code {
-                    KeyCode::Char(' ') | KeyCode::Enter if !ai_pending => {
-                        if !state.show_answer {
-                            state.reveal_answer();
-                        } else {
-                            state.handle_review(ReviewStatus::Pass).await?;
+                let typing = state.awaiting_typed_answer() && !ai_pending;
+                // while an answer is typed, letters bound to exit are just letters
+                let exit_key = keymap.matches(DrillAction::Exit, &key)
+                    && !(typing && matches!(key.code, KeyCode::Char(_)));
+                if is_ctrl_c(&key) || exit_key {
+                    break Ok(());
+                }
+                if typing {
+                    let input = &mut state.answer_input;
+                    match key.code {
+                        KeyCode::Enter => state.submit_typed_answer(),
+                        KeyCode::Char(ch) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            input.insert_char(ch);
                         }
+                        KeyCode::Backspace => input.backspace(),
+                        KeyCode::Delete => input.delete(),
+                        KeyCode::Left => input.move_left(),
+                        KeyCode::Right => input.move_right(),
+                        KeyCode::Home => input.move_home(),
+                        KeyCode::End => input.move_end(),
+                        _ => {}
                     }
-                    KeyCode::Char('F') | KeyCode::Char('f') if state.show_answer && !ai_pending => {
-                        state.handle_review(ReviewStatus::Fail).await?;
-                    }
-                    KeyCode::Char('O') | KeyCode::Char('o')
-                        if !ai_pending
-                            && !state.show_answer
-                            && !state.current_medias.is_empty() =>
+                    continue;
+                }
+                let pressed = |action| keymap.matches(action, &key);
+                if !ai_pending && !state.show_answer && pressed(DrillAction::Reveal) {
+                    state.reveal_answer();
+                } else if !ai_pending && state.show_answer && pressed(DrillAction::Pass) {
+                    state.handle_review(state.default_grade()).await?;
+                } else if state.undo.is_some() && pressed(DrillAction::Undo) {
+                    state.undo_last_review().await?;
+                } else if !ai_pending
+                    && !state.current_medias.is_empty()
+                    && pressed(DrillAction::Open)
+                {
+                    if let Some(media) = state.media_to_open()
+                        && let Err(err) = media.play()
                     {
-                        state.current_medias[0].play()?;
+                        state.edit_note =
+                            Some((format!("Couldn't open media: {err}"), Instant::now()));
                     }
+                } else if !ai_pending && pressed(DrillAction::Bury) {
+                    state.set_aside(false).await?;
+                } else if !ai_pending && pressed(DrillAction::Suspend) {
+                    state.set_aside(true).await?;
+                } else if !ai_pending && pressed(DrillAction::Edit) {
+                    let card = state
+                        .current_card()
+                        .expect("card should exist while session is active");
+                    let (start, _) = card.file_card_range;
+                    let before = forward_card_at(&card.file_path, start, state.card_separator)
+                        .ok()
+                        .flatten();
+
+                    teardown_terminal(&mut terminal)?;
+                    let edited = open_in_editor(&card.file_path, start + 1);
+                    enter_drill_screen(&mut terminal)?;
+                    if let Some(images) = &mut inline_images {
+                        images.forget();
+                    }
+                    edited?;
 
-                    _ => {}
+                    let note = match before {
+                        Some(before) => match state.reload_edited_card(&before).await {
+                            Ok(true) => "Card updated".to_string(),
+                            Ok(false) => "Card unchanged".to_string(),
+                            Err(err) => format!("Kept the old card: {err}"),
+                        },
+                        None => "Card file changed since the drill started".to_string(),
+                    };
+                    state.edit_note = Some((note, Instant::now()));
+                } else if state.show_answer
+                    && !ai_pending
+                    && let Some(grade) = grade_for_key(&keymap, &key)
+                {
+                    state.handle_review(grade).await?;
                 }
             }
         }
     }
     .await;
 
+    if let Some(images) = &mut inline_images {
+        images.sync(&mut terminal, None)?;
+    }
+
+    let elapsed = state.started_at.elapsed();
+    let summary_result = match &loop_result {
+        Ok(()) if state.tally.reviews() > 0 => {
+            show_session_summary(&mut terminal, &state.tally, elapsed, state.time_up)
+        }
+        _ => Ok(()),
+    };
+
     teardown_terminal(&mut terminal)?;
 
-    loop_result
+    loop_result.and(summary_result)
 }
 
-fn teardown_terminal(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
-    disable_raw_mode().context("failed to disable raw mode")?;
-    execute!(
-        terminal.backend_mut(),
-        PopKeyboardEnhancementFlags,
-        LeaveAlternateScreen
-    )
-    .context("failed to restore terminal")?;
-    terminal.show_cursor().context("failed to show cursor")?;
-    Ok(())
+/// Most media `O` lists, one per digit key.
+const MAX_MEDIA_CHOICES: usize = 9;
+
+/// Which of `count` media the digit key `code` picks, counting from 1.
+fn media_choice(code: KeyCode, count: usize) -> Option<usize> {
+    let KeyCode::Char(ch) = code else {
+        return None;
+    };
+    let idx = (ch.to_digit(10)? as usize).checked_sub(1)?;
+    (idx < count.min(MAX_MEDIA_CHOICES)).then_some(idx)
 }
 
-fn instructions_text(state: &DrillState<'_>) -> Vec<Line<'static>> {
-    let mut lines = Vec::new();
-    if state.current_ai_pending() {
-        lines.push(Line::from(vec![
-            Theme::span("Enhancing card with AI"),
-            Theme::bullet(),
-            Theme::key_chip("Esc"),
-            Theme::span(" / "),
-            Theme::key_chip("Ctrl+C"),
-            Theme::span(" exit"),
-        ]));
-    } else if state.show_answer {
-        lines.push(Line::from(vec![
-            Theme::key_chip("Space"),
-            Theme::span(" or "),
-            Theme::key_chip("Enter"),
-            Span::styled(" Pass", Theme::success()),
-            Theme::bullet(),
-            Theme::key_chip("F"),
-            Span::styled(" Fail", Theme::danger()),
-            Theme::bullet(),
-            Theme::key_chip("Esc"),
-            Theme::span(" / "),
-            Theme::key_chip("Ctrl+C"),
-            Theme::span(" exit"),
-        ]));
+/// The media's label, with the file name when the label alone is vague.
+fn media_choice_label(media: &Media) -> String {
+    let file_name = media.file_name().unwrap_or_default();
+    if file_name.is_empty() || media.label() == file_name {
+        media.label().to_string()
     } else {
-        let mut line = vec![
-            Theme::key_chip("Space"),
-            Theme::span(" or "),
-            Theme::key_chip("Enter"),
-            Theme::span(" show answer"),
-            Theme::bullet(),
-            Theme::key_chip("Esc"),
-            Theme::span(" / "),
-            Theme::key_chip("Ctrl+C"),
-            Theme::span(" exit"),
-        ];
-        if !state.current_medias.is_empty() {
-            let num_media = state.current_medias.len();
-            line.push(Theme::bullet());
-            line.push(Theme::span(format!(
-                "{} found in card ",
-                pluralize("media file", num_media)
-            )));
-            line.push(Theme::key_chip("O"));
-            line.push(Theme::span(" open"));
+        format!("{} ({file_name})", media.label())
+    }
+}
+
+fn is_ctrl_c(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// The grade the keymap binds `key` to in the TUI.
+fn grade_for_key(keymap: &Keymap, key: &KeyEvent) -> Option<ReviewStatus> {
+    [
+        (DrillAction::Fail, ReviewStatus::Again),
+        (DrillAction::Hard, ReviewStatus::Hard),
+        (DrillAction::Good, ReviewStatus::Good),
+        (DrillAction::Easy, ReviewStatus::Easy),
+    ]
+    .into_iter()
+    .find(|(action, _)| keymap.matches(*action, key))
+    .map(|(_, grade)| grade)
+}
+
+/// Maps a grade key to its review in `--plain` mode, where `F` and `1`–`4`
+/// grade and an empty line passes.
+fn review_for_key(ch: char) -> Option<ReviewStatus> {
+    match ch {
+        'F' | 'f' | '1' => Some(ReviewStatus::Again),
+        '2' => Some(ReviewStatus::Hard),
+        '3' => Some(ReviewStatus::Good),
+        '4' => Some(ReviewStatus::Easy),
+        _ => None,
+    }
+}
+
+enum PlainInput {
+    Enter,
+    Key(char),
+    Quit,
+}
+
+fn read_plain_input<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    prompt: &str,
+) -> Result<PlainInput> {
+    write!(output, "{} ", Palette::dim(prompt))?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        writeln!(output)?;
+        return Ok(PlainInput::Quit);
+    }
+    Ok(match line.trim().chars().next() {
+        None => PlainInput::Enter,
+        Some('q' | 'Q') => PlainInput::Quit,
+        Some(ch) => PlainInput::Key(ch),
+    })
+}
+
+/// Reads a whole line, or `None` once the input ends.
+fn read_plain_line<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    prompt: &str,
+) -> Result<Option<String>> {
+    write!(output, "{} ", Palette::dim(prompt))?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        writeln!(output)?;
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+/// Line-based drill over any reader/writer pair, for terminals where the
+/// alternate-screen TUI is awkward. Media playback and AI preprocessing are off.
+async fn plain_drill_loop<R: BufRead, W: Write>(
+    state: &mut DrillState<'_>,
+    mut input: R,
+    output: &mut W,
+) -> Result<()> {
+    loop {
+        if state.is_complete() {
+            break;
+        }
+        if state.awaiting_redo_choice {
+            writeln!(
+                output,
+                "\n{} Reviewed {} in {}. {} coming again.",
+                Palette::paint(Palette::WARNING, "Time's up."),
+                pluralize("card", state.tally.reviews()),
+                format_duration(state.started_at.elapsed()),
+                pluralize("card", state.remaining_cards()),
+            )?;
+            match read_plain_input(
+                &mut input,
+                output,
+                "[Enter] finish the redo queue, [q] end:",
+            )? {
+                PlainInput::Quit => break,
+                _ => state.awaiting_redo_choice = false,
+            }
+            continue;
+        }
+
+        let card = state
+            .current_card()
+            .expect("card should exist while session is active");
+        let mut header = format!(
+            "Card {}/{} · {} coming again · {}",
+            state.current_idx + 1,
+            state.cards.len(),
+            state.redo_cards.len(),
+            card.file_path.display()
+        );
+        if let Some(remaining) = state.time_remaining() {
+            header.push_str(&format!(" · {} left", format_duration(remaining)));
+        }
+        writeln!(output, "\n{}", Palette::paint(Palette::ACCENT, header))?;
+        writeln!(output, "{}", format_card_text(&card, false).trim_end())?;
+
+        if let Some(answer) = state.expected_typed_answer(&card) {
+            // the whole line is the answer, so `q` can't quit here
+            let Some(typed) = read_plain_line(&mut input, output, "Type your answer:")? else {
+                break;
+            };
+            let correct = answer_matches(answer, &typed);
+            let verdict = if correct {
+                Palette::paint(Palette::SUCCESS, "Correct")
+            } else {
+                Palette::paint(Palette::DANGER, "Incorrect")
+            };
+            writeln!(output, "{verdict}")?;
+            state.reveal_answer();
+            state.typed_answer = Some(TypedAnswer { typed, correct });
+        } else {
+            match read_plain_input(
+                &mut input,
+                output,
+                "[Enter] show answer, [b] bury, [s] suspend, [u] undo, [q] quit:",
+            )? {
+                PlainInput::Quit => break,
+                PlainInput::Key('u' | 'U') if state.undo.is_some() => {
+                    state.undo_last_review().await?;
+                    writeln!(
+                        output,
+                        "{}",
+                        Palette::paint(Palette::WARNING, "Undid last review")
+                    )?;
+                    continue;
+                }
+                PlainInput::Key(ch @ ('b' | 'B' | 's' | 'S')) => {
+                    let suspend = ch.eq_ignore_ascii_case(&'s');
+                    state.set_aside(suspend).await?;
+                    let message = if suspend {
+                        "Suspended until `repeater unsuspend`"
+                    } else {
+                        "Buried until next session"
+                    };
+                    writeln!(output, "{}", Palette::paint(Palette::WARNING, message))?;
+                    continue;
+                }
+                _ => {}
+            }
+            state.reveal_answer();
+        }
+        let revealed = match &card.content {
+            CardContent::Basic { answer, .. } | CardContent::TypeAnswer { answer, .. } => {
+                answer.clone()
+            }
+            CardContent::Cloze { .. } => format_card_text(&card, true),
+        };
+        writeln!(output, "{}", revealed.trim_end())?;
+
+        let grade_prompt = match state.default_grade() {
+            ReviewStatus::Again => {
+                "[Enter] fail, [f/1] again, [2] hard, [3] good, [4] easy, [q] quit:"
+            }
+            _ => "[Enter] pass, [f/1] again, [2] hard, [3] good, [4] easy, [q] quit:",
+        };
+        let action = loop {
+            match read_plain_input(&mut input, output, grade_prompt)? {
+                PlainInput::Quit => break None,
+                PlainInput::Enter => break Some(state.default_grade()),
+                PlainInput::Key(ch) => {
+                    if let Some(action) = review_for_key(ch) {
+                        break Some(action);
+                    }
+                }
+            }
+        };
+        let Some(action) = action else {
+            break;
+        };
+        state.handle_review(action).await?;
+        if let Some(last_action) = &state.last_action {
+            let color = match action {
+                ReviewStatus::Again => Palette::DANGER,
+                ReviewStatus::Hard => Palette::WARNING,
+                ReviewStatus::Good | ReviewStatus::Easy => Palette::SUCCESS,
+            };
+            writeln!(
+                output,
+                "{}",
+                Palette::paint(color, last_action.print().trim())
+            )?;
+        }
+    }
+
+    if state.tally.reviews() > 0 {
+        let title = if state.time_up {
+            "Time's up"
+        } else {
+            "Session summary"
+        };
+        writeln!(output, "\n{}", Palette::paint(Palette::ACCENT, title))?;
+        for line in summary_lines(&state.tally, state.started_at.elapsed()) {
+            writeln!(output, "{}", line_to_plain(&line))?;
+        }
+    }
+    Ok(())
+}
+
+fn line_to_plain(line: &Line<'_>) -> String {
+    line.spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
+
+fn show_session_summary(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    tally: &SessionTally,
+    elapsed: Duration,
+    time_up: bool,
+) -> Result<()> {
+    let title = if time_up {
+        "Time's up"
+    } else {
+        "Session summary"
+    };
+    loop {
+        terminal
+            .draw(|frame| {
+                let area = frame.area();
+                frame.render_widget(Theme::backdrop(), area);
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(5), Constraint::Length(3)])
+                    .split(area);
+
+                let panel = Paragraph::new(summary_lines(tally, elapsed))
+                    .block(Theme::panel_with_line(Theme::section_header(title)))
+                    .wrap(Wrap { trim: false });
+                frame.render_widget(panel, chunks[0]);
+
+                let footer = Paragraph::new(Line::from(vec![Theme::span("Press any key to exit")]))
+                    .block(Theme::panel_with_line(Theme::section_header("Controls")));
+                frame.render_widget(footer, chunks[1]);
+            })
+            .context("failed to render frame")?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            return Ok(());
+        }
+    }
+}
+
+fn summary_lines(tally: &SessionTally, elapsed: Duration) -> Vec<Line<'static>> {
+    let reviews = tally.reviews();
+    let average = elapsed.checked_div(reviews as u32).unwrap_or_default();
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Theme::label_span(format!("{label:<18}")),
+            Theme::span(value),
+        ])
+    };
+    let mut lines = vec![
+        row("Cards reviewed", reviews.to_string()),
+        Line::from(vec![
+            Theme::label_span(format!("{:<18}", "Pass / Fail")),
+            Span::styled(tally.passed().to_string(), Theme::success()),
+            Theme::span(" / "),
+            Span::styled(tally.again.to_string(), Theme::danger()),
+        ]),
+        row(
+            "Grades",
+            format!(
+                "Again {} · Hard {} · Good {} · Easy {}",
+                tally.again, tally.hard, tally.good, tally.easy
+            ),
+        ),
+        row("Came back again", tally.requeued.to_string()),
+        row("Total time", format_duration(elapsed)),
+        row("Average per card", format!("{:.1}s", average.as_secs_f64())),
+    ];
+    if tally.buried + tally.suspended > 0 {
+        lines.push(row(
+            "Set aside",
+            format!("Buried {} · Suspended {}", tally.buried, tally.suspended),
+        ));
+    }
+    lines
+}
+
+fn render_time_up(frame: &mut ratatui::Frame<'_>, state: &DrillState<'_>) {
+    let area = frame.area();
+    frame.render_widget(Theme::backdrop(), area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(5)])
+        .split(area);
+
+    let body = vec![
+        Line::from(vec![
+            Theme::span("Reviewed "),
+            Theme::label_span(pluralize("card", state.tally.reviews())),
+            Theme::span(" in "),
+            Theme::label_span(format_duration(state.started_at.elapsed())),
+            Theme::span("."),
+        ]),
+        Line::from(""),
+        Line::from(Theme::span(format!(
+            "{} coming again from this session.",
+            pluralize("card", state.remaining_cards())
+        ))),
+    ];
+    let panel = Paragraph::new(body)
+        .block(Theme::panel_with_line(Theme::section_header("Time's up")))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(panel, chunks[0]);
+
+    let mut controls = key_chips(&state.keymap, DrillAction::Reveal, " or ");
+    controls.push(Theme::span(" finish the redo queue"));
+    controls.push(Theme::bullet());
+    controls.extend(key_chips(&state.keymap, DrillAction::Exit, " / "));
+    controls.push(Theme::span(" end session"));
+    let footer = Paragraph::new(Line::from(controls))
+        .block(Theme::panel_with_line(Theme::section_header("Controls")));
+    frame.render_widget(footer, chunks[1]);
+}
+
+fn enter_drill_screen(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
+    enable_raw_mode().context("failed to enable raw mode")?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        PushKeyboardEnhancementFlags(
+            KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+                | KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+        )
+    )
+    .context("failed to configure terminal")?;
+    terminal.clear().context("failed to clear terminal")?;
+    terminal.hide_cursor().context("failed to hide cursor")?;
+    Ok(())
+}
+
+/// Runs `$VISUAL` or `$EDITOR` (falling back to `vi`) on `path`, opened at the
+/// 1-based `line`, and waits for it to exit.
+fn open_in_editor(path: &Path, line: usize) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().expect("editor command is not empty");
+    let status = Command::new(program)
+        .args(words)
+        .arg(format!("+{line}"))
+        .arg(path)
+        .status()
+        .with_context(|| format!("failed to launch editor `{editor}`"))?;
+    if !status.success() {
+        bail!("editor `{editor}` exited with {status}");
+    }
+    Ok(())
+}
+
+fn teardown_terminal(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
+    disable_raw_mode().context("failed to disable raw mode")?;
+    execute!(
+        terminal.backend_mut(),
+        PopKeyboardEnhancementFlags,
+        LeaveAlternateScreen
+    )
+    .context("failed to restore terminal")?;
+    terminal.show_cursor().context("failed to show cursor")?;
+    Ok(())
+}
+
+/// Chips for the keys bound to `action`, e.g. `Space or Enter`.
+fn key_chips(keymap: &Keymap, action: DrillAction, separator: &'static str) -> Vec<Span<'static>> {
+    key_label_chips(keymap.labels(action), separator)
+}
+
+fn key_label_chips(labels: Vec<String>, separator: &'static str) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    for (idx, label) in labels.into_iter().enumerate() {
+        if idx > 0 {
+            spans.push(Theme::span(separator));
         }
+        spans.push(Theme::key_chip(label));
+    }
+    spans
+}
+
+/// The keys that end the session, leaving out letters while an answer is typed.
+fn exit_chips(keymap: &Keymap, typing: bool) -> Vec<Span<'static>> {
+    let labels = keymap
+        .keys(DrillAction::Exit)
+        .iter()
+        .zip(keymap.labels(DrillAction::Exit))
+        .filter(|(key, _)| !(typing && matches!(key, KeyCode::Char(_))))
+        .map(|(_, label)| label)
+        .chain(std::iter::once("Ctrl+C".to_string()))
+        .collect();
+    let mut spans = key_label_chips(labels, " / ");
+    spans.push(Theme::span(" exit"));
+    spans
+}
+
+/// The "media files found" hint and its open keys, when the card shows any.
+fn open_media_chips(state: &DrillState<'_>) -> Vec<Span<'static>> {
+    let num_media = state.current_medias.len();
+    if num_media == 0 {
+        return Vec::new();
+    }
+    let mut spans = vec![
+        Theme::bullet(),
+        Theme::span(format!(
+            "{} found in card ",
+            pluralize("media file", num_media)
+        )),
+    ];
+    spans.extend(key_chips(&state.keymap, DrillAction::Open, " or "));
+    spans.push(Theme::span(" open"));
+    spans
+}
+
+fn instructions_text(state: &DrillState<'_>) -> Vec<Line<'static>> {
+    let keymap = &state.keymap;
+    let mut lines = Vec::new();
+    if state.current_ai_pending() {
+        let mut line = vec![Theme::span("Enhancing card with AI"), Theme::bullet()];
+        line.extend(exit_chips(keymap, false));
+        lines.push(Line::from(line));
+    } else if state.choosing_media {
+        let mut line = vec![Theme::span("Open which media?")];
+        for (idx, media) in state
+            .current_medias
+            .iter()
+            .take(MAX_MEDIA_CHOICES)
+            .enumerate()
+        {
+            line.push(Theme::bullet());
+            line.push(Theme::key_chip(format!("{}", idx + 1)));
+            line.push(Theme::span(format!(" {}", media_choice_label(media))));
+        }
+        line.push(Theme::bullet());
+        line.push(Theme::span("any other key cancels"));
+        lines.push(Line::from(line));
+    } else if state.awaiting_typed_answer() {
+        let mut line = vec![
+            Theme::span("Type your answer"),
+            Theme::bullet(),
+            Theme::key_chip("Enter"),
+            Theme::span(" check"),
+            Theme::bullet(),
+        ];
+        line.extend(exit_chips(keymap, true));
         lines.push(Line::from(line));
+    } else if state.show_answer {
+        let default_grade = match state.default_grade() {
+            ReviewStatus::Again => Span::styled(" Fail", Theme::danger()),
+            _ => Span::styled(" Pass", Theme::success()),
+        };
+        let mut line = key_chips(keymap, DrillAction::Pass, " or ");
+        line.push(default_grade);
+        for (action, label, style) in [
+            (DrillAction::Fail, " Fail", Theme::danger()),
+            (DrillAction::Hard, " Hard", Theme::emphasis()),
+            (DrillAction::Good, " Good", Theme::success()),
+            (DrillAction::Easy, " Easy", Theme::success()),
+        ] {
+            line.push(Theme::bullet());
+            line.extend(key_chips(keymap, action, " or "));
+            line.push(Span::styled(label, style));
+        }
+        line.push(Theme::bullet());
+        line.extend(exit_chips(keymap, false));
+        line.extend(open_media_chips(state));
+        lines.push(Line::from(line));
+    } else {
+        let mut line = key_chips(keymap, DrillAction::Reveal, " or ");
+        line.push(Theme::span(" show answer"));
+        for (action, label) in [
+            (DrillAction::Bury, " bury"),
+            (DrillAction::Suspend, " suspend"),
+            (DrillAction::Edit, " edit"),
+        ] {
+            line.push(Theme::bullet());
+            line.extend(key_chips(keymap, action, " or "));
+            line.push(Theme::span(label));
+        }
+        line.push(Theme::bullet());
+        line.extend(exit_chips(keymap, false));
+        line.extend(open_media_chips(state));
+        lines.push(Line::from(line));
+    }
+
+    if let Some(card) = state.cards.get(state.current_idx)
+        && card.ai_status == AIStatus::AiFailed
+        && let Some(error) = state.ai_errors.get(&card.card_hash)
+    {
+        lines.push(Line::from(vec![Theme::span(format!(
+            "Showing the card as written. AI enhancement failed: {}",
+            error.lines().next().unwrap_or_default()
+        ))]));
     }
 
-    if let Some(action) = &state.last_action
+    if let Some((note, edited_at)) = &state.edit_note
+        && edited_at.elapsed().as_secs_f64() < FLASH_SECS
+    {
+        lines.push(Line::from(vec![Span::styled(
+            note.clone(),
+            Theme::emphasis(),
+        )]));
+    } else if let Some(undone_at) = state.last_undo_at
+        && undone_at.elapsed().as_secs_f64() < FLASH_SECS
+    {
+        lines.push(Line::from(vec![Span::styled(
+            "Undid last review",
+            Theme::emphasis(),
+        )]));
+    } else if let Some(action) = &state.last_action
         && action.last_reviewed_at.elapsed().as_secs_f64() < FLASH_SECS
     {
         let style = match action.action {
-            ReviewStatus::Pass => Theme::success(),
-            ReviewStatus::Fail => Theme::danger(),
+            ReviewStatus::Again => Theme::danger(),
+            ReviewStatus::Hard => Theme::emphasis(),
+            ReviewStatus::Good | ReviewStatus::Easy => Theme::success(),
         };
-        lines.push(Line::from(vec![
-            Theme::span("Last:"),
-            Span::styled(action.print(), style),
-        ]));
+        let mut line = vec![Theme::span("Last:"), Span::styled(action.print(), style)];
+        if state.undo.is_some() {
+            line.push(Theme::bullet());
+            line.extend(key_chips(keymap, DrillAction::Undo, " or "));
+            line.push(Theme::span(" undo"));
+        }
+        lines.push(Line::from(line));
     }
 
     lines
 }
 
+/// The typed answer panel: the answer being typed, then once submitted
+/// whether it matched, with a diff against the card's answer if it didn't.
+fn typed_answer_lines(state: &DrillState<'_>, card: &Card) -> Vec<Line<'static>> {
+    let Some(answer) = state.expected_typed_answer(card) else {
+        return Vec::new();
+    };
+    let Some(typed) = &state.typed_answer else {
+        return vec![Line::from(Theme::span(state.answer_input.content()))];
+    };
+    if typed.correct {
+        return vec![Line::from(vec![
+            Span::styled("Correct", Theme::success()),
+            Theme::bullet(),
+            Theme::span(typed.typed.clone()),
+        ])];
+    }
+    let diff = diff_answer(answer, &typed.typed)
+        .into_iter()
+        .map(|segment| match segment {
+            DiffSegment::Same(text) => Theme::span(text),
+            DiffSegment::Missing(text) => {
+                Span::styled(text, Theme::success().add_modifier(Modifier::UNDERLINED))
+            }
+            DiffSegment::Extra(text) => {
+                Span::styled(text, Theme::danger().add_modifier(Modifier::CROSSED_OUT))
+            }
+        });
+    vec![
+        Line::from(vec![
+            Span::styled("Incorrect", Theme::danger()),
+            Theme::bullet(),
+            Theme::span(typed.typed.clone()),
+        ]),
+        Line::from(diff.collect::<Vec<_>>()),
+    ]
+}
+
 fn format_card_text(card: &Card, show_answer: bool) -> String {
+    let (question, answer) = card_sections(card, show_answer);
+    question + answer.as_deref().unwrap_or_default()
+}
+
+/// The question side of a card as drilled and, once revealed, its answer
+/// side. A cloze card is all question side.
+fn card_sections(card: &Card, show_answer: bool) -> (String, Option<String>) {
     match &card.content {
-        CardContent::Basic { question, answer } => {
-            let mut text = format!("Q:\n{}\n\nA:\n", question);
-            if show_answer {
-                text.push_str(answer);
-            }
-            text
-        }
-        CardContent::Cloze { text, cloze_range } => {
-            let body = match (cloze_range, show_answer) {
-                (Some(range), false) => mask_cloze_text(text, range),
-                _ => text.clone(),
+        CardContent::Basic { question, answer } | CardContent::TypeAnswer { question, answer } => (
+            format!("Q:\n{}\n\nA:\n", question),
+            show_answer.then(|| answer.clone()),
+        ),
+        CardContent::Cloze { text, cloze_ranges } => {
+            let body = if show_answer {
+                reveal_cloze_text(text, cloze_ranges)
+            } else {
+                mask_cloze_text(text, cloze_ranges)
             };
-            format!("C:\n{}", body)
+            (format!("C:\n{}", body), None)
         }
     }
 }
 
+/// The media on the visible sides of a card, question side first, and where
+/// the answer side's media start. Answer media only show up once revealed.
+fn card_media(card: &Card, show_answer: bool) -> (Vec<Media>, usize) {
+    let base_dir = card.file_path.parent();
+    let (question, answer) = card_sections(card, show_answer);
+    let mut medias = extract_media(&question, base_dir);
+    let answer_start = medias.len();
+    if let Some(answer) = answer {
+        medias.extend(extract_media(&answer, base_dir));
+    }
+    (medias, answer_start)
+}
+
 async fn preprocess_cards_in_order(
     drill_preprocessor: DrillPreprocessor,
     cards: Vec<Card>,
     updates: mpsc::UnboundedSender<AiUpdate>,
-) -> Result<()> {
+) {
     for card in cards.into_iter() {
-        let needs_ai = matches!(
-            card.ai_status,
-            AIStatus::ClozeNeedDeletion | AIStatus::QuestionNeedRephrasing
-        );
-        if !needs_ai {
+        if !is_ai_pending(&card) {
             continue;
         }
 
         let mut updated_card = card.clone();
-        drill_preprocessor
+        // A failed request only costs this card its enhancement, not the session.
+        let (updated_card, error) = match drill_preprocessor
             .preprocess_cards(std::slice::from_mut(&mut updated_card))
-            .await?;
+            .await
+        {
+            Ok(()) => (updated_card, None),
+            Err(err) => (card.clone(), Some(format!("{err:#}"))),
+        };
 
+        // Written fixes give the card a new hash, so match on the old one.
         let _ = updates.send(AiUpdate {
-            card_hash: updated_card.card_hash.clone(),
+            card_hash: card.card_hash,
             card: updated_card,
+            error,
         });
     }
-    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use crate::card::ClozeRange;
+    use crate::parser::cards_from_md;
 
     use super::*;
     use std::path::PathBuf;
@@ -469,6 +1820,17 @@ mod tests {
         Card::new(PathBuf::from("test.md"), (0, 1), content, "hash".into())
     }
 
+    /// `count` basic cards with the distinct hashes `card-0`, `card-1`, ….
+    fn numbered_cards(count: usize) -> Vec<Card> {
+        (0..count)
+            .map(|i| {
+                let mut card = basic_card(&format!("Q{i}"), &format!("A{i}"));
+                card.card_hash = format!("card-{i}");
+                card
+            })
+            .collect()
+    }
+
     fn cloze_card(text: &str) -> Card {
         let start = text.find('[').unwrap();
         let end = text[start..].find(']').unwrap() + start + 1;
@@ -477,12 +1839,30 @@ mod tests {
             (0, 1),
             CardContent::Cloze {
                 text: text.into(),
-                cloze_range: Some(ClozeRange::new(start, end).unwrap()),
+                cloze_ranges: vec![ClozeRange::new(start, end).unwrap()],
             },
             "hash".into(),
         )
     }
 
+    #[test]
+    fn checklist_numbers_cards_and_masks_clozes() {
+        let mut cloze = cloze_card("Water boils at [100C]");
+        cloze.file_card_range = (4, 5);
+        let cards = [basic_card("What powers\nthe cell?", "Mitochondria"), cloze];
+        let mut output = Vec::new();
+        write_checklist(&cards, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("1. What powers the cell?  "));
+        assert!(lines[0].contains("test.md:1"));
+        assert!(lines[1].starts_with("2. Water boils at [____]  "));
+        assert!(lines[1].contains("test.md:5"));
+        assert!(!output.contains("100C") && !output.contains("Mitochondria"));
+    }
+
     #[test]
     fn basic_card_hides_answer_until_revealed() {
         let card = basic_card("What?", "Answer");
@@ -507,6 +1887,21 @@ mod tests {
         assert!(revealed.contains("[東京]"));
     }
 
+    #[test]
+    fn digits_pick_one_of_several_media() {
+        assert_eq!(media_choice(KeyCode::Char('1'), 2), Some(0));
+        assert_eq!(media_choice(KeyCode::Char('2'), 2), Some(1));
+        assert_eq!(media_choice(KeyCode::Char('3'), 2), None);
+        assert_eq!(media_choice(KeyCode::Char('0'), 2), None);
+        assert_eq!(media_choice(KeyCode::Char('o'), 2), None);
+        assert_eq!(media_choice(KeyCode::Esc, 2), None);
+        assert_eq!(media_choice(KeyCode::Char('9'), 12), Some(8));
+
+        let medias = extract_media("![dog](dog.jpg) [bark](media/bark.mp3) [](a.ogg)", None);
+        let labels: Vec<String> = medias.iter().map(media_choice_label).collect();
+        assert_eq!(labels, vec!["image (dog.jpg)", "bark (bark.mp3)", "a.ogg"]);
+    }
+
     #[test]
     fn last_action_prints_human_friendly_intervals() {
         fn formatted(minutes: f64, status: ReviewStatus) -> String {
@@ -519,27 +1914,79 @@ mod tests {
         }
 
         assert_eq!(
-            formatted(10.0, ReviewStatus::Pass),
+            formatted(10.0, ReviewStatus::Good),
             " Pass (See again in <15 mins)"
         );
         assert_eq!(
-            formatted(20.0, ReviewStatus::Pass),
+            formatted(20.0, ReviewStatus::Good),
             " Pass (See again in <30 mins)"
         );
         assert_eq!(
-            formatted(60.0, ReviewStatus::Pass),
+            formatted(60.0, ReviewStatus::Good),
             " Pass (See again in <12 hours)"
         );
         assert_eq!(
-            formatted(22.0 * 60.0, ReviewStatus::Pass),
+            formatted(22.0 * 60.0, ReviewStatus::Good),
             " Pass (See again in <1 day)"
         );
         assert_eq!(
-            formatted(3.0 * MINUTES_PER_DAY, ReviewStatus::Fail),
+            formatted(3.0 * MINUTES_PER_DAY, ReviewStatus::Again),
             " Fail (See again in 3 days)"
         );
     }
 
+    #[test]
+    fn ai_progress_counts_cards_as_updates_arrive() {
+        let db = in_memory_db();
+        let cards: Vec<Card> = ["one", "two", "three"]
+            .iter()
+            .map(|question| {
+                let mut card = basic_card(question, "A");
+                card.card_hash = question.to_string();
+                card.ai_status = AIStatus::QuestionNeedRephrasing;
+                card
+            })
+            .collect();
+        let mut state = DrillState::new(&db, cards.clone());
+        assert_eq!(state.ai_progress(), Some((0, 3)));
+
+        state.apply_ai_update(AiUpdate {
+            card_hash: "two".into(),
+            card: cards[1].clone(),
+            error: None,
+        });
+        assert_eq!(state.ai_progress(), Some((1, 3)));
+    }
+
+    #[test]
+    fn failed_ai_update_keeps_the_card_as_written() {
+        let db = in_memory_db();
+        let mut card = basic_card("Q", "A");
+        card.ai_status = AIStatus::QuestionNeedRephrasing;
+        let mut state = DrillState::new(&db, vec![card.clone()]);
+        assert!(state.current_ai_pending());
+
+        state.apply_ai_update(AiUpdate {
+            card_hash: card.card_hash.clone(),
+            card: card.clone(),
+            error: Some("Failed to get response from LLM after 3 attempts: timed out".into()),
+        });
+
+        assert!(!state.current_ai_pending());
+        assert_eq!(state.ai_progress(), None);
+        assert_eq!(state.cards[0].ai_status, AIStatus::AiFailed);
+        assert_eq!(
+            format_card_text(&state.cards[0], false),
+            format_card_text(&card, false)
+        );
+        let lines = instructions_text(&state);
+        assert!(
+            lines
+                .iter()
+                .any(|line| flatten_line(line).contains("AI enhancement failed"))
+        );
+    }
+
     #[test]
     fn instructions_show_answer_branch_includes_pass_and_fail() {
         let db = in_memory_db();
@@ -551,6 +1998,108 @@ mod tests {
 
         assert!(commands.contains("Pass"));
         assert!(commands.contains("Fail"));
+        assert!(commands.contains("Hard"));
+        assert!(commands.contains("Easy"));
+        assert!(commands.contains(" 1 "));
+        assert!(commands.contains(" 3 "));
+        assert!(commands.contains("Good"));
+    }
+
+    #[test]
+    fn instructions_and_grades_follow_the_keymap() {
+        let db = in_memory_db();
+        let keymap = Keymap::parse("fail=j easy=l exit=q,esc").unwrap();
+        let mut state = DrillState::new(&db, vec![basic_card("Q", "A")]).with_keymap(keymap);
+        state.show_answer = true;
+
+        let commands = flatten_line(&instructions_text(&state)[0]);
+        assert!(commands.contains(" J  Fail"));
+        assert!(commands.contains(" L  Easy"));
+        assert!(commands.contains(" Q  /  Esc  /  Ctrl+C  exit"));
+        assert!(!commands.contains(" F "));
+
+        let press = |ch| KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE);
+        assert_eq!(
+            grade_for_key(&state.keymap, &press('J')),
+            Some(ReviewStatus::Again)
+        );
+        assert_eq!(grade_for_key(&state.keymap, &press('f')), None);
+        assert_eq!(
+            grade_for_key(&state.keymap, &press('3')),
+            Some(ReviewStatus::Good)
+        );
+    }
+
+    #[test]
+    fn autoplay_fires_once_per_reveal_and_only_for_audio() {
+        let db = in_memory_db();
+        let mut state = DrillState::new(&db, vec![basic_card("Q", "A")]).with_autoplay_audio(true);
+        state.current_medias = extract_media("![map](map.png) [word](word.mp3)", None);
+        assert!(state.take_autoplay_audio().is_none());
+
+        state.show_answer = true;
+        let audio = state.take_autoplay_audio().unwrap();
+        assert_eq!(audio.label(), "word");
+        assert!(state.take_autoplay_audio().is_none());
+
+        // the next card starts hidden, which rearms the autoplay
+        state.show_answer = false;
+        assert!(state.take_autoplay_audio().is_none());
+        state.show_answer = true;
+        assert!(state.take_autoplay_audio().is_some());
+
+        state.current_medias = extract_media("![map](map.png) [clip](clip.mp4)", None);
+        state.show_answer = false;
+        state.take_autoplay_audio();
+        state.show_answer = true;
+        assert!(state.take_autoplay_audio().is_none());
+
+        let mut off = DrillState::new(&db, vec![basic_card("Q", "A")]);
+        off.current_medias = extract_media("[word](word.mp3)", None);
+        off.show_answer = true;
+        assert!(off.take_autoplay_audio().is_none());
+    }
+
+    #[test]
+    fn answer_media_shows_up_only_once_revealed() {
+        let card = basic_card(
+            "Which bird? ![bird](bird.png) [call](call.mp3)",
+            "A wren ![wren](wren.png) [name](wren.mp3)",
+        );
+        let (hidden, answer_start) = card_media(&card, false);
+        assert_eq!(hidden.len(), 2);
+        assert_eq!(answer_start, 2);
+
+        let (shown, answer_start) = card_media(&card, true);
+        assert_eq!(shown.len(), 4);
+        assert_eq!(answer_start, 2);
+        assert_eq!(shown[3].label(), "name");
+
+        let db = in_memory_db();
+        let mut state = DrillState::new(&db, vec![card]).with_autoplay_audio(true);
+        (state.current_medias, state.answer_media_start) = (shown, answer_start);
+        state.show_answer = true;
+        assert_eq!(state.take_autoplay_audio().unwrap().label(), "name");
+
+        let cloze = cloze_card("The wren [sings] [call](call.mp3)");
+        let (medias, answer_start) = card_media(&cloze, true);
+        assert_eq!((medias.len(), answer_start), (1, 1));
+    }
+
+    #[test]
+    fn open_plays_answer_media_after_reveal() {
+        let card = basic_card("Which bird sings this?", "A wren [call](wren.mp3)");
+        let db = in_memory_db();
+        let mut state = DrillState::new(&db, vec![card.clone()]);
+        (state.current_medias, state.answer_media_start) = card_media(&card, false);
+        assert!(state.media_to_open().is_none());
+        assert!(!flatten_line(&instructions_text(&state)[0]).contains("open"));
+
+        state.reveal_answer();
+        (state.current_medias, state.answer_media_start) = card_media(&card, true);
+        assert!(flatten_line(&instructions_text(&state)[0]).contains(" O  open"));
+        assert_eq!(state.media_to_open().unwrap().label(), "call");
+        assert!(!state.choosing_media);
     }
 
     #[test]
@@ -559,7 +2108,7 @@ mod tests {
         let mut state = DrillState::new(&db, vec![basic_card("Q", "A")]);
         state.show_answer = true;
         state.last_action = Some(LastAction {
-            action: ReviewStatus::Fail,
+            action: ReviewStatus::Again,
             show_again_duration: 0.0,
             last_reviewed_at: Instant::now(),
         });
@@ -572,6 +2121,340 @@ mod tests {
         assert!(last_line.contains("Fail"));
     }
 
+    #[tokio::test]
+    async fn again_only_redo_policy_leaves_short_passes_for_later() {
+        let db = DB::new_in_memory().await.unwrap();
+        let cards = numbered_cards(4);
+        db.add_cards_batch(&cards).await.unwrap();
+
+        // a new card passed comes back after the first learning step, within the window
+        let mut state = DrillState::new(&db, cards[..2].to_vec());
+        state.handle_review(ReviewStatus::Good).await.unwrap();
+        state.handle_review(ReviewStatus::Again).await.unwrap();
+        assert_eq!(state.redo_cards.len(), 2);
+
+        let mut state =
+            DrillState::new(&db, cards[2..].to_vec()).with_redo_policy(RedoPolicy::AgainOnly);
+        state.handle_review(ReviewStatus::Good).await.unwrap();
+        state.handle_review(ReviewStatus::Again).await.unwrap();
+        assert_eq!(state.redo_cards.len(), 1);
+        assert_eq!(state.redo_cards[0].card_hash, "card-3");
+        assert_eq!(state.tally.requeued, 1);
+    }
+
+    #[tokio::test]
+    async fn undo_restores_card_and_queue_position() {
+        let db = DB::new_in_memory().await.unwrap();
+        let mut first = basic_card("Q1", "A1");
+        first.card_hash = "first".into();
+        let mut second = basic_card("Q2", "A2");
+        second.card_hash = "second".into();
+        db.add_cards_batch(&[first.clone(), second.clone()])
+            .await
+            .unwrap();
+
+        let mut state = DrillState::new(&db, vec![first.clone(), second]);
+        state.show_answer = true;
+        state.handle_review(ReviewStatus::Again).await.unwrap();
+        assert_eq!(state.current_idx, 1);
+        assert_eq!(state.redo_cards.len(), 1);
+
+        state.undo_last_review().await.unwrap();
+        assert_eq!(state.current_idx, 0);
+        assert!(state.redo_cards.is_empty());
+        assert!(state.undo.is_none());
+        assert!(!state.show_answer);
+        assert_eq!(
+            db.get_card_performance(&first).await.unwrap(),
+            Performance::New
+        );
+
+        let lines = instructions_text(&state);
+        assert!(flatten_line(lines.last().unwrap()).contains("Undid last review"));
+    }
+
+    #[tokio::test]
+    async fn count_splits_due_reviews_from_new_cards_within_the_limits() {
+        let db = DB::new_in_memory().await.unwrap();
+        let path = PathBuf::from("deck.md");
+        let cards: Vec<Card> = (0..4)
+            .map(|idx| {
+                crate::parser::content_to_card(
+                    &path,
+                    &format!("Q: question {idx}?\nA: answer"),
+                    idx * 3,
+                    idx * 3 + 2,
+                )
+                .unwrap()
+            })
+            .collect();
+        db.add_cards_batch(&cards).await.unwrap();
+        db.update_card_performance(&cards[0], ReviewStatus::Again, None)
+            .await
+            .unwrap();
+        let hash_cards: HashMap<String, Card> = cards
+            .iter()
+            .map(|card| (card.card_hash.clone(), card.clone()))
+            .collect();
+
+        let options = DueOptions {
+            new_card_limit: Some(2),
+            ..DueOptions::default()
+        };
+        let due = db.due_today(&hash_cards, options).await.unwrap();
+        assert_eq!(count_due(&db, &due).await.unwrap(), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn undo_after_redo_queue_swap_reinserts_card() {
+        let db = DB::new_in_memory().await.unwrap();
+        let card = basic_card("Q", "A");
+        db.add_card(&card).await.unwrap();
+
+        let mut state = DrillState::new(&db, vec![card.clone()]);
+        state.handle_review(ReviewStatus::Again).await.unwrap();
+        // rendering the next frame swaps the redo queue in
+        assert!(state.current_card().is_some());
+        assert_eq!(state.cards.len(), 1);
+
+        state.undo_last_review().await.unwrap();
+        assert_eq!(state.cards.len(), 1);
+        assert_eq!(state.current_idx, 0);
+        assert!(state.redo_cards.is_empty());
+    }
+
+    #[tokio::test]
+    async fn time_limit_drops_unseen_cards_but_keeps_redo_queue() {
+        let db = DB::new_in_memory().await.unwrap();
+        let cards = numbered_cards(3);
+        db.add_cards_batch(&cards).await.unwrap();
+
+        let mut state = DrillState::new(&db, cards).with_time_limit(Some(Duration::ZERO));
+        state.show_answer = true;
+        state.handle_review(ReviewStatus::Again).await.unwrap();
+
+        assert!(state.time_up);
+        assert!(state.awaiting_redo_choice);
+        assert_eq!(state.cards.len(), 1);
+        assert_eq!(state.remaining_cards(), 1);
+        assert_eq!(state.tally.reviews(), 1);
+
+        state.awaiting_redo_choice = false;
+        let redo = state.current_card().unwrap();
+        assert_eq!(redo.card_hash, "card-0");
+        state.show_answer = true;
+        state.handle_review(ReviewStatus::Easy).await.unwrap();
+        assert!(state.is_complete());
+    }
+
+    #[test]
+    fn summary_reports_counts_and_timing() {
+        let mut tally = SessionTally::default();
+        tally.record(ReviewStatus::Good, false);
+        tally.record(ReviewStatus::Again, true);
+        tally.record(ReviewStatus::Easy, false);
+        tally.record(ReviewStatus::Again, true);
+        tally.unrecord(ReviewStatus::Again, true);
+        assert_eq!(tally.reviews(), 3);
+        assert_eq!(tally.passed(), 2);
+
+        let lines: Vec<String> = summary_lines(&tally, Duration::from_secs(90))
+            .iter()
+            .map(flatten_line)
+            .collect();
+        assert!(lines[0].ends_with("3"));
+        assert!(lines[1].ends_with("2 / 1"));
+        assert!(lines[3].ends_with("1"));
+        assert!(lines[4].ends_with("1:30"));
+        assert!(lines[5].ends_with("30.0s"));
+    }
+
+    #[tokio::test]
+    async fn buried_and_suspended_cards_leave_the_session() {
+        let db = DB::new_in_memory().await.unwrap();
+        let cards = numbered_cards(3);
+        db.add_cards_batch(&cards).await.unwrap();
+        let card_hashes: HashMap<String, Card> = cards
+            .iter()
+            .map(|card| (card.card_hash.clone(), card.clone()))
+            .collect();
+
+        let mut state = DrillState::new(&db, cards);
+        state.show_answer = true;
+        state.handle_review(ReviewStatus::Again).await.unwrap();
+        state.set_aside(true).await.unwrap();
+        assert!(state.undo.is_none());
+        assert_eq!(state.current_card().unwrap().card_hash, "card-2");
+        state.set_aside(false).await.unwrap();
+
+        // the redo pass still shows the failed card, which can be buried too
+        assert_eq!(state.current_card().unwrap().card_hash, "card-0");
+        state.set_aside(false).await.unwrap();
+        assert!(state.is_complete());
+        assert_eq!((state.tally.buried, state.tally.suspended), (2, 1));
+
+        let due = db
+            .due_today(&card_hashes, DueOptions::default())
+            .await
+            .unwrap();
+        assert!(due.iter().all(|card| card.card_hash != "card-1"));
+    }
+
+    #[tokio::test]
+    async fn unfinished_cards_keep_the_queue_order_without_buried_cards() {
+        let db = DB::new_in_memory().await.unwrap();
+        let cards = numbered_cards(4);
+        db.add_cards_batch(&cards).await.unwrap();
+
+        let mut state = DrillState::new(&db, cards);
+        state.show_answer = true;
+        state.handle_review(ReviewStatus::Again).await.unwrap();
+        state.show_answer = true;
+        state.handle_review(ReviewStatus::Easy).await.unwrap();
+        state.set_aside(false).await.unwrap();
+
+        let hashes: Vec<String> = state
+            .unfinished_cards()
+            .into_iter()
+            .map(|card| card.card_hash)
+            .collect();
+        assert_eq!(hashes, vec!["card-3", "card-0"]);
+    }
+
+    #[tokio::test]
+    async fn edited_card_replaces_the_old_one_and_keeps_its_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.md");
+        std::fs::write(
+            &path,
+            "Q: Capital of Frnace?\nA: Paris\n---\nQ: Other?\nA: Card\n",
+        )
+        .unwrap();
+        let cards = cards_from_md(&path, CardSeparator::Rule).unwrap();
+        let db = DB::new_in_memory().await.unwrap();
+        db.add_cards_batch(&cards).await.unwrap();
+        let before = cards[0].clone();
+        db.update_card_performance(&before, ReviewStatus::Good, None)
+            .await
+            .unwrap();
+
+        let mut state = DrillState::new(&db, cards.clone());
+        assert!(!state.reload_edited_card(&before).await.unwrap());
+
+        std::fs::write(
+            &path,
+            "Q: Capital of France?\nA: Paris\n---\nQ: Other?\nA: Card\n",
+        )
+        .unwrap();
+        assert!(state.reload_edited_card(&before).await.unwrap());
+        let after = state.current_card().unwrap();
+        assert_ne!(after.card_hash, before.card_hash);
+        assert!(
+            matches!(&after.content, CardContent::Basic { question, .. } if question.contains("France"))
+        );
+        assert_eq!(state.cards[1].card_hash, cards[1].card_hash);
+        let logs = db.review_logs().await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].card_hash, after.card_hash);
+
+        std::fs::write(&path, "").unwrap();
+        assert!(state.reload_edited_card(&after).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn typed_answers_pick_the_default_grade() {
+        let db = DB::new_in_memory().await.unwrap();
+        let cards = vec![
+            basic_card("Capital of France?", "Paris"),
+            cloze_card("[Rome]"),
+        ];
+        let mut state = DrillState::new(&db, cards).with_typed_answers(true);
+        assert!(state.awaiting_typed_answer());
+
+        for ch in "pariss".chars() {
+            state.answer_input.insert_char(ch);
+        }
+        state.submit_typed_answer();
+        assert!(state.show_answer);
+        assert_eq!(state.default_grade(), ReviewStatus::Good);
+
+        state.hide_answer();
+        state.answer_input.insert_char('x');
+        state.submit_typed_answer();
+        assert_eq!(state.default_grade(), ReviewStatus::Again);
+        let card = state.current_card().unwrap();
+        let panel: Vec<String> = typed_answer_lines(&state, &card)
+            .iter()
+            .map(flatten_line)
+            .collect();
+        assert!(panel[0].starts_with("Incorrect"));
+        assert_eq!(panel[1], "xParis");
+
+        state.current_idx = 1;
+        state.hide_answer();
+        assert!(!state.awaiting_typed_answer());
+        assert_eq!(state.default_grade(), ReviewStatus::Good);
+    }
+
+    #[tokio::test]
+    async fn plain_loop_reads_grades_from_lines() {
+        let db = DB::new_in_memory().await.unwrap();
+        let cards = numbered_cards(2);
+        db.add_cards_batch(&cards).await.unwrap();
+
+        let mut state = DrillState::new(&db, cards);
+        // reveal, ignore an unknown key, fail; reveal, easy; quit at the redo card
+        let input = "\nx\nf\n\n4\nq\n".as_bytes();
+        let mut output = Vec::new();
+        plain_drill_loop(&mut state, input, &mut output)
+            .await
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(state.tally.again, 1);
+        assert_eq!(state.tally.easy, 1);
+        assert_eq!(state.redo_cards.len(), 0);
+        assert_eq!(state.cards[state.current_idx].card_hash, "card-0");
+        assert_eq!(db.review_logs().await.unwrap().len(), 2);
+        assert!(output.contains("Card 1/2"));
+        assert!(output.contains("A1"));
+        assert!(output.contains("Session summary"));
+        assert!(output.contains("Pass / Fail"));
+    }
+
+    #[tokio::test]
+    async fn type_answer_cards_are_typed_without_the_flag() {
+        let db = DB::new_in_memory().await.unwrap();
+        let mut card = Card::new(
+            PathBuf::from("test.md"),
+            (0, 1),
+            CardContent::TypeAnswer {
+                question: "Capital of Peru?".into(),
+                answer: "Lima".into(),
+            },
+            "typed".into(),
+        );
+        card.card_hash = "typed".into();
+        db.add_cards_batch(std::slice::from_ref(&card))
+            .await
+            .unwrap();
+
+        let mut state = DrillState::new(&db, vec![card]);
+        assert!(state.awaiting_typed_answer());
+        // type a wrong answer, then accept the suggested grade with Enter
+        let input = "lama\n\n".as_bytes();
+        let mut output = Vec::new();
+        plain_drill_loop(&mut state, input, &mut output)
+            .await
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("Incorrect"));
+        assert!(output.contains("Lima"));
+        assert!(output.contains("[Enter] fail"));
+        assert_eq!(state.tally.again, 1);
+    }
+
     fn extract_placeholder(text: &str) -> String {
         let start = text.find('[').unwrap();
         let end = text[start..].find(']').unwrap() + start;
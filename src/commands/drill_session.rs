@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::card::Card;
+
+const SESSION_FILE_NAME: &str = "drill_session.json";
+
+/// The cards an interrupted drill had left, in the order it would have shown
+/// them, and the paths it was started with.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedSession {
+    paths: Vec<PathBuf>,
+    card_hashes: Vec<String>,
+}
+
+/// The drilled paths in a stable form, so `drill deck` and `drill ./deck/`
+/// resume the same session.
+pub fn session_paths(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = paths
+        .iter()
+        .map(|path| fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Saves the cards left in a drill of `paths`, or forgets the saved session
+/// when nothing is left.
+pub fn finish(data_dir: &Path, paths: &[PathBuf], remaining: &[Card]) -> Result<()> {
+    if remaining.is_empty() {
+        return clear(data_dir);
+    }
+    let session = SavedSession {
+        paths: paths.to_vec(),
+        card_hashes: remaining
+            .iter()
+            .map(|card| card.card_hash.clone())
+            .collect(),
+    };
+    let path = data_dir.join(SESSION_FILE_NAME);
+    let contents = serde_json::to_string_pretty(&session)?;
+    fs::write(&path, format!("{contents}\n"))
+        .with_context(|| format!("Failed to save the drill session at {}", path.display()))
+}
+
+/// The card hashes left by the last drill of exactly these `paths`. A
+/// session saved for other paths, or a file that can't be read, gives `None`.
+pub fn load(data_dir: &Path, paths: &[PathBuf]) -> Option<Vec<String>> {
+    let contents = fs::read_to_string(data_dir.join(SESSION_FILE_NAME)).ok()?;
+    let session: SavedSession = serde_json::from_str(&contents).ok()?;
+    (session.paths == paths).then_some(session.card_hashes)
+}
+
+pub fn clear(data_dir: &Path) -> Result<()> {
+    let path = data_dir.join(SESSION_FILE_NAME);
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err)
+            .with_context(|| format!("Failed to remove the drill session at {}", path.display())),
+        _ => Ok(()),
+    }
+}
+
+/// The saved cards that still exist and are still due, in their saved order.
+pub fn resumable(saved: &[String], due: &[Card]) -> Vec<Card> {
+    let due: HashMap<&str, &Card> = due
+        .iter()
+        .map(|card| (card.card_hash.as_str(), card))
+        .collect();
+    saved
+        .iter()
+        .filter_map(|hash| due.get(hash.as_str()).map(|card| (*card).clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardContent;
+    use tempfile::tempdir;
+
+    fn card(hash: &str) -> Card {
+        Card::new(
+            PathBuf::from("deck.md"),
+            (0, 1),
+            CardContent::Basic {
+                question: format!("Q {hash}"),
+                answer: "A".into(),
+            },
+            hash.into(),
+        )
+    }
+
+    #[test]
+    fn saved_sessions_resume_for_the_same_paths_with_cards_still_due() {
+        let dir = tempdir().unwrap();
+        let paths = vec![PathBuf::from("/decks/spanish")];
+        finish(dir.path(), &paths, &[card("c"), card("a"), card("b")]).unwrap();
+
+        assert_eq!(load(dir.path(), &[PathBuf::from("/decks/french")]), None);
+        let saved = load(dir.path(), &paths).unwrap();
+        assert_eq!(saved, vec!["c", "a", "b"]);
+
+        // "d" is due but wasn't in the session
+        let due = [card("a"), card("b"), card("c"), card("d")];
+        let hashes: Vec<String> = resumable(&saved, &due)
+            .into_iter()
+            .map(|card| card.card_hash)
+            .collect();
+        assert_eq!(hashes, vec!["c", "a", "b"]);
+        // "a" has since been reviewed elsewhere
+        let hashes: Vec<String> = resumable(&saved, &due[1..])
+            .into_iter()
+            .map(|card| card.card_hash)
+            .collect();
+        assert_eq!(hashes, vec!["c", "b"]);
+
+        finish(dir.path(), &paths, &[]).unwrap();
+        assert_eq!(load(dir.path(), &paths), None);
+        clear(dir.path()).unwrap();
+    }
+
+    #[test]
+    fn session_paths_ignore_order_and_spelling() {
+        let dir = tempdir().unwrap();
+        let deck = dir.path().join("deck");
+        fs::create_dir(&deck).unwrap();
+        let other = dir.path().join("other.md");
+
+        assert_eq!(
+            session_paths(&[other.clone(), deck.join("."), deck.clone()]),
+            session_paths(&[deck, other])
+        );
+    }
+}
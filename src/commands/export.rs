@@ -0,0 +1,254 @@
+use crate::crud::{CardExportRow, DB};
+use crate::palette::Palette;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+const CSV_HEADER: [&str; 9] = [
+    "card_hash",
+    "added_at",
+    "last_reviewed_at",
+    "stability",
+    "difficulty",
+    "interval_raw",
+    "interval_days",
+    "due_date",
+    "review_count",
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+pub async fn run(
+    db: &DB,
+    path: &Path,
+    format: ExportFormat,
+    since: Option<NaiveDate>,
+) -> Result<()> {
+    let rows = db.export_cards(since.map(local_midnight)).await?;
+    if let Some(date) = since
+        && rows.is_empty()
+    {
+        println!(
+            "{} {}",
+            Palette::dim("No cards reviewed or added since"),
+            Palette::paint(Palette::ACCENT, date),
+        );
+        println!("{}", Palette::dim("Nothing was written."));
+        return Ok(());
+    }
+    let contents = match format {
+        ExportFormat::Csv => to_csv(&rows),
+        ExportFormat::Json => to_json(&rows)?,
+    };
+
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {}", parent.display()))?;
+    }
+    fs::write(path, contents)
+        .with_context(|| format!("failed to write export to {}", path.display()))?;
+
+    println!(
+        "{} {} {} {}",
+        Palette::paint(Palette::SUCCESS, "Exported"),
+        Palette::paint(Palette::INFO, rows.len()),
+        Palette::dim("cards to"),
+        Palette::paint(Palette::ACCENT, path.display()),
+    );
+    Ok(())
+}
+
+/// The start of `date` on this machine's clock. When midnight falls in a
+/// daylight-saving gap, the day starts an hour later.
+fn local_midnight(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    midnight
+        .and_local_timezone(Local)
+        .earliest()
+        .or_else(|| {
+            (midnight + chrono::Duration::hours(1))
+                .and_local_timezone(Local)
+                .earliest()
+        })
+        .map_or_else(|| midnight.and_utc(), |start| start.to_utc())
+}
+
+fn to_csv(rows: &[CardExportRow]) -> String {
+    let mut out = CSV_HEADER.join(",");
+    out.push('\n');
+    for row in rows {
+        let fields = [
+            csv_field(&row.card_hash),
+            csv_field(&row.added_at),
+            row.last_reviewed_at
+                .as_deref()
+                .map(csv_field)
+                .unwrap_or_default(),
+            optional(row.stability),
+            optional(row.difficulty),
+            optional(row.interval_raw),
+            optional(row.interval_days),
+            row.due_date.as_deref().map(csv_field).unwrap_or_default(),
+            row.review_count.to_string(),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// The columns of [`CSV_HEADER`], leaving out what only backups carry.
+#[derive(Serialize)]
+struct JsonRow<'a> {
+    card_hash: &'a str,
+    added_at: &'a str,
+    last_reviewed_at: Option<&'a str>,
+    stability: Option<f64>,
+    difficulty: Option<f64>,
+    interval_raw: Option<f64>,
+    interval_days: Option<i64>,
+    due_date: Option<&'a str>,
+    review_count: i64,
+}
+
+fn to_json(rows: &[CardExportRow]) -> Result<String> {
+    let rows: Vec<JsonRow> = rows
+        .iter()
+        .map(|row| JsonRow {
+            card_hash: &row.card_hash,
+            added_at: &row.added_at,
+            last_reviewed_at: row.last_reviewed_at.as_deref(),
+            stability: row.stability,
+            difficulty: row.difficulty,
+            interval_raw: row.interval_raw,
+            interval_days: row.interval_days,
+            due_date: row.due_date.as_deref(),
+            review_count: row.review_count,
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&rows)? + "\n")
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fsrs::ReviewStatus;
+    use crate::parser::content_to_card;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn csv_export_has_stable_header_and_rows() {
+        let db = DB::new_in_memory().await.unwrap();
+        let fresh = content_to_card(&PathBuf::from("a.md"), "C: ping? [pong]", 1, 1).unwrap();
+        let reviewed = content_to_card(&PathBuf::from("b.md"), "Q: 1+1?\nA: 2", 1, 2).unwrap();
+        db.add_card(&fresh).await.unwrap();
+        db.add_card(&reviewed).await.unwrap();
+        db.update_card_performance(&reviewed, ReviewStatus::Good, None)
+            .await
+            .unwrap();
+
+        let rows = db.export_all_cards().await.unwrap();
+        assert_eq!(rows.len(), 2);
+        let csv = to_csv(&rows);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "card_hash,added_at,last_reviewed_at,stability,difficulty,interval_raw,interval_days,due_date,review_count"
+        );
+        assert_eq!(lines.len(), 3);
+
+        let fresh_line = lines
+            .iter()
+            .find(|line| line.starts_with(&fresh.card_hash))
+            .unwrap();
+        assert!(fresh_line.ends_with(",,,,,0,,0"));
+        let reviewed_line = lines
+            .iter()
+            .find(|line| line.starts_with(&reviewed.card_hash))
+            .unwrap();
+        assert_eq!(reviewed_line.split(',').count(), CSV_HEADER.len());
+        assert!(reviewed_line.ends_with(",1"));
+
+        let json: serde_json::Value = serde_json::from_str(&to_json(&rows).unwrap()).unwrap();
+        let keys: Vec<&str> = json[0]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let mut header = CSV_HEADER.to_vec();
+        header.sort_unstable();
+        assert_eq!(keys, header);
+    }
+
+    #[tokio::test]
+    async fn since_keeps_cards_reviewed_or_added_from_that_day() {
+        let db = DB::new_in_memory().await.unwrap();
+        let fresh = content_to_card(&PathBuf::from("a.md"), "C: ping? [pong]", 1, 1).unwrap();
+        let reviewed = content_to_card(&PathBuf::from("b.md"), "Q: 1+1?\nA: 2", 1, 2).unwrap();
+        db.add_cards_batch(&[fresh.clone(), reviewed.clone()])
+            .await
+            .unwrap();
+        let in_three_days = Utc::now() + chrono::Duration::days(3);
+        db.update_card_performance(&reviewed, ReviewStatus::Good, Some(in_three_days))
+            .await
+            .unwrap();
+
+        let today = Local::now().date_naive();
+        let rows = db.export_cards(Some(local_midnight(today))).await.unwrap();
+        assert_eq!(rows.len(), 2);
+        let tomorrow = today.succ_opt().unwrap();
+        let rows = db
+            .export_cards(Some(local_midnight(tomorrow)))
+            .await
+            .unwrap();
+        let hashes: Vec<&str> = rows.iter().map(|row| row.card_hash.as_str()).collect();
+        assert_eq!(hashes, vec![reviewed.card_hash.as_str()]);
+        let later = in_three_days.date_naive() + chrono::Duration::days(2);
+        assert!(
+            db.export_cards(Some(local_midnight(later)))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // an empty export is not an error and leaves no file behind
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recent.csv");
+        run(&db, &path, ExportFormat::Csv, Some(later))
+            .await
+            .unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn csv_field_quotes_special_characters() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,76 @@
+use crate::card::Card;
+use crate::commands::check::card_preview;
+use crate::crud::{DB, Leech};
+use crate::palette::Palette;
+use crate::parser::{ScanOptions, register_all_cards};
+use crate::utils::pluralize;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+/// Lists leech cards under `paths` with their location, so they can be
+/// rewritten or split up.
+pub async fn run(db: &DB, paths: Vec<PathBuf>, scan: ScanOptions) -> Result<()> {
+    let (card_hashes, _) = register_all_cards(db, paths, scan).await?;
+    let leeches = find_leeches(&card_hashes, &db.leeches().await?);
+    if leeches.is_empty() {
+        println!("{}", Palette::dim("No leeches under the given paths."));
+        return Ok(());
+    }
+
+    let threshold = db.get_settings().await?.leech_threshold;
+    println!(
+        "{} {}",
+        Palette::paint(Palette::WARNING, pluralize("leech", leeches.len())),
+        Palette::dim(format!(
+            "(failed {} or more)",
+            pluralize("time", threshold as usize)
+        )),
+    );
+    for (card, leech) in leeches {
+        let mut status = pluralize("failure", leech.lapses as usize);
+        if leech.suspended {
+            status.push_str(", suspended");
+        }
+        println!(
+            "  {} {} {}",
+            Palette::paint(
+                Palette::ACCENT,
+                format!(
+                    "{}:{}",
+                    card.file_path.display(),
+                    card.file_card_range.0 + 1
+                )
+            ),
+            card_preview(card),
+            Palette::dim(format!("({status})")),
+        );
+    }
+    Ok(())
+}
+
+/// Registered cards flagged as leeches, counting a reverse card's failures
+/// against the card it came from, sorted by file and line.
+fn find_leeches<'a>(
+    card_hashes: &'a HashMap<String, Card>,
+    leeches: &HashMap<String, Leech>,
+) -> Vec<(&'a Card, Leech)> {
+    let mut found: Vec<(&Card, Leech)> = card_hashes
+        .values()
+        .filter_map(|card| {
+            card.hashes_with_reverse()
+                .filter_map(|hash| leeches.get(&hash).copied())
+                .reduce(|a, b| Leech {
+                    lapses: a.lapses.max(b.lapses),
+                    suspended: a.suspended || b.suspended,
+                })
+                .map(|leech| (card, leech))
+        })
+        .collect();
+    found.sort_by(|(a, _), (b, _)| {
+        (&a.file_path, a.file_card_range).cmp(&(&b.file_path, b.file_card_range))
+    });
+    found
+}
@@ -1,3 +1,14 @@
+pub mod backup;
 pub mod check;
+pub mod config;
 pub mod create;
 pub mod drill;
+pub mod drill_session;
+pub mod export;
+pub mod leeches;
+pub mod optimize;
+pub mod prune;
+pub mod reschedule;
+pub mod search;
+pub mod stats;
+pub mod unsuspend;
@@ -0,0 +1,132 @@
+use crate::crud::DB;
+use crate::crud::review_log::ReviewLogRow;
+use crate::palette::Palette;
+use crate::settings::{fsrs_params_path, write_fsrs_parameters};
+use crate::utils::pluralize;
+
+use anyhow::{Context, Result};
+use fsrs::{ComputeParametersInput, FSRS, FSRSItem, FSRSReview};
+
+pub const MIN_REVIEWS_FOR_OPTIMIZE: usize = 400;
+
+pub async fn run(db: &DB) -> Result<()> {
+    let logs = db.review_logs().await?;
+    if logs.len() < MIN_REVIEWS_FOR_OPTIMIZE {
+        println!(
+            "{} {} {}",
+            Palette::paint(Palette::WARNING, "Not enough review history yet:"),
+            Palette::paint(Palette::INFO, pluralize("review", logs.len())),
+            Palette::dim(format!("logged, {} needed.", MIN_REVIEWS_FOR_OPTIMIZE)),
+        );
+        println!(
+            "{}",
+            Palette::dim(format!(
+                "Keep drilling and try again in {}.",
+                pluralize("review", MIN_REVIEWS_FOR_OPTIMIZE - logs.len())
+            ))
+        );
+        return Ok(());
+    }
+
+    let train_set = training_items(&logs);
+    println!(
+        "{} {}",
+        Palette::dim("Optimizing FSRS parameters from"),
+        Palette::paint(Palette::INFO, pluralize("review", logs.len())),
+    );
+    let parameters = tokio::task::spawn_blocking(move || -> Result<Vec<f32>> {
+        let fsrs = FSRS::new(None)?;
+        let parameters = fsrs.compute_parameters(ComputeParametersInput {
+            train_set,
+            ..Default::default()
+        })?;
+        Ok(parameters)
+    })
+    .await?
+    .context("FSRS optimization failed")?;
+
+    let path = fsrs_params_path(db.data_dir());
+    write_fsrs_parameters(&path, &parameters)?;
+    println!(
+        "{} {}",
+        Palette::paint(Palette::SUCCESS, "Saved optimized parameters to"),
+        Palette::paint(Palette::ACCENT, path.display()),
+    );
+    Ok(())
+}
+
+/// Builds one training item per review after a card's first, each holding the
+/// card's history up to and including that review.
+fn training_items(logs: &[ReviewLogRow]) -> Vec<FSRSItem> {
+    let mut items = Vec::new();
+    for card_logs in logs.chunk_by(|a, b| a.card_hash == b.card_hash) {
+        let reviews: Vec<FSRSReview> = card_logs
+            .iter()
+            .enumerate()
+            .map(|(idx, log)| FSRSReview {
+                rating: log.grade as u32,
+                delta_t: if idx == 0 { 0 } else { log.elapsed_days as u32 },
+            })
+            .collect();
+        for end in 2..=reviews.len() {
+            items.push(FSRSItem {
+                reviews: reviews[..end].to_vec(),
+            });
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fsrs::ReviewStatus;
+    use crate::parser::content_to_card;
+    use chrono::Duration;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn reviews_are_logged_and_grouped_into_training_items() {
+        let db = DB::new_in_memory().await.unwrap();
+        let card = content_to_card(&PathBuf::from("a.md"), "C: ping? [pong]", 1, 1).unwrap();
+        let other = content_to_card(&PathBuf::from("b.md"), "Q: 1+1?\nA: 2", 1, 2).unwrap();
+        db.add_card(&card).await.unwrap();
+        db.add_card(&other).await.unwrap();
+
+        let start = chrono::Utc::now() - Duration::days(10);
+        for (offset, status) in [
+            (0, ReviewStatus::Good),
+            (3, ReviewStatus::Again),
+            (5, ReviewStatus::Easy),
+        ] {
+            db.update_card_performance(&card, status, Some(start + Duration::days(offset)))
+                .await
+                .unwrap();
+        }
+        db.update_card_performance(&other, ReviewStatus::Hard, Some(start))
+            .await
+            .unwrap();
+
+        let logs = db.review_logs().await.unwrap();
+        assert_eq!(logs.len(), 4);
+        let card_logs: Vec<_> = logs
+            .iter()
+            .filter(|log| log.card_hash == card.card_hash)
+            .map(|log| (log.grade, log.elapsed_days))
+            .collect();
+        assert_eq!(card_logs, vec![(3, 0), (1, 3), (4, 2)]);
+
+        let items = training_items(&logs);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].reviews.len(), 3);
+
+        // undoing a review also drops its log entry
+        let before = db.get_card_performance(&other).await.unwrap();
+        db.update_card_performance(&other, ReviewStatus::Good, None)
+            .await
+            .unwrap();
+        db.restore_card_performance(&other, &before).await.unwrap();
+        assert_eq!(db.review_logs().await.unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::commands::check::text_preview;
+use crate::crud::DB;
+use crate::palette::Palette;
+use crate::parser::{ScanOptions, register_all_cards};
+use crate::utils::{ask_yn, pluralize};
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+/// Orphans listed in the confirmation prompt before the rest are counted.
+const PREVIEWED_ORPHANS: usize = 5;
+
+pub async fn run(db: &DB, paths: Vec<PathBuf>, scan: ScanOptions) -> Result<()> {
+    let roots = paths.clone();
+    let (card_hashes, _) = register_all_cards(db, paths, scan).await?;
+    let orphans = db.orphaned_cards(&card_hashes, &roots).await?;
+    if orphans.is_empty() {
+        println!(
+            "{}",
+            Palette::paint(Palette::SUCCESS, "No orphaned cards in the database.")
+        );
+        return Ok(());
+    }
+
+    let mut prompt = format!(
+        "Found {} in cards.db for cards no longer under the given paths:",
+        Palette::paint(Palette::WARNING, pluralize("row", orphans.len()))
+    );
+    for orphan in orphans.iter().take(PREVIEWED_ORPHANS) {
+        let content = db.card_content(&orphan.card_hash).await?;
+        prompt.push_str(&format!(
+            "\n  {} {}",
+            Palette::paint(
+                Palette::ACCENT,
+                format!("{}:{}", orphan.file_path.display(), orphan.start_line + 1)
+            ),
+            Palette::dim(content.as_deref().map(text_preview).unwrap_or_default()),
+        ));
+    }
+    if orphans.len() > PREVIEWED_ORPHANS {
+        prompt.push_str(&format!(
+            "\n  {}",
+            Palette::dim(format!("and {} more", orphans.len() - PREVIEWED_ORPHANS))
+        ));
+    }
+    prompt.push_str("\nTheir scheduling history will be deleted.");
+    if !ask_yn(prompt) {
+        println!("{}", Palette::dim("Nothing deleted."));
+        return Ok(());
+    }
+
+    let hashes: Vec<String> = orphans.into_iter().map(|orphan| orphan.card_hash).collect();
+    let deleted = db.delete_cards_by_hash(&hashes).await?;
+    println!(
+        "{} {}",
+        Palette::paint(Palette::SUCCESS, "Removed"),
+        Palette::paint(Palette::INFO, pluralize("orphaned card", deleted as usize)),
+    );
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use crate::card::Card;
+use crate::crud::DB;
+use crate::palette::Palette;
+use crate::parser::{ScanOptions, register_all_cards};
+use crate::utils::pluralize;
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+/// Makes every overdue card under `paths` due `days` from now.
+pub async fn postpone(db: &DB, paths: Vec<PathBuf>, days: u32, scan: ScanOptions) -> Result<()> {
+    let shifted = shift(db, paths, i64::from(days), scan).await?;
+    report(
+        shifted,
+        "Postponed",
+        "No overdue cards under the given paths.",
+        format!("to {} from now", pluralize("day", days as usize)),
+    )
+}
+
+/// Brings every card due later under `paths` forward by `days`, but no
+/// earlier than now.
+pub async fn advance(db: &DB, paths: Vec<PathBuf>, days: u32, scan: ScanOptions) -> Result<()> {
+    let shifted = shift(db, paths, -i64::from(days), scan).await?;
+    report(
+        shifted,
+        "Advanced",
+        "No upcoming cards under the given paths.",
+        format!("by {}", pluralize("day", days as usize)),
+    )
+}
+
+async fn shift(db: &DB, paths: Vec<PathBuf>, days: i64, scan: ScanOptions) -> Result<u64> {
+    let (card_hashes, _) = register_all_cards(db, paths, scan).await?;
+    let hashes: Vec<String> = card_hashes
+        .values()
+        .flat_map(Card::hashes_with_reverse)
+        .collect();
+    db.shift_due_dates(&hashes, days).await
+}
+
+fn report(shifted: u64, verb: &str, nothing_to_do: &str, detail: String) -> Result<()> {
+    if shifted == 0 {
+        println!("{}", Palette::dim(nothing_to_do));
+        return Ok(());
+    }
+    println!(
+        "{} {} {}",
+        Palette::paint(Palette::SUCCESS, verb),
+        Palette::paint(Palette::INFO, pluralize("card", shifted as usize)),
+        Palette::dim(detail),
+    );
+    Ok(())
+}
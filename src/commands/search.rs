@@ -0,0 +1,145 @@
+use crate::card::{Card, CardDirection};
+use crate::palette::Palette;
+use crate::parser::{CardSeparator, ScanOptions, collect_all_cards};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::IsTerminal;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    /// 1-based, so `path:line` opens at the match in editors.
+    pub line: usize,
+    pub text: String,
+    /// Byte range of the match within `text`.
+    pub matched: Range<usize>,
+}
+
+impl SearchHit {
+    fn format(&self, highlight: bool) -> String {
+        let text = if highlight {
+            format!(
+                "{}{}{}",
+                &self.text[..self.matched.start],
+                Palette::paint(Palette::WARNING, &self.text[self.matched.clone()]),
+                &self.text[self.matched.end..],
+            )
+        } else {
+            self.text.clone()
+        };
+        format!("{}:{}: {}", self.path.display(), self.line, text)
+    }
+}
+
+/// Plain queries match as case-insensitive substrings; `--regex` queries are
+/// used as written, so add `(?i)` for a case-insensitive pattern.
+pub fn build_matcher(query: &str, regex: bool) -> Result<Regex> {
+    let pattern = if regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!regex)
+        .build()
+        .with_context(|| format!("Invalid pattern \"{query}\""))
+}
+
+/// Matches the source lines each card was parsed from, so hits point at the
+/// exact line in the deck. Sorted by path, then line.
+pub fn search_cards(cards: &[Card], matcher: &Regex) -> Result<Vec<SearchHit>> {
+    let mut card_lines: BTreeMap<&Path, BTreeSet<usize>> = BTreeMap::new();
+    for card in cards {
+        if card.direction != CardDirection::Forward {
+            continue;
+        }
+        let (start, end) = card.file_card_range;
+        card_lines
+            .entry(card.file_path.as_path())
+            .or_default()
+            .extend(start..end.max(start + 1));
+    }
+
+    let mut hits = Vec::new();
+    for (path, line_indices) in card_lines {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let lines: Vec<&str> = contents.lines().collect();
+        for index in line_indices {
+            let Some(text) = lines.get(index).map(|line| line.trim_end()) else {
+                continue;
+            };
+            if let Some(found) = matcher.find(text) {
+                hits.push(SearchHit {
+                    path: path.to_path_buf(),
+                    line: index + 1,
+                    text: text.to_string(),
+                    matched: found.range(),
+                });
+            }
+        }
+    }
+    Ok(hits)
+}
+
+pub async fn run(
+    paths: Vec<PathBuf>,
+    query: &str,
+    regex: bool,
+    separator: CardSeparator,
+    scan: ScanOptions,
+) -> Result<()> {
+    let matcher = build_matcher(query, regex)?;
+    let cards = collect_all_cards(paths, scan, separator).await?;
+    let hits = search_cards(&cards, &matcher)?;
+    if hits.is_empty() {
+        eprintln!("{}", Palette::dim("No cards match."));
+        return Ok(());
+    }
+
+    // Keep piped output free of escape codes so grep and quickfix lists can read it.
+    let highlight = std::io::stdout().is_terminal();
+    for hit in &hits {
+        println!("{}", hit.format(highlight));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::cards_from_md;
+
+    #[test]
+    fn finds_matching_card_lines_with_their_line_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.md");
+        std::fs::write(
+            &path,
+            "# Notes about the Mitochondria\n\nQ: What powers the cell?\nA: The mitochondria\n\nC: [Paris] is the capital of France\nTokyo ::: Japan\n",
+        )
+        .unwrap();
+        let cards = cards_from_md(&path, CardSeparator::Rule).unwrap();
+
+        let hits = search_cards(&cards, &build_matcher("MITOCHONDRIA", false).unwrap()).unwrap();
+        assert_eq!(hits.len(), 1, "only card lines are searched: {hits:?}");
+        assert_eq!(hits[0].line, 4);
+        assert_eq!(&hits[0].text[hits[0].matched.clone()], "mitochondria");
+        assert_eq!(
+            hits[0].format(false),
+            format!("{}:4: A: The mitochondria", path.display())
+        );
+
+        let hits = search_cards(&cards, &build_matcher(r"^(C|Tokyo)\b", true).unwrap()).unwrap();
+        let lines: Vec<usize> = hits.iter().map(|hit| hit.line).collect();
+        assert_eq!(lines, vec![6, 7]);
+
+        assert!(build_matcher("(", true).is_err());
+        assert!(build_matcher("(", false).is_ok());
+    }
+}
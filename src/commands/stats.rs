@@ -0,0 +1,180 @@
+use crate::{
+    card::Card,
+    crud::DB,
+    palette::Palette,
+    parser::{ScanOptions, register_all_cards},
+    stats::{CardLifeCycle, CardStats, DEFAULT_HORIZON_DAYS},
+};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+
+const ROOT_GROUP: &str = ".";
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct DeckSummary {
+    pub deck: String,
+    pub cards: i64,
+    pub new: i64,
+    pub young: i64,
+    pub mature: i64,
+    pub due: i64,
+    pub mean_retrievability: Option<f64>,
+}
+
+impl DeckSummary {
+    fn from_stats(deck: String, stats: &CardStats) -> Self {
+        let lifecycle = |kind: CardLifeCycle| *stats.card_lifecycles.get(&kind).unwrap_or(&0);
+        Self {
+            deck,
+            cards: stats.num_cards,
+            new: lifecycle(CardLifeCycle::New),
+            young: lifecycle(CardLifeCycle::Young),
+            mature: lifecycle(CardLifeCycle::Mature),
+            due: stats.due_cards,
+            mean_retrievability: stats.retrievability_histogram.mean(),
+        }
+    }
+}
+
+pub async fn run(db: &DB, paths: Vec<PathBuf>, json: bool, scan: ScanOptions) -> Result<()> {
+    let roots = paths.clone();
+    let (card_hashes, _) = register_all_cards(db, paths, scan).await?;
+
+    let mut summaries = Vec::new();
+    for (deck, group) in group_by_deck(&roots, card_hashes) {
+        let stats = db.collection_stats(&group, DEFAULT_HORIZON_DAYS).await?;
+        summaries.push(DeckSummary::from_stats(deck, &stats));
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+    } else {
+        render_plain(&summaries);
+    }
+    Ok(())
+}
+
+fn group_by_deck(
+    roots: &[PathBuf],
+    card_hashes: HashMap<String, Card>,
+) -> BTreeMap<String, HashMap<String, Card>> {
+    let mut groups: BTreeMap<String, HashMap<String, Card>> = BTreeMap::new();
+    for (hash, card) in card_hashes {
+        let deck = card
+            .deck
+            .clone()
+            .unwrap_or_else(|| deck_name(roots, &card.file_path));
+        groups.entry(deck).or_default().insert(hash, card);
+    }
+    groups
+}
+
+fn deck_name(roots: &[PathBuf], file_path: &Path) -> String {
+    for root in roots {
+        if root.is_file() && root == file_path {
+            return root.display().to_string();
+        }
+        let Ok(relative) = file_path.strip_prefix(root) else {
+            continue;
+        };
+        let mut components = relative.components();
+        let first = components.next();
+        return match (first, components.next()) {
+            (Some(dir), Some(_)) => root.join(dir).display().to_string(),
+            _ => root.display().to_string(),
+        };
+    }
+    file_path
+        .parent()
+        .map(|parent| parent.display().to_string())
+        .unwrap_or_else(|| ROOT_GROUP.to_string())
+}
+
+fn render_plain(summaries: &[DeckSummary]) {
+    if summaries.is_empty() {
+        println!("{}", Palette::dim("No cards found."));
+        return;
+    }
+    for summary in summaries {
+        println!("{}", Palette::paint(Palette::ACCENT, &summary.deck));
+        println!(
+            "  {} {} {} {} {} {} {} {}",
+            Palette::dim("Cards:"),
+            Palette::paint(Palette::INFO, summary.cards),
+            Palette::dim("New:"),
+            Palette::paint(Palette::INFO, summary.new),
+            Palette::dim("Young:"),
+            Palette::paint(Palette::INFO, summary.young),
+            Palette::dim("Mature:"),
+            Palette::paint(Palette::INFO, summary.mature),
+        );
+        let due_color = if summary.due > 0 {
+            Palette::WARNING
+        } else {
+            Palette::SUCCESS
+        };
+        let retrievability = summary
+            .mean_retrievability
+            .map(|v| format!("{}%", (v * 100.0).round()))
+            .unwrap_or_else(|| String::from("NA - No cards reviewed"));
+        println!(
+            "  {} {} {} {}",
+            Palette::dim("Due now:"),
+            Palette::paint(due_color, summary.due),
+            Palette::dim("Mean retrievability:"),
+            Palette::paint(Palette::INFO, retrievability),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deck_name_uses_top_level_directory() {
+        let roots = vec![PathBuf::from("notes")];
+        assert_eq!(
+            deck_name(&roots, Path::new("notes/biology/cells/mito.md")),
+            "notes/biology"
+        );
+        assert_eq!(deck_name(&roots, Path::new("notes/inbox.md")), "notes");
+    }
+
+    #[test]
+    fn deck_name_keeps_explicit_files() {
+        let roots = vec![PathBuf::from("test_data/test.md")];
+        assert_eq!(
+            deck_name(&roots, Path::new("test_data/test.md")),
+            "test_data/test.md"
+        );
+    }
+
+    #[tokio::test]
+    async fn groups_test_data_into_single_deck() {
+        let db = DB::new_in_memory().await.unwrap();
+        let roots = vec![PathBuf::from("test_data")];
+        let (card_hashes, _) = register_all_cards(&db, roots.clone(), ScanOptions::default())
+            .await
+            .unwrap();
+        let groups = group_by_deck(&roots, card_hashes);
+        assert_eq!(groups.len(), 1);
+
+        let (deck, group) = groups.into_iter().next().unwrap();
+        let stats = db
+            .collection_stats(&group, DEFAULT_HORIZON_DAYS)
+            .await
+            .unwrap();
+        let summary = DeckSummary::from_stats(deck, &stats);
+        assert_eq!(summary.cards, 12);
+        assert_eq!(summary.new, 12);
+        assert_eq!(summary.due, 12);
+        assert_eq!(summary.mean_retrievability, None);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::card::Card;
+use crate::crud::DB;
+use crate::palette::Palette;
+use crate::parser::{ScanOptions, register_all_cards};
+use crate::utils::pluralize;
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+pub async fn run(db: &DB, paths: Vec<PathBuf>, scan: ScanOptions) -> Result<()> {
+    let (card_hashes, _) = register_all_cards(db, paths, scan).await?;
+    // reverse cards from `drill --both-ways` can be suspended too
+    let hashes: Vec<String> = card_hashes
+        .values()
+        .flat_map(Card::hashes_with_reverse)
+        .collect();
+
+    let unsuspended = db.unsuspend_cards(&hashes).await?;
+    if unsuspended == 0 {
+        println!(
+            "{}",
+            Palette::dim("No suspended cards under the given paths.")
+        );
+        return Ok(());
+    }
+    println!(
+        "{} {}",
+        Palette::paint(Palette::SUCCESS, "Unsuspended"),
+        Palette::paint(Palette::INFO, pluralize("card", unsuspended as usize)),
+    );
+    Ok(())
+}
@@ -2,56 +2,152 @@ use anyhow::Result;
 use futures::TryStreamExt;
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use anyhow::anyhow;
+use clap::ValueEnum;
+use rand::seq::SliceRandom;
 
-use crate::card::Card;
+use crate::card::{Card, CardDirection};
 
+use crate::fsrs::Performance;
 use crate::fsrs::ReviewStatus;
 use crate::fsrs::ReviewedPerformance;
 use crate::fsrs::update_performance;
-use crate::fsrs::{LEARN_AHEAD_THRESHOLD_MINS, Performance};
+use crate::parser::get_hash;
+use crate::settings::Settings;
+use crate::stats::{CardLifeCycle, counts_as_due};
+use crate::utils::start_of_study_day;
 
 use super::DB;
 
-impl DB {
-    pub async fn add_card(&self, card: &Card) -> Result<()> {
-        let now = chrono::Utc::now().to_rfc3339();
+/// Where new cards go relative to cards that are due for review.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DrillOrder {
+    /// Most overdue cards first, then cards due today, then new cards.
+    #[default]
+    DueFirst,
+    NewFirst,
+    /// Alternate between due and new cards.
+    Interleave,
+}
 
-        sqlx::query!(
-            r#"
-        INSERT or ignore INTO cards (
-            card_hash,
-            added_at,
-            last_reviewed_at,
-            stability,
-            difficulty,
-            interval_raw,
-            interval_days,
-            due_date,
-            review_count
-        )
-        VALUES (?, ?, NULL, NULL, NULL, NULL, 0, NULL, 0)
-        "#,
-            card.card_hash,
-            now
-        )
-        .execute(&self.pool)
-        .await?;
+/// Which due cards [`DB::due_today`] returns and in what order.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DueOptions {
+    pub card_limit: Option<usize>,
+    pub new_card_limit: Option<usize>,
+    /// Most cards to take from any one file.
+    pub per_file_limit: Option<usize>,
+    pub order: DrillOrder,
+    /// Shuffle the due and the new cards, each among themselves, so the
+    /// order still holds.
+    pub shuffle: bool,
+    /// Only take cards at this stage, applied before the limits.
+    pub only: Option<CardLifeCycle>,
+}
 
-        Ok(())
+struct PerformanceRow {
+    card_hash: String,
+    last_reviewed_at: Option<chrono::DateTime<chrono::Utc>>,
+    stability: Option<f64>,
+    difficulty: Option<f64>,
+    interval_raw: Option<f64>,
+    interval_days: Option<i64>,
+    due_date: Option<chrono::DateTime<chrono::Utc>>,
+    review_count: i64,
+}
+
+impl PerformanceRow {
+    fn into_performance(self) -> Result<Performance> {
+        let card_hash = self.card_hash;
+        if self.review_count == 0 {
+            return Ok(Performance::default());
+        }
+        let reviewed = ReviewedPerformance {
+            last_reviewed_at: self
+                .last_reviewed_at
+                .ok_or_else(|| anyhow!("missing last_reviewed_at for card {card_hash}"))?,
+            stability: self
+                .stability
+                .ok_or_else(|| anyhow!("missing stability for card {card_hash}"))?,
+            difficulty: self
+                .difficulty
+                .ok_or_else(|| anyhow!("missing difficulty for card {card_hash}"))?,
+            interval_raw: self
+                .interval_raw
+                .ok_or_else(|| anyhow!("missing interval_raw for card {card_hash}"))?,
+            interval_days: self
+                .interval_days
+                .ok_or_else(|| anyhow!("missing interval_days for card {card_hash}"))?
+                as usize,
+            due_date: self
+                .due_date
+                .ok_or_else(|| anyhow!("missing due_date for card {card_hash}"))?,
+            review_count: self.review_count as usize,
+        };
+
+        Ok(Performance::Reviewed(reviewed))
+    }
+}
+
+/// A card flagged for failing too many reviews.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Leech {
+    /// Failed reviews so far.
+    pub lapses: i64,
+    pub suspended: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CardExportRow {
+    pub card_hash: String,
+    pub added_at: String,
+    pub last_reviewed_at: Option<String>,
+    pub stability: Option<f64>,
+    pub difficulty: Option<f64>,
+    pub interval_raw: Option<f64>,
+    pub interval_days: Option<i64>,
+    pub due_date: Option<String>,
+    pub review_count: i64,
+    /// Absent from backups written before cards could be suspended.
+    #[serde(default)]
+    pub suspended: bool,
+    /// Absent from backups written before leeches were tracked.
+    #[serde(default)]
+    pub lapses: i64,
+    #[serde(default)]
+    pub leech: bool,
+    /// Whether becoming a leech suspended the card, so undoing the lapse can
+    /// lift it again.
+    #[serde(default)]
+    pub leech_suspended: bool,
+    /// The card's text, absent from older backups and rows not seen since.
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+impl DB {
+    pub async fn add_card(&self, card: &Card) -> Result<()> {
+        self.add_cards_batch(std::slice::from_ref(card)).await
     }
 
     pub async fn add_cards_batch(&self, cards: &[Card]) -> Result<()> {
         let mut tx = self.pool.begin().await?;
 
         let now = chrono::Utc::now().to_rfc3339();
+        let mut locations = CardLocations::default();
 
         for card in cards {
             let added_at = now.clone();
+            let (file_path, start_line, end_line) = match locations.get(card) {
+                Some((path, start, end)) => (Some(path), Some(start), Some(end)),
+                None => (None, None, None),
+            };
+            let content = card.markdown();
             sqlx::query!(
                 r#"
-            INSERT or ignore INTO cards (
+            INSERT INTO cards (
                 card_hash,
                 added_at,
                 last_reviewed_at,
@@ -60,12 +156,27 @@ impl DB {
                 interval_raw,
                 interval_days,
                 due_date,
-                review_count
+                review_count,
+                file_path,
+                start_line,
+                end_line,
+                suspended,
+                content
             )
-            VALUES (?, ?, NULL, NULL, NULL, NULL, 0, NULL, 0)
+            VALUES (?, ?, NULL, NULL, NULL, NULL, 0, NULL, 0, ?, ?, ?, ?, ?)
+            ON CONFLICT (card_hash) DO UPDATE SET
+                file_path = EXCLUDED.file_path,
+                start_line = EXCLUDED.start_line,
+                end_line = EXCLUDED.end_line,
+                content = EXCLUDED.content
             "#,
                 card.card_hash,
-                added_at
+                added_at,
+                file_path,
+                start_line,
+                end_line,
+                card.suspended_by_default,
+                content
             )
             .execute(&mut *tx)
             .await?;
@@ -75,6 +186,18 @@ impl DB {
         Ok(())
     }
 
+    /// The text stored for `card_hash` when it was last registered, if any.
+    /// Rows written before the text was kept have none until seen again.
+    pub async fn card_content(&self, card_hash: &str) -> Result<Option<String>> {
+        let content = sqlx::query_scalar!(
+            r#"SELECT content as "content?: String" FROM cards WHERE card_hash = ?"#,
+            card_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(content.flatten())
+    }
+
     pub async fn card_exists(&self, card: &Card) -> Result<bool> {
         let count: i64 = sqlx::query_scalar!(
             r#"SELECT COUNT(1) as "count!: i64" FROM cards WHERE card_hash = ?"#,
@@ -85,6 +208,167 @@ impl DB {
         Ok(count > 0)
     }
 
+    /// Pairs each registered card with the reviewed DB row that last sat at the
+    /// same file and line range under a different hash, i.e. cards whose text
+    /// was edited since their last review. Ambiguous locations are skipped.
+    pub async fn find_edited_cards(
+        &self,
+        card_hashes: &HashMap<String, Card>,
+    ) -> Result<Vec<(String, Card)>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                card_hash,
+                file_path as "file_path!: String",
+                start_line as "start_line!: i64",
+                end_line as "end_line!: i64"
+            FROM cards
+            WHERE file_path IS NOT NULL AND review_count > 0
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut stale_by_location: HashMap<(String, i64, i64), Vec<String>> = HashMap::new();
+        for row in rows {
+            if card_hashes.contains_key(&row.card_hash) {
+                continue;
+            }
+            stale_by_location
+                .entry((row.file_path, row.start_line, row.end_line))
+                .or_default()
+                .push(row.card_hash);
+        }
+
+        let mut locations = CardLocations::default();
+        let mut edits: Vec<(String, Card)> = card_hashes
+            .values()
+            .filter_map(|card| {
+                let stale = stale_by_location.get(&locations.get(card)?)?;
+                match stale.as_slice() {
+                    [old_hash] => Some((old_hash.clone(), card.clone())),
+                    _ => None,
+                }
+            })
+            .collect();
+        edits.sort_by(|(_, a), (_, b)| {
+            (&a.file_path, a.file_card_range).cmp(&(&b.file_path, b.file_card_range))
+        });
+        Ok(edits)
+    }
+
+    /// Moves the scheduling state and review log of `old_hash` to `new_hash`,
+    /// along with the reverse card's if `drill --both-ways` created one.
+    /// Returns `false` without changing anything if the new card already has
+    /// reviews of its own.
+    pub async fn rekey_card(&self, old_hash: &str, new_hash: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        if !move_card_row(&mut tx, old_hash, new_hash).await? {
+            return Ok(false);
+        }
+        let reverse_hash = |hash: &str| get_hash(&format!("{hash} reverse"));
+        if let (Some(old_reverse), Some(new_reverse)) =
+            (reverse_hash(old_hash), reverse_hash(new_hash))
+        {
+            move_card_row(&mut tx, &old_reverse, &new_reverse).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Keeps a card out of every drill until it is unsuspended.
+    pub async fn suspend_card(&self, card: &Card) -> Result<()> {
+        sqlx::query!(
+            "UPDATE cards SET suspended = 1, leech_suspended = 0 WHERE card_hash = ?",
+            card.card_hash
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns the number of cards that were suspended before the call.
+    pub async fn unsuspend_cards(&self, card_hashes: &[String]) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut unsuspended = 0;
+        for card_hash in card_hashes {
+            unsuspended += sqlx::query!(
+                r#"
+                UPDATE cards SET suspended = 0, leech_suspended = 0
+                WHERE card_hash = ? AND suspended = 1
+                "#,
+                card_hash
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(unsuspended)
+    }
+
+    /// Moves the due dates of reviewed cards by `days` without touching their
+    /// memory state. A positive shift postpones cards that are already due to
+    /// `days` from now, so none of them is overdue afterwards; a negative one
+    /// brings forward cards due later, but never to before now. New cards are
+    /// left alone. Returns the number of cards moved.
+    pub async fn shift_due_dates(&self, card_hashes: &[String], days: i64) -> Result<u64> {
+        let now = chrono::Utc::now();
+        let shift = chrono::Duration::days(days);
+        let mut tx = self.pool.begin().await?;
+        let mut shifted = 0;
+        for card_hash in card_hashes {
+            let due_date = sqlx::query_scalar!(
+                r#"
+                SELECT due_date as "due_date?: chrono::DateTime<chrono::Utc>"
+                FROM cards
+                WHERE card_hash = ? AND review_count > 0
+                "#,
+                card_hash
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .flatten();
+            let new_due_date = match due_date {
+                Some(due) if days > 0 && due <= now => now + shift,
+                Some(due) if days < 0 && due > now => (due + shift).max(now),
+                _ => continue,
+            };
+            shifted += sqlx::query!(
+                "UPDATE cards SET due_date = ? WHERE card_hash = ?",
+                new_due_date,
+                card_hash
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(shifted)
+    }
+
+    /// Deletes the given cards' scheduling rows. Their review log is kept so
+    /// `optimize` can still learn from it. Returns the number of rows removed.
+    /// The file index is cleared too, so a deleted card that is still in a
+    /// deck gets registered again on the next scan.
+    pub async fn delete_cards_by_hash(&self, card_hashes: &[String]) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = 0;
+        for card_hash in card_hashes {
+            deleted += sqlx::query!("DELETE FROM cards WHERE card_hash = ?", card_hash)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        if deleted > 0 {
+            sqlx::query!("DELETE FROM file_index")
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
     pub async fn update_card_performance(
         &self,
         card: &Card,
@@ -97,7 +381,24 @@ impl DB {
             None => chrono::Utc::now(),
         };
 
-        let new_performance = update_performance(current_performance, review_status, now)?;
+        let elapsed_days = match current_performance {
+            Performance::New => 0,
+            Performance::Reviewed(ReviewedPerformance {
+                last_reviewed_at, ..
+            }) => now
+                .signed_duration_since(last_reviewed_at)
+                .num_days()
+                .max(0),
+        };
+
+        let settings = self.get_settings().await?;
+        let new_performance = update_performance(
+            current_performance,
+            review_status,
+            now,
+            &settings,
+            &card.card_hash,
+        )?;
 
         let interval_days = new_performance.interval_days as i64;
         let review_count = new_performance.review_count as i64;
@@ -127,13 +428,165 @@ impl DB {
         .execute(&self.pool)
         .await?;
 
+        self.log_review(&card.card_hash, review_status, now, elapsed_days)
+            .await?;
+        if review_status == ReviewStatus::Again {
+            self.record_lapse(&card.card_hash, &settings).await?;
+        }
+
         Ok(new_performance.interval_raw)
     }
 
+    /// Counts a failed review and flags the card as a leech once its failures
+    /// reach the threshold, suspending it then if leeches are set to suspend.
+    async fn record_lapse(&self, card_hash: &str, settings: &Settings) -> Result<()> {
+        let threshold = settings.leech_threshold as i64;
+        let suspend = settings.suspend_leeches;
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET
+                lapses = lapses + 1,
+                leech = CASE WHEN lapses + 1 >= ? THEN 1 ELSE leech END,
+                suspended = CASE
+                    WHEN leech = 0 AND lapses + 1 >= ? AND ? THEN 1
+                    ELSE suspended
+                END,
+                leech_suspended = CASE
+                    WHEN leech = 0 AND lapses + 1 >= ? AND ? AND suspended = 0 THEN 1
+                    ELSE leech_suspended
+                END
+            WHERE card_hash = ?
+            "#,
+            threshold,
+            threshold,
+            suspend,
+            threshold,
+            suspend,
+            card_hash,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Recounts a card's failures from its review log after a review is undone,
+    /// clearing the leech flag (and the suspension it caused) below the threshold.
+    async fn recount_lapses(&self, card_hash: &str, settings: &Settings) -> Result<()> {
+        let threshold = settings.leech_threshold as i64;
+        let again = ReviewStatus::Again.grade() as i64;
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET lapses = (
+                SELECT COUNT(*) FROM review_log
+                WHERE review_log.card_hash = cards.card_hash AND review_log.grade = ?
+            )
+            WHERE card_hash = ?
+            "#,
+            again,
+            card_hash,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET
+                leech = 0,
+                suspended = CASE WHEN leech_suspended THEN 0 ELSE suspended END,
+                leech_suspended = 0
+            WHERE card_hash = ? AND leech = 1 AND lapses < ?
+            "#,
+            card_hash,
+            threshold,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Every card flagged as a leech, keyed by hash.
+    pub async fn leeches(&self) -> Result<HashMap<String, Leech>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                card_hash,
+                lapses as "lapses!: i64",
+                suspended as "suspended!: bool"
+            FROM cards
+            WHERE leech = 1
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let leech = Leech {
+                    lapses: row.lapses,
+                    suspended: row.suspended,
+                };
+                (row.card_hash, leech)
+            })
+            .collect())
+    }
+
+    pub async fn restore_card_performance(
+        &self,
+        card: &Card,
+        performance: &Performance,
+    ) -> Result<()> {
+        let reviewed = match performance {
+            Performance::New => None,
+            Performance::Reviewed(reviewed) => Some(reviewed),
+        };
+        let last_reviewed_at = reviewed.map(|r| r.last_reviewed_at);
+        let stability = reviewed.map(|r| r.stability);
+        let difficulty = reviewed.map(|r| r.difficulty);
+        let interval_raw = reviewed.map(|r| r.interval_raw);
+        let interval_days = reviewed.map(|r| r.interval_days as i64).unwrap_or(0);
+        let due_date = reviewed.map(|r| r.due_date);
+        let review_count = reviewed.map(|r| r.review_count as i64).unwrap_or(0);
+
+        sqlx::query!(
+            r#"
+            UPDATE cards
+            SET
+                last_reviewed_at = ?,
+                stability = ?,
+                difficulty = ?,
+                interval_raw = ?,
+                interval_days = ?,
+                due_date = ?,
+                review_count = ?
+            WHERE card_hash = ?
+            "#,
+            last_reviewed_at,
+            stability,
+            difficulty,
+            interval_raw,
+            interval_days,
+            due_date,
+            review_count,
+            card.card_hash,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.delete_last_review_log(&card.card_hash).await?;
+        let settings = self.get_settings().await?;
+        self.recount_lapses(&card.card_hash, &settings).await?;
+
+        Ok(())
+    }
+
     pub async fn get_card_performance(&self, card: &Card) -> Result<Performance> {
-        let row = sqlx::query!(
+        let row = sqlx::query_as!(
+            PerformanceRow,
             r#"
             SELECT
+                card_hash,
                 last_reviewed_at as "last_reviewed_at?: chrono::DateTime<chrono::Utc>",
                 stability as "stability?: f64",
                 difficulty as "difficulty?: f64",
@@ -149,107 +602,288 @@ impl DB {
         .fetch_one(&self.pool)
         .await?;
 
-        let review_count: i64 = row.review_count;
-        if review_count == 0 {
-            return Ok(Performance::default());
+        row.into_performance()
+    }
+
+    /// Scheduling state of every unsuspended card in `card_hashes`.
+    pub async fn card_performances(
+        &self,
+        card_hashes: &HashMap<String, Card>,
+    ) -> Result<Vec<(String, Performance)>> {
+        let mut rows = sqlx::query_as!(
+            PerformanceRow,
+            r#"
+            SELECT
+                card_hash,
+                last_reviewed_at as "last_reviewed_at?: chrono::DateTime<chrono::Utc>",
+                stability as "stability?: f64",
+                difficulty as "difficulty?: f64",
+                interval_raw as "interval_raw?: f64",
+                interval_days as "interval_days?: i64",
+                due_date as "due_date?: chrono::DateTime<chrono::Utc>",
+                review_count as "review_count!: i64"
+            FROM cards
+            WHERE suspended = 0
+            "#,
+        )
+        .fetch(&self.pool);
+
+        let mut performances = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            if !card_hashes.contains_key(&row.card_hash) {
+                continue;
+            }
+            let card_hash = row.card_hash.clone();
+            performances.push((card_hash, row.into_performance()?));
         }
-        let reviewed = ReviewedPerformance {
-            last_reviewed_at: row
-                .last_reviewed_at
-                .ok_or_else(|| anyhow!("missing last_reviewed_at for card {}", card.card_hash))?,
-            stability: row
-                .stability
-                .ok_or_else(|| anyhow!("missing stability for card {}", card.card_hash))?,
-            difficulty: row
-                .difficulty
-                .ok_or_else(|| anyhow!("missing difficulty for card {}", card.card_hash))?,
-            interval_raw: row
-                .interval_raw
-                .ok_or_else(|| anyhow!("missing interval_raw for card {}", card.card_hash))?,
-            interval_days: row
-                .interval_days
-                .ok_or_else(|| anyhow!("missing interval_days for card {}", card.card_hash))?
-                as usize,
-            due_date: row
-                .due_date
-                .ok_or_else(|| anyhow!("missing due_date for card {}", card.card_hash))?,
-            review_count: review_count as usize,
-        };
+        Ok(performances)
+    }
 
-        Ok(Performance::Reviewed(reviewed))
+    pub async fn export_all_cards(&self) -> Result<Vec<CardExportRow>> {
+        self.export_cards(None).await
+    }
+
+    /// Every card row, or with `since` only the cards reviewed or added at or
+    /// after it.
+    pub async fn export_cards(
+        &self,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<CardExportRow>> {
+        let since = since.map(|since| since.to_rfc3339());
+        let rows = sqlx::query_as!(
+            CardExportRow,
+            r#"
+            SELECT
+                card_hash,
+                added_at,
+                last_reviewed_at,
+                stability as "stability?: f64",
+                difficulty as "difficulty?: f64",
+                interval_raw as "interval_raw?: f64",
+                interval_days as "interval_days?: i64",
+                due_date,
+                review_count as "review_count!: i64",
+                suspended as "suspended!: bool",
+                lapses as "lapses!: i64",
+                leech as "leech!: bool",
+                leech_suspended as "leech_suspended!: bool",
+                content as "content?: String"
+            FROM cards
+            WHERE ?1 IS NULL
+                OR julianday(last_reviewed_at) >= julianday(?1)
+                OR julianday(added_at) >= julianday(?1)
+            ORDER BY added_at ASC, card_hash ASC
+            "#,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
     }
 
     pub async fn due_today(
         &self,
         card_hashes: &HashMap<String, Card>,
-        card_limit: Option<usize>,
-        new_card_limit: Option<usize>,
+        options: DueOptions,
     ) -> Result<Vec<Card>> {
-        let now = (chrono::Utc::now() + LEARN_AHEAD_THRESHOLD_MINS).to_rfc3339();
+        let settings = self.get_settings().await?;
+        let (day_start_hour, learn_ahead) = (settings.day_start_hour, settings.learn_ahead);
+        let now = chrono::Local::now();
+        // review cards due before the next study day are picked out below
+        let cutoff = (now.to_utc() + chrono::Duration::from_std(learn_ahead)?)
+            .max(start_of_study_day(&now, day_start_hour) + chrono::Duration::days(1))
+            .to_rfc3339();
+
+        // the hashes go in as one JSON array so SQLite only reads the rows of
+        // these cards, not every card in a database shared across collections
+        let hashes = serde_json::to_string(&card_hashes.keys().collect::<Vec<_>>())?;
 
         // most overdue cards first
         // then cards due today
-        // then new cards
+        // then new cards, oldest first
         let mut rows = sqlx::query!(
             r#"
-        SELECT card_hash, review_count as "review_count!: i64"
+        SELECT
+            card_hash,
+            review_count as "review_count!: i64",
+            interval_raw as "interval_raw?: f64",
+            due_date as "due_date?: chrono::DateTime<chrono::Utc>"
         FROM cards
-        WHERE due_date <= ? OR due_date IS NULL
+        WHERE card_hash IN (SELECT value FROM json_each(?))
+            AND (due_date <= ? OR due_date IS NULL)
+            AND suspended = 0
         ORDER BY
             CASE WHEN due_date IS NULL THEN 1 ELSE 0 END,
-            due_date ASC
+            due_date ASC,
+            rowid ASC
         "#,
-            now
+            hashes,
+            cutoff
         )
         .fetch(&self.pool);
 
-        let mut cards: Vec<Card> = Vec::new();
-        let mut num_new_cards = 0;
+        let mut due_cards: Vec<Card> = Vec::new();
+        let mut new_cards: Vec<Card> = Vec::new();
+        let mut cards_per_file: HashMap<&Path, usize> = HashMap::new();
 
         while let Some(row) = rows.try_next().await? {
-            if !card_hashes.contains_key(&row.card_hash) {
+            let Some(card) = card_hashes.get(&row.card_hash) else {
+                continue;
+            };
+            let interval = row.interval_raw.unwrap_or_default();
+            if let Some(due_date) = row.due_date
+                && !counts_as_due(due_date, interval, &now, day_start_hour, learn_ahead)
+            {
+                continue;
+            }
+            let lifecycle = CardLifeCycle::classify(row.review_count, interval);
+            if options.only.is_some_and(|only| only != lifecycle) {
+                continue;
+            }
+            let from_file = cards_per_file.entry(card.file_path.as_path()).or_default();
+            if options
+                .per_file_limit
+                .is_some_and(|limit| *from_file >= limit)
+            {
                 continue;
             }
 
-            let is_new = row.review_count == 0;
-
-            if is_new
-                && let Some(limit) = new_card_limit
-                && num_new_cards >= limit
+            if row.review_count > 0 {
+                due_cards.push(card.clone());
+            } else if options
+                .new_card_limit
+                .is_none_or(|limit| new_cards.len() < limit)
             {
+                new_cards.push(card.clone());
+            } else {
                 continue;
             }
+            *from_file += 1;
+        }
 
-            if let Some(card) = card_hashes.get(&row.card_hash) {
-                cards.push(card.clone());
+        Ok(arrange_session(due_cards, new_cards, options))
+    }
+}
 
-                if is_new {
-                    num_new_cards += 1;
+/// Orders due and new cards for a session and applies the card limit, which
+/// keeps the cards that come first in that order.
+fn arrange_session(due_cards: Vec<Card>, new_cards: Vec<Card>, options: DueOptions) -> Vec<Card> {
+    let due = due_cards.into_iter().map(|card| (false, card));
+    let new = new_cards.into_iter().map(|card| (true, card));
+    let mut session: Vec<(bool, Card)> = match options.order {
+        DrillOrder::DueFirst => due.chain(new).collect(),
+        DrillOrder::NewFirst => new.chain(due).collect(),
+        DrillOrder::Interleave => {
+            let (mut due, mut new) = (due.fuse(), new.fuse());
+            let mut session = Vec::new();
+            loop {
+                let (next_due, next_new) = (due.next(), new.next());
+                if next_due.is_none() && next_new.is_none() {
+                    break session;
                 }
+                session.extend(next_due);
+                session.extend(next_new);
+            }
+        }
+    };
+    if let Some(limit) = options.card_limit {
+        session.truncate(limit);
+    }
 
-                if let Some(limit) = card_limit
-                    && cards.len() >= limit
-                {
-                    break;
-                }
+    if options.shuffle {
+        for group in [false, true] {
+            let slots: Vec<usize> = (0..session.len())
+                .filter(|&idx| session[idx].0 == group)
+                .collect();
+            let mut cards: Vec<Card> = slots.iter().map(|&idx| session[idx].1.clone()).collect();
+            cards.shuffle(&mut rand::rng());
+            for (idx, card) in slots.into_iter().zip(cards) {
+                session[idx].1 = card;
             }
         }
+    }
 
-        Ok(cards)
+    session.into_iter().map(|(_, card)| card).collect()
+}
+
+/// Card locations with each deck file canonicalized once, however many cards
+/// it holds.
+#[derive(Default)]
+struct CardLocations {
+    canonical_paths: HashMap<PathBuf, String>,
+}
+
+impl CardLocations {
+    /// Where a forward card sits on disk. Reverse cards share their forward
+    /// card's lines, so they are left out to keep locations unique.
+    fn get(&mut self, card: &Card) -> Option<(String, i64, i64)> {
+        if card.direction != CardDirection::Forward {
+            return None;
+        }
+        let path = self
+            .canonical_paths
+            .entry(card.file_path.clone())
+            .or_insert_with(|| {
+                std::fs::canonicalize(&card.file_path)
+                    .unwrap_or_else(|_| card.file_path.clone())
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .clone();
+        let (start, end) = card.file_card_range;
+        Some((path, start as i64, end as i64))
     }
 }
 
+async fn move_card_row(
+    conn: &mut sqlx::SqliteConnection,
+    old_hash: &str,
+    new_hash: &str,
+) -> Result<bool> {
+    let new_reviews = sqlx::query_scalar!(
+        r#"SELECT review_count as "review_count!: i64" FROM cards WHERE card_hash = ?"#,
+        new_hash
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if new_reviews.is_some_and(|count| count > 0) {
+        return Ok(false);
+    }
+
+    sqlx::query!("DELETE FROM cards WHERE card_hash = ?", new_hash)
+        .execute(&mut *conn)
+        .await?;
+    let moved = sqlx::query!(
+        "UPDATE cards SET card_hash = ? WHERE card_hash = ?",
+        new_hash,
+        old_hash
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    sqlx::query!(
+        "UPDATE review_log SET card_hash = ? WHERE card_hash = ?",
+        new_hash,
+        old_hash
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(moved > 0)
+}
+
 #[cfg(test)]
 mod tests {
 
     use std::collections::HashMap;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
-    use crate::fsrs::{Performance, ReviewStatus};
+    use crate::card::{Card, CardContent};
+    use crate::fsrs::{FUZZ_RATIO, Performance, ReviewStatus, ReviewedPerformance};
     use crate::parser::content_to_card;
-    use crate::stats::CardLifeCycle;
+    use crate::stats::{CardLifeCycle, DEFAULT_HORIZON_DAYS};
 
-    use super::DB;
+    use super::{DB, DrillOrder, DueOptions, arrange_session};
 
     #[tokio::test]
     async fn follow_card_progress() {
@@ -266,18 +900,24 @@ mod tests {
 
         // should be in stats
         let card_hashes = HashMap::from([(card.card_hash.clone(), card.clone())]);
-        let stats = db.collection_stats(&card_hashes).await.unwrap();
+        let stats = db
+            .collection_stats(&card_hashes, DEFAULT_HORIZON_DAYS)
+            .await
+            .unwrap();
         assert_eq!(stats.num_cards, 1);
         assert_eq!(stats.due_cards, 1);
         assert_eq!(stats.card_lifecycles.get(&CardLifeCycle::New).unwrap(), &1);
 
         // should be due today
-        let due_today_cards = db.due_today(&card_hashes, None, None).await.unwrap();
+        let due_today_cards = db
+            .due_today(&card_hashes, DueOptions::default())
+            .await
+            .unwrap();
         assert_eq!(due_today_cards.len(), 1);
 
         // check short-term scheduling
         for _ in 0..3 {
-            db.update_card_performance(&card, ReviewStatus::Pass, None)
+            db.update_card_performance(&card, ReviewStatus::Good, None)
                 .await
                 .unwrap();
         }
@@ -292,52 +932,52 @@ mod tests {
 
         // wait the interval and then pass again
         let mut future_time = chrono::Utc::now() + chrono::Duration::days(1);
-        db.update_card_performance(&card, ReviewStatus::Pass, Some(future_time))
+        db.update_card_performance(&card, ReviewStatus::Good, Some(future_time))
             .await
             .unwrap();
 
         match db.get_card_performance(&card).await.unwrap() {
             Performance::Reviewed(reviewed) => {
                 assert_eq!(reviewed.review_count, 4);
-                assert_eq!(reviewed.interval_raw, 7.32306712962963);
-                assert_eq!(reviewed.interval_days, 7);
+                assert_fuzzed(reviewed.interval_raw, 7.32306712962963);
+                assert_eq!(reviewed.interval_days, reviewed.interval_raw as usize);
             }
             _ => panic!(),
         }
 
         // wait the interval and then pass again
         future_time += chrono::Duration::days(7);
-        db.update_card_performance(&card, ReviewStatus::Pass, Some(future_time))
+        db.update_card_performance(&card, ReviewStatus::Good, Some(future_time))
             .await
             .unwrap();
 
         match db.get_card_performance(&card).await.unwrap() {
             Performance::Reviewed(reviewed) => {
                 assert_eq!(reviewed.review_count, 5);
-                assert_eq!(reviewed.interval_raw, 31.727581018518517);
-                assert_eq!(reviewed.interval_days, 31);
+                assert_fuzzed(reviewed.interval_raw, 31.727581018518517);
+                assert_eq!(reviewed.interval_days, reviewed.interval_raw as usize);
             }
             _ => panic!(),
         }
 
         // now collapse it with a failure
         future_time += chrono::Duration::days(31);
-        db.update_card_performance(&card, ReviewStatus::Fail, Some(future_time))
+        db.update_card_performance(&card, ReviewStatus::Again, Some(future_time))
             .await
             .unwrap();
 
         match db.get_card_performance(&card).await.unwrap() {
             Performance::Reviewed(reviewed) => {
                 assert_eq!(reviewed.review_count, 6);
-                assert_eq!(reviewed.interval_raw, 2.5044675925925928);
-                assert_eq!(reviewed.interval_days, 2);
+                assert_fuzzed(reviewed.interval_raw, 2.5044675925925928);
+                assert_eq!(reviewed.interval_days, reviewed.interval_raw as usize);
             }
             _ => panic!(),
         }
 
         // another failure
         future_time += chrono::Duration::days(2);
-        db.update_card_performance(&card, ReviewStatus::Fail, Some(future_time))
+        db.update_card_performance(&card, ReviewStatus::Again, Some(future_time))
             .await
             .unwrap();
 
@@ -349,4 +989,613 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    #[tokio::test]
+    async fn orphaned_rows_are_reported_and_deleted() {
+        let db = DB::new_in_memory().await.unwrap();
+        let deck = tempfile::tempdir().unwrap();
+        let other_deck = tempfile::tempdir().unwrap();
+        let path = deck.path().join("test.md");
+        let other_path = other_deck.path().join("other.md");
+        std::fs::write(&path, "").unwrap();
+        std::fs::write(&other_path, "").unwrap();
+        let kept = content_to_card(&path, "Q: kept?\nA: yes", 1, 2).unwrap();
+        let edited = content_to_card(&path, "Q: old wording?\nA: yes", 3, 4).unwrap();
+        let elsewhere = content_to_card(&other_path, "Q: other deck?\nA: yes", 1, 2).unwrap();
+        let reverse = kept.reversed().unwrap();
+        let reverse_hash = reverse.card_hash.clone();
+        db.add_cards_batch(&[kept.clone(), edited.clone(), elsewhere.clone(), reverse])
+            .await
+            .unwrap();
+
+        let card_hashes = HashMap::from([(kept.card_hash.clone(), kept.clone())]);
+        let roots = [deck.path().to_path_buf()];
+        let stats = db
+            .collection_stats(&card_hashes, DEFAULT_HORIZON_DAYS)
+            .await
+            .unwrap();
+        assert_eq!(stats.total_cards_in_db, 4);
+        let orphans = db.orphaned_cards(&card_hashes, &roots).await.unwrap();
+        // the other deck was not scanned, so its rows are not orphans
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].card_hash, edited.card_hash);
+        assert_eq!(orphans[0].file_path, path.canonicalize().unwrap());
+        assert_eq!(orphans[0].start_line, 3);
+        // the orphan can still be shown without its markdown
+        assert_eq!(
+            db.card_content(&edited.card_hash).await.unwrap().as_deref(),
+            Some("Q: old wording?\nA: yes")
+        );
+        assert_eq!(
+            db.card_content(&reverse_hash).await.unwrap().as_deref(),
+            Some("Q: yes\nA: kept?")
+        );
+
+        assert_eq!(
+            db.delete_cards_by_hash(std::slice::from_ref(&edited.card_hash))
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(!db.card_exists(&edited).await.unwrap());
+        assert!(db.card_exists(&kept).await.unwrap());
+        assert!(db.card_exists(&elsewhere).await.unwrap());
+        assert!(
+            db.orphaned_cards(&card_hashes, &roots)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn edited_cards_can_keep_their_history() {
+        let db = DB::new_in_memory().await.unwrap();
+        let path = PathBuf::from("test.md");
+        let original = content_to_card(&path, "Q: capitol of France?\nA: Paris", 4, 6).unwrap();
+        let reverse = original.reversed().unwrap();
+        db.add_cards_batch(&[original.clone(), reverse.clone()])
+            .await
+            .unwrap();
+        for card in [&original, &reverse] {
+            db.update_card_performance(card, ReviewStatus::Good, None)
+                .await
+                .unwrap();
+        }
+        let performance = db.get_card_performance(&original).await.unwrap();
+
+        let edited = content_to_card(&path, "Q: capital of France?\nA: Paris", 4, 6).unwrap();
+        let moved = content_to_card(&path, "Q: unrelated?\nA: yes", 8, 10).unwrap();
+        db.add_cards_batch(&[edited.clone(), moved.clone()])
+            .await
+            .unwrap();
+        let card_hashes = HashMap::from([
+            (edited.card_hash.clone(), edited.clone()),
+            (moved.card_hash.clone(), moved.clone()),
+        ]);
+
+        let edits = db.find_edited_cards(&card_hashes).await.unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].0, original.card_hash);
+        assert_eq!(edits[0].1.card_hash, edited.card_hash);
+
+        assert!(
+            db.rekey_card(&original.card_hash, &edited.card_hash)
+                .await
+                .unwrap()
+        );
+        assert!(!db.card_exists(&original).await.unwrap());
+        assert_eq!(db.get_card_performance(&edited).await.unwrap(), performance);
+        let edited_reverse = edited.reversed().unwrap();
+        assert!(matches!(
+            db.get_card_performance(&edited_reverse).await.unwrap(),
+            Performance::Reviewed(_)
+        ));
+        let logs = db.review_logs().await.unwrap();
+        assert!(logs.iter().all(|log| log.card_hash != original.card_hash));
+        assert!(db.find_edited_cards(&card_hashes).await.unwrap().is_empty());
+
+        // a card reviewed under its new text keeps its own history
+        db.update_card_performance(&moved, ReviewStatus::Good, None)
+            .await
+            .unwrap();
+        assert!(
+            !db.rekey_card(&edited.card_hash, &moved.card_hash)
+                .await
+                .unwrap()
+        );
+        assert!(db.card_exists(&edited).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn reviewed_today_counts_todays_reviews_of_registered_cards() {
+        let db = DB::new_in_memory().await.unwrap();
+        let path = PathBuf::from("test.md");
+        let card = content_to_card(
+            &path,
+            "Q: today?
+A: yes",
+            1,
+            2,
+        )
+        .unwrap();
+        db.add_cards_batch(std::slice::from_ref(&card))
+            .await
+            .unwrap();
+        let card_hashes = HashMap::from([(card.card_hash.clone(), card.clone())]);
+
+        let now = chrono::Utc::now();
+        db.log_review(&card.card_hash, ReviewStatus::Again, now, 0)
+            .await
+            .unwrap();
+        db.log_review(&card.card_hash, ReviewStatus::Good, now, 0)
+            .await
+            .unwrap();
+        db.log_review(
+            &card.card_hash,
+            ReviewStatus::Good,
+            now - chrono::Duration::days(2),
+            0,
+        )
+        .await
+        .unwrap();
+        db.log_review("elsewhere", ReviewStatus::Good, now, 0)
+            .await
+            .unwrap();
+
+        let stats = db
+            .collection_stats(&card_hashes, DEFAULT_HORIZON_DAYS)
+            .await
+            .unwrap();
+        assert_eq!(stats.reviewed_today, 2);
+    }
+
+    #[tokio::test]
+    async fn suspended_cards_are_not_due_until_unsuspended() {
+        let db = DB::new_in_memory().await.unwrap();
+        let path = PathBuf::from("test.md");
+        let card = content_to_card(&path, "Q: broken?\nA: yes", 1, 2).unwrap();
+        let other = content_to_card(&path, "Q: fine?\nA: yes", 3, 4).unwrap();
+        db.add_cards_batch(&[card.clone(), other.clone()])
+            .await
+            .unwrap();
+        let card_hashes = HashMap::from([
+            (card.card_hash.clone(), card.clone()),
+            (other.card_hash.clone(), other.clone()),
+        ]);
+
+        db.suspend_card(&card).await.unwrap();
+        let due = db
+            .due_today(&card_hashes, DueOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].card_hash, other.card_hash);
+        let stats = db
+            .collection_stats(&card_hashes, DEFAULT_HORIZON_DAYS)
+            .await
+            .unwrap();
+        assert_eq!(stats.suspended_cards, 1);
+        assert_eq!(stats.due_cards, 1);
+
+        let hashes = vec![card.card_hash.clone(), other.card_hash.clone()];
+        assert_eq!(db.unsuspend_cards(&hashes).await.unwrap(), 1);
+        assert_eq!(
+            db.due_today(&card_hashes, DueOptions::default())
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn repeated_failures_flag_and_suspend_leeches() {
+        let db = DB::new_in_memory().await.unwrap();
+        db.set_leech_threshold(2).await.unwrap();
+        db.set_suspend_leeches(true).await.unwrap();
+        let path = PathBuf::from("test.md");
+        let card = content_to_card(&path, "Q: hard?\nA: yes", 1, 2).unwrap();
+        db.add_card(&card).await.unwrap();
+        let card_hashes = HashMap::from([(card.card_hash.clone(), card.clone())]);
+
+        db.update_card_performance(&card, ReviewStatus::Again, None)
+            .await
+            .unwrap();
+        db.update_card_performance(&card, ReviewStatus::Good, None)
+            .await
+            .unwrap();
+        assert!(db.leeches().await.unwrap().is_empty());
+
+        let before = db.get_card_performance(&card).await.unwrap();
+        db.update_card_performance(&card, ReviewStatus::Again, None)
+            .await
+            .unwrap();
+        let leech = db.leeches().await.unwrap()[&card.card_hash];
+        assert_eq!(leech.lapses, 2);
+        assert!(leech.suspended);
+        let stats = db
+            .collection_stats(&card_hashes, DEFAULT_HORIZON_DAYS)
+            .await
+            .unwrap();
+        assert_eq!((stats.leech_cards, stats.suspended_cards), (1, 1));
+
+        // undoing the failure that made it a leech lifts the flag and suspension
+        db.restore_card_performance(&card, &before).await.unwrap();
+        assert!(db.leeches().await.unwrap().is_empty());
+        let stats = db
+            .collection_stats(&card_hashes, DEFAULT_HORIZON_DAYS)
+            .await
+            .unwrap();
+        assert_eq!((stats.leech_cards, stats.suspended_cards), (0, 0));
+
+        // an unsuspended leech stays flagged but isn't suspended again
+        db.update_card_performance(&card, ReviewStatus::Again, None)
+            .await
+            .unwrap();
+        db.unsuspend_cards(std::slice::from_ref(&card.card_hash))
+            .await
+            .unwrap();
+        db.update_card_performance(&card, ReviewStatus::Again, None)
+            .await
+            .unwrap();
+        let leech = db.leeches().await.unwrap()[&card.card_hash];
+        assert_eq!(leech.lapses, 3);
+        assert!(!leech.suspended);
+    }
+
+    #[tokio::test]
+    async fn undoing_a_leech_keeps_a_hand_suspension() {
+        let db = DB::new_in_memory().await.unwrap();
+        db.set_leech_threshold(1).await.unwrap();
+        db.set_suspend_leeches(true).await.unwrap();
+        let path = PathBuf::from("test.md");
+        let card = content_to_card(&path, "Q: hard?\nA: yes", 1, 2).unwrap();
+        db.add_card(&card).await.unwrap();
+        db.suspend_card(&card).await.unwrap();
+
+        let before = db.get_card_performance(&card).await.unwrap();
+        db.update_card_performance(&card, ReviewStatus::Again, None)
+            .await
+            .unwrap();
+        assert!(db.leeches().await.unwrap()[&card.card_hash].suspended);
+
+        db.restore_card_performance(&card, &before).await.unwrap();
+        assert!(db.leeches().await.unwrap().is_empty());
+        let row = &db.export_all_cards().await.unwrap()[0];
+        assert!(row.suspended);
+        assert_eq!((row.lapses, row.leech), (0, false));
+    }
+
+    #[tokio::test]
+    async fn cards_suspended_by_default_start_suspended_once() {
+        let db = DB::new_in_memory().await.unwrap();
+        let path = PathBuf::from("test.md");
+        let mut card = content_to_card(&path, "Q: later?\nA: yes", 1, 2).unwrap();
+        card.suspended_by_default = true;
+        let card_hashes = HashMap::from([(card.card_hash.clone(), card.clone())]);
+
+        db.add_card(&card).await.unwrap();
+        let due = db
+            .due_today(&card_hashes, DueOptions::default())
+            .await
+            .unwrap();
+        assert!(due.is_empty());
+
+        // registering the file again keeps an explicit unsuspend
+        db.unsuspend_cards(std::slice::from_ref(&card.card_hash))
+            .await
+            .unwrap();
+        db.add_card(&card).await.unwrap();
+        let due = db
+            .due_today(&card_hashes, DueOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn shifting_due_dates_moves_only_reviewed_cards() {
+        let db = DB::new_in_memory().await.unwrap();
+        let path = PathBuf::from("deck.md");
+        let overdue = content_to_card(&path, "Q: overdue?\nA: yes", 1, 2).unwrap();
+        let upcoming = content_to_card(&path, "Q: upcoming?\nA: yes", 3, 4).unwrap();
+        let new = content_to_card(&path, "Q: new?\nA: yes", 5, 6).unwrap();
+        for card in [&overdue, &upcoming, &new] {
+            db.add_card(card).await.unwrap();
+        }
+        let long_ago = chrono::Utc::now() - chrono::Duration::days(60);
+        db.update_card_performance(&overdue, ReviewStatus::Again, Some(long_ago))
+            .await
+            .unwrap();
+        db.update_card_performance(&upcoming, ReviewStatus::Easy, None)
+            .await
+            .unwrap();
+        let due_date = |perf: Performance| match perf {
+            Performance::Reviewed(reviewed) => reviewed.due_date,
+            Performance::New => panic!("expected a reviewed card"),
+        };
+        let before_overdue = due_date(db.get_card_performance(&overdue).await.unwrap());
+        let before_upcoming = due_date(db.get_card_performance(&upcoming).await.unwrap());
+
+        let hashes: Vec<String> = [&overdue, &upcoming, &new]
+            .iter()
+            .map(|card| card.card_hash.clone())
+            .collect();
+        let before_shift = chrono::Utc::now();
+        assert_eq!(db.shift_due_dates(&hashes, 5).await.unwrap(), 1);
+        let postponed = due_date(db.get_card_performance(&overdue).await.unwrap());
+        assert!(postponed > before_overdue + chrono::Duration::days(5));
+        assert!(postponed >= before_shift + chrono::Duration::days(5));
+        assert!(postponed <= chrono::Utc::now() + chrono::Duration::days(5));
+        assert_eq!(
+            due_date(db.get_card_performance(&upcoming).await.unwrap()),
+            before_upcoming
+        );
+
+        assert_eq!(db.shift_due_dates(&hashes, -365).await.unwrap(), 2);
+        let advanced = due_date(db.get_card_performance(&upcoming).await.unwrap());
+        assert!(advanced < before_upcoming && advanced <= chrono::Utc::now());
+        assert_eq!(
+            db.get_card_performance(&new).await.unwrap(),
+            Performance::New
+        );
+    }
+
+    #[tokio::test]
+    async fn restore_card_performance_reverts_review() {
+        let db = DB::new_in_memory().await.unwrap();
+        let card = content_to_card(&PathBuf::from("test.md"), "C: ping? [pong]", 1, 1).unwrap();
+        db.add_card(&card).await.unwrap();
+
+        let before_first = db.get_card_performance(&card).await.unwrap();
+        db.update_card_performance(&card, ReviewStatus::Good, None)
+            .await
+            .unwrap();
+        let before_second = db.get_card_performance(&card).await.unwrap();
+        db.update_card_performance(&card, ReviewStatus::Again, None)
+            .await
+            .unwrap();
+
+        db.restore_card_performance(&card, &before_second)
+            .await
+            .unwrap();
+        assert_eq!(db.get_card_performance(&card).await.unwrap(), before_second);
+
+        db.restore_card_performance(&card, &before_first)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_card_performance(&card).await.unwrap(),
+            Performance::New
+        );
+        let card_hashes = HashMap::from([(card.card_hash.clone(), card.clone())]);
+        let due_today_cards = db
+            .due_today(&card_hashes, DueOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(due_today_cards.len(), 1);
+    }
+
+    /// Intervals over a day are fuzzed, so compare against the unfuzzed value
+    /// with the fuzz ratio as the tolerance.
+    fn assert_fuzzed(actual: f64, unfuzzed: f64) {
+        assert!(
+            (actual - unfuzzed).abs() <= unfuzzed * FUZZ_RATIO + 1e-6,
+            "{actual} is not within fuzz of {unfuzzed}"
+        );
+    }
+
+    #[tokio::test]
+    async fn per_file_limit_balances_cards_across_files() {
+        let db = DB::new_in_memory().await.unwrap();
+        let mut cards = Vec::new();
+        for (file, count) in [("big.md", 5), ("small.md", 1)] {
+            for idx in 0..count {
+                let content = format!("Q: {file} {idx}?\nA: yes");
+                cards.push(content_to_card(&PathBuf::from(file), &content, idx, idx + 1).unwrap());
+            }
+        }
+        db.add_cards_batch(&cards).await.unwrap();
+        let card_hashes: HashMap<_, _> = cards
+            .iter()
+            .map(|card| (card.card_hash.clone(), card.clone()))
+            .collect();
+        let from_big = |due: &[Card]| {
+            due.iter()
+                .filter(|card| card.file_path == Path::new("big.md"))
+                .count()
+        };
+
+        let options = DueOptions {
+            per_file_limit: Some(2),
+            ..DueOptions::default()
+        };
+        let due = db.due_today(&card_hashes, options).await.unwrap();
+        assert_eq!((due.len(), from_big(&due)), (3, 2));
+
+        let options = DueOptions {
+            per_file_limit: Some(2),
+            new_card_limit: Some(2),
+            ..DueOptions::default()
+        };
+        let due = db.due_today(&card_hashes, options).await.unwrap();
+        assert_eq!(due.len(), 2);
+        assert!(from_big(&due) <= 2);
+    }
+
+    #[tokio::test]
+    async fn due_today_only_reads_the_given_cards_and_keeps_new_ones_in_added_order() {
+        let db = DB::new_in_memory().await.unwrap();
+        let path = PathBuf::from("deck.md");
+        for name in ["other deck", "first", "second", "third"] {
+            let card = content_to_card(&path, &format!("Q: {name}?\nA: yes"), 1, 2).unwrap();
+            db.add_cards_batch(&[card]).await.unwrap();
+        }
+        let cards: Vec<Card> = ["third", "first", "second"]
+            .iter()
+            .map(|name| content_to_card(&path, &format!("Q: {name}?\nA: yes"), 1, 2).unwrap())
+            .collect();
+        let card_hashes: HashMap<String, Card> = cards
+            .iter()
+            .map(|card| (card.card_hash.clone(), card.clone()))
+            .collect();
+
+        let options = DueOptions {
+            new_card_limit: Some(2),
+            ..DueOptions::default()
+        };
+        let due = db.due_today(&card_hashes, options).await.unwrap();
+        let hashes: Vec<&str> = due.iter().map(|card| card.card_hash.as_str()).collect();
+        assert_eq!(hashes, [&cards[1].card_hash, &cards[2].card_hash]);
+    }
+
+    #[tokio::test]
+    async fn a_wider_learn_ahead_window_pulls_in_cards_due_soon() {
+        let db = DB::new_in_memory().await.unwrap();
+        let path = PathBuf::from("deck.md");
+        let cards: Vec<Card> = ["in 5 minutes", "in 45 minutes", "in 3 hours"]
+            .iter()
+            .map(|name| content_to_card(&path, &format!("Q: {name}?\nA: yes"), 1, 2).unwrap())
+            .collect();
+        db.add_cards_batch(&cards).await.unwrap();
+        let now = chrono::Utc::now();
+        for (card, minutes) in cards.iter().zip([5, 45, 180]) {
+            let performance = Performance::Reviewed(ReviewedPerformance {
+                last_reviewed_at: now,
+                stability: 0.1,
+                difficulty: 5.0,
+                interval_raw: 0.01,
+                interval_days: 0,
+                due_date: now + chrono::Duration::minutes(minutes),
+                review_count: 1,
+            });
+            db.restore_card_performance(card, &performance)
+                .await
+                .unwrap();
+        }
+        let card_hashes: HashMap<String, Card> = cards
+            .iter()
+            .map(|card| (card.card_hash.clone(), card.clone()))
+            .collect();
+        let due_hashes = |due: Vec<Card>| -> Vec<String> {
+            due.into_iter().map(|card| card.card_hash).collect()
+        };
+
+        let due = db
+            .due_today(&card_hashes, DueOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(due_hashes(due), [cards[0].card_hash.clone()]);
+
+        db.set_learn_ahead(std::time::Duration::from_secs(60 * 60))
+            .await
+            .unwrap();
+        let due = db
+            .due_today(&card_hashes, DueOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            due_hashes(due),
+            [cards[0].card_hash.clone(), cards[1].card_hash.clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_option_drills_a_single_lifecycle() {
+        let db = DB::new_in_memory().await.unwrap();
+        let path = PathBuf::from("deck.md");
+        let cards: Vec<Card> = ["new 1", "new 2", "young"]
+            .iter()
+            .map(|name| content_to_card(&path, &format!("Q: {name}?\nA: yes"), 1, 2).unwrap())
+            .collect();
+        db.add_cards_batch(&cards).await.unwrap();
+        let card_hashes: HashMap<String, Card> = cards
+            .iter()
+            .map(|card| (card.card_hash.clone(), card.clone()))
+            .collect();
+        let young = &cards[2];
+        db.update_card_performance(young, ReviewStatus::Again, None)
+            .await
+            .unwrap();
+
+        let only = |only, card_limit| DueOptions {
+            only: Some(only),
+            card_limit,
+            ..DueOptions::default()
+        };
+        let due = db
+            .due_today(&card_hashes, only(CardLifeCycle::Young, None))
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].card_hash, young.card_hash);
+        let due = db
+            .due_today(&card_hashes, only(CardLifeCycle::New, Some(1)))
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_ne!(due[0].card_hash, young.card_hash);
+        let due = db
+            .due_today(&card_hashes, only(CardLifeCycle::Mature, None))
+            .await
+            .unwrap();
+        assert!(due.is_empty());
+    }
+
+    #[test]
+    fn session_order_and_limit_keep_due_and_new_cards_grouped() {
+        let path = PathBuf::from("deck.md");
+        let cards = |names: &[&str]| -> Vec<Card> {
+            names
+                .iter()
+                .map(|name| content_to_card(&path, &format!("Q: {name}?\nA: yes"), 1, 2).unwrap())
+                .collect()
+        };
+        let due = cards(&["due 1", "due 2", "due 3"]);
+        let new = cards(&["new 1", "new 2"]);
+        let questions = |session: Vec<Card>| -> Vec<String> {
+            session
+                .into_iter()
+                .map(|card| match card.content {
+                    CardContent::Basic { question, .. } => question,
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+        let arrange = |order, card_limit, shuffle| {
+            let options = DueOptions {
+                card_limit,
+                order,
+                shuffle,
+                ..DueOptions::default()
+            };
+            questions(arrange_session(due.clone(), new.clone(), options))
+        };
+
+        assert_eq!(
+            arrange(DrillOrder::DueFirst, Some(4), false),
+            ["due 1?", "due 2?", "due 3?", "new 1?"]
+        );
+        assert_eq!(
+            arrange(DrillOrder::NewFirst, Some(3), false),
+            ["new 1?", "new 2?", "due 1?"]
+        );
+        assert_eq!(
+            arrange(DrillOrder::Interleave, None, false),
+            ["due 1?", "new 1?", "due 2?", "new 2?", "due 3?"]
+        );
+
+        let shuffled = arrange(DrillOrder::NewFirst, None, true);
+        assert!(
+            shuffled[..2]
+                .iter()
+                .all(|question| question.starts_with("new"))
+        );
+        assert!(
+            shuffled[2..]
+                .iter()
+                .all(|question| question.starts_with("due"))
+        );
+    }
 }
@@ -2,33 +2,47 @@ use anyhow::Result;
 use sqlx::SqlitePool;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use crate::utils::get_data_dir;
+use crate::settings::{fsrs_params_path, load_fsrs_parameters};
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct DB {
     pub(super) pool: SqlitePool,
+    pub(super) fsrs_parameters: Vec<f32>,
+    data_dir: PathBuf,
 }
 
 impl DB {
-    pub async fn new() -> Result<Self> {
-        let data_dir = get_data_dir()?;
+    /// Opens `cards.db` in `data_dir`, where the other data files are kept too.
+    pub async fn new(data_dir: &Path) -> Result<Self> {
         let db_path = data_dir.join("cards.db");
 
         let options =
             SqliteConnectOptions::from_str(&db_path.to_string_lossy())?.create_if_missing(true);
 
-        Self::connect(options).await
+        let mut db = Self::connect(options, data_dir.to_path_buf()).await?;
+        db.fsrs_parameters = load_fsrs_parameters(&fsrs_params_path(data_dir));
+        Ok(db)
     }
-    async fn connect(options: SqliteConnectOptions) -> Result<Self> {
+    async fn connect(options: SqliteConnectOptions, data_dir: PathBuf) -> Result<Self> {
         let pool = SqlitePoolOptions::new()
             .max_connections(5)
             .connect_with(options)
             .await?;
 
         sqlx::migrate!("./migrations").run(&pool).await?;
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            fsrs_parameters: fsrs::DEFAULT_PARAMETERS.to_vec(),
+            data_dir,
+        })
+    }
+
+    /// The directory holding `cards.db`, `auth.json`, and the other data files.
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
     }
 }
 
@@ -36,7 +50,7 @@ impl DB {
 impl DB {
     pub async fn new_in_memory() -> Result<Self> {
         let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
-        Self::connect(options).await
+        Self::connect(options, std::env::temp_dir()).await
     }
 }
 
@@ -54,6 +68,6 @@ mod tests {
             .unwrap()
             .create_if_missing(true);
 
-        DB::connect(options).await.unwrap();
+        DB::connect(options, temp_dir()).await.unwrap();
     }
 }
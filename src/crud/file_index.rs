@@ -0,0 +1,210 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::card::Card;
+use crate::parser::CardSeparator;
+
+use super::DB;
+
+/// Bump whenever a parser change could split or hash a file differently, or
+/// registration starts storing something new, so entries written before are
+/// treated as stale. Version 2 re-registers cards indexed before their
+/// content was kept; version 3 re-parses blank-line decks, where `---` no
+/// longer ends a card; version 4 drops tag-only lines from card text.
+pub const FILE_INDEX_VERSION: i64 = 4;
+
+/// A deck file's modification time and size. A file whose stamp still matches
+/// its index entry hasn't changed since it was parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileStamp {
+    pub modified_ns: i64,
+    pub size: i64,
+}
+
+impl FileStamp {
+    /// `None` when the platform can't report a modification time.
+    pub fn of(metadata: &Metadata) -> Option<Self> {
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            modified_ns: i64::try_from(modified.as_nanos()).ok()?,
+            size: i64::try_from(metadata.len()).ok()?,
+        })
+    }
+}
+
+/// The cards a deck file held when it was last parsed.
+#[derive(Clone, Debug)]
+pub struct IndexedFile {
+    /// The `FILE_INDEX_VERSION` the entry was written with.
+    pub version: i64,
+    pub stamp: FileStamp,
+    pub card_separator: CardSeparator,
+    pub cards: Vec<Card>,
+}
+
+impl IndexedFile {
+    /// Whether the cards can be reused for a file stamped `stamp` that is
+    /// split into cards by `separator` with this version of the parser.
+    pub fn is_current(&self, stamp: FileStamp, separator: CardSeparator) -> bool {
+        self.version == FILE_INDEX_VERSION
+            && self.stamp == stamp
+            && self.card_separator == separator
+    }
+}
+
+impl DB {
+    /// Every indexed deck file by path. Entries that no longer deserialize,
+    /// e.g. after an upgrade changed the card format, are left out so their
+    /// files are parsed again.
+    pub async fn file_index(&self) -> Result<HashMap<PathBuf, IndexedFile>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT file_path, parser_version, modified_ns, size, card_separator, cards
+            FROM file_index
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let index = rows
+            .into_iter()
+            .filter_map(|row| {
+                let card_separator = CardSeparator::from_str(&row.card_separator, true).ok()?;
+                let cards = serde_json::from_str(&row.cards).ok()?;
+                let stamp = FileStamp {
+                    modified_ns: row.modified_ns,
+                    size: row.size,
+                };
+                let file = IndexedFile {
+                    version: row.parser_version,
+                    stamp,
+                    card_separator,
+                    cards,
+                };
+                Some((PathBuf::from(row.file_path), file))
+            })
+            .collect();
+        Ok(index)
+    }
+
+    /// Records freshly parsed deck files. Their cards must already be
+    /// registered, since indexed files skip registration.
+    pub async fn index_files(&self, files: &[(PathBuf, IndexedFile)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (path, file) in files {
+            let file_path = path.to_string_lossy();
+            let card_separator = file.card_separator.name();
+            let cards = serde_json::to_string(&file.cards)?;
+            sqlx::query!(
+                r#"
+                INSERT INTO file_index (
+                    file_path, parser_version, modified_ns, size, card_separator, cards
+                )
+                VALUES (?, ?, ?, ?, ?, ?)
+                ON CONFLICT (file_path) DO UPDATE SET
+                    parser_version = EXCLUDED.parser_version,
+                    modified_ns = EXCLUDED.modified_ns,
+                    size = EXCLUDED.size,
+                    card_separator = EXCLUDED.card_separator,
+                    cards = EXCLUDED.cards
+                "#,
+                file_path,
+                file.version,
+                file.stamp.modified_ns,
+                file.stamp.size,
+                card_separator,
+                cards
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Drops the entries under `roots` whose files a full scan of those roots
+    /// didn't see, e.g. decks that were deleted or renamed since.
+    pub async fn prune_file_index(
+        &self,
+        roots: &[PathBuf],
+        seen: &HashSet<PathBuf>,
+    ) -> Result<u64> {
+        let paths = sqlx::query_scalar!("SELECT file_path FROM file_index")
+            .fetch_all(&self.pool)
+            .await?;
+        let mut tx = self.pool.begin().await?;
+        let mut pruned = 0;
+        for file_path in paths {
+            let path = PathBuf::from(&file_path);
+            if seen.contains(&path) || !roots.iter().any(|root| path.starts_with(root)) {
+                continue;
+            }
+            pruned += sqlx::query!("DELETE FROM file_index WHERE file_path = ?", file_path)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(pruned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardContent;
+
+    #[tokio::test]
+    async fn deleting_cards_forgets_the_file_index() {
+        let db = DB::new_in_memory().await.unwrap();
+        let card = Card::new(
+            PathBuf::from("deck.md"),
+            (0, 2),
+            CardContent::Basic {
+                question: "Q".into(),
+                answer: "A".into(),
+            },
+            "hash".into(),
+        );
+        db.add_cards_batch(std::slice::from_ref(&card))
+            .await
+            .unwrap();
+        let indexed = IndexedFile {
+            version: FILE_INDEX_VERSION,
+            stamp: FileStamp {
+                modified_ns: 1,
+                size: 8,
+            },
+            card_separator: CardSeparator::BlankLine,
+            cards: vec![card],
+        };
+        db.index_files(&[("deck.md".into(), indexed)])
+            .await
+            .unwrap();
+
+        let index = db.file_index().await.unwrap();
+        let indexed = &index[&PathBuf::from("deck.md")];
+        assert!(indexed.is_current(
+            FileStamp {
+                modified_ns: 1,
+                size: 8
+            },
+            CardSeparator::BlankLine
+        ));
+        assert!(!indexed.is_current(indexed.stamp, CardSeparator::Rule));
+        let older = IndexedFile {
+            version: FILE_INDEX_VERSION - 1,
+            ..indexed.clone()
+        };
+        assert!(!older.is_current(indexed.stamp, CardSeparator::BlankLine));
+        assert_eq!(indexed.cards[0].card_hash, "hash");
+
+        db.delete_cards_by_hash(&["hash".into()]).await.unwrap();
+        assert!(db.file_index().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+
+use crate::llm::LlmFeature;
+
+use super::DB;
+
+impl DB {
+    /// The stored LLM output for a card, if `feature` has already run on it.
+    pub async fn cached_llm_result(
+        &self,
+        card_hash: &str,
+        feature: LlmFeature,
+    ) -> Result<Option<String>> {
+        let kind = feature.name();
+        let result = sqlx::query_scalar!(
+            r#"
+            SELECT result
+            FROM llm_cache
+            WHERE card_hash = ? AND kind = ?
+            "#,
+            card_hash,
+            kind
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn store_llm_result(
+        &self,
+        card_hash: &str,
+        feature: LlmFeature,
+        result: &str,
+    ) -> Result<()> {
+        let kind = feature.name();
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query!(
+            r#"
+            INSERT INTO llm_cache (card_hash, kind, result, created_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (card_hash, kind)
+            DO UPDATE SET result = EXCLUDED.result, created_at = EXCLUDED.created_at
+            "#,
+            card_hash,
+            kind,
+            result,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forgets every cached LLM result and returns how many were removed.
+    pub async fn clear_llm_cache(&self) -> Result<u64> {
+        let removed = sqlx::query!("DELETE FROM llm_cache")
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn results_are_cached_per_card_and_feature() {
+        let db = DB::new_in_memory().await.unwrap();
+        assert_eq!(
+            db.cached_llm_result("hash", LlmFeature::Cloze)
+                .await
+                .unwrap(),
+            None
+        );
+
+        db.store_llm_result("hash", LlmFeature::Cloze, "[Paris] is in France")
+            .await
+            .unwrap();
+        db.store_llm_result("hash", LlmFeature::Cloze, "Paris is in [France]")
+            .await
+            .unwrap();
+        assert_eq!(
+            db.cached_llm_result("hash", LlmFeature::Cloze)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("Paris is in [France]")
+        );
+        assert_eq!(
+            db.cached_llm_result("hash", LlmFeature::Rephrase)
+                .await
+                .unwrap(),
+            None
+        );
+
+        assert_eq!(db.clear_llm_cache().await.unwrap(), 1);
+        assert_eq!(
+            db.cached_llm_result("hash", LlmFeature::Cloze)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -1,7 +1,15 @@
 pub mod cards;
 pub mod db;
+pub mod file_index;
+pub mod llm_cache;
+pub mod review_log;
+pub mod settings;
+pub mod snapshot;
 pub mod stats;
 pub mod version;
 
 pub use crate::check_version::VersionUpdateStats;
+pub use cards::{CardExportRow, DrillOrder, DueOptions, Leech};
 pub use db::DB;
+pub use file_index::{FILE_INDEX_VERSION, FileStamp, IndexedFile};
+pub use snapshot::{Snapshot, SnapshotImport, SnapshotSettings};
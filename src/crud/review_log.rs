@@ -0,0 +1,79 @@
+use anyhow::Result;
+
+use crate::fsrs::ReviewStatus;
+
+use super::DB;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewLogRow {
+    pub card_hash: String,
+    pub grade: i64,
+    pub reviewed_at: chrono::DateTime<chrono::Utc>,
+    pub elapsed_days: i64,
+}
+
+impl DB {
+    pub async fn log_review(
+        &self,
+        card_hash: &str,
+        review_status: ReviewStatus,
+        reviewed_at: chrono::DateTime<chrono::Utc>,
+        elapsed_days: i64,
+    ) -> Result<()> {
+        let grade = review_status.grade() as i64;
+        sqlx::query!(
+            r#"
+            INSERT INTO review_log (card_hash, grade, reviewed_at, elapsed_days)
+            VALUES (?, ?, ?, ?)
+            "#,
+            card_hash,
+            grade,
+            reviewed_at,
+            elapsed_days
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drops the most recent log entry for a card, used when a review is undone.
+    pub async fn delete_last_review_log(&self, card_hash: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM review_log
+            WHERE id = (
+                SELECT id FROM review_log
+                WHERE card_hash = ?
+                ORDER BY id DESC
+                LIMIT 1
+            )
+            "#,
+            card_hash
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Every logged review, grouped by card and in chronological order.
+    pub async fn review_logs(&self) -> Result<Vec<ReviewLogRow>> {
+        let rows = sqlx::query_as!(
+            ReviewLogRow,
+            r#"
+            SELECT
+                card_hash,
+                grade as "grade!: i64",
+                reviewed_at as "reviewed_at!: chrono::DateTime<chrono::Utc>",
+                elapsed_days as "elapsed_days!: i64"
+            FROM review_log
+            ORDER BY card_hash ASC, reviewed_at ASC, id ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
@@ -0,0 +1,411 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+
+use crate::keymap::Keymap;
+use crate::parser::CardSeparator;
+use crate::settings::{
+    Settings, format_learning_steps, parse_learning_steps, validate_day_start_hour,
+    validate_learn_ahead, validate_leech_threshold, validate_max_interval, validate_retention,
+};
+
+use super::DB;
+
+impl DB {
+    pub async fn get_settings(&self) -> Result<Settings> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                desired_retention AS "desired_retention?: f64",
+                learning_steps,
+                max_interval_days AS "max_interval_days?: u32",
+                fuzz_intervals AS "fuzz_intervals?: bool",
+                card_separator,
+                leech_threshold AS "leech_threshold?: u32",
+                suspend_leeches AS "suspend_leeches?: bool",
+                version_check AS "version_check?: bool",
+                day_start_hour AS "day_start_hour?: u32",
+                learn_ahead_secs AS "learn_ahead_secs?: u32",
+                keymap
+            FROM settings
+            WHERE id = 1
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let mut settings = Settings {
+            fsrs_parameters: self.fsrs_parameters.clone(),
+            ..Settings::default()
+        };
+        if let Some(row) = row {
+            if let Some(retention) = row.desired_retention {
+                settings.desired_retention = retention as f32;
+            }
+            if let Some(steps) = row.learning_steps {
+                settings.learning_steps = parse_learning_steps(&steps)?;
+            }
+            settings.max_interval_days = row.max_interval_days;
+            if let Some(fuzz) = row.fuzz_intervals {
+                settings.fuzz_intervals = fuzz;
+            }
+            if let Some(separator) = row.card_separator {
+                settings.card_separator = CardSeparator::from_str(&separator, true)
+                    .map_err(|err| anyhow!("Invalid card separator \"{separator}\": {err}"))?;
+            }
+            if let Some(threshold) = row.leech_threshold {
+                settings.leech_threshold = threshold;
+            }
+            if let Some(suspend) = row.suspend_leeches {
+                settings.suspend_leeches = suspend;
+            }
+            if let Some(enabled) = row.version_check {
+                settings.version_check = enabled;
+            }
+            if let Some(hour) = row.day_start_hour {
+                settings.day_start_hour = hour;
+            }
+            if let Some(secs) = row.learn_ahead_secs {
+                settings.learn_ahead = Duration::from_secs(secs.into());
+            }
+            if let Some(keymap) = row.keymap {
+                settings.keymap = Keymap::parse(&keymap)
+                    .map_err(|err| anyhow!("Invalid drill keys \"{keymap}\": {err}"))?;
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Caps scheduled intervals at `days`, or removes the cap with `None`.
+    pub async fn set_max_interval(&self, days: Option<u32>) -> Result<()> {
+        let days = days.map(validate_max_interval).transpose()?;
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, max_interval_days)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET max_interval_days = EXCLUDED.max_interval_days
+            "#,
+            days
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_fuzz_intervals(&self, enabled: bool) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, fuzz_intervals)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET fuzz_intervals = EXCLUDED.fuzz_intervals
+            "#,
+            enabled
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_card_separator(&self, separator: CardSeparator) -> Result<()> {
+        let separator = separator.name();
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, card_separator)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET card_separator = EXCLUDED.card_separator
+            "#,
+            separator
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Flags cards as leeches once they have failed `failures` reviews.
+    pub async fn set_leech_threshold(&self, failures: u32) -> Result<()> {
+        let failures = validate_leech_threshold(failures)?;
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, leech_threshold)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET leech_threshold = EXCLUDED.leech_threshold
+            "#,
+            failures
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_suspend_leeches(&self, enabled: bool) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, suspend_leeches)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET suspend_leeches = EXCLUDED.suspend_leeches
+            "#,
+            enabled
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Starts each study day `hour` hours after local midnight.
+    pub async fn set_day_start_hour(&self, hour: u32) -> Result<()> {
+        let hour = validate_day_start_hour(hour)?;
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, day_start_hour)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET day_start_hour = EXCLUDED.day_start_hour
+            "#,
+            hour
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Counts cards due within `window` as due now.
+    pub async fn set_learn_ahead(&self, window: Duration) -> Result<()> {
+        let secs = validate_learn_ahead(window)?.as_secs() as u32;
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, learn_ahead_secs)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET learn_ahead_secs = EXCLUDED.learn_ahead_secs
+            "#,
+            secs
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Binds the drill keys, or restores the default keys with `None`.
+    pub async fn set_keymap(&self, keymap: Option<&Keymap>) -> Result<()> {
+        let keymap = keymap.map(Keymap::format);
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, keymap)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET keymap = EXCLUDED.keymap
+            "#,
+            keymap
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_version_check(&self, enabled: bool) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, version_check)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET version_check = EXCLUDED.version_check
+            "#,
+            enabled
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_learning_steps(&self, steps: &[Duration]) -> Result<()> {
+        let steps = format_learning_steps(steps);
+        // round-trip so anything stored can be read back
+        parse_learning_steps(&steps)?;
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, learning_steps)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET learning_steps = EXCLUDED.learning_steps
+            "#,
+            steps
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_desired_retention(&self, retention: f32) -> Result<()> {
+        let retention = validate_retention(retention)? as f64;
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (id, desired_retention)
+            VALUES (1, $1)
+            ON CONFLICT (id)
+            DO UPDATE SET desired_retention = EXCLUDED.desired_retention
+            "#,
+            retention
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::keymap::Keymap;
+    use crate::parser::CardSeparator;
+    use crate::settings::{
+        DEFAULT_DESIRED_RETENTION, DEFAULT_LEARN_AHEAD, DEFAULT_LEECH_THRESHOLD, Settings,
+        format_step, parse_learn_ahead,
+    };
+
+    use super::DB;
+
+    #[tokio::test]
+    async fn retention_round_trips_and_rejects_out_of_range() {
+        let db = DB::new_in_memory().await.unwrap();
+        assert_eq!(db.get_settings().await.unwrap(), Settings::default());
+        assert_eq!(
+            db.get_settings().await.unwrap().desired_retention,
+            DEFAULT_DESIRED_RETENTION
+        );
+
+        db.set_desired_retention(0.85).await.unwrap();
+        assert!((db.get_settings().await.unwrap().desired_retention - 0.85).abs() < 1e-6);
+
+        assert!(db.set_desired_retention(0.5).await.is_err());
+        assert!((db.get_settings().await.unwrap().desired_retention - 0.85).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn learning_steps_round_trip() {
+        let db = DB::new_in_memory().await.unwrap();
+        let steps = vec![Duration::from_secs(30), Duration::from_secs(3600)];
+        db.set_learning_steps(&steps).await.unwrap();
+        db.set_desired_retention(0.8).await.unwrap();
+
+        let settings = db.get_settings().await.unwrap();
+        assert_eq!(settings.learning_steps, steps);
+        assert!((settings.desired_retention - 0.8).abs() < 1e-6);
+        assert!(db.set_learning_steps(&[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn max_interval_can_be_set_and_cleared() {
+        let db = DB::new_in_memory().await.unwrap();
+        assert_eq!(db.get_settings().await.unwrap().max_interval_days, None);
+
+        db.set_max_interval(Some(365)).await.unwrap();
+        assert_eq!(
+            db.get_settings().await.unwrap().max_interval_days,
+            Some(365)
+        );
+        assert!(db.set_max_interval(Some(0)).await.is_err());
+
+        db.set_max_interval(None).await.unwrap();
+        assert_eq!(db.get_settings().await.unwrap().max_interval_days, None);
+
+        assert!(db.get_settings().await.unwrap().fuzz_intervals);
+        db.set_fuzz_intervals(false).await.unwrap();
+        assert!(!db.get_settings().await.unwrap().fuzz_intervals);
+    }
+
+    #[tokio::test]
+    async fn leech_settings_round_trip() {
+        let db = DB::new_in_memory().await.unwrap();
+        let settings = db.get_settings().await.unwrap();
+        assert_eq!(settings.leech_threshold, DEFAULT_LEECH_THRESHOLD);
+        assert!(!settings.suspend_leeches);
+
+        db.set_leech_threshold(4).await.unwrap();
+        db.set_suspend_leeches(true).await.unwrap();
+        let settings = db.get_settings().await.unwrap();
+        assert_eq!(settings.leech_threshold, 4);
+        assert!(settings.suspend_leeches);
+        assert!(db.set_leech_threshold(0).await.is_err());
+
+        assert!(db.get_settings().await.unwrap().version_check);
+        db.set_version_check(false).await.unwrap();
+        assert!(!db.get_settings().await.unwrap().version_check);
+
+        assert_eq!(db.get_settings().await.unwrap().day_start_hour, 0);
+        db.set_day_start_hour(4).await.unwrap();
+        assert_eq!(db.get_settings().await.unwrap().day_start_hour, 4);
+        assert!(db.set_day_start_hour(24).await.is_err());
+
+        assert_eq!(
+            db.get_settings().await.unwrap().learn_ahead,
+            DEFAULT_LEARN_AHEAD
+        );
+        db.set_learn_ahead(Duration::from_secs(5 * 60))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_settings().await.unwrap().learn_ahead,
+            Duration::from_secs(5 * 60)
+        );
+        assert!(
+            db.set_learn_ahead(Duration::from_secs(86_400))
+                .await
+                .is_err()
+        );
+        db.set_learn_ahead(parse_learn_ahead("0m").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            format_step(db.get_settings().await.unwrap().learn_ahead),
+            "0m"
+        );
+    }
+
+    #[tokio::test]
+    async fn keymap_can_be_set_and_reset() {
+        let db = DB::new_in_memory().await.unwrap();
+        assert_eq!(db.get_settings().await.unwrap().keymap, Keymap::default());
+
+        let keymap = Keymap::parse("fail=j good=k").unwrap();
+        db.set_keymap(Some(&keymap)).await.unwrap();
+        assert_eq!(db.get_settings().await.unwrap().keymap, keymap);
+
+        db.set_keymap(None).await.unwrap();
+        assert_eq!(db.get_settings().await.unwrap().keymap, Keymap::default());
+    }
+
+    #[tokio::test]
+    async fn card_separator_round_trips() {
+        let db = DB::new_in_memory().await.unwrap();
+        assert_eq!(
+            db.get_settings().await.unwrap().card_separator,
+            CardSeparator::Rule
+        );
+
+        db.set_card_separator(CardSeparator::BlankLine)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_settings().await.unwrap().card_separator,
+            CardSeparator::BlankLine
+        );
+    }
+}
@@ -0,0 +1,566 @@
+use anyhow::{Context, Result, anyhow, bail};
+use clap::ValueEnum;
+use fsrs::DEFAULT_PARAMETERS;
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+use crate::settings::{
+    DEFAULT_DESIRED_RETENTION, DEFAULT_LEARN_AHEAD, DEFAULT_LEARNING_STEPS,
+    DEFAULT_LEECH_THRESHOLD, format_learning_steps, parse_learning_steps, validate_day_start_hour,
+    validate_fsrs_parameters, validate_learn_ahead, validate_leech_threshold,
+    validate_max_interval, validate_retention,
+};
+
+use crate::keymap::Keymap;
+use crate::parser::CardSeparator;
+
+use super::{CardExportRow, DB};
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything in `cards.db` plus any non-default settings, as one portable value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub created_at: String,
+    pub desired_retention: Option<f32>,
+    pub fsrs_parameters: Option<Vec<f32>>,
+    /// Absent from backups written before learning steps were configurable.
+    #[serde(default)]
+    pub learning_steps: Option<String>,
+    #[serde(default)]
+    pub max_interval_days: Option<u32>,
+    #[serde(default)]
+    pub fuzz_intervals: Option<bool>,
+    /// `blank-line` when cards end at blank lines; absent for the default.
+    #[serde(default)]
+    pub card_separator: Option<String>,
+    #[serde(default)]
+    pub leech_threshold: Option<u32>,
+    #[serde(default)]
+    pub suspend_leeches: Option<bool>,
+    #[serde(default)]
+    pub version_check: Option<bool>,
+    #[serde(default)]
+    pub day_start_hour: Option<u32>,
+    /// Drill key bindings as `config --keys` writes them, e.g. `fail=j,1`.
+    #[serde(default)]
+    pub keymap: Option<String>,
+    #[serde(default)]
+    pub learn_ahead_secs: Option<u64>,
+    pub cards: Vec<CardExportRow>,
+    pub reviews: Vec<SnapshotReview>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotReview {
+    pub card_hash: String,
+    pub grade: i64,
+    pub reviewed_at: String,
+    pub elapsed_days: i64,
+}
+
+/// The settings a snapshot carries, parsed and validated. Settings left at
+/// their defaults when the backup was made are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotSettings {
+    pub desired_retention: Option<f32>,
+    pub fsrs_parameters: Option<Vec<f32>>,
+    pub learning_steps: Option<Vec<Duration>>,
+    pub max_interval_days: Option<u32>,
+    pub fuzz_intervals: Option<bool>,
+    pub card_separator: Option<CardSeparator>,
+    pub leech_threshold: Option<u32>,
+    pub suspend_leeches: Option<bool>,
+    pub version_check: Option<bool>,
+    pub day_start_hour: Option<u32>,
+    pub keymap: Option<Keymap>,
+    pub learn_ahead: Option<Duration>,
+}
+
+impl Snapshot {
+    /// Parses and validates every setting, so a restore can reject a bad
+    /// backup before it writes anything.
+    pub fn settings(&self) -> Result<SnapshotSettings> {
+        if let Some(parameters) = &self.fsrs_parameters {
+            validate_fsrs_parameters(parameters).context("Invalid FSRS parameters in backup")?;
+        }
+        let card_separator = self
+            .card_separator
+            .as_deref()
+            .map(|name| {
+                CardSeparator::from_str(name, true)
+                    .map_err(|err| anyhow!("Invalid card separator \"{name}\" in backup: {err}"))
+            })
+            .transpose()?;
+        let keymap = self
+            .keymap
+            .as_deref()
+            .map(|keys| {
+                Keymap::parse(keys)
+                    .map_err(|err| anyhow!("Invalid drill keys \"{keys}\" in backup: {err}"))
+            })
+            .transpose()?;
+        Ok(SnapshotSettings {
+            desired_retention: self.desired_retention.map(validate_retention).transpose()?,
+            fsrs_parameters: self.fsrs_parameters.clone(),
+            learning_steps: self
+                .learning_steps
+                .as_deref()
+                .map(|steps| {
+                    parse_learning_steps(steps)
+                        .with_context(|| format!("Invalid learning steps \"{steps}\" in backup"))
+                })
+                .transpose()?,
+            max_interval_days: self
+                .max_interval_days
+                .map(validate_max_interval)
+                .transpose()?,
+            fuzz_intervals: self.fuzz_intervals,
+            card_separator,
+            leech_threshold: self
+                .leech_threshold
+                .map(validate_leech_threshold)
+                .transpose()?,
+            suspend_leeches: self.suspend_leeches,
+            version_check: self.version_check,
+            day_start_hour: self
+                .day_start_hour
+                .map(validate_day_start_hour)
+                .transpose()?,
+            keymap,
+            learn_ahead: self
+                .learn_ahead_secs
+                .map(|secs| validate_learn_ahead(Duration::from_secs(secs)))
+                .transpose()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SnapshotImport {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub reviews_added: usize,
+}
+
+impl DB {
+    pub async fn export_snapshot(&self) -> Result<Snapshot> {
+        let settings = self.get_settings().await?;
+        let reviews = self
+            .review_logs()
+            .await?
+            .into_iter()
+            .map(|log| SnapshotReview {
+                card_hash: log.card_hash,
+                grade: log.grade,
+                reviewed_at: log.reviewed_at.to_rfc3339(),
+                elapsed_days: log.elapsed_days,
+            })
+            .collect();
+
+        Ok(Snapshot {
+            version: SNAPSHOT_VERSION,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            desired_retention: (settings.desired_retention != DEFAULT_DESIRED_RETENTION)
+                .then_some(settings.desired_retention),
+            fsrs_parameters: (settings.fsrs_parameters != DEFAULT_PARAMETERS)
+                .then_some(settings.fsrs_parameters),
+            learning_steps: (settings.learning_steps != DEFAULT_LEARNING_STEPS)
+                .then(|| format_learning_steps(&settings.learning_steps)),
+            max_interval_days: settings.max_interval_days,
+            fuzz_intervals: (!settings.fuzz_intervals).then_some(false),
+            card_separator: (settings.card_separator != CardSeparator::default())
+                .then(|| settings.card_separator.name().to_string()),
+            leech_threshold: (settings.leech_threshold != DEFAULT_LEECH_THRESHOLD)
+                .then_some(settings.leech_threshold),
+            suspend_leeches: settings.suspend_leeches.then_some(true),
+            version_check: (!settings.version_check).then_some(false),
+            day_start_hour: (settings.day_start_hour != 0).then_some(settings.day_start_hour),
+            keymap: (settings.keymap != Keymap::default()).then(|| settings.keymap.format()),
+            learn_ahead_secs: (settings.learn_ahead != DEFAULT_LEARN_AHEAD)
+                .then_some(settings.learn_ahead.as_secs()),
+            cards: self.export_all_cards().await?,
+            reviews,
+        })
+    }
+
+    /// Merges a snapshot into the database by `card_hash`. Local cards missing
+    /// from the snapshot are kept; on conflicts the more recently reviewed copy
+    /// wins. Reviews already in the log are skipped. The snapshot's settings
+    /// are applied too, except the FSRS parameters, which live in their own file.
+    pub async fn import_snapshot(&self, snapshot: &Snapshot) -> Result<SnapshotImport> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            bail!(
+                "Unsupported backup version {} (expected {})",
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+        let settings = snapshot.settings()?;
+        let mut report = SnapshotImport::default();
+        let mut tx = self.pool.begin().await?;
+
+        for card in &snapshot.cards {
+            let local = sqlx::query!(
+                r#"
+                SELECT last_reviewed_at
+                FROM cards
+                WHERE card_hash = ?
+                "#,
+                card.card_hash
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            match local {
+                None => {
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO cards (
+                            card_hash,
+                            added_at,
+                            last_reviewed_at,
+                            stability,
+                            difficulty,
+                            interval_raw,
+                            interval_days,
+                            due_date,
+                            review_count,
+                            suspended,
+                            lapses,
+                            leech,
+                            leech_suspended,
+                            content
+                        )
+                        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                        "#,
+                        card.card_hash,
+                        card.added_at,
+                        card.last_reviewed_at,
+                        card.stability,
+                        card.difficulty,
+                        card.interval_raw,
+                        card.interval_days,
+                        card.due_date,
+                        card.review_count,
+                        card.suspended,
+                        card.lapses,
+                        card.leech,
+                        card.leech_suspended,
+                        card.content
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                    report.added += 1;
+                }
+                Some(local)
+                    if is_newer(
+                        card.last_reviewed_at.as_deref(),
+                        local.last_reviewed_at.as_deref(),
+                    ) =>
+                {
+                    sqlx::query!(
+                        r#"
+                        UPDATE cards
+                        SET
+                            last_reviewed_at = ?,
+                            stability = ?,
+                            difficulty = ?,
+                            interval_raw = ?,
+                            interval_days = ?,
+                            due_date = ?,
+                            review_count = ?,
+                            suspended = ?,
+                            lapses = ?,
+                            leech = ?,
+                            leech_suspended = ?,
+                            content = COALESCE(content, ?)
+                        WHERE card_hash = ?
+                        "#,
+                        card.last_reviewed_at,
+                        card.stability,
+                        card.difficulty,
+                        card.interval_raw,
+                        card.interval_days,
+                        card.due_date,
+                        card.review_count,
+                        card.suspended,
+                        card.lapses,
+                        card.leech,
+                        card.leech_suspended,
+                        card.content,
+                        card.card_hash
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                    report.updated += 1;
+                }
+                Some(_) => report.unchanged += 1,
+            }
+        }
+
+        for review in &snapshot.reviews {
+            let reviewed_at = chrono::DateTime::parse_from_rfc3339(&review.reviewed_at)
+                .with_context(|| format!("Invalid review timestamp {}", review.reviewed_at))?
+                .with_timezone(&chrono::Utc);
+            let inserted = sqlx::query!(
+                r#"
+                INSERT INTO review_log (card_hash, grade, reviewed_at, elapsed_days)
+                SELECT ?, ?, ?, ?
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM review_log
+                    WHERE card_hash = ? AND reviewed_at = ?
+                )
+                "#,
+                review.card_hash,
+                review.grade,
+                reviewed_at,
+                review.elapsed_days,
+                review.card_hash,
+                reviewed_at
+            )
+            .execute(&mut *tx)
+            .await?;
+            report.reviews_added += inserted.rows_affected() as usize;
+        }
+        // restored rows have no file location yet, and indexed files would
+        // skip the registration that fills it in
+        sqlx::query!("DELETE FROM file_index")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        if let Some(retention) = settings.desired_retention {
+            self.set_desired_retention(retention).await?;
+        }
+        if let Some(steps) = &settings.learning_steps {
+            self.set_learning_steps(steps).await?;
+        }
+        if let Some(days) = settings.max_interval_days {
+            self.set_max_interval(Some(days)).await?;
+        }
+        if let Some(enabled) = settings.fuzz_intervals {
+            self.set_fuzz_intervals(enabled).await?;
+        }
+        if let Some(separator) = settings.card_separator {
+            self.set_card_separator(separator).await?;
+        }
+        if let Some(failures) = settings.leech_threshold {
+            self.set_leech_threshold(failures).await?;
+        }
+        if let Some(enabled) = settings.suspend_leeches {
+            self.set_suspend_leeches(enabled).await?;
+        }
+        if let Some(enabled) = settings.version_check {
+            self.set_version_check(enabled).await?;
+        }
+        if let Some(hour) = settings.day_start_hour {
+            self.set_day_start_hour(hour).await?;
+        }
+        if let Some(keymap) = &settings.keymap {
+            self.set_keymap(Some(keymap)).await?;
+        }
+        if let Some(window) = settings.learn_ahead {
+            self.set_learn_ahead(window).await?;
+        }
+        Ok(report)
+    }
+}
+
+/// Whether `incoming` was reviewed after `local`. A card that was never
+/// reviewed loses to any reviewed copy.
+fn is_newer(incoming: Option<&str>, local: Option<&str>) -> bool {
+    let parse = |value: &str| chrono::DateTime::parse_from_rfc3339(value).ok();
+    match (incoming, local) {
+        (None, _) => false,
+        (Some(_), None) => true,
+        (Some(incoming), Some(local)) => match (parse(incoming), parse(local)) {
+            (Some(incoming), Some(local)) => incoming > local,
+            _ => incoming > local,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::Duration;
+
+    use crate::fsrs::ReviewStatus;
+    use crate::parser::content_to_card;
+    use crate::settings::Settings;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn import_merges_by_hash_and_keeps_the_newer_review() {
+        let path = PathBuf::from("deck.md");
+        let shared = content_to_card(&path, "Q: shared?\nA: yes", 1, 2).unwrap();
+        let stale = content_to_card(&path, "Q: stale?\nA: yes", 3, 4).unwrap();
+        let backup_only = content_to_card(&path, "Q: backup only?\nA: yes", 5, 6).unwrap();
+        let local_only = content_to_card(&path, "Q: local only?\nA: yes", 7, 8).unwrap();
+        let earlier = chrono::Utc::now() - Duration::days(5);
+        let later = chrono::Utc::now() - Duration::days(1);
+
+        let remote = DB::new_in_memory().await.unwrap();
+        for card in [&shared, &stale, &backup_only] {
+            remote.add_card(card).await.unwrap();
+        }
+        remote.suspend_card(&backup_only).await.unwrap();
+        remote
+            .update_card_performance(&shared, ReviewStatus::Good, Some(later))
+            .await
+            .unwrap();
+        remote
+            .update_card_performance(&stale, ReviewStatus::Good, Some(earlier))
+            .await
+            .unwrap();
+        remote.set_desired_retention(0.8).await.unwrap();
+        remote.set_leech_threshold(3).await.unwrap();
+        remote.set_version_check(false).await.unwrap();
+        remote.set_day_start_hour(4).await.unwrap();
+        let keymap = Keymap::parse("fail=j").unwrap();
+        remote.set_keymap(Some(&keymap)).await.unwrap();
+        remote
+            .set_learn_ahead(std::time::Duration::from_secs(3600))
+            .await
+            .unwrap();
+        remote
+            .set_card_separator(CardSeparator::BlankLine)
+            .await
+            .unwrap();
+        let snapshot = remote.export_snapshot().await.unwrap();
+        assert_eq!(snapshot.card_separator.as_deref(), Some("blank-line"));
+        assert_eq!(snapshot.leech_threshold, Some(3));
+        assert_eq!(snapshot.suspend_leeches, None);
+        assert_eq!(snapshot.version_check, Some(false));
+        assert_eq!(snapshot.day_start_hour, Some(4));
+        assert_eq!(snapshot.keymap, Some(keymap.format()));
+        assert_eq!(snapshot.learn_ahead_secs, Some(3600));
+        assert_eq!(snapshot.desired_retention, Some(0.8));
+        assert_eq!(snapshot.fsrs_parameters, None);
+        assert_eq!(snapshot.learning_steps, None);
+
+        let local = DB::new_in_memory().await.unwrap();
+        for card in [&shared, &stale, &local_only] {
+            local.add_card(card).await.unwrap();
+        }
+        local
+            .update_card_performance(&shared, ReviewStatus::Again, Some(earlier))
+            .await
+            .unwrap();
+        local
+            .update_card_performance(&stale, ReviewStatus::Easy, Some(later))
+            .await
+            .unwrap();
+
+        let indexed = crate::crud::IndexedFile {
+            version: crate::crud::FILE_INDEX_VERSION,
+            stamp: crate::crud::FileStamp {
+                modified_ns: 1,
+                size: 1,
+            },
+            card_separator: local.get_settings().await.unwrap().card_separator,
+            cards: vec![shared.clone()],
+        };
+        local.index_files(&[(path.clone(), indexed)]).await.unwrap();
+
+        let report = local.import_snapshot(&snapshot).await.unwrap();
+        assert!(local.file_index().await.unwrap().is_empty());
+        assert_eq!(
+            report,
+            SnapshotImport {
+                added: 1,
+                updated: 1,
+                unchanged: 1,
+                reviews_added: 2,
+            }
+        );
+        let cards = local.export_all_cards().await.unwrap();
+        assert_eq!(cards.len(), 4);
+        let suspended: Vec<_> = cards.iter().filter(|card| card.suspended).collect();
+        assert_eq!(suspended.len(), 1);
+        assert_eq!(suspended[0].card_hash, backup_only.card_hash);
+        assert_eq!(
+            local.card_content(&backup_only.card_hash).await.unwrap(),
+            remote.card_content(&backup_only.card_hash).await.unwrap()
+        );
+        assert!(suspended[0].content.is_some());
+        assert_eq!(
+            local.get_card_performance(&shared).await.unwrap(),
+            remote.get_card_performance(&shared).await.unwrap()
+        );
+        assert_ne!(
+            local.get_card_performance(&stale).await.unwrap(),
+            remote.get_card_performance(&stale).await.unwrap()
+        );
+        let settings = local.get_settings().await.unwrap();
+        assert!((settings.desired_retention - 0.8).abs() < 1e-6);
+        assert_eq!(settings.leech_threshold, 3);
+        assert!(!settings.version_check);
+        assert_eq!(settings.day_start_hour, 4);
+        assert_eq!(settings.keymap, keymap);
+        assert_eq!(settings.learn_ahead.as_secs(), 3600);
+        assert_eq!(settings.card_separator, CardSeparator::BlankLine);
+
+        // importing the same snapshot twice changes nothing
+        let again = local.import_snapshot(&snapshot).await.unwrap();
+        assert_eq!(again.added + again.updated + again.reviews_added, 0);
+
+        let mut future = snapshot.clone();
+        future.version = SNAPSHOT_VERSION + 1;
+        assert!(local.import_snapshot(&future).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn restored_leech_suspension_can_still_be_undone() {
+        let card = content_to_card(&PathBuf::from("deck.md"), "Q: hard?\nA: yes", 1, 2).unwrap();
+        let remote = DB::new_in_memory().await.unwrap();
+        remote.set_leech_threshold(1).await.unwrap();
+        remote.set_suspend_leeches(true).await.unwrap();
+        remote.add_card(&card).await.unwrap();
+        let before = remote.get_card_performance(&card).await.unwrap();
+        remote
+            .update_card_performance(&card, ReviewStatus::Again, None)
+            .await
+            .unwrap();
+        let snapshot = remote.export_snapshot().await.unwrap();
+        assert!(snapshot.cards[0].leech_suspended);
+
+        let local = DB::new_in_memory().await.unwrap();
+        local.import_snapshot(&snapshot).await.unwrap();
+        assert!(local.leeches().await.unwrap()[&card.card_hash].suspended);
+
+        local
+            .restore_card_performance(&card, &before)
+            .await
+            .unwrap();
+        assert!(local.leeches().await.unwrap().is_empty());
+        assert!(!local.export_all_cards().await.unwrap()[0].suspended);
+    }
+
+    #[tokio::test]
+    async fn invalid_settings_reject_the_whole_backup() {
+        let card = content_to_card(&PathBuf::from("deck.md"), "Q: kept?\nA: yes", 1, 2).unwrap();
+        let remote = DB::new_in_memory().await.unwrap();
+        remote.add_card(&card).await.unwrap();
+        remote.set_desired_retention(0.8).await.unwrap();
+        let mut snapshot = remote.export_snapshot().await.unwrap();
+        snapshot.keymap = Some(String::from("fail=j good=j"));
+
+        let local = DB::new_in_memory().await.unwrap();
+        assert!(snapshot.settings().is_err());
+        assert!(local.import_snapshot(&snapshot).await.is_err());
+        assert!(local.export_all_cards().await.unwrap().is_empty());
+        assert_eq!(local.get_settings().await.unwrap(), Settings::default());
+
+        snapshot.keymap = None;
+        snapshot.card_separator = Some(String::from("semicolon"));
+        assert!(local.import_snapshot(&snapshot).await.is_err());
+        assert!(local.export_all_cards().await.unwrap().is_empty());
+    }
+}
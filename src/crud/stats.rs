@@ -1,12 +1,14 @@
 use super::DB;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 use crate::card::Card;
 
 use futures::TryStreamExt;
 
-use crate::stats::CardStats;
+use crate::stats::{CardStats, OrphanedCard};
+use crate::utils::{start_of_study_day, study_date};
 use anyhow::Result;
 
 pub struct CardStatsRow {
@@ -17,15 +19,94 @@ pub struct CardStatsRow {
     pub difficulty: Option<f64>,
     pub stability: Option<f64>,
     pub last_reviewed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub suspended: bool,
+    pub leech: bool,
+}
+
+/// `card_hashes` and their reverses as a JSON array, for `json_each`.
+fn known_hashes(card_hashes: &HashMap<String, Card>) -> Result<String> {
+    // `drill --both-ways` stores reverse cards that are never registered from disk
+    let reverse_hashes: HashSet<String> = card_hashes
+        .values()
+        .filter_map(Card::reversed)
+        .map(|card| card.card_hash)
+        .collect();
+    Ok(serde_json::to_string(
+        &card_hashes
+            .keys()
+            .chain(reverse_hashes.iter())
+            .collect::<Vec<_>>(),
+    )?)
 }
 
 impl DB {
-    pub async fn collection_stats(&self, card_hashes: &HashMap<String, Card>) -> Result<CardStats> {
+    /// Rows last seen in a file under `roots` that match none of
+    /// `card_hashes` (or their reverses), i.e. cards deleted or edited since.
+    /// Rows of other collections sharing the database, and rows with no
+    /// recorded location, are never orphans.
+    pub async fn orphaned_cards(
+        &self,
+        card_hashes: &HashMap<String, Card>,
+        roots: &[PathBuf],
+    ) -> Result<Vec<OrphanedCard>> {
+        let known = known_hashes(card_hashes)?;
+        // stored locations are canonical, see `add_cards_batch`
+        let roots: Vec<PathBuf> = roots
+            .iter()
+            .map(|root| std::fs::canonicalize(root).unwrap_or_else(|_| root.clone()))
+            .collect();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                card_hash,
+                file_path as "file_path!: String",
+                start_line as "start_line!: i64"
+            FROM cards
+            WHERE file_path IS NOT NULL
+                AND card_hash NOT IN (SELECT value FROM json_each(?))
+            ORDER BY file_path, start_line
+            "#,
+            known
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| OrphanedCard {
+                card_hash: row.card_hash,
+                file_path: PathBuf::from(row.file_path),
+                start_line: row.start_line,
+            })
+            .filter(|orphan| roots.iter().any(|root| orphan.file_path.starts_with(root)))
+            .collect())
+    }
+
+    /// Stats for `card_hashes`, forecasting due cards `horizon_days` ahead.
+    pub async fn collection_stats(
+        &self,
+        card_hashes: &HashMap<String, Card>,
+        horizon_days: u32,
+    ) -> Result<CardStats> {
+        let settings = self.get_settings().await?;
+        let day_start_hour = settings.day_start_hour;
+        let now = chrono::Local::now();
         let mut stats = CardStats {
             num_cards: card_hashes.len() as i64,
+            today: study_date(&now, day_start_hour),
             ..Default::default()
         };
+        // the hashes go in as a JSON array so SQLite only reads the rows of
+        // this collection, not every card in a database shared across several
+        let registered = serde_json::to_string(&card_hashes.keys().collect::<Vec<_>>())?;
+        let known = known_hashes(card_hashes)?;
 
+        // one read transaction, so the counts all describe the same moment
+        let mut tx = self.pool.begin().await?;
+        stats.total_cards_in_db =
+            sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!: i64" FROM cards"#)
+                .fetch_one(&mut *tx)
+                .await?;
         let mut rows = sqlx::query_as!(
             CardStatsRow,
             r#"
@@ -36,20 +117,52 @@ impl DB {
                 interval_raw as "interval_raw?: f64",
                 difficulty as "difficulty?: f64",
                 stability as "stability?: f64",
-                last_reviewed_at as "last_reviewed_at?: chrono::DateTime<chrono::Utc>"
+                last_reviewed_at as "last_reviewed_at?: chrono::DateTime<chrono::Utc>",
+                suspended as "suspended!: bool",
+                leech as "leech!: bool"
             FROM cards
+            WHERE card_hash IN (SELECT value FROM json_each(?))
             "#,
+            registered
         )
-        .fetch(&self.pool);
+        .fetch(&mut *tx);
 
         while let Some(row) = rows.try_next().await? {
-            stats.total_cards_in_db += 1;
-            let card = match card_hashes.get(&row.card_hash) {
-                Some(card) => card,
-                None => continue,
+            let Some(card) = card_hashes.get(&row.card_hash) else {
+                continue;
             };
-            stats.update(card, &row);
+            if row.leech {
+                stats.leech_cards += 1;
+            }
+            if row.suspended {
+                stats.suspended_cards += 1;
+                continue;
+            }
+            stats.update(
+                card,
+                &row,
+                horizon_days,
+                day_start_hour,
+                settings.learn_ahead,
+            );
         }
+        drop(rows);
+
+        let day_start = start_of_study_day(&now, day_start_hour);
+        let reviewed_today = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!: i64"
+            FROM review_log
+            WHERE julianday(reviewed_at) >= julianday(?)
+                AND card_hash IN (SELECT value FROM json_each(?))
+            "#,
+            day_start,
+            known
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        stats.reviewed_today = reviewed_today as usize;
+        tx.commit().await?;
 
         Ok(stats)
     }
@@ -1,38 +1,74 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Utc};
-use fsrs::{DEFAULT_PARAMETERS, FSRS, MemoryState};
+use fsrs::{FSRS, MemoryState};
+
+use crate::settings::Settings;
 
-const DESIRED_RETENTION: f32 = 0.9;
 const SECONDS_PER_DAY: f64 = 86_400.0;
 
-pub const LEARN_AHEAD_THRESHOLD_MINS: Duration = Duration::minutes(20);
-
-fn early_interval_cap(review_count: usize, review_status: ReviewStatus) -> Option<Duration> {
-    match review_count {
-        0 => Some(Duration::minutes(1)),
-        1 => match review_status {
-            ReviewStatus::Pass => Some(Duration::minutes(10)),
-            ReviewStatus::Fail => Some(Duration::minutes(1)),
-        },
-        2 => match review_status {
-            ReviewStatus::Pass => Some(Duration::days(1)),
-            ReviewStatus::Fail => Some(Duration::minutes(10)),
-        },
-        _ => None,
+/// Intervals longer than a day are moved by up to this fraction either way.
+pub const FUZZ_RATIO: f64 = 0.05;
+
+/// The learning step for a card's `review_count`-th review. A failed review
+/// goes back to the first step.
+fn early_interval_cap(
+    review_count: usize,
+    review_status: ReviewStatus,
+    learning_steps: &[std::time::Duration],
+) -> Option<Duration> {
+    // Easy skips the learning ramp entirely and trusts the FSRS interval.
+    if review_status == ReviewStatus::Easy || review_count >= learning_steps.len() {
+        return None;
+    }
+    let step = match review_status {
+        ReviewStatus::Again => learning_steps[0],
+        _ => learning_steps[review_count],
+    };
+    Duration::from_std(step).ok()
+}
+
+/// Nudges intervals longer than a day by up to [`FUZZ_RATIO`] so cards added
+/// together don't stay due together. The offset comes from the card hash and
+/// review count, so replaying the same review gives the same interval.
+fn fuzz_interval(interval: Duration, card_hash: &str, review_count: usize) -> Duration {
+    if interval <= Duration::days(1) {
+        return interval;
     }
+    let seed = blake3::hash(format!("{card_hash}:{review_count}").as_bytes());
+    let (head, _) = seed
+        .as_bytes()
+        .split_first_chunk::<8>()
+        .expect("hash is 32 bytes");
+    let unit = u64::from_le_bytes(*head) as f64 / u64::MAX as f64;
+    let factor = 1.0 + FUZZ_RATIO * (2.0 * unit - 1.0);
+    Duration::seconds((interval.num_seconds() as f64 * factor).round() as i64)
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ReviewStatus {
-    Pass,
-    Fail,
+    Again,
+    Hard,
+    Good,
+    Easy,
 }
 
 impl ReviewStatus {
+    /// The 1-4 rating FSRS uses in review logs.
+    pub fn grade(&self) -> u32 {
+        match self {
+            ReviewStatus::Again => 1,
+            ReviewStatus::Hard => 2,
+            ReviewStatus::Good => 3,
+            ReviewStatus::Easy => 4,
+        }
+    }
+
     pub fn label(&self) -> &'static str {
         match self {
-            ReviewStatus::Pass => "Pass",
-            ReviewStatus::Fail => "Fail",
+            ReviewStatus::Again => "Fail",
+            ReviewStatus::Hard => "Hard",
+            ReviewStatus::Good => "Pass",
+            ReviewStatus::Easy => "Easy",
         }
     }
 }
@@ -55,8 +91,8 @@ pub enum Performance {
     Reviewed(ReviewedPerformance),
 }
 
-fn fsrs_model() -> Result<FSRS> {
-    FSRS::new(Some(&DEFAULT_PARAMETERS)).context("failed to initialize FSRS model")
+fn fsrs_model(parameters: &[f32]) -> Result<FSRS> {
+    FSRS::new(Some(parameters)).context("failed to initialize FSRS model")
 }
 
 fn next_state_for_review(
@@ -64,8 +100,10 @@ fn next_state_for_review(
     review_status: ReviewStatus,
 ) -> fsrs::ItemState {
     match review_status {
-        ReviewStatus::Pass => next_states.good,
-        ReviewStatus::Fail => next_states.again,
+        ReviewStatus::Again => next_states.again,
+        ReviewStatus::Hard => next_states.hard,
+        ReviewStatus::Good => next_states.good,
+        ReviewStatus::Easy => next_states.easy,
     }
 }
 
@@ -73,6 +111,8 @@ pub fn update_performance(
     perf: Performance,
     review_status: ReviewStatus,
     reviewed_at: DateTime<Utc>,
+    settings: &Settings,
+    card_hash: &str,
 ) -> Result<ReviewedPerformance> {
     let (memory_state, last_reviewed_at, review_count) = match perf {
         Performance::New => (None, None, 0),
@@ -96,17 +136,27 @@ pub fn update_performance(
         .map(|last| reviewed_at.signed_duration_since(last).num_days().max(0) as u32)
         .unwrap_or(0);
 
-    let fsrs = fsrs_model()?;
-    let next_states = fsrs.next_states(memory_state, DESIRED_RETENTION, elapsed_days)?;
+    let fsrs = fsrs_model(&settings.fsrs_parameters)?;
+    let next_states = fsrs.next_states(memory_state, settings.desired_retention, elapsed_days)?;
     let next_state = next_state_for_review(next_states, review_status);
 
     let interval_raw = next_state.interval as f64;
     let fsrs_seconds = (interval_raw * SECONDS_PER_DAY).round().max(1.0) as i64;
     let fsrs_duration = Duration::seconds(fsrs_seconds);
 
-    let interval_duration = early_interval_cap(review_count, review_status)
-        .map(|cap| fsrs_duration.min(cap))
-        .unwrap_or(fsrs_duration);
+    let interval_duration =
+        early_interval_cap(review_count, review_status, &settings.learning_steps)
+            .map(|cap| fsrs_duration.min(cap))
+            .unwrap_or(fsrs_duration);
+    let interval_duration = if settings.fuzz_intervals {
+        fuzz_interval(interval_duration, card_hash, review_count)
+    } else {
+        interval_duration
+    };
+    let interval_duration = match settings.max_interval_days {
+        Some(days) => interval_duration.min(Duration::days(days.into())),
+        None => interval_duration,
+    };
 
     let interval_effective_days = interval_duration.num_seconds() as f64 / SECONDS_PER_DAY;
     let interval_days = interval_duration.num_days().max(0) as usize;
@@ -125,7 +175,11 @@ pub fn update_performance(
 
 #[cfg(test)]
 mod tests {
-    use super::{Performance, ReviewStatus, ReviewedPerformance, update_performance};
+    use super::{
+        FUZZ_RATIO, Performance, ReviewStatus, ReviewedPerformance, fuzz_interval,
+        update_performance,
+    };
+    use crate::settings::Settings;
     use chrono::Duration;
     fn approx_eq(a: f64, b: f64) -> bool {
         (a - b).abs() < 1e-2
@@ -135,7 +189,13 @@ mod tests {
     fn test_update_new_card() {
         let reviewed_at = chrono::Utc::now();
 
-        let result = update_performance(Performance::New, ReviewStatus::Pass, reviewed_at);
+        let result = update_performance(
+            Performance::New,
+            ReviewStatus::Good,
+            reviewed_at,
+            &Settings::default(),
+            "card",
+        );
         dbg!(result.as_ref().unwrap());
         let ReviewedPerformance {
             last_reviewed_at,
@@ -168,9 +228,14 @@ mod tests {
             due_date: now,
             review_count: 1,
         };
-        let result =
-            update_performance(Performance::Reviewed(initial_perf), ReviewStatus::Pass, now)
-                .unwrap();
+        let result = update_performance(
+            Performance::Reviewed(initial_perf),
+            ReviewStatus::Good,
+            now,
+            &Settings::default(),
+            "card",
+        )
+        .unwrap();
         assert_eq!(result.last_reviewed_at, now);
         assert!(result.interval_days == 0);
         assert_eq!(result.review_count, 2);
@@ -188,10 +253,198 @@ mod tests {
             due_date: now + Duration::days(4),
             review_count: 3,
         };
-        let result =
-            update_performance(Performance::Reviewed(initial_perf), ReviewStatus::Fail, now)
-                .unwrap();
+        let result = update_performance(
+            Performance::Reviewed(initial_perf),
+            ReviewStatus::Again,
+            now,
+            &Settings::default(),
+            "card",
+        )
+        .unwrap();
         assert_eq!(result.interval_raw, 0.7213425925925926);
         assert_eq!(result.review_count, 4);
     }
+
+    #[test]
+    fn test_grades_order_intervals() {
+        let now = chrono::Utc::now();
+        let initial_perf = ReviewedPerformance {
+            last_reviewed_at: now - Duration::days(10),
+            stability: 10.0,
+            difficulty: 5.0,
+            interval_raw: 10.0,
+            interval_days: 10,
+            due_date: now,
+            review_count: 5,
+        };
+        let intervals: Vec<f64> = [
+            ReviewStatus::Again,
+            ReviewStatus::Hard,
+            ReviewStatus::Good,
+            ReviewStatus::Easy,
+        ]
+        .into_iter()
+        .map(|status| {
+            update_performance(
+                Performance::Reviewed(initial_perf),
+                status,
+                now,
+                &Settings::default(),
+                "card",
+            )
+            .unwrap()
+            .interval_raw
+        })
+        .collect();
+        assert!(intervals.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_easy_skips_learning_caps() {
+        let now = chrono::Utc::now();
+        let good = update_performance(
+            Performance::New,
+            ReviewStatus::Good,
+            now,
+            &Settings::default(),
+            "card",
+        )
+        .unwrap();
+        let easy = update_performance(
+            Performance::New,
+            ReviewStatus::Easy,
+            now,
+            &Settings::default(),
+            "card",
+        )
+        .unwrap();
+        assert!(approx_eq(good.interval_raw, 0.0006944444444444445));
+        assert!(easy.interval_days >= 1);
+    }
+
+    #[test]
+    fn learning_steps_cap_early_reviews_and_reset_on_failure() {
+        let now = chrono::Utc::now();
+        let settings = Settings {
+            learning_steps: vec![
+                std::time::Duration::from_secs(5 * 60),
+                std::time::Duration::from_secs(2 * 60 * 60),
+                std::time::Duration::from_secs(2 * 24 * 60 * 60),
+            ],
+            ..Settings::default()
+        };
+        let first =
+            update_performance(Performance::New, ReviewStatus::Good, now, &settings, "card")
+                .unwrap();
+        assert_eq!(first.due_date - now, Duration::minutes(5));
+
+        let second = update_performance(
+            Performance::Reviewed(first),
+            ReviewStatus::Good,
+            now,
+            &settings,
+            "card",
+        )
+        .unwrap();
+        assert!(second.due_date - now <= Duration::hours(2));
+
+        let failed = update_performance(
+            Performance::Reviewed(second),
+            ReviewStatus::Again,
+            now,
+            &settings,
+            "card",
+        )
+        .unwrap();
+        assert_eq!(failed.review_count, 3);
+        assert!(failed.due_date - now <= Duration::minutes(5));
+    }
+
+    #[test]
+    fn max_interval_clamps_the_whole_schedule() {
+        let now = chrono::Utc::now();
+        let perf = Performance::Reviewed(ReviewedPerformance {
+            last_reviewed_at: now - Duration::days(200),
+            stability: 200.0,
+            difficulty: 3.0,
+            interval_raw: 200.0,
+            interval_days: 200,
+            due_date: now,
+            review_count: 8,
+        });
+        let uncapped =
+            update_performance(perf, ReviewStatus::Easy, now, &Settings::default(), "card")
+                .unwrap();
+        assert!(uncapped.interval_days > 30);
+
+        let settings = Settings {
+            max_interval_days: Some(30),
+            ..Settings::default()
+        };
+        let capped = update_performance(perf, ReviewStatus::Easy, now, &settings, "card").unwrap();
+        assert_eq!(capped.interval_days, 30);
+        assert!(approx_eq(capped.interval_raw, 30.0));
+        assert_eq!(capped.due_date, now + Duration::days(30));
+    }
+
+    #[test]
+    fn fuzz_is_bounded_and_reproducible() {
+        let interval = Duration::days(40);
+        let fuzzed: Vec<Duration> = (0..20)
+            .map(|i| fuzz_interval(interval, &format!("card-{i}"), 5))
+            .collect();
+        let spread = Duration::seconds((40.0 * 86_400.0 * FUZZ_RATIO) as i64);
+        assert!(fuzzed.iter().all(|d| (*d - interval).abs() <= spread));
+        assert!(fuzzed.iter().any(|d| *d != fuzzed[0]));
+        assert_eq!(fuzz_interval(interval, "card-0", 5), fuzzed[0]);
+        assert_eq!(
+            fuzz_interval(Duration::hours(20), "card-0", 5),
+            Duration::hours(20)
+        );
+
+        let now = chrono::Utc::now();
+        let perf = Performance::Reviewed(ReviewedPerformance {
+            last_reviewed_at: now - Duration::days(10),
+            stability: 10.0,
+            difficulty: 5.0,
+            interval_raw: 10.0,
+            interval_days: 10,
+            due_date: now,
+            review_count: 5,
+        });
+        let exact = Settings {
+            fuzz_intervals: false,
+            ..Settings::default()
+        };
+        let a = update_performance(perf, ReviewStatus::Good, now, &exact, "a").unwrap();
+        let b = update_performance(perf, ReviewStatus::Good, now, &exact, "b").unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn lower_retention_stretches_intervals() {
+        let now = chrono::Utc::now();
+        let perf = Performance::Reviewed(ReviewedPerformance {
+            last_reviewed_at: now - Duration::days(10),
+            stability: 10.0,
+            difficulty: 5.0,
+            interval_raw: 10.0,
+            interval_days: 10,
+            due_date: now,
+            review_count: 5,
+        });
+        let strict_settings = Settings {
+            desired_retention: 0.95,
+            ..Settings::default()
+        };
+        let relaxed_settings = Settings {
+            desired_retention: 0.80,
+            ..Settings::default()
+        };
+        let strict =
+            update_performance(perf, ReviewStatus::Good, now, &strict_settings, "card").unwrap();
+        let relaxed =
+            update_performance(perf, ReviewStatus::Good, now, &relaxed_settings, "card").unwrap();
+        assert!(relaxed.interval_raw > strict.interval_raw);
+    }
 }
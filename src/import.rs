@@ -5,51 +5,273 @@ use serde_json::Value;
 use sqlx::{Row, SqlitePool};
 use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
-use std::path::{Path, PathBuf};
+use std::io::{self, IsTerminal};
+use std::path::{Component, Path, PathBuf};
 use tempfile::NamedTempFile;
 use zip::ZipArchive;
 
 use anyhow::{Context, Result, anyhow, bail};
+use clap::ValueEnum;
+use dialoguer::{MultiSelect, theme::ColorfulTheme};
 
 use crate::crud::DB;
+use crate::import_csv;
+use crate::import_mnemosyne;
 use crate::palette::Palette;
 use crate::parser::get_hash;
+use crate::utils::{ask_yn, pluralize};
 
 static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<[^>]+>").unwrap());
 static CLOZE_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(?s)\{\{c\d+::(.*?)(?:::(.*?))?\}\}").unwrap());
+static IMG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<img\b[^>]*?\bsrc\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))[^>]*>"#).unwrap()
+});
+static SOUND_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[sound:([^\]]+)\]").unwrap());
+static MEDIA_LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\]\(<?media/([^)>]+)>?\)").unwrap());
+static TEMPLATE_FIELD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{([^{}]+)\}\}").unwrap());
+
+const MEDIA_DIR: &str = "media";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ImportSource {
+    Anki,
+    Mnemosyne,
+    Csv,
+}
 
 #[derive(Clone)]
-struct DeckInfo {
-    name: String,
-    components: Vec<String>,
+pub(crate) struct DeckInfo {
+    pub(crate) name: String,
+    pub(crate) components: Vec<String>,
 }
 
-#[derive(Clone, Copy)]
-enum ModelKind {
-    Basic,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ModelKind {
+    /// A front/back note type, with the fields each card template asks and
+    /// answers with, indexed by template ordinal. Templates that couldn't be
+    /// read are `None`.
+    Basic(Vec<Option<TemplateFields>>),
     Cloze,
 }
 
+/// The note fields a card template shows as its question and its answer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct TemplateFields {
+    pub(crate) question: usize,
+    pub(crate) answer: usize,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct CardRecord {
+    /// Anki note id, or Mnemosyne fact id, so skipped cards can be traced back.
+    pub(crate) note_id: i64,
+    pub(crate) deck_id: i64,
+    pub(crate) model_id: i64,
+    pub(crate) card_order: i64,
+    pub(crate) fields: Vec<String>,
+}
+
+/// Why a card was left out of the export.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SkipReason {
+    EmptyFront,
+    EmptyBack,
+    UnknownModel,
+    Duplicate,
+}
+
+impl SkipReason {
+    const ALL: [SkipReason; 4] = [
+        SkipReason::EmptyFront,
+        SkipReason::EmptyBack,
+        SkipReason::UnknownModel,
+        SkipReason::Duplicate,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SkipReason::EmptyFront => "empty front",
+            SkipReason::EmptyBack => "empty back",
+            SkipReason::UnknownModel => "unknown model",
+            SkipReason::Duplicate => "duplicate",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
-struct CardRecord {
-    deck_id: i64,
-    model_id: i64,
-    card_order: i64,
-    fields: Vec<String>,
+pub(crate) struct SkippedCard {
+    pub(crate) note_id: i64,
+    pub(crate) deck_id: i64,
+    pub(crate) reason: SkipReason,
+}
+
+/// Card entries grouped by deck id, plus the cards that were left out.
+pub(crate) struct Exports {
+    pub(crate) per_deck: HashMap<i64, Vec<String>>,
+    pub(crate) skipped: Vec<SkippedCard>,
+}
+
+/// Media files bundled in an apkg. The zip stores them under numeric names and
+/// the `media` JSON manifest maps those back to the original filenames.
+struct MediaArchive {
+    zip: ZipArchive<File>,
+    entries: HashMap<String, String>,
+}
+
+impl MediaArchive {
+    fn open(apkg: &Path) -> Result<Self> {
+        let file = File::open(apkg)
+            .with_context(|| format!("failed to open apkg file: {}", apkg.display()))?;
+        let mut zip = ZipArchive::new(file).context("failed to read apkg as zip archive")?;
+        let entries = match zip.by_name("media") {
+            Ok(mut manifest) => {
+                let mut raw = String::new();
+                io::Read::read_to_string(&mut manifest, &mut raw)
+                    .context("failed to read apkg media manifest")?;
+                parse_media_manifest(&raw)?
+            }
+            Err(_) => HashMap::new(),
+        };
+        Ok(Self { zip, entries })
+    }
+
+    fn copy_to(&mut self, name: &str, dir: &Path) -> Result<bool> {
+        if !is_plain_file_name(name) {
+            return Ok(false);
+        }
+        let Some(entry_name) = self.entries.get(name) else {
+            return Ok(false);
+        };
+        let mut entry = self
+            .zip
+            .by_name(entry_name)
+            .with_context(|| format!("apkg is missing media file {name}"))?;
+        fs::create_dir_all(dir)?;
+        let mut out = File::create(dir.join(name))?;
+        io::copy(&mut entry, &mut out)?;
+        Ok(true)
+    }
+}
+
+/// How `repeater import` writes the decks it converts.
+#[derive(Clone, Debug, Default)]
+pub struct ImportOptions {
+    /// Print what would be written without touching the export folder.
+    pub dry_run: bool,
+    /// One file per deck in the export folder instead of nested folders.
+    pub flat: bool,
+    /// Export every deck without asking which ones to keep.
+    pub all_decks: bool,
+    /// Ignore Anki card templates and swap front and back on odd template
+    /// ordinals, as older versions did.
+    pub model_reverse: bool,
+    /// File listing the cards that were skipped and why.
+    pub report: Option<PathBuf>,
+    /// Overwrite existing deck files without asking.
+    pub force: bool,
+    /// Skip the running commentary; prompts, dry-run plans, warnings, and
+    /// the closing summary still print.
+    pub quiet: bool,
 }
 
-pub async fn run(_db: &DB, anki_path: &Path, export_path: &Path) -> Result<()> {
+impl ImportSource {
+    fn name(self) -> &'static str {
+        match self {
+            ImportSource::Anki => "anki",
+            ImportSource::Mnemosyne => "mnemosyne",
+            ImportSource::Csv => "csv",
+        }
+    }
+}
+
+impl ImportOptions {
+    /// The flags set here that imports from `source` have no use for.
+    fn unsupported_by(&self, source: ImportSource) -> Vec<&'static str> {
+        let mut flags = Vec::new();
+        if source != ImportSource::Anki && self.model_reverse {
+            flags.push("--model-reverse");
+        }
+        if source == ImportSource::Csv {
+            // a CSV becomes one Markdown file, with no decks or note ids
+            for (set, flag) in [
+                (self.all_decks, "--all"),
+                (self.flat, "--flat"),
+                (self.report.is_some(), "--report"),
+            ] {
+                if set {
+                    flags.push(flag);
+                }
+            }
+        }
+        flags
+    }
+}
+
+pub async fn run(
+    _db: &DB,
+    source: ImportSource,
+    import_path: &Path,
+    export_path: &Path,
+    options: &ImportOptions,
+) -> Result<()> {
+    let unsupported = options.unsupported_by(source);
+    if !unsupported.is_empty() {
+        bail!(
+            "{} can't be used with --source {}",
+            unsupported.join(", "),
+            source.name()
+        );
+    }
+    match source {
+        ImportSource::Anki => run_anki(import_path, export_path, options)
+            .await
+            .context("Importing from Anki is a work in progress, please report issues on https://github.com/shaankhosla/repeater"),
+        ImportSource::Mnemosyne => {
+            let (decks, models, cards) = import_mnemosyne::load(import_path, options).await?;
+            let cards = choose_decks(&decks, cards, options.all_decks, options.quiet)?;
+            let exports = build_exports(cards, &models, options.quiet);
+            if let Some(report) = &options.report {
+                write_report(report, &decks, &exports.skipped)?;
+            }
+            write_exports(export_path, &decks, exports.per_deck, options, None)?;
+            Ok(())
+        }
+        ImportSource::Csv => import_csv::run(import_path, export_path, options),
+    }
+}
+
+async fn run_anki(anki_path: &Path, export_path: &Path, options: &ImportOptions) -> Result<()> {
     validate_path(anki_path)?;
     let db_path = extract_collection_db(anki_path)?;
     let db_url = format!("sqlite://{}", db_path.path().display());
     let export_db = SqlitePool::connect(&db_url)
         .await
         .context("failed to connect to Anki database")?;
-    let (decks, models) = load_metadata(&export_db).await?;
-    let cards = load_cards(&export_db).await?;
-    let exports = build_exports(cards, &models);
-    write_exports(export_path, &decks, exports)?;
+    let (decks, mut models) = load_metadata(&export_db, options.quiet).await?;
+    if options.model_reverse {
+        for model in models.values_mut() {
+            if let ModelKind::Basic(templates) = model {
+                templates.clear();
+            }
+        }
+    }
+    let cards = load_cards(&export_db, options.quiet).await?;
+    // picked before deduplication, so a card shared with a left-out deck
+    // still lands in the kept one
+    let cards = choose_decks(&decks, cards, options.all_decks, options.quiet)?;
+    let exports = build_exports(cards, &models, options.quiet);
+    if let Some(report) = &options.report {
+        write_report(report, &decks, &exports.skipped)?;
+    }
+    let mut media = MediaArchive::open(anki_path)?;
+    write_exports(
+        export_path,
+        &decks,
+        exports.per_deck,
+        options,
+        Some(&mut media),
+    )?;
     Ok(())
 }
 
@@ -92,6 +314,7 @@ fn extract_collection_db(apkg: &Path) -> Result<NamedTempFile> {
 
 async fn load_metadata(
     pool: &SqlitePool,
+    quiet: bool,
 ) -> Result<(HashMap<i64, DeckInfo>, HashMap<i64, ModelKind>)> {
     let row = sqlx::query("SELECT decks, models FROM col LIMIT 1")
         .fetch_one(pool)
@@ -101,11 +324,13 @@ async fn load_metadata(
     let models_raw: String = row.try_get("models")?;
     let decks = parse_decks(&decks_raw)?;
     let models = parse_models(&models_raw)?;
-    println!(
-        "{} decks and {} models in DB schema",
-        Palette::paint(Palette::WARNING, decks.len()),
-        Palette::paint(Palette::WARNING, models.len())
-    );
+    if !quiet {
+        println!(
+            "{} decks and {} models in DB schema",
+            Palette::paint(Palette::WARNING, decks.len()),
+            Palette::paint(Palette::WARNING, models.len())
+        );
+    }
     Ok((decks, models))
 }
 
@@ -138,7 +363,7 @@ fn parse_models(json: &str) -> Result<HashMap<i64, ModelKind>> {
             if let Some(id) = model.get("id").and_then(|v| v.as_i64()) {
                 let kind = match model.get("type").and_then(|v| v.as_i64()).unwrap_or(0) {
                     1 => ModelKind::Cloze,
-                    _ => ModelKind::Basic,
+                    _ => ModelKind::Basic(template_fields(model)),
                 };
                 models.insert(id, kind);
             }
@@ -147,10 +372,75 @@ fn parse_models(json: &str) -> Result<HashMap<i64, ModelKind>> {
     Ok(models)
 }
 
-async fn load_cards(pool: &SqlitePool) -> Result<Vec<CardRecord>> {
+/// The question and answer field of each of a model's card templates, by
+/// ordinal. The question is the first field the front template names; the
+/// answer is the first field the back template adds to it.
+fn template_fields(model: &Value) -> Vec<Option<TemplateFields>> {
+    let field_ords: HashMap<&str, usize> = model
+        .get("flds")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(idx, field)| {
+            let name = field.get("name")?.as_str()?;
+            let ord = field
+                .get("ord")
+                .and_then(Value::as_u64)
+                .map_or(idx, |ord| ord as usize);
+            Some((name, ord))
+        })
+        .collect();
+    let Some(templates) = model.get("tmpls").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    let mut by_ord = vec![None; templates.len()];
+    for (idx, template) in templates.iter().enumerate() {
+        let ord = template
+            .get("ord")
+            .and_then(Value::as_u64)
+            .map_or(idx, |ord| ord as usize);
+        // an ord past the template list can only come from a broken model
+        let Some(slot) = by_ord.get_mut(ord) else {
+            continue;
+        };
+        let format = |key| template.get(key).and_then(Value::as_str).unwrap_or("");
+        let front = referenced_fields(format("qfmt"), &field_ords);
+        let back = referenced_fields(format("afmt"), &field_ords);
+        let fields = front.first().and_then(|&question| {
+            let answer = back.into_iter().find(|field| !front.contains(field))?;
+            Some(TemplateFields { question, answer })
+        });
+        *slot = fields;
+    }
+    by_ord
+}
+
+/// The note fields a template shows, in order. Section tags like
+/// `{{#Field}}` only test a field, and `{{FrontSide}}` is not a field.
+fn referenced_fields(format: &str, field_ords: &HashMap<&str, usize>) -> Vec<usize> {
+    let mut fields = Vec::new();
+    for caps in TEMPLATE_FIELD_RE.captures_iter(format) {
+        let tag = caps[1].trim();
+        if tag.starts_with(['#', '^', '/', '!']) {
+            continue;
+        }
+        // filters come first: {{text:Front}}, {{type:Back}}, {{hint:Notes}}
+        let name = tag.rsplit(':').next().unwrap_or(tag).trim();
+        if let Some(&ord) = field_ords.get(name)
+            && !fields.contains(&ord)
+        {
+            fields.push(ord);
+        }
+    }
+    fields
+}
+
+async fn load_cards(pool: &SqlitePool, quiet: bool) -> Result<Vec<CardRecord>> {
     let rows = sqlx::query(
         r#"
         SELECT
+            notes.id   AS nid,  -- note id
             cards.did  AS did,  -- deck id
             cards.ord  AS ord,  -- card order (template ordinal)
             notes.mid  AS mid,  -- model (note type) id
@@ -164,6 +454,7 @@ async fn load_cards(pool: &SqlitePool) -> Result<Vec<CardRecord>> {
     .await?;
     let mut cards = Vec::with_capacity(rows.len());
     for row in rows {
+        let note_id: i64 = row.try_get("nid")?;
         let deck_id: i64 = row.try_get("did")?;
         let card_order: i64 = row.try_get("ord")?;
         let model_id: i64 = row.try_get("mid")?;
@@ -171,6 +462,7 @@ async fn load_cards(pool: &SqlitePool) -> Result<Vec<CardRecord>> {
         //"Examples of supervised methods with built-in feature selection\u{1f}Decision trees<br><div>LASSO (linear regression with L1 regularization)</div>\u{1f}<a href=\"https://machinelearningmastery.com/feature-selection-with-real-and-categorical-data/\">https://machinelearningmastery.com/feature-selection-with-real-and-categorical-data/</a>\u{1f}"
         let fields_raw: String = row.try_get("flds")?;
         let card = CardRecord {
+            note_id,
             deck_id,
             model_id,
             card_order,
@@ -178,125 +470,355 @@ async fn load_cards(pool: &SqlitePool) -> Result<Vec<CardRecord>> {
         };
         cards.push(card);
     }
-    println!(
-        "{} cards in DB",
-        Palette::paint(Palette::WARNING, cards.len())
-    );
+    if !quiet {
+        println!(
+            "{} cards in DB",
+            Palette::paint(Palette::WARNING, cards.len())
+        );
+    }
     Ok(cards)
 }
 
-fn build_exports(
+pub(crate) fn build_exports(
     cards: Vec<CardRecord>,
     models: &HashMap<i64, ModelKind>,
-) -> HashMap<i64, Vec<String>> {
+    quiet: bool,
+) -> Exports {
     let mut per_deck: HashMap<i64, Vec<String>> = HashMap::new();
-    let mut num_duplicates = 0;
+    let mut skipped = Vec::new();
     let mut content_hashes: HashSet<String> = HashSet::new();
 
-    let mut unexportable = 0;
     for card in cards {
-        let Some(model) = models.get(&card.model_id) else {
-            println!(
-                "Card with an unknown model id found: {}",
-                Palette::paint(Palette::DANGER, card.model_id)
-            );
-            continue;
-        };
-        let entry = match model {
-            ModelKind::Basic => basic_entry(&card.fields, card.card_order),
-            ModelKind::Cloze => cloze_entry(&card.fields),
-        };
-
-        let Some(content) = entry else {
-            unexportable += 1;
-            continue;
-        };
-        let Some(content_hash) = get_hash(&content) else {
-            unexportable += 1;
-            continue;
+        let entry = match models.get(&card.model_id) {
+            Some(ModelKind::Basic(templates)) => {
+                let template = usize::try_from(card.card_order)
+                    .ok()
+                    .and_then(|ord| templates.get(ord).copied().flatten());
+                basic_entry(&card.fields, card.card_order, template)
+            }
+            Some(ModelKind::Cloze) => cloze_entry(&card.fields),
+            None => Err(SkipReason::UnknownModel),
         };
-        if !content_hashes.insert(content_hash) {
-            num_duplicates += 1;
-            continue;
+        let entry = entry.and_then(|content| {
+            // entries start with `Q:` or `C:`, so they are never blank
+            let hash = get_hash(&content).expect("card entries are never blank");
+            if content_hashes.insert(hash) {
+                Ok(content)
+            } else {
+                Err(SkipReason::Duplicate)
+            }
+        });
+        match entry {
+            Ok(content) => per_deck.entry(card.deck_id).or_default().push(content),
+            Err(reason) => skipped.push(SkippedCard {
+                note_id: card.note_id,
+                deck_id: card.deck_id,
+                reason,
+            }),
         }
-        per_deck.entry(card.deck_id).or_default().push(content);
     }
+
+    let breakdown: Vec<String> = SkipReason::ALL
+        .iter()
+        .map(|&reason| {
+            let count = skipped.iter().filter(|card| card.reason == reason).count();
+            format!(
+                "{} {}",
+                Palette::paint(Palette::WARNING, count),
+                reason.label()
+            )
+        })
+        .collect();
+    if !quiet {
+        println!(
+            "Skipped {} cards: {}",
+            Palette::paint(Palette::WARNING, skipped.len()),
+            breakdown.join(", ")
+        );
+    }
+    Exports { per_deck, skipped }
+}
+
+/// Checklist entries for the decks that have cards, sorted by name, each
+/// labelled with its card count.
+fn deck_choices(decks: &HashMap<i64, DeckInfo>, cards: &[CardRecord]) -> Vec<(i64, String)> {
+    let mut counts: HashMap<i64, usize> = HashMap::new();
+    for card in cards {
+        *counts.entry(card.deck_id).or_default() += 1;
+    }
+    let mut choices: Vec<(i64, &str, usize)> = counts
+        .into_iter()
+        .map(|(deck_id, count)| {
+            let name = decks
+                .get(&deck_id)
+                .map_or("Deck", |deck| deck.name.as_str());
+            (deck_id, name, count)
+        })
+        .collect();
+    choices.sort_by_key(|(_, name, _)| *name);
+    choices
+        .into_iter()
+        .map(|(deck_id, name, count)| (deck_id, format!("{name} ({})", pluralize("card", count))))
+        .collect()
+}
+
+/// Asks which decks to export, every deck checked to start with. Skipped with
+/// `all_decks`, for a single deck, or when stdin isn't a terminal, so scripts
+/// keep exporting everything.
+fn choose_decks(
+    decks: &HashMap<i64, DeckInfo>,
+    mut cards: Vec<CardRecord>,
+    all_decks: bool,
+    quiet: bool,
+) -> Result<Vec<CardRecord>> {
+    let choices = deck_choices(decks, &cards);
+    if all_decks || choices.len() < 2 || !io::stdin().is_terminal() {
+        return Ok(cards);
+    }
+    let labels: Vec<&str> = choices.iter().map(|(_, label)| label.as_str()).collect();
+    let picked = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Decks to import (Space toggles, Enter confirms)")
+        .items(&labels)
+        .defaults(&vec![true; labels.len()])
+        .report(false)
+        .interact()
+        .context("Failed to read the deck selection")?;
+    let keep: HashSet<i64> = picked.into_iter().map(|idx| choices[idx].0).collect();
+    cards.retain(|card| keep.contains(&card.deck_id));
+    if !quiet {
+        println!(
+            "Importing {} of {} decks",
+            Palette::paint(Palette::WARNING, keep.len()),
+            Palette::paint(Palette::WARNING, choices.len())
+        );
+    }
+    Ok(cards)
+}
+
+/// Writes one tab-separated line per skipped card, so the notes can be found
+/// and fixed in the source collection.
+fn write_report(
+    path: &Path,
+    decks: &HashMap<i64, DeckInfo>,
+    skipped: &[SkippedCard],
+) -> Result<()> {
+    let mut report = String::from("note_id\treason\tdeck\n");
+    for card in skipped {
+        let deck = decks
+            .get(&card.deck_id)
+            .map(|deck| deck.name.as_str())
+            .unwrap_or("");
+        report.push_str(&format!(
+            "{}\t{}\t{}\n",
+            card.note_id,
+            card.reason.label(),
+            deck
+        ));
+    }
+    fs::write(path, report)
+        .with_context(|| format!("Failed to write import report to {}", path.display()))?;
     println!(
-        "Removing {} duplicates",
-        Palette::paint(Palette::WARNING, num_duplicates)
-    );
-    println!(
-        "{} unexportable cards",
-        Palette::paint(Palette::WARNING, unexportable)
+        "Wrote {} skipped cards to {}",
+        Palette::paint(Palette::WARNING, skipped.len()),
+        Palette::paint(Palette::ACCENT, path.display())
     );
-    per_deck
+    Ok(())
 }
 
 fn write_exports(
     export_path: &Path,
     decks: &HashMap<i64, DeckInfo>,
     exports: HashMap<i64, Vec<String>>,
-) -> Result<()> {
+    options: &ImportOptions,
+    mut media: Option<&mut MediaArchive>,
+) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    let mut total_cards = 0;
+    let mut taken_paths = HashSet::new();
     for deck_id in decks.keys() {
         let exports_per_deck = exports.get(deck_id).map(|v| v.len()).unwrap_or(0);
-        println!(
-            "Deck {} has {} cards",
-            Palette::paint(Palette::ACCENT, decks.get(deck_id).unwrap().name.as_str()),
-            Palette::paint(Palette::WARNING, exports_per_deck)
-        );
+        if !options.quiet {
+            println!(
+                "Deck {} has {} cards",
+                Palette::paint(Palette::ACCENT, decks.get(deck_id).unwrap().name.as_str()),
+                Palette::paint(Palette::WARNING, exports_per_deck)
+            );
+        }
     }
     let mut entries: Vec<(i64, Vec<String>)> = exports
         .into_iter()
         .filter(|(_, cards)| !cards.is_empty())
         .collect();
-    println!(
-        "There are {} decks with at least one card",
-        Palette::paint(Palette::WARNING, entries.len())
-    );
+    if !options.quiet {
+        println!(
+            "There are {} decks with at least one card",
+            Palette::paint(Palette::WARNING, entries.len())
+        );
+    }
     entries.sort_by(|(a, _), (b, _)| {
         let name_a = decks.get(a).map(|d| d.name.as_str()).unwrap_or("");
         let name_b = decks.get(b).map(|d| d.name.as_str()).unwrap_or("");
         name_a.cmp(name_b)
     });
-    for (deck_id, cards) in entries {
-        let deck = decks
-            .get(&deck_id)
-            .ok_or_else(|| anyhow!("missing deck metadata for id {}", deck_id))?;
-        let mut path = PathBuf::from(export_path);
-        if deck.components.len() > 1 {
-            for component in &deck.components[..deck.components.len() - 1] {
-                path.push(component);
+    let planned: Vec<(PathBuf, Vec<String>)> = entries
+        .into_iter()
+        .map(|(deck_id, cards)| {
+            let deck = decks
+                .get(&deck_id)
+                .ok_or_else(|| anyhow!("missing deck metadata for id {}", deck_id))?;
+            let path = deck_path(
+                export_path,
+                &deck.components,
+                options.flat,
+                &mut taken_paths,
+            );
+            Ok((path, cards))
+        })
+        .collect::<Result<_>>()?;
+    let existing = existing_files(planned.iter().map(|(path, _)| path.as_path()));
+    if options.dry_run {
+        for path in &existing {
+            println!(
+                "Would overwrite {}",
+                Palette::paint(Palette::DANGER, path.display())
+            );
+        }
+    } else {
+        confirm_overwrite(&existing, options.force)?;
+    }
+    for (path, cards) in planned {
+        let mut content = String::new();
+        for card in &cards {
+            content.push_str(card);
+        }
+        let media_names = media_references(&content);
+        let media_dir = path
+            .parent()
+            .map(|parent| parent.join(MEDIA_DIR))
+            .unwrap_or_else(|| PathBuf::from(MEDIA_DIR));
+        if options.dry_run {
+            println!(
+                "Would write {} cards to {}",
+                Palette::paint(Palette::WARNING, cards.len()),
+                Palette::paint(Palette::ACCENT, path.display())
+            );
+            if !media_names.is_empty() {
+                println!(
+                    "Would copy {} media files to {}",
+                    Palette::paint(Palette::WARNING, media_names.len()),
+                    Palette::paint(Palette::ACCENT, media_dir.display())
+                );
             }
+            written.push(path);
+            continue;
         }
-        let file_stem = deck
-            .components
-            .last()
-            .cloned()
-            .unwrap_or_else(|| "Deck".to_string());
-        path.push(format!("{file_stem}.md"));
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
-        let mut content = String::new();
-        for card in &cards {
-            content.push_str(card);
+        if let Some(media) = media.as_deref_mut() {
+            let mut copied = 0;
+            for name in &media_names {
+                if media.copy_to(name, &media_dir)? {
+                    copied += 1;
+                } else {
+                    eprintln!(
+                        "{} media file {} is referenced but missing from the apkg",
+                        Palette::paint(Palette::WARNING, "Warning:"),
+                        Palette::paint(Palette::DANGER, name)
+                    );
+                }
+            }
+            if copied > 0 && !options.quiet {
+                println!(
+                    "Copied {} media files to {}",
+                    Palette::paint(Palette::WARNING, copied),
+                    Palette::paint(Palette::ACCENT, media_dir.display())
+                );
+            }
+        }
+        if !options.quiet {
+            println!(
+                "Writing {} cards to {}",
+                Palette::paint(Palette::WARNING, cards.len()),
+                Palette::paint(Palette::ACCENT, path.display())
+            );
         }
+        fs::write(&path, content)?;
+        written.push(path);
+        total_cards += cards.len();
+    }
+    if options.dry_run {
         println!(
-            "Writing {} cards to {}",
-            Palette::paint(Palette::WARNING, cards.len()),
-            Palette::paint(Palette::ACCENT, path.display())
+            "{}",
+            Palette::dim("Dry run: no files were written. Rerun without --dry-run to import.")
+        );
+    } else {
+        println!(
+            "Imported {} into {}",
+            Palette::paint(Palette::WARNING, pluralize("card", total_cards)),
+            Palette::paint(Palette::WARNING, pluralize("file", written.len()))
         );
-        fs::write(&path, content)?;
     }
-    Ok(())
+    Ok(written)
+}
+
+/// The files among `paths` that are already on disk.
+pub(crate) fn existing_files<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .filter(|path| path.exists())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Lists the deck files an import would replace and asks before going on.
+/// Without a terminal to ask on, only `--force` lets the import overwrite them.
+pub(crate) fn confirm_overwrite(existing: &[PathBuf], force: bool) -> Result<()> {
+    if existing.is_empty() || force {
+        return Ok(());
+    }
+    println!(
+        "Importing would overwrite {}:",
+        Palette::paint(Palette::DANGER, pluralize("existing file", existing.len()))
+    );
+    for path in existing {
+        println!("  {}", Palette::paint(Palette::ACCENT, path.display()));
+    }
+    if io::stdin().is_terminal() && ask_yn("Overwrite them?".to_string()) {
+        return Ok(());
+    }
+    bail!("Import cancelled; nothing was written. Rerun with --force to overwrite these files.")
 }
 
 fn split_fields(raw: &str) -> Vec<String> {
     raw.split('\x1f').map(clean_field).collect()
 }
 
-fn clean_field(field: &str) -> String {
+pub(crate) fn clean_field(field: &str) -> String {
+    // Media links are swapped for placeholders so the tag stripping below
+    // can't eat `<...>` link destinations.
+    let mut links: Vec<String> = Vec::new();
+    let mut placeholder = |link: String| {
+        links.push(link);
+        format!("\u{1}{}\u{1}", links.len() - 1)
+    };
     let mut text = field.replace("\r\n", "\n");
+    text = IMG_RE
+        .replace_all(&text, |caps: &regex::Captures| {
+            let src = caps
+                .get(1)
+                .or_else(|| caps.get(2))
+                .or_else(|| caps.get(3))
+                .map(|m| m.as_str())
+                .unwrap_or("");
+            placeholder(media_link("!", "", src))
+        })
+        .into_owned();
+    text = SOUND_RE
+        .replace_all(&text, |caps: &regex::Captures| {
+            placeholder(media_link("", "audio", &caps[1]))
+        })
+        .into_owned();
     text = text.replace("<br />", "\n");
     text = text.replace("<br>", "\n");
     text = text.replace("<div>", "\n");
@@ -306,10 +828,111 @@ fn clean_field(field: &str) -> String {
     text = text.replace("<li>", "\n- ");
     text = text.replace("</li>", "");
     let without_tags = TAG_RE.replace_all(&text, "");
-    decode_html_entities(without_tags.trim()).to_string()
+    let mut cleaned = decode_html_entities(without_tags.trim()).to_string();
+    for (idx, link) in links.iter().enumerate() {
+        cleaned = cleaned.replace(&format!("\u{1}{idx}\u{1}"), link);
+    }
+    cleaned
+}
+
+/// Points a markdown link at the deck's `media/` folder. Only the file name is
+/// kept so a crafted reference can't escape that folder.
+fn media_link(prefix: &str, label: &str, src: &str) -> String {
+    let decoded = decode_html_entities(src.trim());
+    let Some(name) = Path::new(decoded.as_ref())
+        .file_name()
+        .and_then(|name| name.to_str())
+    else {
+        return String::new();
+    };
+    if name.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        format!("{prefix}[{label}](<{MEDIA_DIR}/{name}>)")
+    } else {
+        format!("{prefix}[{label}]({MEDIA_DIR}/{name})")
+    }
+}
+
+/// Whether `name` is a single file name, so joining it to the media folder
+/// can't write anywhere else. Rejects `..`, absolute paths, and subfolders.
+fn is_plain_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
+fn media_references(content: &str) -> Vec<String> {
+    let mut names: Vec<String> = MEDIA_LINK_RE
+        .captures_iter(content)
+        .map(|caps| caps[1].to_string())
+        .filter(|name| {
+            let plain = is_plain_file_name(name);
+            if !plain {
+                eprintln!(
+                    "{} skipping media file {} outside the media folder",
+                    Palette::paint(Palette::WARNING, "Warning:"),
+                    Palette::paint(Palette::DANGER, name)
+                );
+            }
+            plain
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
 }
 
-fn deck_components(name: &str) -> Vec<String> {
+fn parse_media_manifest(json: &str) -> Result<HashMap<String, String>> {
+    let value: Value = serde_json::from_str(json).context("failed to parse media manifest")?;
+    let mut entries = HashMap::new();
+    if let Some(map) = value.as_object() {
+        for (entry_name, real_name) in map {
+            if let Some(real_name) = real_name.as_str()
+                && is_plain_file_name(real_name)
+            {
+                entries.insert(real_name.to_string(), entry_name.clone());
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Markdown file a deck is written to: nested folders per `::` component, or
+/// with `flat`, one file in `export_path` named after the whole deck path
+/// (`Data Science - Clustering.md`). Decks whose names collide, ignoring case,
+/// get a numbered suffix instead of overwriting each other.
+fn deck_path(
+    export_path: &Path,
+    components: &[String],
+    flat: bool,
+    taken_paths: &mut HashSet<String>,
+) -> PathBuf {
+    let mut dir = PathBuf::from(export_path);
+    let file_stem = if flat {
+        sanitize_component(&components.join(" - "))
+    } else {
+        if let Some((_, parents)) = components.split_last() {
+            dir.extend(parents);
+        }
+        components.last().cloned().unwrap_or_default()
+    };
+    let file_stem = if file_stem.is_empty() {
+        "Deck".to_string()
+    } else {
+        file_stem
+    };
+
+    let mut path = dir.join(format!("{file_stem}.md"));
+    let mut suffix = 2;
+    while !taken_paths.insert(path.to_string_lossy().to_lowercase()) {
+        path = dir.join(format!("{file_stem} ({suffix}).md"));
+        suffix += 1;
+    }
+    path
+}
+
+pub(crate) fn deck_components(name: &str) -> Vec<String> {
     let mut parts: Vec<String> = name
         .split("::")
         .map(sanitize_component)
@@ -337,30 +960,40 @@ fn sanitize_component(input: &str) -> String {
     out.trim().to_string()
 }
 
-fn basic_entry(fields: &[String], ord: i64) -> Option<String> {
-    if fields.len() < 2 {
-        return None;
-    }
-    let (question, answer) = if ord % 2 == 0 {
-        (&fields[0], &fields[1])
-    } else {
-        (&fields[1], &fields[0])
+/// Without the card's template, even ordinals are taken as front-to-back
+/// and odd ones as the reverse, as in Anki's stock note types.
+fn basic_entry(
+    fields: &[String],
+    ord: i64,
+    template: Option<TemplateFields>,
+) -> Result<String, SkipReason> {
+    let field = |idx: usize| fields.get(idx).map_or("", String::as_str);
+    let (question, answer) = match template {
+        Some(template) => (field(template.question), field(template.answer)),
+        None if ord % 2 == 0 => (field(0), field(1)),
+        None => (field(1), field(0)),
     };
-    let mut entry = format_section("Q", question)?;
-    entry.push_str(&format_section("A", answer)?);
+    let mut entry = format_section("Q", question).ok_or(SkipReason::EmptyFront)?;
+    entry.push_str(&format_section("A", answer).ok_or(SkipReason::EmptyBack)?);
     entry.push('\n');
-    Some(entry)
+    Ok(entry)
 }
 
-fn cloze_entry(fields: &[String]) -> Option<String> {
-    let text = fields.first()?;
+/// Some note types keep the cloze text outside the first field, so the first
+/// field holding a `{{c1::...}}` deletion wins, then the first non-empty one.
+fn cloze_entry(fields: &[String]) -> Result<String, SkipReason> {
+    let text = fields
+        .iter()
+        .find(|field| CLOZE_RE.is_match(field))
+        .or_else(|| fields.iter().find(|field| !field.trim().is_empty()))
+        .ok_or(SkipReason::EmptyFront)?;
     let converted = convert_cloze(text);
-    let mut entry = format_section("C", converted.trim())?;
+    let mut entry = format_section("C", converted.trim()).ok_or(SkipReason::EmptyFront)?;
     entry.push('\n');
-    Some(entry)
+    Ok(entry)
 }
 
-fn format_section(label: &str, value: &str) -> Option<String> {
+pub(crate) fn format_section(label: &str, value: &str) -> Option<String> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
         return None;
@@ -376,8 +1009,11 @@ fn format_section(label: &str, value: &str) -> Option<String> {
 fn convert_cloze(text: &str) -> String {
     CLOZE_RE
         .replace_all(text, |caps: &regex::Captures| {
-            let inner = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            format!("[{}]", inner.trim())
+            let inner = caps.get(1).map(|m| m.as_str()).unwrap_or("").trim();
+            match caps.get(2).map(|m| m.as_str().trim()) {
+                Some(hint) if !hint.is_empty() => format!("[{inner}|{hint}]"),
+                _ => format!("[{inner}]"),
+            }
         })
         .into_owned()
 }
@@ -392,24 +1028,197 @@ mod tests {
         assert_eq!(clean_field(input), "Hello & world");
     }
 
+    #[test]
+    fn flags_the_source_has_no_use_for_are_named() {
+        let options = ImportOptions {
+            flat: true,
+            model_reverse: true,
+            report: Some(PathBuf::from("skipped.tsv")),
+            ..Default::default()
+        };
+        assert!(options.unsupported_by(ImportSource::Anki).is_empty());
+        assert_eq!(
+            options.unsupported_by(ImportSource::Mnemosyne),
+            vec!["--model-reverse"]
+        );
+        assert_eq!(
+            options.unsupported_by(ImportSource::Csv),
+            vec!["--model-reverse", "--flat", "--report"]
+        );
+        assert!(
+            ImportOptions::default()
+                .unsupported_by(ImportSource::Csv)
+                .is_empty()
+        );
+    }
+
     #[test]
     fn basic_entry_swaps_fields_on_reverse_cards() {
         let fields = vec!["Front".into(), "Back".into()];
-        let forward = basic_entry(&fields, 0).unwrap();
+        let forward = basic_entry(&fields, 0, None).unwrap();
         assert!(forward.contains("Q: Front"));
         assert!(forward.contains("A: Back"));
 
-        let reverse = basic_entry(&fields, 1).unwrap();
+        let reverse = basic_entry(&fields, 1, None).unwrap();
         assert!(reverse.contains("Q: Back"));
         assert!(reverse.contains("A: Front"));
 
-        assert!(basic_entry(&["Only".into()], 0).is_none());
+        assert_eq!(
+            basic_entry(&["Only".into()], 0, None),
+            Err(SkipReason::EmptyBack)
+        );
+        assert_eq!(
+            basic_entry(&["Only".into()], 1, None),
+            Err(SkipReason::EmptyFront)
+        );
+        assert_eq!(cloze_entry(&[" ".into()]), Err(SkipReason::EmptyFront));
+    }
+
+    #[test]
+    fn templates_decide_which_fields_ask_and_answer() {
+        let models = parse_models(
+            r#"{"7": {"id": 7, "type": 0,
+                "flds": [{"name": "Word", "ord": 0}, {"name": "Meaning", "ord": 1},
+                         {"name": "Audio", "ord": 2}],
+                "tmpls": [
+                    {"ord": 0, "qfmt": "{{Word}}", "afmt": "{{FrontSide}}<hr id=answer>{{Meaning}}"},
+                    {"ord": 1, "qfmt": "{{#Audio}}{{Audio}}{{/Audio}}", "afmt": "{{FrontSide}}{{text:Word}}"},
+                    {"ord": 2, "qfmt": "{{type:Meaning}}", "afmt": "{{FrontSide}}<hr>{{Word}} {{Audio}}"},
+                    {"ord": 3, "qfmt": "{{Deck}}", "afmt": "{{Word}}"}
+                ]}}"#,
+        )
+        .unwrap();
+        let ModelKind::Basic(templates) = &models[&7] else {
+            panic!("Expected ModelKind::Basic");
+        };
+        let fields = |question, answer| Some(TemplateFields { question, answer });
+        assert_eq!(
+            templates,
+            &vec![fields(0, 1), fields(2, 0), fields(1, 0), None]
+        );
+
+        let note = vec![
+            "hola".into(),
+            "hello".into(),
+            "[audio](media/hola.mp3)".into(),
+        ];
+        let entry = basic_entry(&note, 2, templates[2]).unwrap();
+        assert!(entry.contains("Q: hello"));
+        assert!(entry.contains("A: hola"));
+        // the third template is even but still asks for the meaning
+        let guessed = basic_entry(&note, 2, None).unwrap();
+        assert!(guessed.contains("Q: hola"));
+
+        // a template claiming an ord past the list is ignored, not allocated
+        let models = parse_models(
+            r#"{"8": {"id": 8, "type": 0,
+                "flds": [{"name": "Front", "ord": 0}, {"name": "Back", "ord": 1}],
+                "tmpls": [
+                    {"ord": 0, "qfmt": "{{Front}}", "afmt": "{{Back}}"},
+                    {"ord": 4000000000, "qfmt": "{{Back}}", "afmt": "{{Front}}"}
+                ]}}"#,
+        )
+        .unwrap();
+        let ModelKind::Basic(templates) = &models[&8] else {
+            panic!("Expected ModelKind::Basic");
+        };
+        assert_eq!(templates, &vec![fields(0, 1), None]);
+    }
+
+    #[test]
+    fn deck_choices_list_decks_with_cards_by_name() {
+        let deck = |name: &str| DeckInfo {
+            name: name.to_string(),
+            components: deck_components(name),
+        };
+        let decks = HashMap::from([
+            (1, deck("Spanish")),
+            (2, deck("Biology")),
+            (3, deck("Empty")),
+        ]);
+        let record = |note_id, deck_id| CardRecord {
+            note_id,
+            deck_id,
+            model_id: 1,
+            card_order: 0,
+            fields: vec!["shared front".into(), "shared back".into()],
+        };
+        let cards = vec![record(1, 1), record(2, 2), record(3, 2)];
+
+        assert_eq!(
+            deck_choices(&decks, &cards),
+            vec![
+                (2, "Biology (2 cards)".to_string()),
+                (1, "Spanish (1 card)".to_string()),
+            ]
+        );
+        let kept = choose_decks(&decks, cards, true, true).unwrap();
+        assert_eq!(kept.len(), 3);
+
+        // leaving Spanish out before deduplicating keeps the shared card in Biology
+        let models = HashMap::from([(1, ModelKind::Basic(Vec::new()))]);
+        let biology = kept.into_iter().filter(|card| card.deck_id == 2).collect();
+        let exports = build_exports(biology, &models, true);
+        assert_eq!(exports.per_deck[&2].len(), 1);
+        assert!(!exports.per_deck.contains_key(&1));
+    }
+
+    #[test]
+    fn cloze_entry_finds_the_cloze_in_any_field() {
+        let fields = vec![
+            "Extra context".into(),
+            "The {{c1::mitochondria}} powers the cell".into(),
+        ];
+        assert_eq!(
+            cloze_entry(&fields).unwrap(),
+            "C: The [mitochondria] powers the cell\n\n"
+        );
+
+        let fields = vec!["".into(), "No deletions here".into()];
+        assert_eq!(cloze_entry(&fields).unwrap(), "C: No deletions here\n\n");
+    }
+
+    #[test]
+    fn clean_field_keeps_media_references() {
+        let field = r#"Where?<br><img src="map of uk.png" /> [sound:bark.mp3]<img class='x' src='../etc/pic.jpg'>"#;
+        let cleaned = clean_field(field);
+        assert_eq!(
+            cleaned,
+            "Where?\n![](<media/map of uk.png>) [audio](media/bark.mp3)![](media/pic.jpg)"
+        );
+        assert_eq!(
+            media_references(&cleaned),
+            vec!["bark.mp3", "map of uk.png", "pic.jpg"]
+        );
+    }
+
+    #[test]
+    fn media_manifest_maps_real_names_to_zip_entries() {
+        let entries = parse_media_manifest(r#"{"0": "bark.mp3", "1": "map.png"}"#).unwrap();
+        assert_eq!(entries.get("bark.mp3").map(String::as_str), Some("0"));
+        assert_eq!(entries.get("map.png").map(String::as_str), Some("1"));
+        assert!(parse_media_manifest("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn media_names_that_leave_the_media_folder_are_dropped() {
+        let entries =
+            parse_media_manifest(r#"{"0": "../x", "1": "/etc/x", "2": "a/b.png", "3": "ok.png"}"#)
+                .unwrap();
+        assert_eq!(entries.keys().collect::<Vec<_>>(), vec!["ok.png"]);
+
+        let content = "![](media/../x) ![](</media//etc/x>) ![](media/ok.png)";
+        assert_eq!(media_references(content), vec!["ok.png"]);
+        assert!(!is_plain_file_name("../x"));
+        assert!(!is_plain_file_name("/etc/x"));
+        assert!(!is_plain_file_name(".."));
+        assert!(is_plain_file_name("map of uk.png"));
     }
 
     #[test]
     fn convert_cloze_rewrites_all_cloze_blocks() {
         let text = "Capital {{c1::Tokyo}} and {{c2::Kyoto::hint}}";
-        assert_eq!(convert_cloze(text), "Capital [Tokyo] and [Kyoto]");
+        assert_eq!(convert_cloze(text), "Capital [Tokyo] and [Kyoto|hint]");
     }
 
     #[test]
@@ -421,6 +1230,50 @@ mod tests {
         assert_eq!(deck_components(""), vec!["Deck".to_string()]);
     }
 
+    #[test]
+    fn deck_path_flattens_and_disambiguates_collisions() {
+        let root = Path::new("out");
+        let mut taken = HashSet::new();
+        let clustering = deck_components("Data Science::Clustering");
+        assert_eq!(
+            deck_path(root, &clustering, true, &mut taken),
+            root.join("Data Science - Clustering.md")
+        );
+        assert_eq!(
+            deck_path(
+                root,
+                &deck_components("data science - clustering"),
+                true,
+                &mut taken
+            ),
+            root.join("data science - clustering (2).md")
+        );
+        assert_eq!(
+            deck_path(
+                root,
+                &deck_components("Data Science - Clustering"),
+                true,
+                &mut taken
+            ),
+            root.join("Data Science - Clustering (3).md")
+        );
+
+        let mut taken = HashSet::new();
+        assert_eq!(
+            deck_path(root, &clustering, false, &mut taken),
+            root.join("Data Science").join("Clustering.md")
+        );
+        assert_eq!(
+            deck_path(
+                root,
+                &deck_components("Data Science::Clustering?"),
+                false,
+                &mut taken
+            ),
+            root.join("Data Science").join("Clustering-.md")
+        );
+    }
+
     #[tokio::test]
     async fn test_with_apkg() {
         let test_file =
@@ -435,13 +1288,42 @@ mod tests {
             .context("failed to connect to Anki database")
             .unwrap();
 
-        let (decks, models) = load_metadata(&export_db).await.unwrap();
+        let (decks, models) = load_metadata(&export_db, true).await.unwrap();
         assert_eq!(decks.len(), 2);
         assert_eq!(models.len(), 2);
-        let cards = load_cards(&export_db).await.unwrap();
+        let cards = load_cards(&export_db, true).await.unwrap();
         assert_eq!(cards.len(), 545);
-        let exports = build_exports(cards, &models);
-        let len = exports.values().next().map(|v: &Vec<String>| v.len());
+        let exports = build_exports(cards, &models, true);
+        let len = exports
+            .per_deck
+            .values()
+            .next()
+            .map(|v: &Vec<String>| v.len());
         assert_eq!(len, Some(320));
+        assert_eq!(exports.skipped.len(), 545 - 320);
+
+        let report_path = tempfile::NamedTempFile::new().unwrap();
+        write_report(report_path.path(), &decks, &exports.skipped).unwrap();
+        let report = fs::read_to_string(report_path.path()).unwrap();
+        assert_eq!(report.lines().count(), exports.skipped.len() + 1);
+        assert!(report.starts_with("note_id\treason\tdeck\n"));
+
+        let export_path =
+            std::env::temp_dir().join(format!("repeater-dry-run-{}", std::process::id()));
+        let planned = write_exports(
+            &export_path,
+            &decks,
+            exports.per_deck,
+            &ImportOptions {
+                dry_run: true,
+                quiet: true,
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(planned.len(), 1);
+        assert!(planned[0].starts_with(&export_path));
+        assert!(!export_path.exists());
     }
 }
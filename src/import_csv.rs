@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::import::{ImportOptions, confirm_overwrite, existing_files, format_section};
+use crate::palette::Palette;
+use crate::parser::get_hash;
+use crate::utils::pluralize;
+
+/// Converts a CSV of `question,answer[,tags]` rows into a single Markdown deck
+/// of `Q:`/`A:` cards separated by `---`.
+pub(crate) fn run(csv_path: &Path, export_path: &Path, options: &ImportOptions) -> Result<()> {
+    if !csv_path.is_file() {
+        bail!("CSV path does not exist: {}", csv_path.display());
+    }
+    let raw = fs::read_to_string(csv_path)
+        .with_context(|| format!("failed to read CSV file: {}", csv_path.display()))?;
+    let rows = parse_csv(&raw)?;
+    if !options.quiet {
+        println!(
+            "{} rows in CSV",
+            Palette::paint(Palette::WARNING, rows.len())
+        );
+    }
+
+    let (entries, num_duplicates, unexportable) = build_entries(&rows);
+    if !options.quiet {
+        println!(
+            "Removing {} duplicates",
+            Palette::paint(Palette::WARNING, num_duplicates)
+        );
+    }
+    if !options.quiet {
+        println!(
+            "{} unexportable rows",
+            Palette::paint(Palette::WARNING, unexportable)
+        );
+    }
+
+    let existing = existing_files([export_path]);
+    if options.dry_run {
+        println!(
+            "Would write {} cards to {}",
+            Palette::paint(Palette::WARNING, entries.len()),
+            Palette::paint(Palette::ACCENT, export_path.display())
+        );
+        if !existing.is_empty() {
+            println!(
+                "Would overwrite {}",
+                Palette::paint(Palette::DANGER, export_path.display())
+            );
+        }
+        println!(
+            "{}",
+            Palette::dim("Dry run: no files were written. Rerun without --dry-run to import.")
+        );
+        return Ok(());
+    }
+    confirm_overwrite(&existing, options.force)?;
+    if let Some(parent) = export_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if !options.quiet {
+        println!(
+            "Writing {} cards to {}",
+            Palette::paint(Palette::WARNING, entries.len()),
+            Palette::paint(Palette::ACCENT, export_path.display())
+        );
+    }
+    fs::write(export_path, entries.concat())?;
+    println!(
+        "Imported {} into {}",
+        Palette::paint(Palette::WARNING, pluralize("card", entries.len())),
+        Palette::paint(Palette::ACCENT, export_path.display())
+    );
+    Ok(())
+}
+
+/// Returns the card entries along with the duplicate and unexportable counts.
+/// A leading `question,answer` header row is skipped.
+fn build_entries(rows: &[Vec<String>]) -> (Vec<String>, usize, usize) {
+    let mut entries = Vec::new();
+    let mut content_hashes: HashSet<String> = HashSet::new();
+    let mut num_duplicates = 0;
+    let mut unexportable = 0;
+
+    for (idx, row) in rows.iter().enumerate() {
+        if idx == 0 && is_header(row) {
+            continue;
+        }
+        let field = |i: usize| row.get(i).map(String::as_str).unwrap_or("");
+        let (Some(question), Some(answer)) =
+            (format_section("Q", field(0)), format_section("A", field(1)))
+        else {
+            unexportable += 1;
+            continue;
+        };
+        // tags don't change the card hash, so they don't count towards duplicates
+        let content = format!("{question}{answer}");
+        let Some(content_hash) = get_hash(&content) else {
+            unexportable += 1;
+            continue;
+        };
+        if !content_hashes.insert(content_hash) {
+            num_duplicates += 1;
+            continue;
+        }
+
+        let mut entry = content;
+        let tags = format_tags(field(2));
+        if !tags.is_empty() {
+            entry.push_str(&tags);
+            entry.push('\n');
+        }
+        entry.push_str("---\n\n");
+        entries.push(entry);
+    }
+    (entries, num_duplicates, unexportable)
+}
+
+fn is_header(row: &[String]) -> bool {
+    let cell = |i: usize| row.get(i).map(|c| c.trim().to_ascii_lowercase());
+    matches!(cell(0).as_deref(), Some("question" | "front"))
+        && matches!(cell(1).as_deref(), Some("answer" | "back"))
+}
+
+/// Turns `spanish, verbs` or `#spanish #verbs` into `#spanish #verbs`.
+fn format_tags(raw: &str) -> String {
+    raw.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .map(|tag| tag.trim_start_matches('#'))
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| format!("#{tag}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses RFC 4180 style CSV: fields may be wrapped in double quotes, which
+/// lets them contain commas, newlines, and `""`-escaped quotes.
+pub(crate) fn parse_csv(input: &str) -> Result<Vec<Vec<String>>> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut rows = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut quote_line = 0;
+    let mut chars = input.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch == '\n' {
+            line += 1;
+        }
+        if in_quotes {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\r' if chars.peek() == Some(&'\n') => {}
+                _ => field.push(ch),
+            }
+            continue;
+        }
+        match ch {
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+                quote_line = line;
+            }
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                push_row(&mut rows, std::mem::take(&mut row));
+            }
+            _ => field.push(ch),
+        }
+    }
+    if in_quotes {
+        bail!("unterminated quoted field starting on line {quote_line}");
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        push_row(&mut rows, row);
+    }
+    Ok(rows)
+}
+
+fn push_row(rows: &mut Vec<Vec<String>>, row: Vec<String>) {
+    if row.iter().any(|field| !field.trim().is_empty()) {
+        rows.push(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::card::CardContent;
+    use crate::parser::{CardSeparator, cards_from_md};
+    use tempfile::tempdir;
+
+    #[test]
+    fn parse_csv_handles_quotes_and_embedded_newlines() {
+        let input = "question,answer,tags\r\n\
+                     hola,hello,spanish\r\n\
+                     \"Say \"\"hi\"\", please\",\"line one\nline two\"\n\
+                     \n\
+                     last,row";
+        let rows = parse_csv(input).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["question", "answer", "tags"],
+                vec!["hola", "hello", "spanish"],
+                vec!["Say \"hi\", please", "line one\nline two"],
+                vec!["last", "row"],
+            ]
+        );
+
+        let err = parse_csv("a,\"never closed\nb,c").unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+
+    #[test]
+    fn imported_csv_round_trips_through_the_parser() {
+        let dir = tempdir().unwrap();
+        let csv_path = dir.path().join("deck.csv");
+        let out_path = dir.path().join("out.md");
+        fs::write(
+            &csv_path,
+            "Question,Answer,Tags\n\
+             hola,hello,\"spanish, greetings\"\n\
+             hola,hello,duplicate\n\
+             \"What are the primary colors?\",\"red\nyellow\nblue\",#art\n\
+             missing answer,\n",
+        )
+        .unwrap();
+
+        run(&csv_path, &out_path, &ImportOptions::default()).unwrap();
+        let cards = cards_from_md(&out_path, CardSeparator::Rule).unwrap();
+        assert_eq!(cards.len(), 2);
+
+        let CardContent::Basic { question, answer } = &cards[0].content else {
+            panic!("expected a basic card");
+        };
+        assert_eq!(question, "hola");
+        assert_eq!(answer, "hello");
+        assert!(cards[0].has_tag("spanish") && cards[0].has_tag("greetings"));
+
+        let CardContent::Basic { answer, .. } = &cards[1].content else {
+            panic!("expected a basic card");
+        };
+        assert_eq!(answer, "red\nyellow\nblue");
+        assert!(cards[1].has_tag("art"));
+    }
+
+    #[test]
+    fn existing_decks_are_only_replaced_when_forced() {
+        let dir = tempdir().unwrap();
+        let csv_path = dir.path().join("deck.csv");
+        let out_path = dir.path().join("out.md");
+        let missing = dir.path().join("missing.md");
+        fs::write(&csv_path, "hola,hello\n").unwrap();
+        fs::write(&out_path, "Q: Mine\nA: Keep me\n").unwrap();
+
+        assert_eq!(
+            existing_files([out_path.as_path(), missing.as_path()]),
+            vec![out_path.clone()]
+        );
+        let dry_run = ImportOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        run(&csv_path, &out_path, &dry_run).unwrap();
+        assert!(fs::read_to_string(&out_path).unwrap().contains("Keep me"));
+
+        let force = ImportOptions {
+            force: true,
+            ..Default::default()
+        };
+        run(&csv_path, &out_path, &force).unwrap();
+        assert!(fs::read_to_string(&out_path).unwrap().contains("hola"));
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use sqlx::{Row, SqlitePool};
+
+use crate::import::{CardRecord, DeckInfo, ImportOptions, ModelKind, clean_field, deck_components};
+use crate::palette::Palette;
+
+const BASIC_MODEL: i64 = 0;
+const CLOZE_MODEL: i64 = 1;
+const UNTAGGED: &str = "__UNTAGGED__";
+const REQUIRED_TABLES: [&str; 5] = ["cards", "facts", "data_for_fact", "tags", "tags_for_card"];
+
+type Loaded = (
+    HashMap<i64, DeckInfo>,
+    HashMap<i64, ModelKind>,
+    Vec<CardRecord>,
+);
+
+/// Reads a Mnemosyne 2.x database. Mnemosyne has no decks, so each card's
+/// first tag becomes its deck (`A::B` tags nest like Anki deck names).
+pub(crate) async fn load(db_path: &Path, options: &ImportOptions) -> Result<Loaded> {
+    if !db_path.is_file() {
+        bail!("Mnemosyne database does not exist: {}", db_path.display());
+    }
+    let db_url = format!("sqlite://{}?mode=ro", db_path.display());
+    let pool = SqlitePool::connect(&db_url)
+        .await
+        .context("failed to connect to Mnemosyne database")?;
+    ensure_mnemosyne_schema(&pool, db_path).await?;
+
+    let fact_data = load_fact_data(&pool).await?;
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            cards._fact_id      AS fact_id,
+            cards.card_type_id  AS card_type_id,
+            cards.fact_view_id  AS fact_view_id,
+            (
+                SELECT MIN(tags.name)
+                FROM tags_for_card
+                JOIN tags ON tags._id = tags_for_card._tag_id
+                WHERE tags_for_card._card_id = cards._id AND tags.name != ?
+            ) AS tag
+        FROM cards
+        ORDER BY cards._fact_id, cards.fact_view_id
+        "#,
+    )
+    .bind(UNTAGGED)
+    .fetch_all(&pool)
+    .await
+    .context("failed to read Mnemosyne cards")?;
+
+    let mut deck_ids: HashMap<String, i64> = HashMap::new();
+    let mut decks = HashMap::new();
+    let mut cards = Vec::with_capacity(rows.len());
+    for row in rows {
+        let fact_id: i64 = row.try_get("fact_id")?;
+        let card_type_id: String = row.try_get("card_type_id")?;
+        let fact_view_id: String = row.try_get("fact_view_id")?;
+        let tag: Option<String> = row.try_get("tag")?;
+
+        let deck_name = tag.unwrap_or_else(|| "Untagged".to_string());
+        let next_id = deck_ids.len() as i64;
+        let deck_id = *deck_ids.entry(deck_name.clone()).or_insert_with(|| {
+            decks.insert(
+                next_id,
+                DeckInfo {
+                    components: deck_components(&deck_name),
+                    name: deck_name,
+                },
+            );
+            next_id
+        });
+
+        let empty = HashMap::new();
+        let data = fact_data.get(&fact_id).unwrap_or(&empty);
+        let (model_id, fields) = card_fields(&card_type_id, &fact_view_id, data);
+        cards.push(CardRecord {
+            note_id: fact_id,
+            deck_id,
+            model_id,
+            card_order: 0,
+            fields,
+        });
+    }
+    if !options.quiet {
+        println!(
+            "{} cards in {} tags",
+            Palette::paint(Palette::WARNING, cards.len()),
+            Palette::paint(Palette::WARNING, decks.len())
+        );
+    }
+
+    let models = HashMap::from([
+        (BASIC_MODEL, ModelKind::Basic(Vec::new())),
+        (CLOZE_MODEL, ModelKind::Cloze),
+    ]);
+    Ok((decks, models, cards))
+}
+
+async fn ensure_mnemosyne_schema(pool: &SqlitePool, db_path: &Path) -> Result<()> {
+    for table in REQUIRED_TABLES {
+        let found = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_optional(pool)
+            .await?;
+        if found.is_none() {
+            bail!(
+                "{} does not look like a Mnemosyne database (missing `{}` table)",
+                db_path.display(),
+                table
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn load_fact_data(pool: &SqlitePool) -> Result<HashMap<i64, HashMap<String, String>>> {
+    let rows = sqlx::query("SELECT _fact_id, key, value FROM data_for_fact")
+        .fetch_all(pool)
+        .await
+        .context("failed to read Mnemosyne fact data")?;
+    let mut facts: HashMap<i64, HashMap<String, String>> = HashMap::new();
+    for row in rows {
+        let fact_id: i64 = row.try_get("_fact_id")?;
+        let key: String = row.try_get("key")?;
+        let value: String = row.try_get("value")?;
+        facts
+            .entry(fact_id)
+            .or_default()
+            .insert(key, clean_field(&value));
+    }
+    Ok(facts)
+}
+
+/// Maps a Mnemosyne card onto `[question, answer]` or `[cloze text]` fields.
+/// Cloned card types (`1::my type`) behave like the type they were cloned from.
+fn card_fields(
+    card_type_id: &str,
+    fact_view_id: &str,
+    data: &HashMap<String, String>,
+) -> (i64, Vec<String>) {
+    let get = |key: &str| data.get(key).cloned().unwrap_or_default();
+    let join = |keys: &[&str]| {
+        keys.iter()
+            .filter_map(|key| data.get(*key).filter(|v| !v.trim().is_empty()))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let base_type = card_type_id.split("::").next().unwrap_or(card_type_id);
+    let reverse = fact_view_id.ends_with(".2");
+
+    match base_type {
+        "5" => (CLOZE_MODEL, vec![get("text")]),
+        "3" if reverse => (BASIC_MODEL, vec![get("m_1"), join(&["f", "p_1", "n"])]),
+        "3" => (BASIC_MODEL, vec![get("f"), join(&["p_1", "m_1", "n"])]),
+        _ if reverse => (BASIC_MODEL, vec![get("b"), get("f")]),
+        _ => (BASIC_MODEL, vec![get("f"), get("b")]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::import::build_exports;
+
+    async fn sample_db(path: &Path) {
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        let pool = SqlitePool::connect(&url).await.unwrap();
+        for statement in [
+            "CREATE TABLE facts (_id INTEGER PRIMARY KEY, id TEXT)",
+            "CREATE TABLE data_for_fact (_fact_id INTEGER, key TEXT, value TEXT)",
+            "CREATE TABLE cards (_id INTEGER PRIMARY KEY, id TEXT, card_type_id TEXT, _fact_id INTEGER, fact_view_id TEXT)",
+            "CREATE TABLE tags (_id INTEGER PRIMARY KEY, id TEXT, name TEXT)",
+            "CREATE TABLE tags_for_card (_card_id INTEGER, _tag_id INTEGER)",
+            "INSERT INTO facts VALUES (1, 'a'), (2, 'b'), (3, 'c')",
+            "INSERT INTO data_for_fact VALUES
+                (1, 'f', 'hola'), (1, 'b', 'hello'),
+                (2, 'f', 'Capital of <b>France</b>?'), (2, 'b', 'Paris'),
+                (3, 'text', 'The [mitochondria] powers the cell')",
+            "INSERT INTO cards VALUES
+                (1, 'c1', '2', 1, '2.1'), (2, 'c2', '2', 1, '2.2'),
+                (3, 'c3', '1', 2, '1.1'), (4, 'c4', '5', 3, '5.1')",
+            "INSERT INTO tags VALUES (1, 't1', 'Languages::Spanish'), (2, 't2', '__UNTAGGED__')",
+            "INSERT INTO tags_for_card VALUES (1, 1), (2, 1), (3, 2), (4, 2)",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn loads_basic_reverse_and_cloze_cards() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("default.db");
+        sample_db(&path).await;
+
+        let (decks, models, cards) = load(&path, &ImportOptions::default()).await.unwrap();
+
+        assert_eq!(cards.len(), 4);
+        let mut deck_names: Vec<_> = decks.values().map(|deck| deck.name.clone()).collect();
+        deck_names.sort();
+        assert_eq!(deck_names, vec!["Languages::Spanish", "Untagged"]);
+        assert_eq!(cards[0].fields, vec!["hola", "hello"]);
+        assert_eq!(cards[1].fields, vec!["hello", "hola"]);
+        assert_eq!(cards[2].fields, vec!["Capital of France?", "Paris"]);
+        assert_eq!(cards[3].model_id, CLOZE_MODEL);
+
+        let exports = build_exports(cards, &models, true);
+        assert!(exports.skipped.is_empty());
+        let mut entries: Vec<String> = exports.per_deck.into_values().flatten().collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![
+                "C: The [mitochondria] powers the cell\n\n",
+                "Q: Capital of France?\nA: Paris\n\n",
+                "Q: hello\nA: hola\n\n",
+                "Q: hola\nA: hello\n\n",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_databases_without_mnemosyne_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("other.db");
+        let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE cards (_id INTEGER)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+
+        let err = load(&path, &ImportOptions::default()).await.err().unwrap();
+        assert!(
+            err.to_string()
+                .contains("does not look like a Mnemosyne database")
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something a key does in the drill TUI. `Ctrl+C` always exits and can't be
+/// rebound.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DrillAction {
+    Reveal,
+    Pass,
+    Fail,
+    Hard,
+    Good,
+    Easy,
+    Open,
+    Bury,
+    Suspend,
+    Edit,
+    Undo,
+    Exit,
+}
+
+impl DrillAction {
+    pub const ALL: [DrillAction; 12] = [
+        DrillAction::Reveal,
+        DrillAction::Pass,
+        DrillAction::Fail,
+        DrillAction::Hard,
+        DrillAction::Good,
+        DrillAction::Easy,
+        DrillAction::Open,
+        DrillAction::Bury,
+        DrillAction::Suspend,
+        DrillAction::Edit,
+        DrillAction::Undo,
+        DrillAction::Exit,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DrillAction::Reveal => "reveal",
+            DrillAction::Pass => "pass",
+            DrillAction::Fail => "fail",
+            DrillAction::Hard => "hard",
+            DrillAction::Good => "good",
+            DrillAction::Easy => "easy",
+            DrillAction::Open => "open",
+            DrillAction::Bury => "bury",
+            DrillAction::Suspend => "suspend",
+            DrillAction::Edit => "edit",
+            DrillAction::Undo => "undo",
+            DrillAction::Exit => "exit",
+        }
+    }
+
+    fn default_keys(self) -> &'static [KeyCode] {
+        match self {
+            DrillAction::Reveal | DrillAction::Pass => &[KeyCode::Char(' '), KeyCode::Enter],
+            DrillAction::Fail => &[KeyCode::Char('f'), KeyCode::Char('1')],
+            DrillAction::Hard => &[KeyCode::Char('2')],
+            DrillAction::Good => &[KeyCode::Char('3')],
+            DrillAction::Easy => &[KeyCode::Char('4')],
+            DrillAction::Open => &[KeyCode::Char('o')],
+            DrillAction::Bury => &[KeyCode::Char('b')],
+            DrillAction::Suspend => &[KeyCode::Char('s')],
+            DrillAction::Edit => &[KeyCode::Char('e')],
+            DrillAction::Undo => &[KeyCode::Char('u')],
+            DrillAction::Exit => &[KeyCode::Esc],
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// The keys bound to each drill action. Letters match in either case.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: BTreeMap<DrillAction, Vec<KeyCode>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: DrillAction::ALL
+                .into_iter()
+                .map(|action| (action, action.default_keys().to_vec()))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Reads bindings like `fail=j,1 good=k`, as written by [`Keymap::format`].
+    /// Actions left out keep their default keys.
+    pub fn parse(input: &str) -> Result<Self> {
+        Self::default().rebind(input)
+    }
+
+    /// This keymap with the bindings in `input` replacing those of the
+    /// actions it names.
+    pub fn rebind(&self, input: &str) -> Result<Self> {
+        let mut keymap = self.clone();
+        for binding in input.split_whitespace() {
+            let (name, keys) = binding
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected ACTION=KEY, got \"{binding}\""))?;
+            let action = DrillAction::from_name(name).ok_or_else(|| {
+                let names: Vec<&str> = DrillAction::ALL.iter().map(|a| a.name()).collect();
+                anyhow!(
+                    "unknown action \"{name}\", expected one of {}",
+                    names.join(", ")
+                )
+            })?;
+            let keys = keys.split(',').map(parse_key).collect::<Result<Vec<_>>>()?;
+            keymap.bindings.insert(action, keys);
+        }
+        keymap.validate()?;
+        Ok(keymap)
+    }
+
+    /// The bindings the way [`Keymap::parse`] reads them, e.g. `reveal=space,enter`.
+    pub fn format(&self) -> String {
+        self.bindings
+            .iter()
+            .map(|(action, keys)| {
+                let keys: Vec<String> = keys.iter().map(|key| format_key(*key)).collect();
+                format!("{}={}", action.name(), keys.join(","))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn keys(&self, action: DrillAction) -> &[KeyCode] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether `key` is bound to `action`. Keys pressed with `Ctrl` never are.
+    pub fn matches(&self, action: DrillAction, key: &KeyEvent) -> bool {
+        !key.modifiers.contains(KeyModifiers::CONTROL)
+            && self
+                .keys(action)
+                .iter()
+                .any(|bound| same_key(*bound, key.code))
+    }
+
+    /// How the footer names each key bound to `action`, e.g. `Space`, `F`.
+    pub fn labels(&self, action: DrillAction) -> Vec<String> {
+        self.keys(action)
+            .iter()
+            .map(|key| key_label(*key))
+            .collect()
+    }
+
+    /// Rejects a key bound to two actions that apply at the same time.
+    /// `reveal` works before the answer shows and `pass` after, so they may
+    /// share keys.
+    fn validate(&self) -> Result<()> {
+        for (action, keys) in &self.bindings {
+            if keys.is_empty() {
+                bail!("no key bound to {}", action.name());
+            }
+            for (other, other_keys) in &self.bindings {
+                if other <= action
+                    || matches!((action, other), (DrillAction::Reveal, DrillAction::Pass))
+                {
+                    continue;
+                }
+                if let Some(key) = keys
+                    .iter()
+                    .find(|key| other_keys.iter().any(|other| same_key(**key, *other)))
+                {
+                    bail!(
+                        "{} is bound to both {} and {}",
+                        format_key(*key),
+                        action.name(),
+                        other.name()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 10] = [
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+];
+
+/// Reads a single character or one of [`NAMED_KEYS`].
+fn parse_key(token: &str) -> Result<KeyCode> {
+    let mut chars = token.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(ch.to_ascii_lowercase()));
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(token))
+        .map(|(_, key)| *key)
+        .ok_or_else(|| {
+            anyhow!("unknown key \"{token}\", expected a character or a name like space")
+        })
+}
+
+fn format_key(key: KeyCode) -> String {
+    match NAMED_KEYS.iter().find(|(_, named)| *named == key) {
+        Some((name, _)) => name.to_string(),
+        None => match key {
+            KeyCode::Char(ch) => ch.to_string(),
+            other => format!("{other:?}").to_lowercase(),
+        },
+    }
+}
+
+fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(ch) => ch.to_uppercase().to_string(),
+        other => {
+            let name = format_key(other);
+            let mut chars = name.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+    }
+}
+
+fn same_key(a: KeyCode, b: KeyCode) -> bool {
+    match (a, b) {
+        (KeyCode::Char(a), KeyCode::Char(b)) => a.to_lowercase().eq(b.to_lowercase()),
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn defaults_match_the_original_keys() {
+        let keymap = Keymap::default();
+        assert!(keymap.matches(DrillAction::Reveal, &press(KeyCode::Char(' '))));
+        assert!(keymap.matches(DrillAction::Pass, &press(KeyCode::Enter)));
+        assert!(keymap.matches(DrillAction::Fail, &press(KeyCode::Char('F'))));
+        assert!(keymap.matches(DrillAction::Fail, &press(KeyCode::Char('1'))));
+        assert!(keymap.matches(DrillAction::Exit, &press(KeyCode::Esc)));
+        assert!(!keymap.matches(
+            DrillAction::Suspend,
+            &KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        ));
+        assert_eq!(Keymap::parse(&keymap.format()).unwrap(), keymap);
+    }
+
+    #[test]
+    fn rebinding_replaces_only_the_named_actions() {
+        let keymap = Keymap::parse("fail=j,1 good=K exit=q,esc").unwrap();
+        assert_eq!(keymap.labels(DrillAction::Fail), vec!["J", "1"]);
+        assert!(keymap.matches(DrillAction::Good, &press(KeyCode::Char('k'))));
+        assert!(!keymap.matches(DrillAction::Fail, &press(KeyCode::Char('f'))));
+        assert_eq!(keymap.labels(DrillAction::Exit), vec!["Q", "Esc"]);
+        assert_eq!(keymap.labels(DrillAction::Reveal), vec!["Space", "Enter"]);
+
+        let keymap = keymap.rebind("easy=l").unwrap();
+        assert_eq!(keymap.labels(DrillAction::Fail), vec!["J", "1"]);
+        assert_eq!(keymap.labels(DrillAction::Easy), vec!["L"]);
+    }
+
+    #[test]
+    fn conflicting_or_unknown_bindings_are_rejected() {
+        let err = Keymap::parse("fail=2").unwrap_err();
+        assert_eq!(err.to_string(), "2 is bound to both fail and hard");
+        assert!(Keymap::parse("reveal=k pass=k").is_ok());
+        assert!(Keymap::parse("skip=k").is_err());
+        assert!(Keymap::parse("fail=pgdn").is_err());
+        assert!(Keymap::parse("fail").is_err());
+    }
+}
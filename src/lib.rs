@@ -1,13 +1,19 @@
 pub mod card;
 pub mod check_version;
+pub mod cloze_suggest;
 pub mod cloze_utils;
 pub mod commands;
 pub mod crud;
 pub mod fsrs;
 pub mod import;
+pub mod import_csv;
+pub mod import_mnemosyne;
+pub mod keymap;
 pub mod llm;
 pub mod palette;
 pub mod parser;
+pub mod settings;
 pub mod stats;
 pub mod tui;
+pub mod typed_answer;
 pub mod utils;
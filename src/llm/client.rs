@@ -1,13 +1,14 @@
 use crate::llm::secrets::API_KEY_ENV;
 use crate::utils::ask_yn;
 use anyhow::{Context, Result, anyhow, bail};
+use std::path::Path;
 
 use async_openai::{Client, config::OpenAIConfig};
 
 use super::secrets::{ApiKeySource, get_api_key_from_sources, prompt_for_api_key, store_api_key};
 
-pub fn ensure_client(user_prompt: &str) -> Result<Client<OpenAIConfig>> {
-    let lookup = get_api_key_from_sources()?;
+pub fn ensure_client(data_dir: &Path, user_prompt: &str) -> Result<Client<OpenAIConfig>> {
+    let lookup = get_api_key_from_sources(data_dir)?;
     let (key, prompted_for_key) = if let Some(api_key) = lookup.api_key {
         (api_key, false)
     } else {
@@ -19,7 +20,7 @@ pub fn ensure_client(user_prompt: &str) -> Result<Client<OpenAIConfig>> {
             );
         }
 
-        store_api_key(&api_key)?;
+        store_api_key(data_dir, &api_key)?;
 
         (api_key, true)
     };
@@ -32,12 +33,12 @@ pub fn ensure_client(user_prompt: &str) -> Result<Client<OpenAIConfig>> {
         }
     }
 
-    let client = initialize_client(&key)?;
+    let client = initialize_client(&key, lookup.base_url.as_deref())?;
     Ok(client)
 }
 
-pub async fn test_configured_api_key() -> Result<ApiKeySource> {
-    let lookup = get_api_key_from_sources()?;
+pub async fn test_configured_api_key(data_dir: &Path) -> Result<ApiKeySource> {
+    let lookup = get_api_key_from_sources(data_dir)?;
     let key = lookup.api_key.ok_or_else(|| {
         anyhow!(
             "LLM features are disabled. To enable, set {} or run `repeater llm key --set <KEY>`.",
@@ -50,13 +51,16 @@ pub async fn test_configured_api_key() -> Result<ApiKeySource> {
             API_KEY_ENV
         )
     })?;
-    let client = initialize_client(&key)?;
+    let client = initialize_client(&key, lookup.base_url.as_deref())?;
     healthcheck_client(&client).await?;
     Ok(source)
 }
 
-fn initialize_client(api_key: &str) -> Result<Client<OpenAIConfig>> {
-    let config = OpenAIConfig::new().with_api_key(api_key);
+fn initialize_client(api_key: &str, base_url: Option<&str>) -> Result<Client<OpenAIConfig>> {
+    let mut config = OpenAIConfig::new().with_api_key(api_key);
+    if let Some(base_url) = base_url {
+        config = config.with_api_base(base_url.trim_end_matches('/'));
+    }
 
     let client = Client::with_config(config);
     Ok(client)
@@ -3,8 +3,6 @@ use async_openai::{Client, config::OpenAIConfig};
 
 use super::response::request_single_text_response;
 
-const CLOZE_MODEL: &str = "gpt-5-nano";
-
 const SYSTEM_PROMPT: &str = r#"
 You convert flashcards into Cloze deletions.
 A Cloze deletion is denoted by square brackets: [hidden text].
@@ -27,8 +25,12 @@ This is the text you should generate the Cloze deletion for:
 
 "#;
 
-pub async fn request_cloze(client: &Client<OpenAIConfig>, text: &str) -> Result<String> {
+pub async fn request_cloze(
+    client: &Client<OpenAIConfig>,
+    model: &str,
+    text: &str,
+) -> Result<String> {
     let user_prompt = format!("{USER_PROMPT_HEADER}{text}");
 
-    request_single_text_response(client, CLOZE_MODEL, SYSTEM_PROMPT, &user_prompt).await
+    request_single_text_response(client, model, SYSTEM_PROMPT, &user_prompt).await
 }
@@ -3,13 +3,19 @@ use std::sync::Arc;
 use anyhow::{Context, Result};
 use async_openai::Client;
 use async_openai::config::OpenAIConfig;
+use serde::{Deserialize, Serialize};
 
 use super::prompt_user::{cloze_user_prompt, rephrase_user_prompt};
-use crate::card::{Card, CardContent, ClozeRange};
-use crate::cloze_utils::find_cloze_ranges;
+use crate::card::{Card, CardContent};
+use crate::cloze_suggest::suggest_cloze;
+use crate::cloze_utils;
+use crate::crud::DB;
 use crate::palette::Palette;
+use crate::parser::CardSeparator;
 
-use super::{ensure_client, request_cloze};
+use super::persist::write_cloze_fix;
+use super::secrets::get_api_key_from_sources;
+use super::{LlmFeature, ensure_client, model_for, request_cloze};
 
 use crate::llm::request_question_rephrase;
 use std::collections::HashMap;
@@ -18,23 +24,69 @@ use futures::stream::{self, StreamExt};
 
 const MAX_CONCURRENT_LLM_REQUESTS: usize = 4;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum AIStatus {
     ClozeNeedDeletion,
     QuestionNeedRephrasing,
     NoNeed,
     AiEnhanced,
+    /// The LLM request failed; the card is drilled as written.
+    AiFailed,
+    /// No API key is configured, so the blank was picked by `cloze_suggest`.
+    ClozeSuggested,
 }
 
 #[derive(Clone, Debug)]
 pub struct DrillPreprocessor {
     client: Option<Arc<Client<OpenAIConfig>>>,
     rephrase_questions: bool,
+    /// Write generated clozes back into the deck files.
+    write_ai_fixes: bool,
+    /// Fill in missing clozes locally because no API key is configured.
+    suggest_clozes: bool,
+    db: DB,
+    /// Results from earlier sessions, keyed by card hash, applied without a request.
+    cached: HashMap<(String, LlmFeature), String>,
+    /// How cards end in the deck files, for finding a card before fixing it.
+    card_separator: CardSeparator,
 }
 
 impl DrillPreprocessor {
-    pub fn new(cards: &[Card], rephrase_questions: bool) -> Result<Self> {
+    pub async fn new(
+        db: &DB,
+        cards: &[Card],
+        rephrase_questions: bool,
+        write_ai_fixes: bool,
+    ) -> Result<Self> {
+        let mut cached = HashMap::new();
+        for card in cards {
+            let Some(feature) = needed_feature(card, rephrase_questions) else {
+                continue;
+            };
+            if let Some(result) = db.cached_llm_result(&card.card_hash, feature).await? {
+                cached.insert((card.card_hash.clone(), feature), result);
+            }
+        }
+        // Only cards without a cached result count towards asking for the LLM.
+        let uncached: Vec<Card> = cards
+            .iter()
+            .filter(|card| {
+                needed_feature(card, rephrase_questions)
+                    .is_some_and(|feature| !cached.contains_key(&(card.card_hash.clone(), feature)))
+            })
+            .cloned()
+            .collect();
+        let cards = uncached.as_slice();
+
         let cards_needing_clozes = count_cards_needing_clozes(cards);
+        // Without a key, missing clozes are guessed locally instead of asking for one.
+        let suggest_clozes =
+            cards_needing_clozes > 0 && get_api_key_from_sources(db.data_dir())?.api_key.is_none();
+        let cards_needing_clozes = if suggest_clozes {
+            0
+        } else {
+            cards_needing_clozes
+        };
         let cards_needing_rephrase = if rephrase_questions {
             count_cards_needing_rephrase(cards)
         } else {
@@ -96,7 +148,7 @@ impl DrillPreprocessor {
                     }
                 };
                 Some(
-                    ensure_client(&prompt)
+                    ensure_client(db.data_dir(), &prompt)
                         .with_context(|| error_message)
                         .map(Arc::new)?,
                 )
@@ -107,16 +159,36 @@ impl DrillPreprocessor {
         Ok(Self {
             client,
             rephrase_questions,
+            write_ai_fixes,
+            suggest_clozes,
+            db: db.clone(),
+            cached,
+            card_separator: db.get_settings().await?.card_separator,
         })
     }
 
     pub fn llm_required(&self) -> bool {
+        // Cached clozes still need the preprocessing pass to be written to disk.
         self.client.is_some()
+            || (self.write_ai_fixes
+                && self
+                    .cached
+                    .keys()
+                    .any(|(_, feature)| *feature == LlmFeature::Cloze))
     }
     pub fn initialize_card_status(&self, cards: &mut [Card]) {
         for card in cards {
+            let write_later = self.write_ai_fixes && does_card_need_cloze(card);
+            if !write_later && self.apply_cached(card) {
+                continue;
+            }
+
             if does_card_need_cloze(card) {
-                card.ai_status = AIStatus::ClozeNeedDeletion;
+                if self.suggest_clozes {
+                    apply_suggested_cloze(card);
+                } else {
+                    card.ai_status = AIStatus::ClozeNeedDeletion;
+                }
             }
 
             if self.rephrase_questions && does_card_need_rephrase(card) {
@@ -126,15 +198,98 @@ impl DrillPreprocessor {
     }
 
     pub async fn preprocess_cards(&self, cards: &mut [Card]) -> Result<()> {
-        let Some(client) = self.client.as_ref() else {
+        let originals = cards.to_vec();
+        for card in cards.iter_mut() {
+            self.apply_cached(card);
+        }
+        if let Some(client) = self.client.as_ref() {
+            let pending: Vec<(usize, LlmFeature)> = cards
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| card.ai_status != AIStatus::AiEnhanced)
+                .filter_map(|(idx, card)| {
+                    needed_feature(card, self.rephrase_questions).map(|feature| (idx, feature))
+                })
+                .collect();
+
+            let data_dir = self.db.data_dir();
+            if self.rephrase_questions {
+                let model = model_for(data_dir, LlmFeature::Rephrase)?;
+                rephrase_basic_questions_with_client(cards, Arc::clone(client), &model).await?;
+            }
+            let model = model_for(data_dir, LlmFeature::Cloze)?;
+            resolve_missing_clozes_with_client(cards, Arc::clone(client), &model).await?;
+
+            for (idx, feature) in pending {
+                let card = &cards[idx];
+                let result = match (&card.content, feature) {
+                    (CardContent::Basic { question, .. }, LlmFeature::Rephrase) => question,
+                    (CardContent::Cloze { text, .. }, LlmFeature::Cloze) => text,
+                    _ => continue,
+                };
+                self.db
+                    .store_llm_result(&card.card_hash, feature, result)
+                    .await?;
+            }
+        }
+
+        if self.write_ai_fixes {
+            for (card, original) in cards.iter_mut().zip(&originals) {
+                self.write_fix(card, original).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves the cloze generated for `original` into its deck file and moves
+    /// the card's history to the fixed card, which replaces `card`.
+    async fn write_fix(&self, card: &mut Card, original: &Card) -> Result<()> {
+        let CardContent::Cloze { text, .. } = &card.content else {
             return Ok(());
         };
-        if self.rephrase_questions {
-            rephrase_basic_questions_with_client(cards, Arc::clone(client)).await?;
+        if !does_card_need_cloze(original) || does_card_need_cloze(card) {
+            return Ok(());
         }
-        resolve_missing_clozes_with_client(cards, Arc::clone(client)).await?;
+        let Some(fixed) =
+            write_cloze_fix(original, text, self.card_separator).with_context(|| {
+                format!(
+                    "Failed to write the generated cloze to {}",
+                    original.file_path.display()
+                )
+            })?
+        else {
+            return Ok(());
+        };
+        self.db
+            .rekey_card(&original.card_hash, &fixed.card_hash)
+            .await?;
+        self.db.add_card(&fixed).await?;
+        *card = fixed;
         Ok(())
     }
+
+    /// Swaps in a cached result for `card`, returning whether one was applied.
+    fn apply_cached(&self, card: &mut Card) -> bool {
+        let Some(feature) = needed_feature(card, self.rephrase_questions) else {
+            return false;
+        };
+        let Some(result) = self.cached.get(&(card.card_hash.clone(), feature)) else {
+            return false;
+        };
+        match &mut card.content {
+            CardContent::Basic { question, .. } => *question = result.clone(),
+            CardContent::Cloze { text, cloze_ranges } => {
+                match cloze_utils::cloze_ranges(result) {
+                    Ok(ranges) if !ranges.is_empty() => *cloze_ranges = ranges,
+                    _ => return false,
+                }
+                *text = result.clone();
+            }
+            CardContent::TypeAnswer { .. } => return false,
+        }
+        card.ai_status = AIStatus::AiEnhanced;
+        true
+    }
 }
 
 async fn replace_questions(
@@ -142,6 +297,7 @@ async fn replace_questions(
     cards_to_rephrase: Vec<(String, String, String)>,
     index_by_hash: &HashMap<String, usize>,
     client: Arc<Client<OpenAIConfig>>,
+    model: &str,
 ) -> Result<()> {
     let mut tasks = stream::iter(
         cards_to_rephrase
@@ -149,14 +305,15 @@ async fn replace_questions(
             .map(|(hash, question, answer)| {
                 let client = Arc::clone(&client);
                 async move {
-                    let new_question = request_question_rephrase(&client, &question, &answer)
-                        .await
-                        .with_context(|| {
-                            format!(
-                                "Failed to rephrase question:\n\nQ: {}\nA: {}",
-                                question, answer
-                            )
-                        })?;
+                    let new_question =
+                        request_question_rephrase(&client, model, &question, &answer)
+                            .await
+                            .with_context(|| {
+                                format!(
+                                    "Failed to rephrase question:\n\nQ: {}\nA: {}",
+                                    question, answer
+                                )
+                            })?;
                     Ok::<_, anyhow::Error>((hash, new_question))
                 }
             }),
@@ -179,9 +336,11 @@ async fn replace_questions(
 pub async fn rephrase_basic_questions_with_client(
     cards: &mut [Card],
     client: Arc<Client<OpenAIConfig>>,
+    model: &str,
 ) -> Result<()> {
     let cards_to_rephrase: Vec<_> = cards
         .iter()
+        .filter(|card| card.ai_status != AIStatus::AiEnhanced)
         .filter_map(|card| {
             if let CardContent::Basic { question, answer } = &card.content {
                 Some((card.card_hash.clone(), question.clone(), answer.clone()))
@@ -201,7 +360,7 @@ pub async fn rephrase_basic_questions_with_client(
         .map(|(idx, card)| (card.card_hash.clone(), idx))
         .collect();
 
-    replace_questions(cards, cards_to_rephrase, &index_by_hash, client).await?;
+    replace_questions(cards, cards_to_rephrase, &index_by_hash, client, model).await?;
     Ok(())
 }
 
@@ -210,13 +369,16 @@ async fn replace_missing_clozes(
     cards_with_no_clozes: Vec<(String, String)>,
     index_by_hash: &HashMap<String, usize>,
     client: Arc<Client<OpenAIConfig>>,
+    model: &str,
 ) -> Result<()> {
     let mut tasks = stream::iter(cards_with_no_clozes.into_iter().map(|(hash, text)| {
         let client = Arc::clone(&client);
         async move {
-            let new_cloze_text = request_cloze(&client, &text).await.with_context(|| {
-                format!("Failed to synthesize cloze text for card:\n\n{}", text)
-            })?;
+            let new_cloze_text = request_cloze(&client, model, &text)
+                .await
+                .with_context(|| {
+                    format!("Failed to synthesize cloze text for card:\n\n{}", text)
+                })?;
             Ok::<_, anyhow::Error>((hash, new_cloze_text))
         }
     }))
@@ -229,18 +391,14 @@ async fn replace_missing_clozes(
         };
         let card = &mut cards[idx];
         if let CardContent::Cloze {
-            text, cloze_range, ..
+            text, cloze_ranges, ..
         } = &mut card.content
         {
-            let cloze_idxs = find_cloze_ranges(&new_cloze_text);
-            let new_cloze_range: ClozeRange = cloze_idxs
-                .first()
-                .map(|(start, end)| ClozeRange::new(*start, *end))
-                .transpose()?
-                .ok_or_else(|| {
-                    anyhow::anyhow!("No cloze range found. LLM output: {new_cloze_text}")
-                })?;
-            *cloze_range = Some(new_cloze_range);
+            let new_cloze_ranges = cloze_utils::cloze_ranges(&new_cloze_text)?;
+            if new_cloze_ranges.is_empty() {
+                anyhow::bail!("No cloze range found. LLM output: {new_cloze_text}");
+            }
+            *cloze_ranges = new_cloze_ranges;
             *text = new_cloze_text;
         }
     }
@@ -251,14 +409,13 @@ async fn replace_missing_clozes(
 pub async fn resolve_missing_clozes_with_client(
     cards: &mut [Card],
     client: Arc<Client<OpenAIConfig>>,
+    model: &str,
 ) -> Result<()> {
     let cards_with_no_clozes: Vec<_> = cards
         .iter()
         .filter_map(|card| {
-            if let CardContent::Cloze {
-                text,
-                cloze_range: None,
-            } = &card.content
+            if let CardContent::Cloze { text, cloze_ranges } = &card.content
+                && cloze_ranges.is_empty()
             {
                 Some((card.card_hash.clone(), text.clone()))
             } else {
@@ -277,7 +434,7 @@ pub async fn resolve_missing_clozes_with_client(
         .map(|(i, c)| (c.card_hash.clone(), i))
         .collect();
 
-    replace_missing_clozes(cards, cards_with_no_clozes, &index_by_hash, client).await?;
+    replace_missing_clozes(cards, cards_with_no_clozes, &index_by_hash, client, model).await?;
 
     Ok(())
 }
@@ -291,11 +448,8 @@ fn count_cards_needing_clozes(cards: &[Card]) -> usize {
 
 pub fn does_card_need_cloze(card: &Card) -> bool {
     matches!(
-        card.content,
-        CardContent::Cloze {
-            cloze_range: None,
-            ..
-        }
+        &card.content,
+        CardContent::Cloze { cloze_ranges, .. } if cloze_ranges.is_empty()
     )
 }
 
@@ -308,3 +462,83 @@ fn count_cards_needing_rephrase(cards: &[Card]) -> usize {
 fn does_card_need_rephrase(card: &Card) -> bool {
     matches!(card.content, CardContent::Basic { .. })
 }
+
+/// Blanks out the span `suggest_cloze` picks. A card with no word to hide is
+/// left as written.
+fn apply_suggested_cloze(card: &mut Card) {
+    let CardContent::Cloze { text, cloze_ranges } = &mut card.content else {
+        return;
+    };
+    let Some(suggestion) = suggest_cloze(text) else {
+        return;
+    };
+    let Ok(ranges) = cloze_utils::cloze_ranges(&suggestion) else {
+        return;
+    };
+    *cloze_ranges = ranges;
+    *text = suggestion;
+    card.ai_status = AIStatus::ClozeSuggested;
+}
+
+/// Which LLM feature, if any, this session would run on `card`.
+fn needed_feature(card: &Card, rephrase_questions: bool) -> Option<LlmFeature> {
+    if does_card_need_cloze(card) {
+        Some(LlmFeature::Cloze)
+    } else if rephrase_questions && does_card_need_rephrase(card) {
+        Some(LlmFeature::Rephrase)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn cached_results_are_applied_without_a_client() {
+        let db = DB::new_in_memory().await.unwrap();
+        let content = CardContent::Cloze {
+            text: "Paris is the capital of France".into(),
+            cloze_ranges: Vec::new(),
+        };
+        let card = Card::new(PathBuf::from("test.md"), (0, 1), content, "hash".into());
+        db.store_llm_result(
+            "hash",
+            LlmFeature::Cloze,
+            "[Paris] is the capital of France",
+        )
+        .await
+        .unwrap();
+
+        let mut cards = vec![card];
+        let preprocessor = DrillPreprocessor::new(&db, &cards, false, false)
+            .await
+            .unwrap();
+        assert!(!preprocessor.llm_required());
+        preprocessor.initialize_card_status(&mut cards);
+
+        assert_eq!(cards[0].ai_status, AIStatus::AiEnhanced);
+        let CardContent::Cloze { text, cloze_ranges } = &cards[0].content else {
+            panic!("expected a cloze card");
+        };
+        assert_eq!(text, "[Paris] is the capital of France");
+        assert_eq!(cloze_ranges.len(), 1);
+    }
+
+    #[test]
+    fn suggested_clozes_make_cards_drillable() {
+        let content = CardContent::Cloze {
+            text: "Paris is the capital of France".into(),
+            cloze_ranges: Vec::new(),
+        };
+        let mut card = Card::new(PathBuf::from("test.md"), (0, 1), content, "hash".into());
+
+        apply_suggested_cloze(&mut card);
+
+        assert!(!does_card_need_cloze(&card));
+        assert_eq!(card.ai_status, AIStatus::ClozeSuggested);
+    }
+}
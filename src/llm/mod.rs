@@ -1,6 +1,8 @@
 pub mod client;
 pub mod cloze;
 pub mod drill_preprocessor;
+pub mod models;
+pub mod persist;
 pub mod prompt_user;
 pub mod rephrase;
 pub mod response;
@@ -8,5 +10,6 @@ pub mod secrets;
 
 pub use client::{ensure_client, test_configured_api_key};
 pub use cloze::request_cloze;
+pub use models::{LlmFeature, model_for, store_model};
 pub use rephrase::request_question_rephrase;
-pub use secrets::{clear_api_key, store_api_key};
+pub use secrets::{clear_api_key, store_api_key, store_base_url};
@@ -0,0 +1,179 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use super::secrets::get_api_key_from_sources;
+use crate::utils::trim_line;
+
+pub const DEFAULT_MODEL: &str = "gpt-5-nano";
+
+const MODELS_FILE_NAME: &str = "llm_models.json";
+
+/// An LLM-backed feature that can run on its own model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum LlmFeature {
+    Cloze,
+    Rephrase,
+}
+
+impl LlmFeature {
+    pub const ALL: [LlmFeature; 2] = [LlmFeature::Cloze, LlmFeature::Rephrase];
+
+    /// Environment variable that overrides the stored model for this feature.
+    pub fn env_var(self) -> &'static str {
+        match self {
+            LlmFeature::Cloze => "REPEATER_CLOZE_MODEL",
+            LlmFeature::Rephrase => "REPEATER_REPHRASE_MODEL",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LlmFeature::Cloze => "cloze",
+            LlmFeature::Rephrase => "rephrase",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ModelSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cloze: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rephrase: Option<String>,
+}
+
+impl ModelSettings {
+    fn model_mut(&mut self, feature: LlmFeature) -> &mut Option<String> {
+        match feature {
+            LlmFeature::Cloze => &mut self.cloze,
+            LlmFeature::Rephrase => &mut self.rephrase,
+        }
+    }
+
+    fn model(&self, feature: LlmFeature) -> Option<&str> {
+        match feature {
+            LlmFeature::Cloze => self.cloze.as_deref(),
+            LlmFeature::Rephrase => self.rephrase.as_deref(),
+        }
+    }
+}
+
+/// The model `feature` should call: its environment variable, then the model
+/// saved with `repeater llm --set-model`, then the provider's `model` in
+/// `auth.json`, then [`DEFAULT_MODEL`].
+pub fn model_for(data_dir: &Path, feature: LlmFeature) -> Result<String> {
+    let stored = read_models_file(&models_file_path(data_dir))?.unwrap_or_default();
+    let provider_model = get_api_key_from_sources(data_dir)?.model;
+    Ok(resolve_model(
+        feature,
+        env::var(feature.env_var()).ok(),
+        &stored,
+        provider_model.as_deref(),
+    ))
+}
+
+fn resolve_model(
+    feature: LlmFeature,
+    env_value: Option<String>,
+    stored: &ModelSettings,
+    provider_model: Option<&str>,
+) -> String {
+    env_value
+        .as_deref()
+        .and_then(trim_line)
+        .or_else(|| stored.model(feature).and_then(trim_line))
+        .or(provider_model)
+        .unwrap_or(DEFAULT_MODEL)
+        .to_string()
+}
+
+/// Saves `model` for `feature`, or for every feature when `feature` is `None`.
+pub fn store_model(data_dir: &Path, feature: Option<LlmFeature>, model: &str) -> Result<()> {
+    let model = trim_line(model).with_context(|| "Cannot store an empty model name")?;
+    let path = models_file_path(data_dir);
+    let mut settings = read_models_file(&path)?.unwrap_or_default();
+    let features = match feature {
+        Some(feature) => vec![feature],
+        None => LlmFeature::ALL.to_vec(),
+    };
+    for feature in features {
+        *settings.model_mut(feature) = Some(model.to_string());
+    }
+    write_models_file(&path, &settings)
+}
+
+fn models_file_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(MODELS_FILE_NAME)
+}
+
+fn read_models_file(path: &Path) -> Result<Option<ModelSettings>> {
+    match fs::read_to_string(path) {
+        Ok(contents) if contents.trim().is_empty() => Ok(Some(ModelSettings::default())),
+        Ok(contents) => serde_json::from_str(&contents)
+            .map(Some)
+            .with_context(|| format!("Failed to parse model settings at {}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => {
+            Err(err).with_context(|| format!("Failed to read model settings at {}", path.display()))
+        }
+    }
+}
+
+fn write_models_file(path: &Path, settings: &ModelSettings) -> Result<()> {
+    let contents = serde_json::to_string_pretty(settings)?;
+    fs::write(path, format!("{contents}\n"))
+        .with_context(|| format!("Failed to write model settings at {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn environment_beats_stored_model_which_beats_default() {
+        let stored = ModelSettings {
+            cloze: Some("gpt-4.1-mini".into()),
+            rephrase: None,
+        };
+        assert_eq!(
+            resolve_model(LlmFeature::Cloze, Some("o4-mini".into()), &stored, None),
+            "o4-mini"
+        );
+        assert_eq!(
+            resolve_model(LlmFeature::Cloze, Some("  ".into()), &stored, None),
+            "gpt-4.1-mini"
+        );
+        assert_eq!(
+            resolve_model(LlmFeature::Rephrase, None, &stored, None),
+            DEFAULT_MODEL
+        );
+        assert_eq!(
+            resolve_model(LlmFeature::Rephrase, None, &stored, Some("llama3.2")),
+            "llama3.2"
+        );
+    }
+
+    #[test]
+    fn model_settings_round_trip_and_skip_unset_features() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(MODELS_FILE_NAME);
+        assert!(read_models_file(&path).unwrap().is_none());
+
+        let mut settings = ModelSettings::default();
+        *settings.model_mut(LlmFeature::Rephrase) = Some("gpt-4.1".into());
+        write_models_file(&path, &settings).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("cloze"));
+        let read_back = read_models_file(&path).unwrap().unwrap();
+        assert_eq!(read_back.model(LlmFeature::Rephrase), Some("gpt-4.1"));
+        assert_eq!(read_back.model(LlmFeature::Cloze), None);
+    }
+}
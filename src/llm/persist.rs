@@ -0,0 +1,138 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+
+use crate::card::{Card, CardContent, ClozeRange};
+use crate::cloze_utils;
+use crate::parser::{CardSeparator, forward_card_at};
+use crate::utils::trim_line;
+
+/// Writes the blanks of `cloze_text` into the lines `card` was parsed from,
+/// leaving every other line and the card's own formatting as it was.
+///
+/// Returns the card as re-read from the file, or `None` when nothing was
+/// written: the file no longer holds `card` unchanged (which is also what
+/// makes a second call a no-op), or `cloze_text` differs from the card by more
+/// than its brackets.
+pub fn write_cloze_fix(
+    card: &Card,
+    cloze_text: &str,
+    separator: CardSeparator,
+) -> Result<Option<Card>> {
+    let CardContent::Cloze { text, cloze_ranges } = &card.content else {
+        return Ok(None);
+    };
+    if !cloze_ranges.is_empty() {
+        return Ok(None);
+    }
+    let ranges = cloze_utils::cloze_ranges(cloze_text)?;
+    if ranges.is_empty() || strip_blanks(cloze_text, &ranges) != *text {
+        return Ok(None);
+    }
+    let (start, end) = card.file_card_range;
+    let path = card.file_path.as_path();
+    if forward_card_at(path, start, separator)?.map(|current| current.card_hash)
+        != Some(card.card_hash.clone())
+    {
+        return Ok(None);
+    }
+
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut lines: Vec<String> = contents.split_inclusive('\n').map(str::to_string).collect();
+    let end = end.max(start + 1).min(lines.len());
+    let old_lines: Vec<&str> = text.split('\n').collect();
+    let new_lines: Vec<&str> = cloze_text.split('\n').collect();
+
+    let mut next = 0;
+    for (offset, line) in lines[start..end].iter_mut().enumerate() {
+        let Some(&old) = old_lines.get(next) else {
+            break;
+        };
+        // Only search past the `C:` marker so the prefix itself is never rewritten.
+        let body_start = if offset == 0 {
+            line.find("C:").map_or(0, |idx| idx + 2)
+        } else {
+            0
+        };
+        if trim_line(&line[body_start..]) != Some(old) {
+            continue;
+        }
+        let Some(found) = line[body_start..].find(old) else {
+            continue;
+        };
+        let at = body_start + found;
+        line.replace_range(at..at + old.len(), new_lines[next]);
+        next += 1;
+    }
+    if next != old_lines.len() {
+        return Ok(None);
+    }
+
+    fs::write(path, lines.concat())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    forward_card_at(path, start, separator)
+}
+
+/// `text` with the brackets of each blank removed.
+fn strip_blanks(text: &str, ranges: &[ClozeRange]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for range in ranges {
+        out.push_str(&text[cursor..range.start]);
+        out.push_str(&text[range.start + 1..range.end - 1]);
+        cursor = range.end;
+    }
+    out.push_str(&text[cursor..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::cards_from_md;
+
+    #[test]
+    fn writes_blanks_into_the_card_lines_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.md");
+        let original = "Q: untouched?\nA: yes\n\nC:\n  Speech is produced\n  in Broca's area\n\nParis :: France\n";
+        fs::write(&path, original).unwrap();
+        let separator = CardSeparator::Rule;
+        let card = cards_from_md(&path, separator)
+            .unwrap()
+            .into_iter()
+            .find(|card| matches!(card.content, CardContent::Cloze { .. }))
+            .unwrap();
+
+        assert!(
+            write_cloze_fix(
+                &card,
+                "Speech is produced\nin Broca's [area] of the brain",
+                separator
+            )
+            .unwrap()
+            .is_none(),
+            "text changes beyond the brackets are not written"
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+
+        let fixed = write_cloze_fix(&card, "Speech is produced\nin [Broca's] area", separator)
+            .unwrap()
+            .expect("the fix is written");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Q: untouched?\nA: yes\n\nC:\n  Speech is produced\n  in [Broca's] area\n\nParis :: France\n"
+        );
+        assert_ne!(fixed.card_hash, card.card_hash);
+        assert_eq!(fixed.file_card_range.0, card.file_card_range.0);
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(
+            write_cloze_fix(&card, "Speech is produced\nin [Broca's] area", separator)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+    }
+}
@@ -74,10 +74,8 @@ pub fn cloze_user_prompt(cards: &[Card], total_needing: usize) -> Option<String>
     let mut sample_text: Option<String> = None;
 
     for card in cards {
-        if let CardContent::Cloze {
-            text,
-            cloze_range: None,
-        } = &card.content
+        if let CardContent::Cloze { text, cloze_ranges } = &card.content
+            && cloze_ranges.is_empty()
             && sample_text.is_none()
         {
             sample_text = Some(text.clone());
@@ -93,7 +91,7 @@ mod tests {
 
     use std::path::PathBuf;
 
-    use crate::parser::cards_from_md;
+    use crate::parser::{CardSeparator, cards_from_md};
 
     use super::*;
 
@@ -117,7 +115,7 @@ mod tests {
     #[test]
     fn test_getting_samples() {
         let card_path = PathBuf::from("test_data/test.md");
-        let cards = cards_from_md(&card_path).expect("should be ok");
+        let cards = cards_from_md(&card_path, CardSeparator::Rule).expect("should be ok");
         let user_prompt = cloze_user_prompt(&cards, 1);
         // dbg!(&user_prompt);
         assert_eq!(
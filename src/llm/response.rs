@@ -1,12 +1,25 @@
-use anyhow::{Context, Result, bail};
+use std::env;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
 use async_openai::{
     Client,
     config::OpenAIConfig,
+    error::OpenAIError,
     types::responses::{
         CreateResponseArgs, InputMessage, InputRole, OutputItem, OutputMessageContent,
     },
 };
 
+use crate::utils::{parse_duration, pluralize};
+
+/// Overrides [`DEFAULT_TIMEOUT`], e.g. `30s` or `2m`.
+pub const TIMEOUT_ENV: &str = "REPEATER_LLM_TIMEOUT";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_ATTEMPTS: u32 = 3;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 pub async fn request_single_text_response(
     client: &Client<OpenAIConfig>,
     model: &str,
@@ -30,11 +43,33 @@ pub async fn request_single_text_response(
         ])
         .build()?;
 
-    let response = client
-        .responses()
-        .create(request)
-        .await
-        .with_context(|| "Failed to get response from LLM")?;
+    let timeout = parse_timeout(env::var(TIMEOUT_ENV).ok())?;
+    let mut attempt = 1;
+    let response = loop {
+        let (error, transient) =
+            match tokio::time::timeout(timeout, client.responses().create(request.clone())).await {
+                Ok(Ok(response)) => break response,
+                Ok(Err(err)) => {
+                    let transient = is_transient(&err);
+                    (anyhow::Error::new(err), transient)
+                }
+                Err(_) => (
+                    anyhow!("No response within {} seconds", timeout.as_secs()),
+                    true,
+                ),
+            };
+        tracing::debug!(model, attempt, transient, "LLM request failed: {error:#}");
+        if !transient || attempt == MAX_ATTEMPTS {
+            return Err(error).with_context(|| {
+                format!(
+                    "Failed to get response from LLM after {}",
+                    pluralize("attempt", attempt as usize)
+                )
+            });
+        }
+        tokio::time::sleep(retry_delay(attempt)).await;
+        attempt += 1;
+    };
 
     for item in response.output {
         if let OutputItem::Message(message) = item {
@@ -52,3 +87,71 @@ pub async fn request_single_text_response(
 
     bail!("No text output returned from model")
 }
+
+fn parse_timeout(value: Option<String>) -> Result<Duration> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(DEFAULT_TIMEOUT),
+        Some(value) => parse_duration(value).with_context(|| format!("Invalid {TIMEOUT_ENV}")),
+    }
+}
+
+/// Waits 1s, then 2s, 4s, ... before retrying after the given attempt.
+fn retry_delay(attempt: u32) -> Duration {
+    FIRST_RETRY_DELAY * 2_u32.pow(attempt.saturating_sub(1))
+}
+
+/// Network failures, rate limits, and server errors are worth another try;
+/// bad requests and invalid keys are not.
+fn is_transient(err: &OpenAIError) -> bool {
+    match err {
+        OpenAIError::Reqwest(err) => {
+            err.is_timeout()
+                || err.is_connect()
+                || err
+                    .status()
+                    .is_some_and(|status| status.as_u16() == 429 || status.is_server_error())
+        }
+        OpenAIError::ApiError(err) => {
+            err.r#type.as_deref() == Some("server_error")
+                || err.code.as_deref() == Some("rate_limit_exceeded")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_openai::error::ApiError;
+
+    use super::*;
+
+    fn api_error(r#type: Option<&str>, code: Option<&str>) -> OpenAIError {
+        OpenAIError::ApiError(ApiError {
+            message: "failed".into(),
+            r#type: r#type.map(str::to_string),
+            param: None,
+            code: code.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn retries_only_transient_errors_with_growing_delays() {
+        assert!(is_transient(&api_error(Some("server_error"), None)));
+        assert!(is_transient(&api_error(None, Some("rate_limit_exceeded"))));
+        assert!(!is_transient(&api_error(
+            Some("invalid_request_error"),
+            Some("invalid_api_key")
+        )));
+        assert!(!is_transient(&OpenAIError::InvalidArgument("bad".into())));
+
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+
+        assert_eq!(parse_timeout(None).unwrap(), DEFAULT_TIMEOUT);
+        assert_eq!(
+            parse_timeout(Some("90s".into())).unwrap(),
+            Duration::from_secs(90)
+        );
+        assert!(parse_timeout(Some("soon".into())).is_err());
+    }
+}
@@ -8,11 +8,11 @@ use anyhow::{Context, Result};
 use dialoguer::{Password, theme::ColorfulTheme};
 use serde::{Deserialize, Serialize};
 
-use crate::utils::get_data_dir;
 use crate::utils::trim_line;
 use crate::{palette::Palette, utils::strip_controls_and_escapes};
 
 pub const API_KEY_ENV: &str = "REPEATER_OPENAI_API_KEY";
+pub const BASE_URL_ENV: &str = "REPEATER_OPENAI_BASE_URL";
 
 const AUTH_FILE_NAME: &str = "auth.json";
 const OPENAI_PROVIDER: &str = "openai";
@@ -31,7 +31,16 @@ struct AuthFile {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ProviderAuth {
+    #[serde(default)]
     key: String,
+    /// OpenAI-compatible endpoint to call instead of api.openai.com, such as
+    /// a local Ollama or LM Studio server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base_url: Option<String>,
+    /// Model to use when neither the environment nor `llm_models.json` picks
+    /// one for a feature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
 }
 
 impl ApiKeySource {
@@ -43,18 +52,23 @@ impl ApiKeySource {
     }
 }
 
-#[cfg(test)]
-const TEST_AUTH_PATH_ENV: &str = "REPEATER_TEST_AUTH_PATH";
-
-pub fn clear_api_key() -> Result<bool> {
-    let auth_path = auth_file_path()?;
+pub fn clear_api_key(data_dir: &Path) -> Result<bool> {
+    let auth_path = auth_file_path(data_dir);
     let Some(mut auth) = read_auth_file(&auth_path)? else {
         return Ok(false);
     };
 
-    if auth.providers.remove(OPENAI_PROVIDER).is_none() {
+    let Some(provider) = auth.providers.get_mut(OPENAI_PROVIDER) else {
         return Ok(false);
+    };
+    if provider.base_url.is_some() {
+        // Keep the endpoint; only the key is being forgotten.
+        let had_key = !provider.key.trim().is_empty();
+        provider.key.clear();
+        write_auth_file(&auth_path, &auth)?;
+        return Ok(had_key);
     }
+    auth.providers.remove(OPENAI_PROVIDER);
 
     if auth.providers.is_empty() {
         fs::remove_file(&auth_path).with_context(|| {
@@ -94,76 +108,85 @@ pub fn prompt_for_api_key(prompt: &str) -> Result<String> {
 pub struct ApiKeyLookup {
     pub api_key: Option<String>,
     pub source: Option<ApiKeySource>,
+    /// Endpoint from `REPEATER_OPENAI_BASE_URL` or the provider entry.
+    pub base_url: Option<String>,
+    /// Default model from the provider entry.
+    pub model: Option<String>,
 }
 
-pub fn store_api_key(api_key: &str) -> Result<()> {
+pub fn store_api_key(data_dir: &Path, api_key: &str) -> Result<()> {
     let trimmed = trim_line(api_key).with_context(|| "Cannot store an empty API key")?;
 
-    let auth_path = auth_file_path()?;
+    let auth_path = auth_file_path(data_dir);
     let mut auth = read_auth_file(&auth_path)?.unwrap_or_default();
 
-    auth.providers.insert(
-        OPENAI_PROVIDER.to_string(),
-        ProviderAuth {
-            key: trimmed.to_string(),
-        },
-    );
+    auth.providers
+        .entry(OPENAI_PROVIDER.to_string())
+        .or_insert_with(|| ProviderAuth {
+            key: String::new(),
+            base_url: None,
+            model: None,
+        })
+        .key = trimmed.to_string();
 
     write_auth_file(&auth_path, &auth)
 }
 
-pub fn get_api_key_from_sources() -> Result<ApiKeyLookup> {
-    // 1. Environment variable
-    if let Ok(value) = env::var(API_KEY_ENV)
-        && !value.trim().is_empty()
-    {
-        return Ok(ApiKeyLookup {
-            api_key: Some(value),
-            source: Some(ApiKeySource::Environment),
-        });
-    }
+/// Saves the endpoint to call instead of OpenAI's; an empty `base_url`
+/// goes back to the default.
+pub fn store_base_url(data_dir: &Path, base_url: &str) -> Result<()> {
+    let auth_path = auth_file_path(data_dir);
+    let mut auth = read_auth_file(&auth_path)?.unwrap_or_default();
 
-    // 2. Auth file
-    let auth_path = auth_file_path()?;
-    let Some(auth) = read_auth_file(&auth_path)? else {
-        return Ok(ApiKeyLookup {
-            api_key: None,
-            source: None,
-        });
-    };
+    let base_url = trim_line(base_url).map(str::to_string);
+    auth.providers
+        .entry(OPENAI_PROVIDER.to_string())
+        .or_insert_with(|| ProviderAuth {
+            key: String::new(),
+            base_url: None,
+            model: None,
+        })
+        .base_url = base_url;
 
-    let key = auth
-        .providers
-        .get(OPENAI_PROVIDER)
-        .map(|entry| entry.key.trim())
-        .filter(|value| !value.is_empty())
-        .map(str::to_string);
-
-    if let Some(api_key) = key {
-        return Ok(ApiKeyLookup {
-            api_key: Some(api_key),
-            source: Some(ApiKeySource::AuthFile),
-        });
-    }
+    write_auth_file(&auth_path, &auth)
+}
+
+pub fn get_api_key_from_sources(data_dir: &Path) -> Result<ApiKeyLookup> {
+    let auth_path = auth_file_path(data_dir);
+    let provider =
+        read_auth_file(&auth_path)?.and_then(|mut auth| auth.providers.remove(OPENAI_PROVIDER));
+    let from_env = |name: &str| {
+        env::var(name)
+            .ok()
+            .and_then(|value| trim_line(&value).map(str::to_string))
+    };
+    let from_file = |value: Option<&str>| value.and_then(trim_line).map(str::to_string);
+
+    // 1. Environment variable, 2. auth file
+    let (api_key, source) = if let Some(key) = from_env(API_KEY_ENV) {
+        (Some(key), Some(ApiKeySource::Environment))
+    } else if let Some(key) = from_file(provider.as_ref().map(|entry| entry.key.as_str())) {
+        (Some(key), Some(ApiKeySource::AuthFile))
+    } else {
+        (None, None)
+    };
 
     Ok(ApiKeyLookup {
-        api_key: None,
-        source: None,
+        api_key,
+        source,
+        base_url: from_env(BASE_URL_ENV).or_else(|| {
+            from_file(
+                provider
+                    .as_ref()
+                    .and_then(|entry| entry.base_url.as_deref()),
+            )
+        }),
+        model: from_file(provider.as_ref().and_then(|entry| entry.model.as_deref())),
     })
 }
 
-fn auth_file_path() -> Result<PathBuf> {
-    #[cfg(test)]
-    {
-        if let Ok(path) = env::var(TEST_AUTH_PATH_ENV)
-            && !path.trim().is_empty()
-        {
-            return Ok(PathBuf::from(path));
-        }
-    }
-
-    let data_dir = get_data_dir()?;
-    Ok(data_dir.join(AUTH_FILE_NAME))
+fn auth_file_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(AUTH_FILE_NAME)
 }
 
 fn read_auth_file(path: &Path) -> Result<Option<AuthFile>> {
@@ -218,6 +241,8 @@ mod tests {
             OPENAI_PROVIDER.to_string(),
             ProviderAuth {
                 key: "test-key".to_string(),
+                base_url: None,
+                model: None,
             },
         );
 
@@ -245,19 +270,17 @@ mod tests {
         let dir = tempdir().unwrap();
         let path = dir.path().join("auth.json");
 
-        unsafe {
-            env::set_var(TEST_AUTH_PATH_ENV, &path);
-        }
-        store_api_key("fake_key").unwrap();
-        store_api_key("real_key").unwrap();
+        store_api_key(dir.path(), "fake_key").unwrap();
+        store_api_key(dir.path(), "real_key").unwrap();
+        assert!(path.exists());
 
-        let api_key = get_api_key_from_sources().unwrap();
+        let api_key = get_api_key_from_sources(dir.path()).unwrap();
         assert_eq!(api_key.api_key.unwrap(), "real_key");
 
-        let cleared = clear_api_key().unwrap();
+        let cleared = clear_api_key(dir.path()).unwrap();
         assert!(cleared);
 
-        let api_key = get_api_key_from_sources().unwrap();
+        let api_key = get_api_key_from_sources(dir.path()).unwrap();
         assert!(api_key.api_key.is_none());
     }
 
@@ -270,6 +293,8 @@ mod tests {
             OPENAI_PROVIDER.to_string(),
             ProviderAuth {
                 key: "saved-key".to_string(),
+                base_url: None,
+                model: None,
             },
         );
 
@@ -285,18 +310,69 @@ mod tests {
     }
 
     #[test]
-    fn load_key_without_store() {
+    fn base_url_is_optional_and_survives_key_changes() {
+        let path = Path::new("auth.json");
+        let parsed = parse_auth_contents(r#"{"openai": {"key": "old"}}"#, path)
+            .unwrap()
+            .unwrap();
+        assert!(parsed.providers[OPENAI_PROVIDER].base_url.is_none());
+        assert!(!serialize_auth(&parsed).unwrap().contains("base_url"));
+
         let dir = tempdir().unwrap();
         let path = dir.path().join("auth.json");
+        store_base_url(dir.path(), "http://localhost:11434/v1").unwrap();
+        store_api_key(dir.path(), "local").unwrap();
+        assert!(clear_api_key(dir.path()).unwrap());
 
-        unsafe {
-            env::set_var(TEST_AUTH_PATH_ENV, &path);
-        }
+        let auth = read_auth_file(&path).unwrap().unwrap();
+        let provider = &auth.providers[OPENAI_PROVIDER];
+        assert_eq!(provider.key, "");
+        assert_eq!(
+            provider.base_url.as_deref(),
+            Some("http://localhost:11434/v1")
+        );
+        store_base_url(dir.path(), " ").unwrap();
+        let auth = read_auth_file(&path).unwrap().unwrap();
+        assert!(auth.providers[OPENAI_PROVIDER].base_url.is_none());
+    }
+
+    #[test]
+    fn lookup_returns_provider_settings_with_the_key() {
+        let path = Path::new("auth.json");
+        let parsed = parse_auth_contents(r#"{"openai": {"key": "old"}}"#, path)
+            .unwrap()
+            .unwrap();
+        assert!(parsed.providers[OPENAI_PROVIDER].model.is_none());
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("auth.json");
+        fs::write(
+            &path,
+            r#"{"openai": {"key": "local", "base_url": "http://localhost:1234/v1", "model": " llama3.2 "}}"#,
+        )
+        .unwrap();
+
+        let lookup = get_api_key_from_sources(dir.path()).unwrap();
+        assert_eq!(lookup.api_key.as_deref(), Some("local"));
+        assert_eq!(lookup.source, Some(ApiKeySource::AuthFile));
+        assert_eq!(lookup.base_url.as_deref(), Some("http://localhost:1234/v1"));
+        assert_eq!(lookup.model.as_deref(), Some("llama3.2"));
+
+        store_api_key(dir.path(), "other").unwrap();
+        let lookup = get_api_key_from_sources(dir.path()).unwrap();
+        assert_eq!(lookup.model.as_deref(), Some("llama3.2"));
+    }
+
+    #[test]
+    fn load_key_without_store() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("auth.json");
 
-        let api_key = get_api_key_from_sources().unwrap();
+        let api_key = get_api_key_from_sources(dir.path()).unwrap();
         assert!(api_key.api_key.is_none());
 
-        let cleared = clear_api_key().unwrap();
+        let cleared = clear_api_key(dir.path()).unwrap();
         assert!(!cleared);
+        assert!(!path.exists());
     }
 }
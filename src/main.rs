@@ -1,10 +1,25 @@
+use std::io::{self, IsTerminal};
 use std::path::PathBuf;
+use std::time::Duration;
 
-use anyhow::{Context, Result, bail};
-use clap::{Parser, Subcommand, ValueHint};
+use anyhow::{Result, bail};
+use chrono::NaiveDate;
+use clap::{ArgAction, Args, Parser, Subcommand, ValueHint, builder::BoolishValueParser};
+use tracing_subscriber::EnvFilter;
 
-use repeater::commands::{check, create, drill};
-use repeater::crud::DB;
+use repeater::commands::drill::RedoPolicy;
+use repeater::commands::export::{self, ExportFormat};
+use repeater::commands::{
+    backup, check, config, create, drill, leeches, optimize, prune, reschedule, search, stats,
+    unsuspend,
+};
+use repeater::crud::{DB, DrillOrder};
+use repeater::import::ImportSource;
+use repeater::palette::{NO_COLOR_ENV, Palette, no_color_requested};
+use repeater::parser::{CardSeparator, ScanOptions};
+use repeater::settings::parse_learn_ahead;
+use repeater::stats::{CardLifeCycle, DEFAULT_HORIZON_DAYS};
+use repeater::utils::{get_data_dir, parse_date, parse_duration, pluralize};
 use repeater::{import, llm};
 
 #[derive(Parser, Debug)]
@@ -18,6 +33,15 @@ use repeater::{import, llm};
     disable_help_subcommand = true
 )]
 struct Cli {
+    /// Keep cards.db, auth.json, and other data here [env: REPEATER_DATA_DIR]
+    #[arg(long, global = true, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    data_dir: Option<PathBuf>,
+    /// Print without colors, as does setting NO_COLOR [env: NO_COLOR]
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Log what repeater is doing to stderr (-v info, -vv debug, -vvv trace) [env: RUST_LOG]
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
     #[command(subcommand)]
     command: Command,
 }
@@ -41,12 +65,62 @@ enum Command {
         /// Maximum number of new cards to drill in a session.
         #[arg(long, value_name = "COUNT")]
         new_card_limit: Option<usize>,
+        /// Maximum number of cards to drill from any single file.
+        #[arg(long, value_name = "COUNT")]
+        per_file_limit: Option<usize>,
         /// Rephrase  card questions via the LLM helper before the session starts.
         #[arg(long = "rephrase", default_value_t = false)]
         rephrase_questions: bool,
         /// Randomize the order of cards in the drill session
         #[arg(long, default_value_t = false)]
         shuffle: bool,
+        /// Where new cards go relative to due cards
+        #[arg(long, value_enum, default_value_t = DrillOrder::DueFirst)]
+        order: DrillOrder,
+        /// Only drill cards with this inline #tag (case-insensitive)
+        #[arg(long, value_name = "TAG")]
+        tag: Option<String>,
+        /// Only drill cards in this deck, set by frontmatter or an @deck: line (case-insensitive)
+        #[arg(long, value_name = "DECK")]
+        deck: Option<String>,
+        /// Also drill every basic card from answer to question
+        #[arg(long, default_value_t = false)]
+        both_ways: bool,
+        /// Stop introducing cards once this much time has passed (e.g. 15m, 1h30m)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        time_limit: Option<Duration>,
+        /// Drill line by line on stdin/stdout instead of the full-screen TUI
+        #[arg(long, default_value_t = false)]
+        plain: bool,
+        /// Type the answer to basic cards and have it graded for you
+        #[arg(long = "type", default_value_t = false, conflicts_with = "plain")]
+        type_answers: bool,
+        /// Which reviewed cards come back later in the session
+        #[arg(long, value_enum, value_name = "POLICY", default_value_t = RedoPolicy::Soon)]
+        redo: RedoPolicy,
+        /// Play a card's first audio file when its answer is revealed
+        #[arg(long, default_value_t = false, conflicts_with = "plain")]
+        autoplay_audio: bool,
+        /// Offer to keep the review history of cards edited in place
+        #[arg(long, default_value_t = false)]
+        migrate_edits: bool,
+        /// Save clozes generated by the LLM helper into the deck files
+        #[arg(long, default_value_t = false, conflicts_with = "plain")]
+        write_ai_fixes: bool,
+        #[command(flatten)]
+        scan: ScanArgs,
+        /// Leave out decks that fail to parse and list them, instead of stopping
+        #[arg(long, default_value_t = false)]
+        skip_errors: bool,
+        /// Only drill cards at this stage
+        #[arg(long, value_enum, value_name = "STAGE")]
+        only: Option<CardLifeCycle>,
+        /// Print how many cards are due, within the limits, and exit without drilling
+        #[arg(long, default_value_t = false)]
+        count: bool,
+        /// Print the due cards as a numbered list, within the limits, and exit without drilling
+        #[arg(long, default_value_t = false, conflicts_with = "count")]
+        list: bool,
     },
     /// Re-index decks and show collection stats
     Check {
@@ -60,22 +134,237 @@ enum Command {
         /// Print a plain summary instead of the TUI dashboard
         #[arg(long, default_value_t = false)]
         plain: bool,
+        /// Print the collection stats as JSON instead of the TUI dashboard
+        #[arg(long, default_value_t = false, conflicts_with_all = ["plain", "migrate_edits"])]
+        json: bool,
+        /// Offer to keep the review history of cards edited in place
+        #[arg(long, default_value_t = false)]
+        migrate_edits: bool,
+        /// How many days, starting today, the due forecast covers
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = DEFAULT_HORIZON_DAYS,
+            value_parser = clap::value_parser!(u32).range(1..=365)
+        )]
+        horizon: u32,
+        /// Also project the daily workload for the next 30 days, assuming every card is passed
+        #[arg(long, default_value_t = false)]
+        forecast: bool,
+        #[command(flatten)]
+        scan: ScanArgs,
+        /// Leave out decks that fail to parse and list them, instead of stopping
+        #[arg(long, default_value_t = false)]
+        skip_errors: bool,
+    },
+    /// Delete DB rows for cards that no longer exist in any deck
+    Prune {
+        #[arg(
+            value_name = "PATHS",
+            num_args = 0..,
+            default_value = ".",
+            value_hint = ValueHint::AnyPath
+        )]
+        paths: Vec<PathBuf>,
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+    /// Bring suspended cards back into drills
+    Unsuspend {
+        #[arg(
+            value_name = "PATHS",
+            num_args = 0..,
+            default_value = ".",
+            value_hint = ValueHint::AnyPath
+        )]
+        paths: Vec<PathBuf>,
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+    /// List cards that keep failing so they can be rewritten
+    Leeches {
+        #[arg(
+            value_name = "PATHS",
+            num_args = 0..,
+            default_value = ".",
+            value_hint = ValueHint::AnyPath
+        )]
+        paths: Vec<PathBuf>,
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+    /// Make overdue cards due a number of days from now, e.g. after a break
+    Postpone {
+        /// How many days from now each overdue card comes due
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        days: u32,
+        #[arg(
+            value_name = "PATHS",
+            num_args = 0..,
+            default_value = ".",
+            value_hint = ValueHint::AnyPath
+        )]
+        paths: Vec<PathBuf>,
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+    /// Bring cards due later forward by a number of days
+    Advance {
+        /// How many days earlier each upcoming card comes due
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        days: u32,
+        #[arg(
+            value_name = "PATHS",
+            num_args = 0..,
+            default_value = ".",
+            value_hint = ValueHint::AnyPath
+        )]
+        paths: Vec<PathBuf>,
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+    /// Find cards by their text and print them as path:line: text
+    Search {
+        /// Text to look for (case-insensitive)
+        query: String,
+        #[arg(
+            value_name = "PATHS",
+            num_args = 0..,
+            default_value = ".",
+            value_hint = ValueHint::AnyPath
+        )]
+        paths: Vec<PathBuf>,
+        /// Treat the query as a regular expression (case-sensitive unless it starts with (?i))
+        #[arg(long, default_value_t = false)]
+        regex: bool,
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+    /// Show per-deck stats grouped by top-level directory
+    Stats {
+        #[arg(
+            value_name = "PATHS",
+            num_args = 0..,
+            default_value = ".",
+            value_hint = ValueHint::AnyPath
+        )]
+        paths: Vec<PathBuf>,
+        /// Emit the grouped summary as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+    /// Export card scheduling data for backup or analysis
+    Export {
+        /// File to write the export to
+        #[arg(value_name = "PATH", value_hint = ValueHint::FilePath)]
+        path: PathBuf,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Only export cards reviewed or added on or after this local date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        since: Option<NaiveDate>,
+    },
+    /// Save cards.db and settings to a single portable file
+    Backup {
+        /// File to write the backup to
+        #[arg(value_name = "PATH", value_hint = ValueHint::FilePath)]
+        path: PathBuf,
+    },
+    /// Merge a backup into cards.db, keeping the most recent review of each card
+    Restore {
+        /// Backup file created by `repeater backup`
+        #[arg(value_name = "PATH", value_hint = ValueHint::FilePath)]
+        path: PathBuf,
     },
     /// Create or append to a card
     Create {
         /// Card path
         #[arg(value_name = "PATH", value_hint = ValueHint::FilePath)]
         path: PathBuf,
+        /// Append a basic card without opening the editor
+        #[arg(long, num_args = 2, value_names = ["QUESTION", "ANSWER"], conflicts_with = "cloze")]
+        basic: Option<Vec<String>>,
+        /// Append a cloze card without opening the editor, e.g. "[Paris] is in France"
+        #[arg(long, value_name = "TEXT")]
+        cloze: Option<String>,
     },
-    /// Import from Anki
+    /// Import from Anki, Mnemosyne, or CSV
     Import {
-        /// Anki export path. Must be an apkg file
+        /// Export to import: an Anki apkg file, a Mnemosyne .db file, or a CSV file
         #[arg(value_name = "PATH", value_hint = ValueHint::FilePath)]
-        anki_path: PathBuf,
-        /// Directory to export to
+        import_path: PathBuf,
+        /// Directory to export to (a single Markdown file for CSV imports)
         #[arg(value_name = "PATH", value_hint = ValueHint::AnyPath)]
         export_path: PathBuf,
+        /// Print the decks, card counts, and file paths without writing anything
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+        /// Application the export came from
+        #[arg(long, value_enum, default_value_t = ImportSource::Anki)]
+        source: ImportSource,
+        /// Write every deck as one file in PATH, named after its full deck path
+        #[arg(long, default_value_t = false)]
+        flat: bool,
+        /// Write the note ids of skipped cards and why they were skipped to a file
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+        report: Option<PathBuf>,
+        /// Import every deck without asking which ones to keep
+        #[arg(long, default_value_t = false)]
+        all: bool,
+        /// Ignore Anki card templates and ask the back field on odd-numbered templates
+        #[arg(long, default_value_t = false)]
+        model_reverse: bool,
+        /// Overwrite existing deck files without asking
+        #[arg(long, default_value_t = false)]
+        force: bool,
+        /// Only print prompts, dry-run plans, and the final summary
+        #[arg(long, default_value_t = false)]
+        quiet: bool,
+    },
+    /// View or change scheduler settings
+    Config {
+        /// Target probability of recalling a card when it comes due (0.70-0.97)
+        #[arg(long, value_name = "RETENTION")]
+        retention: Option<f32>,
+        /// JSON file with a custom FSRS parameter array (17, 19, or 21 weights)
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+        fsrs_params: Option<PathBuf>,
+        /// Delays after each of a new card's first reviews (e.g. "1m 10m 1d")
+        #[arg(long, value_name = "STEPS")]
+        learning_steps: Option<String>,
+        /// Longest interval in days a card can be scheduled for (0 removes the cap)
+        #[arg(long, value_name = "DAYS")]
+        max_interval: Option<u32>,
+        /// Spread intervals longer than a day by a few percent (true or false)
+        #[arg(long, value_name = "BOOL")]
+        fuzz: Option<bool>,
+        /// What ends a card: a `---` line (rule) or also a blank line (blank-line)
+        #[arg(long, value_enum, value_name = "SEPARATOR")]
+        card_separator: Option<CardSeparator>,
+        /// Failed reviews after which a card is flagged as a leech
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        leech_threshold: Option<u32>,
+        /// Suspend cards as soon as they become leeches (true or false)
+        #[arg(long, value_name = "BOOL")]
+        suspend_leeches: Option<bool>,
+        /// Hour after local midnight when a new study day starts (0-23)
+        #[arg(long, value_name = "HOUR")]
+        day_start: Option<u32>,
+        /// Count cards due within this long as due now, 0m to 12h (e.g. 5m, 1h; default 20m)
+        #[arg(long, value_name = "DURATION", value_parser = parse_learn_ahead)]
+        learn_ahead: Option<Duration>,
+        /// Look for new releases during `check` (on or off)
+        #[arg(long, value_name = "on|off", value_parser = BoolishValueParser::new())]
+        version_check: Option<bool>,
+        /// Rebind drill keys, e.g. "fail=j good=k exit=q,esc" ("default" restores them)
+        #[arg(long, value_name = "BINDINGS")]
+        keys: Option<String>,
     },
+    /// Train FSRS parameters on your review history
+    Optimize,
     /// Manage LLM helper settings
     Llm {
         /// Store a new API key in the local auth file
@@ -87,9 +376,41 @@ enum Command {
         /// Verify the configured API key by calling the OpenAI API
         #[arg(long, conflicts_with = "clear")]
         test: bool,
+        /// Use this OpenAI model for LLM features (all of them unless --feature is given)
+        #[arg(long, value_name = "MODEL")]
+        set_model: Option<String>,
+        /// Limit --set-model to one feature
+        #[arg(long, value_enum, requires = "set_model")]
+        feature: Option<llm::LlmFeature>,
+        /// Call an OpenAI-compatible server at this URL instead of OpenAI ("" resets it)
+        #[arg(long, value_name = "URL")]
+        set_base_url: Option<String>,
+        /// Forget cached cloze and rephrase results so they are requested again
+        #[arg(long)]
+        clear_cache: bool,
     },
 }
 
+/// Which files a command that scans decks visits.
+#[derive(Args, Debug)]
+struct ScanArgs {
+    /// Scan hidden files and directories (the default)
+    #[arg(long, overrides_with = "no_hidden")]
+    hidden: bool,
+    /// Skip hidden files and directories
+    #[arg(long, overrides_with = "hidden")]
+    no_hidden: bool,
+}
+
+impl ScanArgs {
+    fn options(&self, skip_errors: bool) -> ScanOptions {
+        ScanOptions {
+            hidden: !self.no_hidden,
+            skip_errors,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     if let Err(err) = run_cli().await {
@@ -98,50 +419,272 @@ async fn main() {
     }
 }
 
+/// Sends `tracing` events to stderr so stdout stays clean for output and
+/// pipes. `RUST_LOG` wins over `--verbose` when it is set.
+fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => "warn",
+        1 => "repeater=info",
+        2 => "repeater=debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal() && Palette::color_enabled())
+        .init();
+}
+
 async fn run_cli() -> Result<()> {
     let cli = Cli::parse();
-    let db = DB::new().await?;
+    Palette::set_color_enabled(
+        !cli.no_color && !no_color_requested(std::env::var_os(NO_COLOR_ENV)),
+    );
+    init_logging(cli.verbose);
+    let data_dir = get_data_dir(cli.data_dir.as_deref())?;
+    let db = DB::new(&data_dir).await?;
 
     match cli.command {
         Command::Drill {
             paths,
             card_limit,
             new_card_limit,
+            per_file_limit,
             rephrase_questions,
             shuffle,
+            order,
+            tag,
+            deck,
+            both_ways,
+            time_limit,
+            plain,
+            type_answers,
+            redo,
+            autoplay_audio,
+            migrate_edits,
+            write_ai_fixes,
+            scan,
+            skip_errors,
+            only,
+            count,
+            list,
         } => {
-            drill::run(&db, paths, card_limit, new_card_limit, rephrase_questions, shuffle).await?;
+            let options = drill::DrillOptions {
+                card_limit,
+                new_card_limit,
+                per_file_limit,
+                rephrase_questions,
+                shuffle,
+                order,
+                tag,
+                deck,
+                both_ways,
+                time_limit,
+                plain,
+                type_answers,
+                redo,
+                autoplay_audio,
+                migrate_edits,
+                write_ai_fixes,
+                scan: scan.options(skip_errors),
+                only,
+                count_only: count,
+                list_only: list,
+            };
+            drill::run(&db, paths, options).await?;
         }
-        Command::Check { paths, plain } => {
-            let _ = check::run(&db, paths, plain).await?;
+        Command::Check {
+            paths,
+            plain,
+            json,
+            migrate_edits,
+            horizon,
+            forecast,
+            scan,
+            skip_errors,
+        } => {
+            let scan = scan.options(skip_errors);
+            let output = if json {
+                check::CheckOutput::Json
+            } else if plain {
+                check::CheckOutput::Plain
+            } else {
+                check::CheckOutput::Dashboard
+            };
+            let _ = check::run(&db, paths, output, migrate_edits, horizon, forecast, scan).await?;
         }
-        Command::Create { path } => {
-            create::run(&db, path).await?;
+        Command::Prune { paths, scan } => {
+            prune::run(&db, paths, scan.options(false)).await?;
+        }
+        Command::Unsuspend { paths, scan } => {
+            unsuspend::run(&db, paths, scan.options(false)).await?;
+        }
+        Command::Leeches { paths, scan } => {
+            leeches::run(&db, paths, scan.options(false)).await?;
+        }
+        Command::Postpone { days, paths, scan } => {
+            reschedule::postpone(&db, paths, days, scan.options(false)).await?;
+        }
+        Command::Advance { days, paths, scan } => {
+            reschedule::advance(&db, paths, days, scan.options(false)).await?;
+        }
+        Command::Search {
+            query,
+            paths,
+            regex,
+            scan,
+        } => {
+            let separator = db.get_settings().await?.card_separator;
+            search::run(paths, &query, regex, separator, scan.options(false)).await?;
+        }
+        Command::Stats { paths, json, scan } => {
+            stats::run(&db, paths, json, scan.options(false)).await?;
+        }
+        Command::Export {
+            path,
+            format,
+            since,
+        } => {
+            export::run(&db, &path, format, since).await?;
+        }
+        Command::Backup { path } => {
+            backup::backup(&db, &path).await?;
+        }
+        Command::Restore { path } => {
+            backup::restore(&db, &path).await?;
+        }
+        Command::Create { path, basic, cloze } => {
+            let new_card = match (basic, cloze) {
+                (Some(mut sides), _) => {
+                    let answer = sides.pop().unwrap_or_default();
+                    let question = sides.pop().unwrap_or_default();
+                    Some(create::NewCard::Basic { question, answer })
+                }
+                (None, Some(text)) => Some(create::NewCard::Cloze { text }),
+                (None, None) => None,
+            };
+            create::run(&db, path, new_card).await?;
         }
         Command::Import {
-            anki_path,
+            import_path,
             export_path,
+            dry_run,
+            source,
+            flat,
+            report,
+            all,
+            model_reverse,
+            force,
+            quiet,
+        } => {
+            let options = import::ImportOptions {
+                dry_run,
+                flat,
+                all_decks: all,
+                model_reverse,
+                report,
+                force,
+                quiet,
+            };
+            import::run(&db, source, &import_path, &export_path, &options).await?
+        }
+        Command::Config {
+            retention,
+            fsrs_params,
+            learning_steps,
+            max_interval,
+            fuzz,
+            card_separator,
+            leech_threshold,
+            suspend_leeches,
+            version_check,
+            day_start,
+            learn_ahead,
+            keys,
         } => {
-            import::run(&db, &anki_path, &export_path)
-                .await.with_context(|| "Importing from Anki is a work in progress, please report issues on https://github.com/shaankhosla/repeater")?
-        },
-        Command::Llm { set, clear, test } => handle_llm_command(set, clear, test).await?,
+            let options = config::ConfigOptions {
+                retention,
+                fsrs_params,
+                learning_steps,
+                max_interval,
+                fuzz,
+                card_separator,
+                leech_threshold,
+                suspend_leeches,
+                version_check,
+                day_start,
+                learn_ahead,
+                keys,
+            };
+            config::run(&db, options).await?
+        }
+        Command::Optimize => optimize::run(&db).await?,
+        Command::Llm {
+            set,
+            clear,
+            test,
+            set_model,
+            feature,
+            set_base_url,
+            clear_cache,
+        } => {
+            let options = LlmOptions {
+                set,
+                clear,
+                test,
+                set_model,
+                feature,
+                set_base_url,
+                clear_cache,
+            };
+            handle_llm_command(&db, options).await?
+        }
     }
 
     Ok(())
 }
 
-async fn handle_llm_command(set: Option<String>, clear: bool, test: bool) -> Result<()> {
+struct LlmOptions {
+    set: Option<String>,
+    clear: bool,
+    test: bool,
+    set_model: Option<String>,
+    feature: Option<llm::LlmFeature>,
+    set_base_url: Option<String>,
+    clear_cache: bool,
+}
+
+async fn handle_llm_command(db: &DB, options: LlmOptions) -> Result<()> {
+    let LlmOptions {
+        set,
+        clear,
+        test,
+        set_model,
+        feature,
+        set_base_url,
+        clear_cache,
+    } = options;
     let mut action_taken = false;
 
+    if let Some(url) = set_base_url {
+        llm::store_base_url(db.data_dir(), &url)?;
+        if url.trim().is_empty() {
+            println!("Using the default OpenAI endpoint.");
+        } else {
+            println!("Using the OpenAI-compatible server at {}.", url.trim());
+        }
+        action_taken = true;
+    }
+
     if let Some(key) = set {
-        llm::store_api_key(&key)?;
+        llm::store_api_key(db.data_dir(), &key)?;
         println!("Stored OpenAI API key in the local auth file.");
         action_taken = true;
     }
 
     if clear {
-        let removed = llm::clear_api_key()?;
+        let removed = llm::clear_api_key(db.data_dir())?;
         if removed {
             println!("Removed the stored OpenAI API key.");
         } else {
@@ -151,13 +694,34 @@ async fn handle_llm_command(set: Option<String>, clear: bool, test: bool) -> Res
     }
 
     if test {
-        let source = llm::test_configured_api_key().await?;
+        let source = llm::test_configured_api_key(db.data_dir()).await?;
         println!("OpenAI API key from the {} is valid.", source.description());
         action_taken = true;
     }
 
+    if let Some(model) = set_model {
+        llm::store_model(db.data_dir(), feature, &model)?;
+        let target = match feature {
+            Some(feature) => format!("{} cards", feature.name()),
+            None => "all LLM features".to_string(),
+        };
+        println!("Using {} for {target}.", model.trim());
+        action_taken = true;
+    }
+
+    if clear_cache {
+        let removed = db.clear_llm_cache().await?;
+        println!(
+            "Cleared {}.",
+            pluralize("cached LLM result", removed as usize)
+        );
+        action_taken = true;
+    }
+
     if !action_taken {
-        bail!("No action provided. Use --set, --clear, or --test.");
+        bail!(
+            "No action provided. Use --set, --clear, --test, --set-model, --set-base-url, or --clear-cache."
+        );
     }
     Ok(())
 }
@@ -1,7 +1,19 @@
+use std::ffi::OsString;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use ratatui::style::Color;
 
+/// Turns colored output off when set to any non-empty value (see no-color.org).
+pub const NO_COLOR_ENV: &str = "NO_COLOR";
+
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Whether a `NO_COLOR` value asks for plain output.
+pub fn no_color_requested(value: Option<OsString>) -> bool {
+    value.is_some_and(|value| !value.is_empty())
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct PaletteColor {
     tui: Color,
@@ -35,11 +47,44 @@ impl Palette {
     pub const DANGER: PaletteColor = PaletteColor::new(Color::Red, "\x1b[31m");
     pub const BORDER: PaletteColor = PaletteColor::new(Color::Gray, "\x1b[90m");
 
+    /// Turns the escapes written by [`Palette::paint`] and [`Palette::dim`]
+    /// on or off for the rest of the process. The TUI styles are unaffected.
+    pub fn set_color_enabled(enabled: bool) {
+        COLOR_ENABLED.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn color_enabled() -> bool {
+        COLOR_ENABLED.load(Ordering::Relaxed)
+    }
+
     pub fn paint(color: PaletteColor, value: impl fmt::Display) -> String {
-        format!("{}{}{}", color.ansi(), value, Self::RESET)
+        styled(Self::color_enabled(), color.ansi(), value)
     }
 
     pub fn dim(value: impl fmt::Display) -> String {
-        format!("{}{}{}", Self::DIM, value, Self::RESET)
+        styled(Self::color_enabled(), Self::DIM, value)
+    }
+}
+
+fn styled(enabled: bool, escape: &str, value: impl fmt::Display) -> String {
+    if enabled {
+        format!("{}{}{}", escape, value, Palette::RESET)
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_output_drops_the_escapes() {
+        assert_eq!(styled(true, Palette::WARNING.ansi(), 3), "\x1b[33m3\x1b[0m");
+        assert_eq!(styled(false, Palette::WARNING.ansi(), 3), "3");
+
+        assert!(!no_color_requested(None));
+        assert!(!no_color_requested(Some("".into())));
+        assert!(no_color_requested(Some("1".into())));
     }
 }
@@ -0,0 +1,454 @@
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+
+use crate::palette::Palette;
+
+/// Token rules for one language. Deliberately small: enough to tell keywords,
+/// strings, numbers, and comments apart in a flashcard-sized snippet.
+pub struct Syntax {
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    ignore_case: bool,
+}
+
+static RUST: Syntax = Syntax {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+    ignore_case: false,
+};
+
+static PYTHON: Syntax = Syntax {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "False", "finally", "for", "from", "global", "if", "import",
+        "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return", "True",
+        "try", "while", "with", "yield",
+    ],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    ignore_case: false,
+};
+
+static JAVASCRIPT: Syntax = Syntax {
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "of",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "typeof",
+        "undefined",
+        "var",
+        "void",
+        "while",
+        "yield",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    ignore_case: false,
+};
+
+static C: Syntax = Syntax {
+    keywords: &[
+        "auto",
+        "bool",
+        "break",
+        "case",
+        "char",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extern",
+        "false",
+        "float",
+        "for",
+        "if",
+        "include",
+        "int",
+        "long",
+        "namespace",
+        "new",
+        "nullptr",
+        "private",
+        "public",
+        "return",
+        "short",
+        "signed",
+        "sizeof",
+        "static",
+        "struct",
+        "switch",
+        "template",
+        "this",
+        "true",
+        "typedef",
+        "union",
+        "unsigned",
+        "using",
+        "void",
+        "while",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\''],
+    ignore_case: false,
+};
+
+static GO: Syntax = Syntax {
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "false",
+        "for",
+        "func",
+        "go",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "nil",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "true",
+        "type",
+        "var",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    ignore_case: false,
+};
+
+static JAVA: Syntax = Syntax {
+    keywords: &[
+        "abstract",
+        "boolean",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "continue",
+        "default",
+        "do",
+        "double",
+        "else",
+        "extends",
+        "false",
+        "final",
+        "finally",
+        "for",
+        "if",
+        "implements",
+        "import",
+        "int",
+        "interface",
+        "long",
+        "new",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "throws",
+        "true",
+        "try",
+        "var",
+        "void",
+        "while",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\''],
+    ignore_case: false,
+};
+
+static SHELL: Syntax = Syntax {
+    keywords: &[
+        "case", "do", "done", "echo", "elif", "else", "esac", "exit", "export", "fi", "for",
+        "function", "if", "in", "local", "return", "then", "while",
+    ],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    ignore_case: false,
+};
+
+static SQL: Syntax = Syntax {
+    keywords: &[
+        "AND", "AS", "ASC", "BY", "CREATE", "DELETE", "DESC", "DISTINCT", "FROM", "GROUP",
+        "HAVING", "IN", "INDEX", "INSERT", "INTO", "IS", "JOIN", "KEY", "LEFT", "LIMIT", "NOT",
+        "NULL", "ON", "OR", "ORDER", "PRIMARY", "SELECT", "SET", "TABLE", "UPDATE", "VALUES",
+        "WHERE", "WITH",
+    ],
+    line_comments: &["--"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['\'', '"'],
+    ignore_case: true,
+};
+
+/// Looks up the rules for a fenced block's info string (` ```rust `).
+/// Returns `None` for languages without rules.
+pub fn syntax_for(info: &str) -> Option<&'static Syntax> {
+    let lang = info
+        .split([' ', ',', '{'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let syntax = match lang.as_str() {
+        "rust" | "rs" => &RUST,
+        "python" | "py" => &PYTHON,
+        "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => &JAVASCRIPT,
+        "c" | "h" | "cpp" | "c++" | "cc" | "hpp" => &C,
+        "go" | "golang" => &GO,
+        "java" | "kotlin" => &JAVA,
+        "sh" | "bash" | "shell" | "zsh" => &SHELL,
+        "sql" | "sqlite" => &SQL,
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+pub fn highlight_code(code: &str, syntax: &Syntax) -> Vec<Line<'static>> {
+    let mut in_block_comment = false;
+    code.lines()
+        .map(|line| highlight_line(line, syntax, &mut in_block_comment))
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Plain,
+    Keyword,
+    String,
+    Number,
+    Comment,
+}
+
+impl Token {
+    fn style(self) -> Style {
+        match self {
+            Token::Plain => Style::default(),
+            Token::Keyword => Style::default()
+                .fg(Palette::ACCENT.tui())
+                .add_modifier(Modifier::BOLD),
+            Token::String => Style::default().fg(Palette::SUCCESS.tui()),
+            Token::Number => Style::default().fg(Palette::WARNING.tui()),
+            Token::Comment => Style::default().add_modifier(Modifier::DIM | Modifier::ITALIC),
+        }
+    }
+}
+
+fn highlight_line(line: &str, syntax: &Syntax, in_block_comment: &mut bool) -> Line<'static> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut push = |text: &str, token: Token| match spans.last_mut() {
+        Some(last) if last.style == token.style() => last.content.to_mut().push_str(text),
+        _ => spans.push(Span::styled(text.to_string(), token.style())),
+    };
+
+    let mut rest = line;
+    while let Some(ch) = rest.chars().next() {
+        if *in_block_comment {
+            let (_, close) = syntax.block_comment.unwrap_or_default();
+            let end = match rest.find(close) {
+                Some(index) => {
+                    *in_block_comment = false;
+                    index + close.len()
+                }
+                None => rest.len(),
+            };
+            push(&rest[..end], Token::Comment);
+            rest = &rest[end..];
+            continue;
+        }
+        if syntax
+            .line_comments
+            .iter()
+            .any(|marker| rest.starts_with(marker))
+        {
+            push(rest, Token::Comment);
+            break;
+        }
+        if let Some((open, _)) = syntax.block_comment
+            && rest.starts_with(open)
+        {
+            *in_block_comment = true;
+            push(open, Token::Comment);
+            rest = &rest[open.len()..];
+            continue;
+        }
+
+        let (end, token) = if syntax.quotes.contains(&ch) {
+            (string_end(rest, ch), Token::String)
+        } else if ch.is_ascii_digit() {
+            let end = word_end(rest, |c| c.is_alphanumeric() || c == '_' || c == '.');
+            (end, Token::Number)
+        } else if ch.is_alphabetic() || ch == '_' {
+            let end = word_end(rest, |c| c.is_alphanumeric() || c == '_');
+            let token = if is_keyword(syntax, &rest[..end]) {
+                Token::Keyword
+            } else {
+                Token::Plain
+            };
+            (end, token)
+        } else {
+            (ch.len_utf8(), Token::Plain)
+        };
+        push(&rest[..end], token);
+        rest = &rest[end..];
+    }
+    Line::from(spans)
+}
+
+/// Byte offset just past the closing quote, or the end of the line when the
+/// string is unterminated. Backslash escapes are skipped.
+fn string_end(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (index, ch) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            return index + ch.len_utf8();
+        }
+    }
+    text.len()
+}
+
+fn word_end(text: &str, in_word: impl Fn(char) -> bool) -> usize {
+    text.char_indices()
+        .find(|(_, ch)| !in_word(*ch))
+        .map(|(index, _)| index)
+        .unwrap_or(text.len())
+}
+
+fn is_keyword(syntax: &Syntax, word: &str) -> bool {
+    if syntax.ignore_case {
+        let word = word.to_ascii_uppercase();
+        syntax.keywords.contains(&word.as_str())
+    } else {
+        syntax.keywords.contains(&word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(line: &Line) -> Vec<(String, Style)> {
+        line.spans
+            .iter()
+            .map(|span| (span.content.to_string(), span.style))
+            .collect()
+    }
+
+    #[test]
+    fn highlights_keywords_strings_numbers_and_comments() {
+        let syntax = syntax_for("rust").unwrap();
+        let lines = highlight_code(
+            "let name = \"a \\\" b\"; // note\nx /* one\ntwo */ 42",
+            syntax,
+        );
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            tokens(&lines[0]),
+            vec![
+                ("let".into(), Token::Keyword.style()),
+                (" name = ".into(), Token::Plain.style()),
+                ("\"a \\\" b\"".into(), Token::String.style()),
+                ("; ".into(), Token::Plain.style()),
+                ("// note".into(), Token::Comment.style()),
+            ]
+        );
+        assert_eq!(
+            tokens(&lines[1]),
+            vec![
+                ("x ".into(), Token::Plain.style()),
+                ("/* one".into(), Token::Comment.style()),
+            ]
+        );
+        assert_eq!(
+            tokens(&lines[2]),
+            vec![
+                ("two */".into(), Token::Comment.style()),
+                (" ".into(), Token::Plain.style()),
+                ("42".into(), Token::Number.style()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sql_keywords_ignore_case_and_unknown_languages_have_no_rules() {
+        let syntax = syntax_for("SQL").unwrap();
+        let line = highlight_line("select id", syntax, &mut false);
+        assert_eq!(line.spans[0].style, Token::Keyword.style());
+        assert!(syntax_for("brainfuck").is_none());
+        assert!(syntax_for("").is_none());
+    }
+}
@@ -1,17 +1,37 @@
-use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span, Text};
 
+use super::highlight::{Syntax, highlight_code, syntax_for};
+
+/// Zero-width space: ratatui wraps at it like a space but draws nothing.
+const BREAK_OPPORTUNITY: char = '\u{200B}';
+/// Tokens at least this long (URLs, hashes, base64) get break opportunities.
+const LONG_TOKEN_CHARS: usize = 24;
+/// Longest stretch of a long token left without a break opportunity.
+const MAX_UNBROKEN_CHARS: usize = 16;
+
 pub fn render_markdown(md: &str) -> Text<'static> {
-    let parser = Parser::new_ext(md, Options::ENABLE_MATH | Options::ENABLE_TASKLISTS);
+    let parser = Parser::new_ext(
+        md,
+        Options::ENABLE_MATH | Options::ENABLE_TASKLISTS | Options::ENABLE_TABLES,
+    );
     let mut lines: Vec<Line> = Vec::new();
     let mut current_line: Vec<Span> = Vec::new();
     let mut styles = vec![Style::default()];
     let mut list_stack: Vec<ListKind> = Vec::new();
     let mut pending_prefix: Option<String> = None;
     let mut in_code_block = false;
+    // Code blocks in a known language are buffered and highlighted as a whole.
+    let mut highlighted_code: Option<(&'static Syntax, String)> = None;
+    let mut table: Option<Table> = None;
 
     for event in parser {
+        if let Some(table) = table.as_mut()
+            && table.push_event(&event)
+        {
+            continue;
+        }
         match event {
             Event::Start(tag) => match tag {
                 Tag::Heading { level, .. } => {
@@ -28,8 +48,13 @@ pub fn render_markdown(md: &str) -> Text<'static> {
                 Tag::Link { .. } => push_style(&mut styles, |style| {
                     style.add_modifier(Modifier::UNDERLINED)
                 }),
-                Tag::CodeBlock(_) => {
+                Tag::CodeBlock(kind) => {
                     flush_line(&mut lines, &mut current_line);
+                    if let CodeBlockKind::Fenced(info) = &kind
+                        && let Some(syntax) = syntax_for(info)
+                    {
+                        highlighted_code = Some((syntax, String::new()));
+                    }
                     in_code_block = true;
                     push_style(&mut styles, |_| {
                         Style::default().add_modifier(Modifier::DIM)
@@ -40,6 +65,10 @@ pub fn render_markdown(md: &str) -> Text<'static> {
                     flush_line(&mut lines, &mut current_line);
                     pending_prefix = Some(list_prefix(list_stack.as_mut_slice()));
                 }
+                Tag::Table(alignments) => {
+                    flush_line(&mut lines, &mut current_line);
+                    table = Some(Table::new(alignments));
+                }
                 _ => {}
             },
             Event::End(tag) => match tag {
@@ -56,6 +85,9 @@ pub fn render_markdown(md: &str) -> Text<'static> {
                 }
                 TagEnd::CodeBlock => {
                     flush_line(&mut lines, &mut current_line);
+                    if let Some((syntax, code)) = highlighted_code.take() {
+                        lines.extend(highlight_code(&code, syntax));
+                    }
                     lines.push(Line::default());
                     in_code_block = false;
                     pop_style(&mut styles);
@@ -74,8 +106,19 @@ pub fn render_markdown(md: &str) -> Text<'static> {
                     flush_line(&mut lines, &mut current_line);
                     pending_prefix = None;
                 }
+                TagEnd::Table => {
+                    if let Some(table) = table.take() {
+                        lines.extend(table.into_lines());
+                    }
+                    lines.push(Line::default());
+                }
                 _ => {}
             },
+            Event::Text(text) if highlighted_code.is_some() => {
+                if let Some((_, code)) = highlighted_code.as_mut() {
+                    code.push_str(&text);
+                }
+            }
             Event::Text(text) => {
                 let processed = if in_code_block {
                     text.to_string()
@@ -106,7 +149,7 @@ pub fn render_markdown(md: &str) -> Text<'static> {
                 &mut current_line,
                 &mut pending_prefix,
             ),
-            Event::InlineMath(math) | Event::DisplayMath(math) => {
+            Event::InlineMath(math) => {
                 let converted = latex_to_unicode_math(math.as_ref());
                 push_text(
                     &converted,
@@ -117,6 +160,19 @@ pub fn render_markdown(md: &str) -> Text<'static> {
                     &mut pending_prefix,
                 );
             }
+            Event::DisplayMath(math) => {
+                // display math gets centered lines of its own, between the surrounding text
+                flush_line(&mut lines, &mut current_line);
+                let style = current_style(&styles).add_modifier(Modifier::ITALIC);
+                let converted = latex_to_unicode_math(math.as_ref());
+                for row in converted
+                    .lines()
+                    .map(str::trim)
+                    .filter(|row| !row.is_empty())
+                {
+                    lines.push(Line::from(Span::styled(row.to_string(), style)).centered());
+                }
+            }
             Event::FootnoteReference(label) => {
                 let rendered = format!("[^{}]", label);
                 push_text(
@@ -161,6 +217,85 @@ pub fn render_markdown(md: &str) -> Text<'static> {
     Text::from(lines)
 }
 
+/// Lets wrapped paragraphs split long unbroken tokens, such as URLs or
+/// base64 blobs, after `/`, `?`, `&`, and similar separators, or every few
+/// characters when there are none, so they fill the panel instead of jumping
+/// to a line of their own and being cut wherever the width runs out.
+pub fn with_break_opportunities(text: Text<'static>) -> Text<'static> {
+    let lines = text
+        .lines
+        .into_iter()
+        .map(|line| {
+            let plain: Vec<char> = line
+                .spans
+                .iter()
+                .flat_map(|span| span.content.chars())
+                .collect();
+            let breaks = break_positions(&plain);
+            if breaks.is_empty() {
+                return line;
+            }
+            let mut idx = 0;
+            let spans: Vec<Span<'static>> = line
+                .spans
+                .into_iter()
+                .map(|span| {
+                    let mut content = String::with_capacity(span.content.len());
+                    for ch in span.content.chars() {
+                        content.push(ch);
+                        if breaks.binary_search(&idx).is_ok() {
+                            content.push(BREAK_OPPORTUNITY);
+                        }
+                        idx += 1;
+                    }
+                    Span::styled(content, span.style)
+                })
+                .collect();
+            Line {
+                spans,
+                style: line.style,
+                alignment: line.alignment,
+            }
+        })
+        .collect::<Vec<_>>();
+    Text {
+        lines,
+        style: text.style,
+        alignment: text.alignment,
+    }
+}
+
+/// Indexes of the characters in `chars` to put a break opportunity after.
+fn break_positions(chars: &[char]) -> Vec<usize> {
+    let mut breaks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        if chars[start].is_whitespace() {
+            start += 1;
+            continue;
+        }
+        let end = chars[start..]
+            .iter()
+            .position(|ch| ch.is_whitespace())
+            .map_or(chars.len(), |len| start + len);
+        if end - start >= LONG_TOKEN_CHARS {
+            let mut unbroken = 0;
+            // never after the last character, where the token ends anyway
+            for (idx, ch) in chars.iter().enumerate().take(end - 1).skip(start) {
+                unbroken += 1;
+                if matches!(ch, '/' | '?' | '&' | '=' | '#' | '-' | '_' | '.' | ',')
+                    || unbroken >= MAX_UNBROKEN_CHARS
+                {
+                    breaks.push(idx);
+                    unbroken = 0;
+                }
+            }
+        }
+        start = end;
+    }
+    breaks
+}
+
 fn push_text(
     text: &str,
     style: Style,
@@ -233,6 +368,104 @@ fn heading_style(level: HeadingLevel) -> Style {
     style
 }
 
+/// Cells of a GFM table, collected as plain text until the table ends so
+/// column widths can be measured.
+struct Table {
+    alignments: Vec<Alignment>,
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+    cell: String,
+}
+
+impl Table {
+    fn new(alignments: Vec<Alignment>) -> Self {
+        Table {
+            alignments,
+            header: None,
+            rows: Vec::new(),
+            row: Vec::new(),
+            cell: String::new(),
+        }
+    }
+
+    /// Consumes the events that make up the table's rows and cells. Returns
+    /// false for everything else, including the end of the table.
+    fn push_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Start(Tag::TableHead | Tag::TableRow) => self.row.clear(),
+            Event::Start(Tag::TableCell) => self.cell.clear(),
+            Event::End(TagEnd::TableCell) => {
+                let cell = std::mem::take(&mut self.cell);
+                self.row.push(cell.trim().to_string());
+            }
+            Event::End(TagEnd::TableHead) => self.header = Some(std::mem::take(&mut self.row)),
+            Event::End(TagEnd::TableRow) => self.rows.push(std::mem::take(&mut self.row)),
+            Event::Text(text) => self.cell.push_str(&latex_to_unicode_math(text)),
+            Event::Code(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                self.cell.push_str(text)
+            }
+            Event::InlineMath(math) | Event::DisplayMath(math) => {
+                self.cell.push_str(&latex_to_unicode_math(math))
+            }
+            Event::SoftBreak | Event::HardBreak => self.cell.push(' '),
+            Event::Start(_) | Event::End(_) => return false,
+            _ => {}
+        }
+        true
+    }
+
+    fn into_lines(self) -> Vec<Line<'static>> {
+        let columns = self.alignments.len();
+        let mut widths = vec![0; columns];
+        for row in self.header.iter().chain(&self.rows) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(Span::raw(cell.as_str()).width());
+            }
+        }
+
+        let border = Style::default().add_modifier(Modifier::DIM);
+        let mut lines = Vec::new();
+        if let Some(header) = &self.header {
+            let style = Style::default().add_modifier(Modifier::BOLD);
+            lines.push(self.row_line(header, &widths, style, border));
+            let separator: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+            lines.push(Line::from(Span::styled(separator.join("─┼─"), border)));
+        }
+        for row in &self.rows {
+            lines.push(self.row_line(row, &widths, Style::default(), border));
+        }
+        lines
+    }
+
+    fn row_line(
+        &self,
+        row: &[String],
+        widths: &[usize],
+        style: Style,
+        border: Style,
+    ) -> Line<'static> {
+        let mut spans = Vec::new();
+        for (column, width) in widths.iter().enumerate() {
+            if column > 0 {
+                spans.push(Span::styled(" │ ", border));
+            }
+            let cell = row.get(column).map(String::as_str).unwrap_or_default();
+            let padding = width.saturating_sub(Span::raw(cell).width());
+            let (left, right) = match self.alignments[column] {
+                Alignment::Right => (padding, 0),
+                Alignment::Center => (padding / 2, padding - padding / 2),
+                Alignment::None | Alignment::Left => (0, padding),
+            };
+            spans.push(Span::styled(
+                format!("{}{cell}{}", " ".repeat(left), " ".repeat(right)),
+                style,
+            ));
+        }
+        Line::from(spans)
+    }
+}
+
 #[derive(Debug)]
 enum ListKind {
     Unordered,
@@ -292,6 +525,8 @@ fn latex_to_unicode_math(input: &str) -> String {
                             out.push('\\');
                             out.push_str(&name);
                         }
+                    } else if name == "sqrt" {
+                        push_root(&mut out, &mut chars);
                     } else if name == "text" || name == "textbf" || name == "mathbf" {
                         if let Some(content) = read_group(&mut chars) {
                             out.push_str(&latex_to_unicode_math(&content));
@@ -320,10 +555,21 @@ fn latex_to_unicode_math(input: &str) -> String {
                     continue;
                 }
                 let (converted, fully_mapped) = convert_script_content(&script, kind);
-                if !fully_mapped {
+                if fully_mapped {
+                    out.push_str(&converted);
+                } else {
+                    // no script form for all of it, so keep the marker and
+                    // show the content at full size, nested scripts included
+                    let plain = latex_to_unicode_math(&script);
                     out.push(ch);
+                    if plain.chars().count() > 1 {
+                        out.push('(');
+                        out.push_str(&plain);
+                        out.push(')');
+                    } else {
+                        out.push_str(&plain);
+                    }
                 }
-                out.push_str(&converted);
             }
             _ => out.push(ch),
         }
@@ -332,7 +578,46 @@ fn latex_to_unicode_math(input: &str) -> String {
     out
 }
 
-#[derive(Copy, Clone)]
+/// Renders `\sqrt[n]{x}` as `√x`, `∛x`, or `ⁿ√x`, wrapping radicands longer
+/// than one character in parentheses so it's clear what's under the root.
+fn push_root<I>(out: &mut String, chars: &mut std::iter::Peekable<I>)
+where
+    I: Iterator<Item = char>,
+{
+    let index = if chars.peek() == Some(&'[') {
+        chars.next();
+        Some(
+            chars
+                .by_ref()
+                .take_while(|ch| *ch != ']')
+                .collect::<String>(),
+        )
+    } else {
+        None
+    };
+    match index.as_deref().map(str::trim) {
+        None | Some("" | "2") => out.push('√'),
+        Some("3") => out.push('∛'),
+        Some("4") => out.push('∜'),
+        Some(index) => {
+            out.push_str(&map_script_text(index, ScriptKind::Superscript).0);
+            out.push('√');
+        }
+    }
+    let Some(radicand) = read_group(chars) else {
+        return;
+    };
+    let radicand = latex_to_unicode_math(&radicand);
+    if radicand.chars().count() > 1 {
+        out.push('(');
+        out.push_str(&radicand);
+        out.push(')');
+    } else {
+        out.push_str(&radicand);
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
 enum ScriptKind {
     Superscript,
     Subscript,
@@ -381,7 +666,24 @@ fn convert_script_content(content: &str, kind: ScriptKind) -> (String, bool) {
                     out.push('\\');
                 }
             },
-            '^' | '_' => {}
+            '^' | '_' => {
+                let nested = read_script(&mut chars);
+                let nested_kind = if ch == '^' {
+                    ScriptKind::Superscript
+                } else {
+                    ScriptKind::Subscript
+                };
+                if nested_kind == kind {
+                    // there's no smaller size, so `e^{x^2}` keeps the inner script level
+                    let (mapped, ok) = convert_script_content(&nested, kind);
+                    fully_mapped &= ok;
+                    out.push_str(&mapped);
+                } else {
+                    fully_mapped = false;
+                }
+            }
+            // separators read fine at full size between script characters
+            ',' => out.push(ch),
             _ => {
                 if !push_mapped_char(&mut out, ch, kind) {
                     fully_mapped = false;
@@ -529,34 +831,97 @@ fn latex_command_to_unicode(command: &str) -> Option<&'static str> {
         "int" => Some("∫"),
         "infty" => Some("∞"),
         "sum" => Some("∑"),
+        "prod" => Some("∏"),
+        "oint" => Some("∮"),
         "times" => Some("×"),
+        "div" => Some("÷"),
         "cdot" => Some("·"),
+        "circ" => Some("∘"),
         "pm" => Some("±"),
-        "leq" => Some("≤"),
-        "geq" => Some("≥"),
-        "neq" => Some("≠"),
+        "mp" => Some("∓"),
+        "leq" | "le" => Some("≤"),
+        "geq" | "ge" => Some("≥"),
+        "neq" | "ne" => Some("≠"),
+        "ll" => Some("≪"),
+        "gg" => Some("≫"),
         "approx" => Some("≈"),
+        "sim" => Some("∼"),
+        "simeq" => Some("≃"),
+        "cong" => Some("≅"),
+        "propto" => Some("∝"),
         "to" | "rightarrow" => Some("→"),
-        "leftarrow" => Some("←"),
+        "leftarrow" | "gets" => Some("←"),
         "leftrightarrow" => Some("↔"),
+        "Rightarrow" | "implies" => Some("⇒"),
+        "Leftarrow" => Some("⇐"),
+        "Leftrightarrow" | "iff" => Some("⇔"),
+        "mapsto" => Some("↦"),
         "partial" => Some("∂"),
         "nabla" => Some("∇"),
-        "neg" => Some("¬"),
-        "land" => Some("∧"),
-        "lor" => Some("∨"),
+        "neg" | "lnot" => Some("¬"),
+        "land" | "wedge" => Some("∧"),
+        "lor" | "vee" => Some("∨"),
         "equiv" => Some("≡"),
+        "forall" => Some("∀"),
+        "exists" => Some("∃"),
+        "nexists" => Some("∄"),
+        "in" => Some("∈"),
+        "notin" => Some("∉"),
+        "ni" => Some("∋"),
+        "subset" => Some("⊂"),
+        "subseteq" => Some("⊆"),
+        "supset" => Some("⊃"),
+        "supseteq" => Some("⊇"),
+        "cup" => Some("∪"),
+        "cap" => Some("∩"),
+        "setminus" => Some("∖"),
+        "emptyset" | "varnothing" => Some("∅"),
+        "perp" => Some("⊥"),
+        "parallel" => Some("∥"),
+        "angle" => Some("∠"),
+        "ldots" | "dots" => Some("…"),
+        "cdots" => Some("⋯"),
+        "langle" => Some("⟨"),
+        "rangle" => Some("⟩"),
+        "ell" => Some("ℓ"),
+        "hbar" => Some("ℏ"),
         "alpha" => Some("α"),
         "beta" => Some("β"),
         "gamma" => Some("γ"),
         "delta" => Some("δ"),
         "epsilon" => Some("ε"),
+        "varepsilon" => Some("ε"),
+        "zeta" => Some("ζ"),
+        "eta" => Some("η"),
         "theta" => Some("θ"),
+        "vartheta" => Some("ϑ"),
+        "iota" => Some("ι"),
+        "kappa" => Some("κ"),
         "lambda" => Some("λ"),
         "mu" => Some("μ"),
+        "nu" => Some("ν"),
+        "xi" => Some("ξ"),
         "pi" => Some("π"),
+        "rho" => Some("ρ"),
         "sigma" => Some("σ"),
+        "tau" => Some("τ"),
+        "upsilon" => Some("υ"),
         "phi" => Some("φ"),
+        "varphi" => Some("φ"),
+        "chi" => Some("χ"),
+        "psi" => Some("ψ"),
         "omega" => Some("ω"),
+        "Gamma" => Some("Γ"),
+        "Delta" => Some("Δ"),
+        "Theta" => Some("Θ"),
+        "Lambda" => Some("Λ"),
+        "Xi" => Some("Ξ"),
+        "Pi" => Some("Π"),
+        "Sigma" => Some("Σ"),
+        "Upsilon" => Some("Υ"),
+        "Phi" => Some("Φ"),
+        "Psi" => Some("Ψ"),
+        "Omega" => Some("Ω"),
         "cos" => Some("cos"),
         "sin" => Some("sin"),
         "tan" => Some("tan"),
@@ -677,7 +1042,10 @@ fn subscript_char(value: char) -> Option<char> {
 mod tests {
     use super::latex_to_unicode_math;
     use super::render_markdown;
+    use super::with_break_opportunities;
     use proptest::prelude::*;
+    use ratatui::layout::Alignment;
+    use ratatui::style::Modifier;
     proptest! {
         #[test]
         fn test_markdown_render( content in "\\PC*") {
@@ -701,6 +1069,49 @@ mod tests {
         assert!(text.lines[3].spans.is_empty());
     }
 
+    #[test]
+    fn renders_tables_as_aligned_columns() {
+        let text = render_markdown("| Element | Z |\n|:--|--:|\n| H | 1 |\n| Neon | 10 |");
+        let rendered: Vec<String> = text.lines.iter().map(|line| line.to_string()).collect();
+
+        assert_eq!(
+            rendered,
+            vec![
+                "Element │  Z",
+                "────────┼───",
+                "H       │  1",
+                "Neon    │ 10",
+                "",
+            ]
+        );
+        assert!(
+            text.lines[0].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::BOLD)
+        );
+    }
+
+    #[test]
+    fn highlights_known_languages_and_dims_the_rest() {
+        let text = render_markdown("```rust\nfn main() {}\n```\n\n```\nfn main() {}\n```");
+
+        assert_eq!(text.lines[0].spans[0].content, "fn");
+        assert!(
+            text.lines[0].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::BOLD)
+        );
+        assert_eq!(text.lines[2].spans[0].content, "fn main() {}");
+        assert!(
+            text.lines[2].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::DIM)
+        );
+    }
+
     #[test]
     fn converts_latex_math_to_unicode() {
         let rendered = latex_to_unicode_math(r"\int_0^\infty e^{-x^2} dx");
@@ -713,12 +1124,97 @@ mod tests {
         assert_eq!(rendered, "-1⁄N∑ᵢ₌₁ⁿ yᵢ");
     }
 
+    #[test]
+    fn converts_greek_capitals_and_set_logic_operators() {
+        assert_eq!(
+            latex_to_unicode_math(r"\Sigma \Delta \Omega \Gamma \Pi"),
+            "Σ Δ Ω Γ Π"
+        );
+        assert_eq!(
+            latex_to_unicode_math(r"\forall x \in A \cup B, \exists y \notin A \cap B"),
+            "∀ x ∈ A ∪ B, ∃ y ∉ A ∩ B"
+        );
+        assert_eq!(
+            latex_to_unicode_math(r"A \subset B \subseteq C \implies \emptyset \mp 1"),
+            "A ⊂ B ⊆ C ⇒ ∅ ∓ 1"
+        );
+    }
+
+    #[test]
+    fn converts_nested_scripts_as_far_as_unicode_allows() {
+        assert_eq!(latex_to_unicode_math("2^{n+1}"), "2ⁿ⁺¹");
+        assert_eq!(latex_to_unicode_math("a_{i,j}"), "aᵢ,ⱼ");
+        assert_eq!(latex_to_unicode_math("x_{n_k}"), "xₙₖ");
+        // a subscript inside a superscript has no Unicode form
+        assert_eq!(latex_to_unicode_math("x^{a_i}"), "x^(aᵢ)");
+        assert_eq!(latex_to_unicode_math(r"e^{\alpha_{i,j}}"), "e^(αᵢ,ⱼ)");
+        assert_eq!(latex_to_unicode_math("x^{q+1}"), "x^(q+1)");
+        assert_eq!(latex_to_unicode_math("x^q"), "x^q");
+    }
+
+    #[test]
+    fn converts_square_and_nth_roots() {
+        assert_eq!(latex_to_unicode_math(r"\sqrt{x}"), "√x");
+        assert_eq!(latex_to_unicode_math(r"\sqrt 2"), "√2");
+        assert_eq!(
+            latex_to_unicode_math(r"\frac{-b \pm \sqrt{b^2 - 4ac}}{2a}"),
+            "-b ± √(b² - 4ac)⁄2a"
+        );
+        assert_eq!(latex_to_unicode_math(r"\sqrt[3]{8}"), "∛8");
+        assert_eq!(latex_to_unicode_math(r"\sqrt[n]{x}"), "ⁿ√x");
+    }
+
     #[test]
     fn renders_plain_text_math_to_unicode() {
         let text = render_markdown("x^2 + y_1");
         assert_eq!(text.lines[0].spans[0].content, "x² + y₁");
     }
 
+    #[test]
+    fn display_math_gets_its_own_centered_line() {
+        let text = render_markdown("Energy is $$E = mc^2$$ in joules, or $x^2$ inline.");
+        let rendered: Vec<String> = text.lines.iter().map(|line| line.to_string()).collect();
+
+        assert_eq!(
+            rendered,
+            vec!["Energy is ", "E = mc²", " in joules, or x² inline.", ""]
+        );
+        assert_eq!(text.lines[1].alignment, Some(Alignment::Center));
+        assert!(
+            text.lines[1].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::ITALIC)
+        );
+        assert_eq!(text.lines[2].alignment, None);
+    }
+
+    #[test]
+    fn long_tokens_get_break_opportunities_and_short_ones_do_not() {
+        let text = with_break_opportunities(render_markdown(
+            "See **https://example.com/docs?page=2** or `aGVsbG8gd29ybGQgaGVsbG8gd29ybGQ=` now",
+        ));
+        let line = &text.lines[0];
+        let rendered: Vec<&str> = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+
+        assert_eq!(rendered[0], "See ");
+        assert_eq!(
+            rendered[1],
+            "https:/\u{200B}/\u{200B}example.\u{200B}com/\u{200B}docs?\u{200B}page=\u{200B}2"
+        );
+        assert!(line.spans[1].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(rendered[3], "aGVsbG8gd29ybGQg\u{200B}aGVsbG8gd29ybGQ=");
+        assert_eq!(rendered[4], " now");
+        assert_eq!(
+            line.to_string().replace('\u{200B}', ""),
+            "See https://example.com/docs?page=2 or aGVsbG8gd29ybGQgaGVsbG8gd29ybGQ= now"
+        );
+    }
+
     #[test]
     fn renders_text_command_without_conversion() {
         let rendered = latex_to_unicode_math(r"\text{correctly predicted positives}");
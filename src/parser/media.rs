@@ -11,19 +11,67 @@ pub enum MediaKind {
     Video,
 }
 
+/// Where a media file lives: on disk, or at an `http(s)` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediaSource {
+    Local(PathBuf),
+    Remote(String),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Media {
     label: String,
-    path: PathBuf,
+    source: MediaSource,
     kind: MediaKind,
 }
 
 impl Media {
+    pub fn source(&self) -> &MediaSource {
+        &self.source
+    }
+
+    /// The file on disk, or `None` for remote media.
+    pub fn local_path(&self) -> Option<&Path> {
+        match &self.source {
+            MediaSource::Local(path) => Some(path),
+            MediaSource::Remote(_) => None,
+        }
+    }
+
+    /// The last segment of the path or URL, without any query string.
+    pub fn file_name(&self) -> Option<String> {
+        match &self.source {
+            MediaSource::Local(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            MediaSource::Remote(url) => url_path(url)
+                .rsplit('/')
+                .next()
+                .filter(|name| !name.is_empty())
+                .map(str::to_string),
+        }
+    }
+
+    pub fn kind(&self) -> &MediaKind {
+        &self.kind
+    }
+
+    /// The link text, `image` for images, or the file name for bare links.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
     pub fn play(&self) -> Result<()> {
-        if !self.path.is_file() || !self.path.exists() {
-            bail!("File does not exist: {}", self.path.display());
+        match &self.source {
+            MediaSource::Local(path) => {
+                if !path.is_file() {
+                    bail!("File does not exist: {}", path.display());
+                }
+                that(path)?;
+            }
+            // the browser or default handler deals with unreachable URLs
+            MediaSource::Remote(url) => that(url)?,
         }
-        that(&self.path)?;
         Ok(())
     }
 }
@@ -48,6 +96,16 @@ fn media_kind_from_path(path: &Path) -> Option<MediaKind> {
     }
 }
 
+fn is_remote(dest: &str) -> bool {
+    let lower = dest.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// The URL without its query string or fragment, where the extension is.
+fn url_path(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
 fn resolve_media_path(path: PathBuf, base_dir: Option<&Path>) -> PathBuf {
     if path.is_relative()
         && let Some(dir) = base_dir
@@ -57,12 +115,23 @@ fn resolve_media_path(path: PathBuf, base_dir: Option<&Path>) -> PathBuf {
     path
 }
 
+/// Where a link points and what kind of media it is, if it looks like media.
+fn media_source(dest: &str, base_dir: Option<&Path>) -> Option<(MediaSource, MediaKind)> {
+    if is_remote(dest) {
+        let kind = media_kind_from_path(Path::new(url_path(dest)))?;
+        return Some((MediaSource::Remote(dest.to_string()), kind));
+    }
+    let resolved_path = resolve_media_path(PathBuf::from(dest), base_dir);
+    let kind = media_kind_from_path(&resolved_path)?;
+    Some((MediaSource::Local(resolved_path), kind))
+}
+
 pub fn extract_media(markdown: &str, base_dir: Option<&Path>) -> Vec<Media> {
     let parser = Parser::new(markdown);
 
     let mut media = Vec::new();
 
-    let mut current_path: Option<PathBuf> = None;
+    let mut current_source: Option<MediaSource> = None;
     let mut current_kind: Option<MediaKind> = None;
     let mut current_label = String::new();
 
@@ -70,9 +139,8 @@ pub fn extract_media(markdown: &str, base_dir: Option<&Path>) -> Vec<Media> {
         match event {
             // [label](path)
             Event::Start(Tag::Link { dest_url, .. }) => {
-                let resolved_path = resolve_media_path(PathBuf::from(dest_url.as_ref()), base_dir);
-                if let Some(kind) = media_kind_from_path(&resolved_path) {
-                    current_path = Some(resolved_path);
+                if let Some((source, kind)) = media_source(&dest_url, base_dir) {
+                    current_source = Some(source);
                     current_kind = Some(kind);
                     current_label.clear();
                 }
@@ -80,36 +148,30 @@ pub fn extract_media(markdown: &str, base_dir: Option<&Path>) -> Vec<Media> {
 
             // ![alt](path)
             Event::Start(Tag::Image { dest_url, .. }) => {
-                let resolved_path = resolve_media_path(PathBuf::from(dest_url.as_ref()), base_dir);
-                if let Some(kind) = media_kind_from_path(&resolved_path) {
+                if let Some((source, kind)) = media_source(&dest_url, base_dir) {
                     media.push(Media {
                         label: "image".to_string(),
-                        path: resolved_path,
+                        source,
                         kind,
                     });
                 }
             }
 
-            Event::Text(text) => {
-                if current_path.is_some() {
-                    current_label.push_str(&text);
-                }
+            Event::Text(text) if current_source.is_some() => {
+                current_label.push_str(&text);
             }
 
             Event::End(TagEnd::Link) => {
-                if let (Some(path), Some(kind)) = (current_path.take(), current_kind.take()) {
-                    media.push(Media {
-                        label: if current_label.is_empty() {
-                            path.file_name()
-                                .and_then(|f| f.to_str())
-                                .unwrap_or("media")
-                                .to_string()
-                        } else {
-                            current_label.clone()
-                        },
-                        path,
+                if let (Some(source), Some(kind)) = (current_source.take(), current_kind.take()) {
+                    let mut found = Media {
+                        label: current_label.clone(),
+                        source,
                         kind,
-                    });
+                    };
+                    if found.label.is_empty() {
+                        found.label = found.file_name().unwrap_or_else(|| "media".to_string());
+                    }
+                    media.push(found);
                 }
                 current_label.clear();
             }
@@ -125,7 +187,7 @@ pub fn extract_media(markdown: &str, base_dir: Option<&Path>) -> Vec<Media> {
 mod tests {
     use std::path::{Path, PathBuf};
 
-    use crate::parser::{Media, MediaKind};
+    use crate::parser::{Media, MediaKind, MediaSource};
 
     use super::extract_media;
 
@@ -149,17 +211,17 @@ This is a normal link and should be ignored:
         let expected = vec![
             Media {
                 label: "image".to_string(),
-                path: PathBuf::from("media/dog.jpg"),
+                source: MediaSource::Local(PathBuf::from("media/dog.jpg")),
                 kind: MediaKind::Image,
             },
             Media {
                 label: "audio".to_string(),
-                path: PathBuf::from("media/dog.mp3"),
+                source: MediaSource::Local(PathBuf::from("media/dog.mp3")),
                 kind: MediaKind::Audio,
             },
             Media {
                 label: "video".to_string(),
-                path: PathBuf::from("media/dog.mp4"),
+                source: MediaSource::Local(PathBuf::from("media/dog.mp4")),
                 kind: MediaKind::Video,
             },
         ];
@@ -174,15 +236,43 @@ This is a normal link and should be ignored:
         let expected = vec![
             Media {
                 label: "image".to_string(),
-                path: PathBuf::from("notes/cards/media/dog.jpg"),
+                source: MediaSource::Local(PathBuf::from("notes/cards/media/dog.jpg")),
                 kind: MediaKind::Image,
             },
             Media {
                 label: "audio".to_string(),
-                path: PathBuf::from("notes/cards/../audio/bark.mp3"),
+                source: MediaSource::Local(PathBuf::from("notes/cards/../audio/bark.mp3")),
                 kind: MediaKind::Audio,
             },
         ];
         assert_eq!(medias, expected);
     }
+
+    #[test]
+    fn remote_urls_are_media_by_their_extension() {
+        let contents = "[say](https://cdn.example.com/audio/hola.mp3?token=abc)\n\
+            ![](HTTPS://example.com/pics/cat.png#top)\n\
+            [page](https://example.com/wiki)\n\
+            [site](https://example.com)";
+        let medias = extract_media(contents, Some(Path::new("notes")));
+        assert_eq!(
+            medias,
+            vec![
+                Media {
+                    label: "say".to_string(),
+                    source: MediaSource::Remote(
+                        "https://cdn.example.com/audio/hola.mp3?token=abc".to_string()
+                    ),
+                    kind: MediaKind::Audio,
+                },
+                Media {
+                    label: "image".to_string(),
+                    source: MediaSource::Remote("HTTPS://example.com/pics/cat.png#top".to_string()),
+                    kind: MediaKind::Image,
+                },
+            ]
+        );
+        assert_eq!(medias[0].local_path(), None);
+        assert_eq!(medias[0].file_name().as_deref(), Some("hola.mp3"));
+    }
 }
@@ -1,9 +1,14 @@
 pub mod hash;
+pub mod highlight;
 pub mod markdown;
 pub mod media;
 pub mod parse_from_file;
 
 pub use hash::get_hash;
-pub use markdown::render_markdown;
-pub use media::{Media, MediaKind, extract_media};
-pub use parse_from_file::{FileSearchStats, cards_from_md, content_to_card, register_all_cards};
+pub use markdown::{render_markdown, with_break_opportunities};
+pub use media::{Media, MediaKind, MediaSource, extract_media};
+pub use parse_from_file::{
+    CardSeparator, DuplicateCard, FileSearchStats, ScanOptions, SkippedFile, cards_from_md,
+    collect_all_cards, content_to_card, content_to_cards, forward_card_at, register_all_cards,
+    report_skipped_files,
+};
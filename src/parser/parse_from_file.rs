@@ -1,28 +1,379 @@
-use crate::cloze_utils::find_cloze_ranges;
+use crate::cloze_utils::cloze_ranges;
 use ignore::WalkBuilder;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
-use crate::card::{Card, CardContent, ClozeRange};
+use crate::card::{Card, CardContent, CardDirection};
+use crate::palette::Palette;
 use crate::parser::get_hash;
-use crate::utils::{is_markdown, trim_line};
+use crate::utils::{is_markdown, pluralize, trim_line};
 use ignore::WalkState;
-use std::collections::HashMap;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::sync::mpsc;
 
-use crate::crud::DB;
+use crate::crud::{DB, FILE_INDEX_VERSION, FileStamp, IndexedFile};
 
-use anyhow::{Result, anyhow, bail};
+use anyhow::{Context, Result, anyhow, bail};
+use clap::ValueEnum;
+
+/// Gitignore-style file listing decks that commands should skip.
+const IGNORE_FILENAME: &str = ".repeaterignore";
+
+/// Files with at least this many cards build and hash them across threads.
+/// Below it, handing the work to the thread pool costs more than it saves.
+const PARALLEL_CARD_THRESHOLD: usize = 64;
+/// Starts a line inside a card block that files the card under a deck.
+const DECK_DIRECTIVE: &str = "@deck:";
+
+/// Which files the deck walker visits.
+#[derive(Clone, Copy, Debug)]
+pub struct ScanOptions {
+    /// Descend into hidden files and directories (names starting with `.`).
+    pub hidden: bool,
+    /// Record decks that fail to parse in [`FileSearchStats::skipped_files`]
+    /// and keep scanning, instead of failing the whole scan.
+    pub skip_errors: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            hidden: true,
+            skip_errors: false,
+        }
+    }
+}
+
+/// What ends a card besides the start of the next one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum CardSeparator {
+    /// A `---` line.
+    #[default]
+    Rule,
+    /// A blank line, for notes that use `---` as a horizontal rule and keep
+    /// prose right after their cards.
+    BlankLine,
+}
+
+impl CardSeparator {
+    pub fn name(self) -> &'static str {
+        match self {
+            CardSeparator::Rule => "rule",
+            CardSeparator::BlankLine => "blank-line",
+        }
+    }
+}
 
 #[derive(Default, Clone, Debug)]
 pub struct FileSearchStats {
     pub files_searched: usize,
     pub markdown_files: usize,
+    /// Decks left out of a `skip_errors` scan, sorted by path.
+    pub skipped_files: Vec<SkippedFile>,
+    /// Cards written in more than one place, which all register as one card.
+    pub duplicate_cards: Vec<DuplicateCard>,
+}
+
+/// A card whose text appears more than once, so its copies share a hash,
+/// a schedule, and a review history.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateCard {
+    pub card_hash: String,
+    /// Where each copy starts, as path and 1-based line, sorted.
+    pub locations: Vec<(PathBuf, usize)>,
+}
+
+/// A deck that failed to parse, and why.
+#[derive(Clone, Debug)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Warns on stderr about the decks a `skip_errors` scan left out.
+pub fn report_skipped_files(stats: &FileSearchStats) {
+    if stats.skipped_files.is_empty() {
+        return;
+    }
+    eprintln!(
+        "{}",
+        Palette::paint(
+            Palette::WARNING,
+            format!(
+                "Skipped {} that failed to parse:",
+                pluralize("file", stats.skipped_files.len())
+            )
+        )
+    );
+    for skipped in &stats.skipped_files {
+        eprintln!(
+            "  {}: {}",
+            Palette::paint(Palette::ACCENT, skipped.path.display()),
+            skipped.error
+        );
+    }
+}
+
+/// Collects inline `#tag` tokens. Markdown headings (`# Title`), anchors
+/// inside words (`page#section`), and inline code (`` `#include` ``) are not
+/// tags.
+fn collect_tags(line: &str, tags: &mut Vec<String>) {
+    // every other piece between backticks is a code span
+    let outside_code = line.split('`').step_by(2);
+    for tag in outside_code
+        .flat_map(str::split_whitespace)
+        .filter_map(word_tag)
+    {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+}
+
+/// The tag a word starts with, if any: `#verbs,` gives `verbs`. Letters and
+/// digits from any script count, but a bare number (`#1`) is not a tag.
+fn word_tag(word: &str) -> Option<&str> {
+    let rest = word.strip_prefix('#')?;
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '/')))
+        .unwrap_or(rest.len());
+    let tag = rest[..end].trim_end_matches(['-', '_', '/']);
+    let named = tag.starts_with(char::is_alphanumeric) && tag.contains(char::is_alphabetic);
+    named.then_some(tag)
 }
 
-fn parse_card_lines(contents: &str) -> (Option<String>, Option<String>, Option<String>) {
+/// Whether a line holds nothing but tags, like `#spanish #verbs`. Such lines
+/// label the card without being part of its question or answer.
+fn is_tag_line(line: &str) -> bool {
+    line.split_whitespace()
+        .all(|word| word_tag(word).is_some_and(|tag| tag.len() + 1 == word.len()))
+}
+
+/// Per-file defaults declared in a YAML block between `---` lines at the very
+/// top of a deck.
+#[derive(Debug, Default, PartialEq)]
+struct Frontmatter {
+    tags: Vec<String>,
+    deck: Option<String>,
+    suspended: bool,
+}
+
+impl Frontmatter {
+    fn apply(&self, card: &mut Card) {
+        for tag in &self.tags {
+            let tag = tag.trim().trim_start_matches('#');
+            if !tag.is_empty() && !card.has_tag(tag) {
+                card.tags.push(tag.to_string());
+            }
+        }
+        // a card's own `@deck:` line wins over the file's deck
+        if card.deck.is_none()
+            && let Some(deck) = self.deck.as_deref().and_then(trim_line)
+        {
+            card.deck = Some(deck.to_string());
+        }
+        card.suspended_by_default |= self.suspended;
+    }
+}
+
+/// Splits a leading frontmatter block off `contents`, returning it with the
+/// number of lines it spans, fences included. A block holding card lines, or
+/// YAML that isn't a mapping, is just the card separator followed by cards or
+/// notes, so it is left in place.
+fn split_frontmatter(contents: &str) -> Result<Option<(Frontmatter, usize)>> {
+    let mut lines = contents.lines();
+    if lines.next().map(str::trim_end) != Some("---") {
+        return Ok(None);
+    }
+    let mut yaml = String::new();
+    for (idx, line) in lines.enumerate() {
+        if line.trim_end() == "---" {
+            return parse_frontmatter(&yaml)
+                .map(|frontmatter| frontmatter.map(|frontmatter| (frontmatter, idx + 2)));
+        }
+        let trimmed = line.trim_start();
+        let card_line = ["Q:", "T:", "A:", "C:"]
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix))
+            || split_single_line(line).is_some();
+        if card_line {
+            return Ok(None);
+        }
+        yaml.push_str(line);
+        yaml.push('\n');
+    }
+    Ok(None)
+}
+
+/// Reads the few YAML forms frontmatter needs: `key: value` lines, with
+/// `tags` as one tag, a `[a, b]` list, or `- a` items on the lines below.
+/// Other keys are ignored, along with anything nested under them. Returns
+/// `None` when the block isn't a mapping, e.g. a line of notes.
+fn parse_frontmatter(yaml: &str) -> Result<Option<Frontmatter>> {
+    // each key with its inline value and any lines nested under it
+    let mut entries: Vec<(&str, &str, Vec<&str>)> = Vec::new();
+    for line in yaml.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) || trimmed == "-" || trimmed.starts_with("- ") {
+            let Some((_, _, nested)) = entries.last_mut() else {
+                return Ok(None);
+            };
+            nested.push(trimmed);
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return Ok(None);
+        };
+        let is_key = !key.is_empty()
+            && key
+                .chars()
+                .all(|ch| ch.is_alphanumeric() || matches!(ch, ' ' | '_' | '-'));
+        if !is_key || !(value.is_empty() || value.starts_with(char::is_whitespace)) {
+            return Ok(None);
+        }
+        entries.push((key.trim_end(), strip_comment(value), Vec::new()));
+    }
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    let mut frontmatter = Frontmatter::default();
+    for (key, value, nested) in entries {
+        match key {
+            "tags" => frontmatter.tags = frontmatter_tags(value, &nested)?,
+            "deck" if nested.is_empty() => {
+                frontmatter.deck = (!value.is_empty()).then(|| unquote(value).to_string());
+            }
+            "suspended" if nested.is_empty() => {
+                frontmatter.suspended = match value {
+                    "true" | "True" | "TRUE" => true,
+                    "false" | "False" | "FALSE" => false,
+                    _ => bail!("Invalid frontmatter: `suspended` must be true or false"),
+                };
+            }
+            "deck" | "suspended" => bail!("Invalid frontmatter: `{key}` takes a single value"),
+            _ => {}
+        }
+    }
+    Ok(Some(frontmatter))
+}
+
+/// `tags: spanish`, `tags: [spanish, verbs]`, or a `- spanish` item per line.
+fn frontmatter_tags(value: &str, nested: &[&str]) -> Result<Vec<String>> {
+    let invalid = || anyhow!("Invalid frontmatter: `tags` must be a tag or a list of tags");
+    let items: Vec<&str> = if value.is_empty() {
+        nested
+            .iter()
+            .map(|line| line.strip_prefix('-').map(strip_comment))
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?
+    } else if !nested.is_empty() {
+        return Err(invalid());
+    } else if let Some(list) = value.strip_prefix('[') {
+        list.strip_suffix(']')
+            .ok_or_else(invalid)?
+            .split(',')
+            .map(str::trim)
+            .collect()
+    } else {
+        vec![value]
+    };
+    items
+        .into_iter()
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            if item.starts_with(['[', ']', '{', '}']) {
+                return Err(invalid());
+            }
+            Ok(unquote(item).to_string())
+        })
+        .collect()
+}
+
+/// Drops a trailing `# comment`, keeping any `#` inside a quoted value.
+fn strip_comment(value: &str) -> &str {
+    let value = value.trim();
+    if let Some(quote) = value.chars().next().filter(|ch| matches!(ch, '"' | '\''))
+        && let Some(end) = value[1..].find(quote)
+    {
+        return &value[..end + 2];
+    }
+    match value.find(" #") {
+        Some(idx) => value[..idx].trim_end(),
+        None => value,
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value)
+}
+
+#[derive(Default, Debug)]
+struct CardLines {
+    question: Option<String>,
+    answer: Option<String>,
+    cloze: Option<String>,
+    tags: Vec<String>,
+    deck: Option<String>,
+    bidirectional: bool,
+    /// The question came from a `T:` line, so its answer is typed.
+    typed: bool,
+}
+
+/// The deck named by an `@deck: Biology` line.
+fn deck_directive(line: &str) -> Option<&str> {
+    line.trim_start().strip_prefix(DECK_DIRECTIVE)
+}
+
+/// `contents` without its `@deck:` lines, so filing a card under a deck
+/// keeps its hash and review history.
+fn without_directives(contents: &str) -> String {
+    contents
+        .split_inclusive('\n')
+        .filter(|line| deck_directive(line).is_none())
+        .collect()
+}
+
+/// Splits a RemNote-style `left::right` line, also reporting whether it used
+/// `:::`. A `::` inside a URL (`http://[::1]/`) is not a separator.
+fn split_single_line(line: &str) -> Option<(&str, &str, bool)> {
+    let url_spans: Vec<Range<usize>> = line
+        .match_indices("://")
+        .map(|(idx, _)| {
+            let start = line[..idx]
+                .char_indices()
+                .rev()
+                .find(|(_, c)| c.is_whitespace())
+                .map_or(0, |(pos, c)| pos + c.len_utf8());
+            let end = line[idx..]
+                .find(char::is_whitespace)
+                .map_or(line.len(), |pos| idx + pos);
+            start..end
+        })
+        .collect();
+    let find = |pattern: &str| {
+        line.match_indices(pattern)
+            .map(|(idx, _)| idx)
+            .find(|idx| !url_spans.iter().any(|span| span.contains(idx)))
+    };
+
+    if let Some(idx) = find(":::") {
+        return Some((&line[..idx], &line[idx + 3..], true));
+    }
+    find("::").map(|idx| (&line[..idx], &line[idx + 2..], false))
+}
+
+fn parse_card_lines(contents: &str, separator: CardSeparator) -> CardLines {
     #[derive(Copy, Clone)]
     enum Section {
         Question,
@@ -34,8 +385,14 @@ fn parse_card_lines(contents: &str) -> (Option<String>, Option<String>, Option<S
     let mut question_lines: Vec<&str> = Vec::new();
     let mut answer_lines: Vec<&str> = Vec::new();
     let mut cloze_lines: Vec<&str> = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    let mut deck: Option<String> = None;
+    let mut bidirectional = false;
+    let mut typed = false;
 
     let mut section = Section::None;
+    // the marker of the open fenced code block, whose lines hold no tags
+    let mut fence: Option<&str> = None;
 
     for raw_line in contents.lines() {
         let trimmed = trim_line(raw_line);
@@ -51,16 +408,50 @@ fn parse_card_lines(contents: &str) -> (Option<String>, Option<String>, Option<S
         }
 
         let line = trimmed.unwrap();
-        if line == "---" {
-            return (
-                join_nonempty(question_lines),
-                join_nonempty(answer_lines),
-                join_nonempty(cloze_lines),
-            );
+        if let Some(name) = deck_directive(line) {
+            deck = trim_line(name).map(str::to_string);
+            continue;
+        }
+        let fence_marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| line.starts_with(marker));
+        let in_code = match (fence, fence_marker) {
+            (None, Some(marker)) => {
+                fence = Some(marker);
+                true
+            }
+            (Some(open), Some(marker)) if open == marker => {
+                fence = None;
+                true
+            }
+            (open, _) => open.is_some(),
+        };
+        if !in_code {
+            collect_tags(line, &mut tags);
+            if is_tag_line(line) {
+                continue;
+            }
+        }
+        // in blank-line mode `---` is a horizontal rule and stays in the card
+        if separator == CardSeparator::Rule && line == "---" {
+            return CardLines {
+                question: join_nonempty(question_lines),
+                answer: join_nonempty(answer_lines),
+                cloze: join_nonempty(cloze_lines),
+                tags,
+                deck,
+                bidirectional,
+                typed,
+            };
         }
 
-        if let Some(rest) = line.strip_prefix("Q:") {
+        if let Some((rest, is_typed)) = line
+            .strip_prefix("Q:")
+            .map(|rest| (rest, false))
+            .or_else(|| line.strip_prefix("T:").map(|rest| (rest, true)))
+        {
             section = Section::Question;
+            typed = is_typed;
             question_lines.clear();
             if let Some(v) = trim_line(rest) {
                 question_lines.push(v);
@@ -86,7 +477,12 @@ fn parse_card_lines(contents: &str) -> (Option<String>, Option<String>, Option<S
             continue;
         }
 
-        if let Some((left, right)) = line.split_once("::") {
+        // Inside a Q/A/C block, `::` is just text (`std::vector`)
+        if matches!(section, Section::None)
+            && let Some((left, right, both_ways)) = split_single_line(line)
+        {
+            // `:::` marks a single-line card that should be drilled both ways
+            bidirectional = both_ways;
             if let Some(left) = trim_line(left)
                 && let Some(right) = trim_line(right)
             {
@@ -129,11 +525,15 @@ fn parse_card_lines(contents: &str) -> (Option<String>, Option<String>, Option<S
         }
     }
 
-    (
-        join_nonempty(question_lines),
-        join_nonempty(answer_lines),
-        join_nonempty(cloze_lines),
-    )
+    CardLines {
+        question: join_nonempty(question_lines),
+        answer: join_nonempty(answer_lines),
+        cloze: join_nonempty(cloze_lines),
+        tags,
+        deck,
+        bidirectional,
+        typed,
+    }
 }
 pub fn content_to_card(
     card_path: &Path,
@@ -141,95 +541,194 @@ pub fn content_to_card(
     file_start_idx: usize,
     file_end_idx: usize,
 ) -> Result<Card> {
-    let (question, answer, cloze) = parse_card_lines(contents);
-
-    let card_hash = get_hash(contents).ok_or_else(|| anyhow!("Unable to hash contents"))?;
-    if let (Some(q), Some(a)) = (question, answer) {
-        let content = CardContent::Basic {
-            question: q,
-            answer: a,
-        };
+    block_to_card(
+        card_path,
+        contents,
+        file_start_idx,
+        file_end_idx,
+        CardSeparator::Rule,
+    )
+}
 
-        Ok(Card::new(
-            card_path.to_path_buf(),
-            (file_start_idx, file_end_idx),
-            content,
-            card_hash,
-        ))
+fn block_to_card(
+    card_path: &Path,
+    contents: &str,
+    file_start_idx: usize,
+    file_end_idx: usize,
+    separator: CardSeparator,
+) -> Result<Card> {
+    let CardLines {
+        question,
+        answer,
+        cloze,
+        tags,
+        deck,
+        typed,
+        ..
+    } = parse_card_lines(contents, separator);
+
+    let card_hash = get_hash(&without_directives(contents))
+        .ok_or_else(|| anyhow!("Unable to hash contents"))?;
+    let content = if let (Some(q), Some(a)) = (question, answer) {
+        if typed {
+            CardContent::TypeAnswer {
+                question: q,
+                answer: a,
+            }
+        } else {
+            CardContent::Basic {
+                question: q,
+                answer: a,
+            }
+        }
     } else if let Some(c) = cloze {
-        let cloze_idxs = find_cloze_ranges(&c);
-        let cloze_range: Option<ClozeRange> = cloze_idxs
-            .first()
-            .map(|(start, end)| ClozeRange::new(*start, *end))
-            .transpose()?;
-
-        let content = CardContent::Cloze {
+        CardContent::Cloze {
+            cloze_ranges: cloze_ranges(&c)?,
             text: c,
-            cloze_range,
-        };
-        Ok(Card::new(
-            card_path.to_path_buf(),
-            (file_start_idx, file_end_idx),
-            content,
-            card_hash,
-        ))
+        }
     } else {
         bail!("Unable to parse anything from card contents:\n{}", contents);
-    }
+    };
+    let mut card = Card::new(
+        card_path.to_path_buf(),
+        (file_start_idx, file_end_idx),
+        content,
+        card_hash,
+    )
+    .with_tags(tags);
+    card.deck = deck;
+    Ok(card)
 }
 
-pub fn cards_from_md(path: &Path) -> Result<Vec<Card>> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
+/// Like [`content_to_card`], but also emits the reverse card for basic cards
+/// marked as bidirectional with `:::`.
+pub fn content_to_cards(
+    card_path: &Path,
+    contents: &str,
+    file_start_idx: usize,
+    file_end_idx: usize,
+) -> Result<Vec<Card>> {
+    block_to_cards(
+        card_path,
+        contents,
+        file_start_idx,
+        file_end_idx,
+        CardSeparator::Rule,
+    )
+}
 
-    let mut cards = Vec::new();
+fn block_to_cards(
+    card_path: &Path,
+    contents: &str,
+    file_start_idx: usize,
+    file_end_idx: usize,
+    separator: CardSeparator,
+) -> Result<Vec<Card>> {
+    let card = block_to_card(card_path, contents, file_start_idx, file_end_idx, separator)?;
+    let reverse = if parse_card_lines(contents, separator).bidirectional {
+        card.reversed()
+    } else {
+        None
+    };
+    Ok(std::iter::once(card).chain(reverse).collect())
+}
+
+pub fn cards_from_md(path: &Path, separator: CardSeparator) -> Result<Vec<Card>> {
+    let contents = fs::read_to_string(path)?;
+    let (frontmatter, body_start) = match split_frontmatter(&contents)
+        .with_context(|| format!("Failed to read the frontmatter of {}", path.display()))?
+    {
+        Some((frontmatter, lines)) => (Some(frontmatter), lines),
+        None => (None, 0),
+    };
+
+    // (text, first line, end line) of each card, in file order
+    let mut blocks: Vec<(String, usize, usize)> = Vec::new();
     let mut track_buffer = false;
     let mut buffer = String::new();
-    let mut line = String::new();
-    let mut start_idx = 0;
-    let mut last_idx = 0;
-    let mut line_idx = 0;
-
-    loop {
-        line.clear();
-        let bytes_read = reader.read_line(&mut line)?;
-        if bytes_read == 0 {
-            break;
-        }
-
-        if line.starts_with("Q:") || line.starts_with("C:") {
+    let mut start_idx = body_start;
+    let mut last_idx = body_start;
+    let mut in_answer = false;
+    let mut after_blank = false;
+
+    for (line_idx, line) in contents.split_inclusive('\n').enumerate().skip(body_start) {
+        if ["Q:", "T:", "C:"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+        {
             track_buffer = true;
+            in_answer = false;
             if trim_line(&buffer).is_some() {
-                cards.push(content_to_card(path, &buffer, start_idx, line_idx)?);
-                buffer.clear();
+                blocks.push((std::mem::take(&mut buffer), start_idx, line_idx));
             }
             start_idx = line_idx;
         }
-        if line.contains("::") {
-            cards.push(content_to_card(path, &buffer, start_idx, line_idx)?);
+        if line.starts_with("A:") {
+            in_answer = true;
+        }
+        // Answers may hold `::` (`std::string`); anywhere else, or after a
+        // blank line, a `::` line closes the open block
+        let single_line = split_single_line(line).is_some();
+        if track_buffer && single_line && (!in_answer || after_blank) {
+            if trim_line(&buffer).is_some() {
+                blocks.push((std::mem::take(&mut buffer), start_idx, line_idx));
+            }
             buffer.clear();
             track_buffer = false;
-            cards.push(content_to_card(path, &line, line_idx, line_idx)?);
         }
-        if line.starts_with("---") && trim_line(&buffer).is_some() {
-            cards.push(content_to_card(path, &buffer, start_idx, line_idx)?);
-            buffer.clear();
+        if !track_buffer && single_line {
+            blocks.push((line.to_string(), line_idx, line_idx));
+        }
+        after_blank = trim_line(line).is_none();
+        let card_ends = match separator {
+            CardSeparator::Rule => line.starts_with("---"),
+            CardSeparator::BlankLine => trim_line(line).is_none(),
+        };
+        if card_ends && trim_line(&buffer).is_some() {
+            blocks.push((std::mem::take(&mut buffer), start_idx, line_idx));
             track_buffer = false;
         }
         if track_buffer {
-            buffer.push_str(&line);
+            buffer.push_str(line);
         }
         last_idx = line_idx;
-        line_idx += 1;
     }
     if !buffer.is_empty() {
-        cards.push(content_to_card(path, &buffer, start_idx, last_idx + 1)?);
+        blocks.push((buffer, start_idx, last_idx + 1));
     }
 
+    let build = |(text, start, end): &(String, usize, usize)| {
+        block_to_cards(path, text, *start, *end, separator)
+    };
+    // rayon's collect keeps the blocks' order, so line ranges stay deterministic
+    let per_block: Vec<Vec<Card>> = if blocks.len() >= PARALLEL_CARD_THRESHOLD {
+        blocks.par_iter().map(build).collect::<Result<_>>()?
+    } else {
+        blocks.iter().map(build).collect::<Result<_>>()?
+    };
+    let mut cards: Vec<Card> = per_block.into_iter().flatten().collect();
+
+    if let Some(frontmatter) = frontmatter {
+        for card in &mut cards {
+            frontmatter.apply(card);
+        }
+    }
     Ok(cards)
 }
 
-fn markdown_walk_builder(paths: &[PathBuf]) -> Result<Option<WalkBuilder>> {
+/// The forward card whose block starts on line `start` of `path`, as the file
+/// reads now.
+pub fn forward_card_at(
+    path: &Path,
+    start: usize,
+    separator: CardSeparator,
+) -> Result<Option<Card>> {
+    Ok(cards_from_md(path, separator)?
+        .into_iter()
+        .find(|card| card.direction == CardDirection::Forward && card.file_card_range.0 == start))
+}
+
+fn markdown_walk_builder(paths: &[PathBuf], scan: ScanOptions) -> Result<Option<WalkBuilder>> {
     let mut iter = paths.iter();
     let Some(first) = iter.next() else {
         return Ok(None);
@@ -238,25 +737,42 @@ fn markdown_walk_builder(paths: &[PathBuf]) -> Result<Option<WalkBuilder>> {
     for path in iter {
         builder.add(path);
     }
-    builder.hidden(false).git_ignore(true).git_exclude(true);
+    builder
+        .hidden(!scan.hidden)
+        .git_ignore(true)
+        .git_exclude(true)
+        .add_custom_ignore_filename(IGNORE_FILENAME);
     Ok(Some(builder))
 }
 
+/// The cards found in one deck file. `parsed` holds the file's stamp when it
+/// was parsed during this scan instead of taken from the file index.
+struct ScannedDeck {
+    path: PathBuf,
+    cards: Vec<Card>,
+    parsed: Option<FileStamp>,
+}
+
 fn run_card_walker(
     paths: Vec<PathBuf>,
-    sender: mpsc::UnboundedSender<Vec<Card>>,
+    scan: ScanOptions,
+    separator: CardSeparator,
+    index: HashMap<PathBuf, IndexedFile>,
+    sender: mpsc::UnboundedSender<ScannedDeck>,
 ) -> Result<FileSearchStats> {
-    let Some(builder) = markdown_walk_builder(&paths)? else {
+    let Some(builder) = markdown_walk_builder(&paths, scan)? else {
         return Ok(FileSearchStats::default());
     };
 
     let error_slot = Arc::new(Mutex::new(None));
     let stats = Arc::new(Mutex::new(FileSearchStats::default()));
+    let index = Arc::new(index);
 
     builder.build_parallel().run(|| {
         let sender = sender.clone();
         let error_slot = Arc::clone(&error_slot);
         let stats = Arc::clone(&stats);
+        let index = Arc::clone(&index);
         Box::new(move |entry| match entry {
             Ok(entry) => {
                 if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
@@ -276,15 +792,41 @@ fn run_card_walker(
                     return WalkState::Continue;
                 }
                 let path = entry.path().to_path_buf();
-                match cards_from_md(&path) {
+                let stamp = entry
+                    .metadata()
+                    .ok()
+                    .and_then(|metadata| FileStamp::of(&metadata));
+                if let Some(stamp) = stamp
+                    && let Some(indexed) = index.get(&path)
+                    && indexed.is_current(stamp, separator)
+                {
+                    let deck = ScannedDeck {
+                        path,
+                        cards: indexed.cards.clone(),
+                        parsed: None,
+                    };
+                    if sender.send(deck).is_err() {
+                        return WalkState::Quit;
+                    }
+                    return WalkState::Continue;
+                }
+                match cards_from_md(&path, separator) {
                     Ok(cards) => {
-                        if cards.is_empty() {
-                            return WalkState::Continue;
-                        }
-                        if sender.send(cards).is_err() {
+                        let deck = ScannedDeck {
+                            path,
+                            cards,
+                            parsed: stamp,
+                        };
+                        if sender.send(deck).is_err() {
                             return WalkState::Quit;
                         }
                     }
+                    Err(err) if scan.skip_errors => {
+                        stats.lock().unwrap().skipped_files.push(SkippedFile {
+                            path,
+                            error: format!("{err:#}"),
+                        });
+                    }
                     Err(err) => {
                         *error_slot.lock().unwrap() =
                             Some(err.context(format!("Failed to parse {}", path.display())));
@@ -305,49 +847,129 @@ fn run_card_walker(
     if let Some(err) = error_slot.lock().unwrap().take() {
         return Err(err);
     }
-    let stats = match Arc::try_unwrap(stats) {
+    let mut stats = match Arc::try_unwrap(stats) {
         Ok(mutex) => mutex.into_inner().unwrap(),
         Err(arc) => arc.lock().unwrap().clone(),
     };
+    stats.skipped_files.sort_by(|a, b| a.path.cmp(&b.path));
     Ok(stats)
 }
 
+/// The hashes seen in more than one place, ordered by where they first appear.
+fn duplicate_cards(locations: HashMap<String, Vec<(PathBuf, usize)>>) -> Vec<DuplicateCard> {
+    let mut duplicates: Vec<DuplicateCard> = locations
+        .into_iter()
+        .filter_map(|(card_hash, mut locations)| {
+            // overlapping roots visit the same file more than once
+            locations.sort();
+            locations.dedup();
+            (locations.len() > 1).then_some(DuplicateCard {
+                card_hash,
+                locations,
+            })
+        })
+        .collect();
+    duplicates.sort_by(|a, b| a.locations.cmp(&b.locations));
+    duplicates
+}
+
+/// Parses every card under `paths` without touching the database.
+pub async fn collect_all_cards(
+    paths: Vec<PathBuf>,
+    scan: ScanOptions,
+    separator: CardSeparator,
+) -> Result<Vec<Card>> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<ScannedDeck>();
+    let walker_handle = tokio::task::spawn_blocking(move || {
+        run_card_walker(paths, scan, separator, HashMap::new(), tx)
+    });
+
+    let mut cards = Vec::new();
+    while let Some(deck) = rx.recv().await {
+        cards.extend(deck.cards);
+    }
+    walker_handle.await??;
+
+    Ok(cards)
+}
+
+/// Parses and registers every card under `paths`. Files unchanged since the
+/// last scan are taken from the file index, skipping both steps.
 pub async fn register_all_cards(
     db: &DB,
     paths: Vec<PathBuf>,
+    scan: ScanOptions,
 ) -> Result<(HashMap<String, Card>, FileSearchStats)> {
-    let (tx, mut rx) = mpsc::unbounded_channel::<Vec<Card>>();
-    let walker_handle = tokio::task::spawn_blocking(move || run_card_walker(paths, tx));
+    let started_at = Instant::now();
+    let separator = db.get_settings().await?.card_separator;
+    let index = db.file_index().await?;
+    let roots = paths.clone();
+    let (tx, mut rx) = mpsc::unbounded_channel::<ScannedDeck>();
+    let walker_handle =
+        tokio::task::spawn_blocking(move || run_card_walker(paths, scan, separator, index, tx));
 
     let mut hash_cards = HashMap::new();
-    while let Some(batch) = rx.recv().await {
-        if batch.is_empty() {
-            continue;
+    let mut locations: HashMap<String, Vec<(PathBuf, usize)>> = HashMap::new();
+    let mut parsed = Vec::new();
+    let mut seen = HashSet::new();
+    while let Some(deck) = rx.recv().await {
+        seen.insert(deck.path.clone());
+        if let Some(stamp) = deck.parsed {
+            if !deck.cards.is_empty() {
+                db.add_cards_batch(&deck.cards).await?;
+            }
+            let indexed = IndexedFile {
+                version: FILE_INDEX_VERSION,
+                stamp,
+                card_separator: separator,
+                cards: deck.cards.clone(),
+            };
+            parsed.push((deck.path, indexed));
         }
-        db.add_cards_batch(&batch).await?;
-        for card in batch {
+        for card in deck.cards {
+            locations
+                .entry(card.card_hash.clone())
+                .or_default()
+                .push((card.file_path.clone(), card.file_card_range.0 + 1));
             hash_cards.insert(card.card_hash.clone(), card);
         }
     }
 
-    let stats = walker_handle.await??;
+    let mut stats = walker_handle.await??;
+    stats.duplicate_cards = duplicate_cards(locations);
+    db.index_files(&parsed).await?;
+    db.prune_file_index(&roots, &seen).await?;
+    tracing::debug!(
+        cards = hash_cards.len(),
+        markdown_files = stats.markdown_files,
+        parsed_files = parsed.len(),
+        elapsed_ms = started_at.elapsed().as_millis() as u64,
+        "Registered cards"
+    );
 
     Ok((hash_cards, stats))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{cards_from_md, content_to_card, parse_card_lines, register_all_cards};
-    use crate::card::CardContent;
+    use super::{
+        CardSeparator, Frontmatter, IGNORE_FILENAME, PARALLEL_CARD_THRESHOLD, ScanOptions,
+        cards_from_md, collect_all_cards, content_to_card, content_to_cards, parse_card_lines,
+        parse_frontmatter, register_all_cards,
+    };
+    use crate::card::{CardContent, CardDirection};
     use crate::crud::DB;
     use std::path::PathBuf;
 
     #[test]
     fn test_card_parsing() {
         let contents = "C:\nRegion: [`us-east-2`]\n\nLocation: [Ohio]\n\n---\n\n";
-        let (question, _, cloze) = parse_card_lines(contents);
-        assert!(question.is_none());
-        assert_eq!("Region: [`us-east-2`]\n\nLocation: [Ohio]", cloze.unwrap());
+        let lines = parse_card_lines(contents, CardSeparator::Rule);
+        assert!(lines.question.is_none());
+        assert_eq!(
+            "Region: [`us-east-2`]\n\nLocation: [Ohio]",
+            lines.cloze.unwrap()
+        );
     }
 
     #[test]
@@ -374,15 +996,100 @@ mod tests {
         assert!(card.is_err());
     }
 
+    #[test]
+    fn typed_prefix_makes_a_type_answer_card() {
+        let card_path = PathBuf::from("test.md");
+
+        let typed = content_to_card(&card_path, "T: Capital of France?\nA: Paris", 1, 1).unwrap();
+        let CardContent::TypeAnswer { question, answer } = &typed.content else {
+            panic!("Expected CardContent::TypeAnswer");
+        };
+        assert_eq!(question, "Capital of France?");
+        assert_eq!(answer, "Paris");
+
+        let basic = content_to_card(&card_path, "Q: Capital of France?\nA: Paris", 1, 1).unwrap();
+        assert!(matches!(basic.content, CardContent::Basic { .. }));
+        assert_ne!(typed.card_hash, basic.card_hash);
+
+        assert!(content_to_card(&card_path, "T: Capital of France?\nA: ", 1, 1).is_err());
+    }
+
+    #[test]
+    fn parses_inline_tags() {
+        let card_path = PathBuf::from("test.md");
+
+        let content =
+            "# Cells\nQ: What powers the cell? #Biology\nA: Mitochondria #cells #biology\n";
+        let card = content_to_card(&card_path, content, 1, 1).unwrap();
+        assert_eq!(card.tags, vec!["Biology", "cells"]);
+        assert!(card.has_tag("biology"));
+        assert!(card.has_tag("#CELLS"));
+        assert!(!card.has_tag("chemistry"));
+
+        let content = "C: See docs.rs/page#section for [#1] items";
+        let card = content_to_card(&card_path, content, 1, 1).unwrap();
+        assert!(card.tags.is_empty());
+
+        let card = content_to_card(&card_path, "hola #spanish, :: hello", 1, 1).unwrap();
+        assert_eq!(card.tags, vec!["spanish"]);
+
+        // a line of nothing but tags labels the card without joining the answer
+        let content = "Q: to eat?\nA: comer\n#spanish #verbs";
+        let card = content_to_card(&card_path, content, 1, 1).unwrap();
+        assert_eq!(card.tags, vec!["spanish", "verbs"]);
+        let CardContent::Basic { answer, .. } = &card.content else {
+            panic!("Expected CardContent::Basic");
+        };
+        assert_eq!(answer, "comer");
+    }
+
+    #[test]
+    fn tags_skip_code_and_accept_any_script() {
+        let card_path = PathBuf::from("test.md");
+
+        let content = "Q: How do you include a header? #c\nA:\n```c\n#include <stdio.h>\n#define\n```\nor `#import` #preprocessor";
+        let card = content_to_card(&card_path, content, 1, 1).unwrap();
+        assert_eq!(card.tags, vec!["c", "preprocessor"]);
+        let CardContent::Basic { answer, .. } = &card.content else {
+            panic!("Expected CardContent::Basic");
+        };
+        assert!(answer.contains("#define"));
+
+        let card = content_to_card(&card_path, "犬 :: dog #日本語 #n5 #2024", 1, 1).unwrap();
+        assert_eq!(card.tags, vec!["日本語", "n5"]);
+    }
+
+    #[test]
+    fn bidirectional_single_line_emits_reverse_card() {
+        let card_path = PathBuf::from("test.md");
+
+        let cards = content_to_cards(&card_path, "hola:::hello", 1, 1).unwrap();
+        assert_eq!(cards.len(), 2);
+        let (forward, reverse) = (&cards[0], &cards[1]);
+        assert_eq!(forward.direction, CardDirection::Forward);
+        assert_eq!(reverse.direction, CardDirection::Reverse);
+        assert_ne!(forward.card_hash, reverse.card_hash);
+        let CardContent::Basic { question, answer } = &reverse.content else {
+            panic!("Expected CardContent::Basic");
+        };
+        assert_eq!(question, "hello");
+        assert_eq!(answer, "hola");
+
+        let plain = content_to_cards(&card_path, "hola::hello", 1, 1).unwrap();
+        assert_eq!(plain.len(), 1);
+        assert!(forward.reversed().is_some());
+        assert!(reverse.reversed().is_none());
+    }
+
     #[test]
     fn basic_cloze() {
         let card_path = PathBuf::from("test.md");
 
         let content = "C: ping? [pong]";
         let card = content_to_card(&card_path, content, 1, 1);
-        if let CardContent::Cloze { text, cloze_range } = &card.expect("should be basic").content {
+        if let CardContent::Cloze { text, cloze_ranges } = &card.expect("should be basic").content {
             assert_eq!(text, "ping? [pong]");
-            let range = cloze_range.as_ref().expect("range to exist");
+            let range = cloze_ranges.first().expect("range to exist");
             assert_eq!(range.start, 6_usize);
             assert_eq!(range.end, 12_usize);
         } else {
@@ -390,10 +1097,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cloze_hint_is_parsed_and_changes_the_hash() {
+        let card_path = PathBuf::from("test.md");
+
+        let card = content_to_card(&card_path, "C: Capital of Japan: [Tokyo|city]", 1, 1).unwrap();
+        let CardContent::Cloze { cloze_ranges, .. } = &card.content else {
+            panic!("Expected CardContent::Cloze");
+        };
+        assert_eq!(cloze_ranges[0].hint.as_deref(), Some("city"));
+
+        let reworded =
+            content_to_card(&card_path, "C: Capital of Japan: [Tokyo|place]", 1, 1).unwrap();
+        assert_ne!(card.card_hash, reworded.card_hash);
+    }
+
     #[test]
     fn test_file_capture() {
         let card_path = PathBuf::from("test_data/test.md");
-        let cards = cards_from_md(&card_path).expect("should be ok");
+        let cards = cards_from_md(&card_path, CardSeparator::Rule).expect("should be ok");
 
         assert_eq!(cards.len(), 10);
     }
@@ -404,7 +1126,9 @@ mod tests {
             .await
             .expect("Failed to connect to or initialize database");
         let dir_path = PathBuf::from("test_data");
-        let (cards, stats) = register_all_cards(&db, vec![dir_path]).await.unwrap();
+        let (cards, stats) = register_all_cards(&db, vec![dir_path], ScanOptions::default())
+            .await
+            .unwrap();
         assert_eq!(cards.len(), 12);
         for card in cards.values() {
             assert!(card.file_path.to_string_lossy().contains("test_data"));
@@ -414,17 +1138,205 @@ mod tests {
 
         let dir_path = PathBuf::from("test_data/");
         let file_path = PathBuf::from("test_data/test.md");
-        let (cards, _) = register_all_cards(&db, vec![dir_path, file_path])
+        let (cards, _) = register_all_cards(&db, vec![dir_path, file_path], ScanOptions::default())
             .await
             .unwrap();
 
         assert_eq!(cards.len(), 12);
     }
 
+    #[tokio::test]
+    async fn repeaterignore_skips_matching_decks_at_any_level() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("biology");
+        std::fs::create_dir_all(nested.join("drafts")).unwrap();
+        std::fs::write(dir.path().join("deck.md"), "Q: kept?\nA: yes\n").unwrap();
+        std::fs::write(dir.path().join("template.md"), "Q: template?\nA: no\n").unwrap();
+        std::fs::write(nested.join("cells.md"), "Q: cells?\nA: yes\n").unwrap();
+        std::fs::write(nested.join("drafts/wip.md"), "Q: draft?\nA: no\n").unwrap();
+        std::fs::write(dir.path().join(IGNORE_FILENAME), "template.md\n").unwrap();
+        std::fs::write(nested.join(IGNORE_FILENAME), "drafts/\n").unwrap();
+
+        let cards = collect_all_cards(
+            vec![dir.path().to_path_buf()],
+            ScanOptions::default(),
+            CardSeparator::Rule,
+        )
+        .await
+        .unwrap();
+        let mut names: Vec<String> = cards
+            .iter()
+            .map(|card| {
+                card.file_path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["cells.md", "deck.md"]);
+    }
+
+    #[tokio::test]
+    async fn hidden_directories_can_be_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".notes")).unwrap();
+        std::fs::write(dir.path().join("deck.md"), "Q: visible?\nA: yes\n").unwrap();
+        std::fs::write(dir.path().join(".notes/hidden.md"), "Q: hidden?\nA: yes\n").unwrap();
+        let paths = vec![dir.path().to_path_buf()];
+
+        let cards = collect_all_cards(paths.clone(), ScanOptions::default(), CardSeparator::Rule)
+            .await
+            .unwrap();
+        assert_eq!(cards.len(), 2);
+
+        let cards = collect_all_cards(
+            paths,
+            ScanOptions {
+                hidden: false,
+                ..ScanOptions::default()
+            },
+            CardSeparator::Rule,
+        )
+        .await
+        .unwrap();
+        assert_eq!(cards.len(), 1);
+        assert!(cards[0].file_path.ends_with("deck.md"));
+    }
+
+    #[test]
+    fn frontmatter_applies_to_every_card_in_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("verbs.md");
+        let contents = "---\ntags: [spanish, Verbs]\ndeck: Spanish\nsuspended: true\n---\n\nQ: to eat? #verbs\nA: comer\n---\nC: to [drink] is beber\n";
+        std::fs::write(&path, contents).unwrap();
+
+        let cards = cards_from_md(&path, CardSeparator::Rule).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].tags, vec!["verbs", "spanish"]);
+        assert_eq!(cards[0].file_card_range, (6, 8));
+        for card in &cards {
+            assert!(card.has_tag("spanish"));
+            assert_eq!(card.deck.as_deref(), Some("Spanish"));
+            assert!(card.suspended_by_default);
+        }
+        let without = "Q: to eat? #verbs\nA: comer\n";
+        assert_eq!(
+            cards[0].card_hash,
+            content_to_card(&path, without, 0, 2).unwrap().card_hash
+        );
+
+        std::fs::write(&path, "---\ntags: spanish\n---\nQ: to eat?\nA: comer\n").unwrap();
+        let cards = cards_from_md(&path, CardSeparator::Rule).unwrap();
+        assert_eq!(cards[0].tags, vec!["spanish"]);
+        assert!(!cards[0].suspended_by_default);
+    }
+
+    #[test]
+    fn frontmatter_reads_the_yaml_forms_decks_use() {
+        let frontmatter = |yaml: &str| parse_frontmatter(yaml).unwrap().unwrap();
+        assert_eq!(
+            frontmatter("tags:\n  - spanish\n  - \"verbs\" # irregular\ndeck: 'Spanish'\n"),
+            Frontmatter {
+                tags: vec!["spanish".into(), "verbs".into()],
+                deck: Some("Spanish".into()),
+                suspended: false,
+            }
+        );
+        assert_eq!(
+            frontmatter(
+                "# shared defaults\ntitle: Verbs\nauthor:\n  name: me\ntags: []\nsuspended: false\n"
+            ),
+            Frontmatter::default()
+        );
+        assert_eq!(frontmatter("tags:\n- a\n- b\n").tags, vec!["a", "b"]);
+        assert_eq!(frontmatter("tags: [a, 'b']\n").tags, vec!["a", "b"]);
+
+        assert_eq!(parse_frontmatter("").unwrap(), None);
+        assert_eq!(parse_frontmatter("Just some notes\n").unwrap(), None);
+        assert_eq!(parse_frontmatter("- a list\n").unwrap(), None);
+        assert!(parse_frontmatter("suspended: maybe\n").is_err());
+        assert!(parse_frontmatter("tags: [a, [b]]\n").is_err());
+        assert!(parse_frontmatter("tags: [a, b\n").is_err());
+        assert!(parse_frontmatter("deck:\n  - a\n").is_err());
+    }
+
+    #[test]
+    fn deck_directive_files_the_card_without_changing_its_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.md");
+        let contents = "---\ndeck: Science\n---\nQ: What powers the cell?\n@deck: Biology\nA: Mitochondria\n---\nC: [Water] boils at 100C\n";
+        std::fs::write(&path, contents).unwrap();
+
+        let cards = cards_from_md(&path, CardSeparator::Rule).unwrap();
+        assert_eq!(cards[0].deck.as_deref(), Some("Biology"));
+        assert!(cards[0].in_deck("biology"));
+        assert_eq!(cards[1].deck.as_deref(), Some("Science"));
+        let CardContent::Basic { question, answer } = &cards[0].content else {
+            panic!("Expected CardContent::Basic");
+        };
+        assert_eq!(question, "What powers the cell?");
+        assert_eq!(answer, "Mitochondria");
+
+        let without = "Q: What powers the cell?\nA: Mitochondria\n";
+        assert_eq!(
+            cards[0].card_hash,
+            content_to_card(&path, without, 0, 2).unwrap().card_hash
+        );
+    }
+
+    #[test]
+    fn leading_separator_is_not_frontmatter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.md");
+        std::fs::write(&path, "---\nQ: first?\nA: yes\n---\nQ: second?\nA: yes\n").unwrap();
+        let cards = cards_from_md(&path, CardSeparator::Rule).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert!(cards.iter().all(|card| card.deck.is_none()));
+
+        std::fs::write(&path, "---\nJust some notes\n---\nQ: third?\nA: yes\n").unwrap();
+        assert_eq!(cards_from_md(&path, CardSeparator::Rule).unwrap().len(), 1);
+
+        std::fs::write(&path, "---\ntags: {nested: map}\n---\nQ: fourth?\nA: yes\n").unwrap();
+        let err = cards_from_md(&path, CardSeparator::Rule).unwrap_err();
+        assert!(format!("{err:#}").contains("frontmatter"));
+    }
+
+    #[test]
+    fn blank_line_separator_ends_cards_before_trailing_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(
+            &path,
+            "Q: first?\nA: yes\n\nSome notes\n---\nQ: second?\nA: no\n---\nstill no\n\nMore notes\n",
+        )
+        .unwrap();
+
+        let answers = |separator| {
+            cards_from_md(&path, separator)
+                .unwrap()
+                .into_iter()
+                .map(|card| match card.content {
+                    CardContent::Basic { answer, .. } => answer,
+                    _ => panic!("Expected CardContent::Basic"),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            answers(CardSeparator::BlankLine),
+            vec!["yes", "no\n---\nstill no"]
+        );
+        let rule = answers(CardSeparator::Rule);
+        assert_eq!(rule.len(), 2);
+        assert!(rule[0].contains("Some notes"));
+        assert_eq!(rule[1], "no");
+    }
+
     #[test]
     fn cards_from_md_returns_error_for_nonexistent_file() {
         let path = PathBuf::from("nonexistent_file.md");
-        let result = cards_from_md(&path);
+        let result = cards_from_md(&path, CardSeparator::Rule);
         assert!(result.is_err());
     }
 
@@ -436,9 +1348,9 @@ mod tests {
         let content = "C: this has no cloze markers";
         let card = content_to_card(&card_path, content, 0, 1)
             .expect("invalid cloze text should still be accepted");
-        if let CardContent::Cloze { text, cloze_range } = card.content {
+        if let CardContent::Cloze { text, cloze_ranges } = card.content {
             assert_eq!(text, "this has no cloze markers");
-            assert!(cloze_range.is_none());
+            assert!(cloze_ranges.is_empty());
         } else {
             panic!("Expected CardContent::Cloze");
         }
@@ -501,6 +1413,61 @@ mod tests {
         assert!(card.is_err());
     }
 
+    #[test]
+    fn double_colons_inside_answers_and_urls_are_not_cards() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("code.md");
+        let contents = "Q: Where are the docs?\nA: http://[::1]:8080/docs\n\nQ: Which type owns text?\nA:\nstd::string, not\nstd::string_view\n\nQ: Where does Windows live?\nA: C:\\Windows\\System32\nor \\\\server\\share::backup\n---\nSee https://[::1]/notes\nhola:::hello\n";
+        std::fs::write(&path, contents).unwrap();
+
+        let cards = cards_from_md(&path, CardSeparator::Rule).unwrap();
+        let answers: Vec<_> = cards
+            .iter()
+            .map(|card| match &card.content {
+                CardContent::Basic { answer, .. } => answer.as_str(),
+                _ => panic!("Expected CardContent::Basic"),
+            })
+            .collect();
+        assert_eq!(
+            answers,
+            vec![
+                "http://[::1]:8080/docs",
+                "std::string, not\nstd::string_view",
+                "C:\\Windows\\System32\nor \\\\server\\share::backup",
+                "hello",
+                "hola",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_double_colon_line_still_ends_the_block_before_it() {
+        let cards =
+            cards_from_md(&PathBuf::from("test_data/test.md"), CardSeparator::Rule).unwrap();
+        let cloze = cards
+            .iter()
+            .find_map(|card| match &card.content {
+                CardContent::Cloze { text, .. } if text.contains("moon") => Some(text.as_str()),
+                _ => None,
+            })
+            .expect("the cloze card is parsed");
+        assert_eq!(cloze, "the moon revolves around the earth");
+        assert!(cards.iter().any(|card| matches!(
+            &card.content,
+            CardContent::Basic { question, answer } if question == "what style is this?" && answer == "remnote style"
+        )));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("after_answer.md");
+        std::fs::write(
+            &path,
+            "Q: Capital of Spain?\nA: Madrid\n\nParis :: France\n",
+        )
+        .unwrap();
+        let cards = cards_from_md(&path, CardSeparator::Rule).unwrap();
+        assert_eq!(cards.len(), 2);
+    }
+
     #[tokio::test]
     async fn register_all_cards_returns_error_for_invalid_card_file() {
         use std::fs;
@@ -520,7 +1487,7 @@ mod tests {
         writeln!(file, "Q: This is a question").unwrap();
         writeln!(file, "C: This is invalid [cloze").unwrap(); // Invalid cloze
 
-        let result = register_all_cards(&db, vec![temp_dir.clone()]).await;
+        let result = register_all_cards(&db, vec![temp_dir.clone()], ScanOptions::default()).await;
 
         // Clean up
         fs::remove_file(&test_file).unwrap();
@@ -531,4 +1498,135 @@ mod tests {
         let err = result.unwrap_err();
         assert!(err.to_string().contains("Failed to parse"));
     }
+
+    #[test]
+    fn large_files_keep_their_cards_in_file_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big.md");
+        let count = PARALLEL_CARD_THRESHOLD * 3;
+        let contents: String = (0..count)
+            .map(|idx| format!("Q: Question {idx}?\nA: Answer {idx}\n---\n"))
+            .collect();
+        std::fs::write(&path, contents).unwrap();
+
+        let cards = cards_from_md(&path, CardSeparator::Rule).unwrap();
+        assert_eq!(cards.len(), count);
+        for (idx, card) in cards.iter().enumerate() {
+            assert_eq!(card.file_card_range, (idx * 3, idx * 3 + 2));
+            let CardContent::Basic { question, .. } = &card.content else {
+                panic!("expected a basic card");
+            };
+            assert_eq!(question, &format!("Question {idx}?"));
+        }
+    }
+
+    #[tokio::test]
+    async fn unchanged_files_come_from_the_file_index() {
+        let db = DB::new_in_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("deck.md");
+        std::fs::write(&deck, "Q: One?\nA: 1\n").unwrap();
+        let paths = vec![dir.path().to_path_buf()];
+
+        let (cards, _) = register_all_cards(&db, paths.clone(), ScanOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(cards.len(), 1);
+        let mut index = db.file_index().await.unwrap();
+        let mut indexed = index.remove(&deck).unwrap();
+        assert_eq!(indexed.cards.len(), 1);
+
+        // an unchanged file isn't parsed again, so a doctored entry shows through
+        indexed.cards[0].card_hash = "from-index".into();
+        db.index_files(&[(deck.clone(), indexed)]).await.unwrap();
+        let (cards, _) = register_all_cards(&db, paths.clone(), ScanOptions::default())
+            .await
+            .unwrap();
+        assert!(cards.contains_key("from-index"));
+
+        std::fs::write(&deck, "Q: One?\nA: 1\n---\nQ: Two?\nA: 2\n").unwrap();
+        let (cards, _) = register_all_cards(&db, paths.clone(), ScanOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(cards.len(), 2);
+        assert!(!cards.contains_key("from-index"));
+
+        // a deck that's gone from the folder leaves the index on the next scan
+        let other = dir.path().join("other.md");
+        std::fs::write(&other, "Q: Three?\nA: 3\n").unwrap();
+        register_all_cards(&db, paths.clone(), ScanOptions::default())
+            .await
+            .unwrap();
+        assert!(db.file_index().await.unwrap().contains_key(&other));
+        std::fs::remove_file(&other).unwrap();
+        register_all_cards(&db, paths, ScanOptions::default())
+            .await
+            .unwrap();
+        let index = db.file_index().await.unwrap();
+        assert!(!index.contains_key(&other));
+        assert!(index.contains_key(&deck));
+    }
+
+    #[tokio::test]
+    async fn skip_errors_registers_the_good_decks_and_lists_the_bad_ones() {
+        let db = DB::new_in_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("good.md"), "Q: Fine?\nA: Yes\n").unwrap();
+        std::fs::write(
+            dir.path().join("malformed.md"),
+            "Q: This is a question\nC: This is invalid [cloze\n",
+        )
+        .unwrap();
+        let scan = ScanOptions {
+            skip_errors: true,
+            ..ScanOptions::default()
+        };
+
+        let (cards, stats) = register_all_cards(&db, vec![dir.path().to_path_buf()], scan)
+            .await
+            .unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(stats.markdown_files, 2);
+        assert_eq!(stats.skipped_files.len(), 1);
+        assert_eq!(stats.skipped_files[0].path, dir.path().join("malformed.md"));
+        assert!(!stats.skipped_files[0].error.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cards_written_in_several_files_are_listed_as_duplicates() {
+        let db = DB::new_in_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.md"), dir.path().join("b.md"));
+        std::fs::write(
+            &a,
+            "Q: Capital of France?\nA: Paris\n\n---\n\nQ: Only here?\nA: Yes\n",
+        )
+        .unwrap();
+        std::fs::write(&b, "# Geography\n\nQ: Capital of France?\nA: Paris\n").unwrap();
+
+        let (cards, stats) =
+            register_all_cards(&db, vec![dir.path().to_path_buf()], ScanOptions::default())
+                .await
+                .unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(stats.duplicate_cards.len(), 1);
+        let duplicate = &stats.duplicate_cards[0];
+        assert_eq!(duplicate.locations, vec![(a, 1), (b, 3)]);
+        assert!(cards.contains_key(&duplicate.card_hash));
+    }
+
+    #[tokio::test]
+    async fn overlapping_roots_do_not_report_a_card_as_its_own_duplicate() {
+        let db = DB::new_in_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("deck.md");
+        std::fs::write(&deck, "Q: Capital of France?\nA: Paris\n").unwrap();
+
+        let roots = vec![dir.path().to_path_buf(), dir.path().to_path_buf()];
+        let (cards, stats) = register_all_cards(&db, roots, ScanOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(cards.len(), 1);
+        assert!(stats.duplicate_cards.is_empty());
+    }
 }
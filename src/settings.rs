@@ -0,0 +1,297 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use fsrs::{DEFAULT_PARAMETERS, FSRS};
+
+use crate::keymap::Keymap;
+use crate::palette::Palette;
+use crate::parser::CardSeparator;
+use crate::utils::{parse_duration, parse_duration_or_zero};
+
+pub const DEFAULT_DESIRED_RETENTION: f32 = 0.9;
+pub const MIN_DESIRED_RETENTION: f32 = 0.70;
+pub const MAX_DESIRED_RETENTION: f32 = 0.97;
+pub const DEFAULT_LEARNING_STEPS: [Duration; 3] = [
+    Duration::from_secs(60),
+    Duration::from_secs(10 * 60),
+    Duration::from_secs(24 * 60 * 60),
+];
+const MAX_LEARNING_STEPS: usize = 10;
+/// Cards due within this long count as due now.
+pub const DEFAULT_LEARN_AHEAD: Duration = Duration::from_secs(20 * 60);
+pub const MAX_LEARN_AHEAD: Duration = Duration::from_secs(12 * 60 * 60);
+pub const MAX_INTERVAL_LIMIT_DAYS: u32 = 36_500;
+/// Failed reviews after which a card is flagged as a leech.
+pub const DEFAULT_LEECH_THRESHOLD: u32 = 8;
+/// Latest hour after local midnight a study day may start at.
+pub const MAX_DAY_START_HOUR: u32 = 23;
+
+const FSRS_PARAMS_FILE_NAME: &str = "fsrs_params.json";
+
+/// User-tunable scheduler settings. Retention and learning steps live in the
+/// `settings` table, FSRS parameters in `fsrs_params.json` in the data dir.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub desired_retention: f32,
+    pub fsrs_parameters: Vec<f32>,
+    /// Maximum delay after each of a card's first reviews, before FSRS takes over.
+    pub learning_steps: Vec<Duration>,
+    /// Longest interval FSRS may schedule. `None` leaves intervals uncapped.
+    pub max_interval_days: Option<u32>,
+    /// Spread intervals longer than a day by a few percent.
+    pub fuzz_intervals: bool,
+    /// What ends a card in the deck files.
+    pub card_separator: CardSeparator,
+    /// Failed reviews after which a card is flagged as a leech.
+    pub leech_threshold: u32,
+    /// Suspend cards as soon as they become leeches.
+    pub suspend_leeches: bool,
+    /// Look up the latest release on GitHub during `check`.
+    pub version_check: bool,
+    /// Hour after local midnight when a new study day begins.
+    pub day_start_hour: u32,
+    /// Cards due within this long count as due now, and a card reviewed
+    /// in a drill comes back the same session if it is due again this soon.
+    pub learn_ahead: Duration,
+    /// Keys bound to each action in the drill TUI.
+    pub keymap: Keymap,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            desired_retention: DEFAULT_DESIRED_RETENTION,
+            fsrs_parameters: DEFAULT_PARAMETERS.to_vec(),
+            learning_steps: DEFAULT_LEARNING_STEPS.to_vec(),
+            max_interval_days: None,
+            fuzz_intervals: true,
+            card_separator: CardSeparator::default(),
+            leech_threshold: DEFAULT_LEECH_THRESHOLD,
+            suspend_leeches: false,
+            version_check: true,
+            day_start_hour: 0,
+            learn_ahead: DEFAULT_LEARN_AHEAD,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+pub fn validate_retention(retention: f32) -> Result<f32> {
+    if !(MIN_DESIRED_RETENTION..=MAX_DESIRED_RETENTION).contains(&retention) {
+        bail!(
+            "Desired retention must be between {:.2} and {:.2}, got {}",
+            MIN_DESIRED_RETENTION,
+            MAX_DESIRED_RETENTION,
+            retention
+        );
+    }
+    Ok(retention)
+}
+
+pub fn validate_max_interval(days: u32) -> Result<u32> {
+    if !(1..=MAX_INTERVAL_LIMIT_DAYS).contains(&days) {
+        bail!(
+            "Maximum interval must be between 1 and {} days, got {}",
+            MAX_INTERVAL_LIMIT_DAYS,
+            days
+        );
+    }
+    Ok(days)
+}
+
+pub fn validate_leech_threshold(failures: u32) -> Result<u32> {
+    if failures == 0 {
+        bail!("Leech threshold must be at least 1 failure");
+    }
+    Ok(failures)
+}
+
+pub fn validate_day_start_hour(hour: u32) -> Result<u32> {
+    if hour > MAX_DAY_START_HOUR {
+        bail!("Day start must be an hour between 0 and {MAX_DAY_START_HOUR}");
+    }
+    Ok(hour)
+}
+
+pub fn validate_learn_ahead(window: Duration) -> Result<Duration> {
+    if window > MAX_LEARN_AHEAD {
+        bail!(
+            "Learn-ahead window must be at most {}, got {}",
+            format_step(MAX_LEARN_AHEAD),
+            format_step(window)
+        );
+    }
+    Ok(window)
+}
+
+/// Parses a learn-ahead window like `20m`; `0m` turns learning ahead off.
+pub fn parse_learn_ahead(input: &str) -> Result<Duration> {
+    validate_learn_ahead(parse_duration_or_zero(input)?)
+}
+
+pub fn validate_fsrs_parameters(parameters: &[f32]) -> Result<()> {
+    if !matches!(parameters.len(), 17 | 19 | 21) {
+        bail!(
+            "Expected 17, 19, or 21 FSRS parameters, got {}",
+            parameters.len()
+        );
+    }
+    FSRS::new(Some(parameters)).context("FSRS rejected the parameters")?;
+    Ok(())
+}
+
+/// Parses a list of steps like `1m 10m 1d`, separated by spaces or commas.
+pub fn parse_learning_steps(input: &str) -> Result<Vec<Duration>> {
+    let steps = input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(parse_duration)
+        .collect::<Result<Vec<_>>>()?;
+    if steps.is_empty() {
+        bail!("Expected at least one learning step, like \"1m 10m 1d\"");
+    }
+    if steps.len() > MAX_LEARNING_STEPS {
+        bail!(
+            "Expected at most {} learning steps, got {}",
+            MAX_LEARNING_STEPS,
+            steps.len()
+        );
+    }
+    Ok(steps)
+}
+
+/// Formats steps the way [`parse_learning_steps`] reads them, e.g. `1m 10m 1d`.
+pub fn format_learning_steps(steps: &[Duration]) -> String {
+    steps
+        .iter()
+        .map(|step| format_step(*step))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Formats a duration in its largest whole unit, e.g. `10m` or `1d`.
+pub fn format_step(step: Duration) -> String {
+    let secs = step.as_secs();
+    if secs == 0 {
+        return String::from("0m");
+    }
+    match [(86_400, 'd'), (3600, 'h'), (60, 'm')]
+        .into_iter()
+        .find(|(unit, _)| secs.is_multiple_of(*unit))
+    {
+        Some((unit, suffix)) => format!("{}{}", secs / unit, suffix),
+        None => format!("{secs}s"),
+    }
+}
+
+pub fn fsrs_params_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(FSRS_PARAMS_FILE_NAME)
+}
+
+/// Reads the parameters file. Returns `Ok(None)` when no file has been saved.
+pub fn read_fsrs_parameters(path: &Path) -> Result<Option<Vec<f32>>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed to read FSRS parameters at {}", path.display()));
+        }
+    };
+    let parameters: Vec<f32> = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse FSRS parameters at {}", path.display()))?;
+    validate_fsrs_parameters(&parameters)
+        .with_context(|| format!("Invalid FSRS parameters at {}", path.display()))?;
+    Ok(Some(parameters))
+}
+
+pub fn write_fsrs_parameters(path: &Path, parameters: &[f32]) -> Result<()> {
+    validate_fsrs_parameters(parameters)?;
+    let contents = format!("{}\n", serde_json::to_string_pretty(parameters)?);
+    fs::write(path, contents)
+        .with_context(|| format!("Failed to write FSRS parameters to {}", path.display()))?;
+    Ok(())
+}
+
+/// Loads custom parameters, falling back to the defaults with a warning so a
+/// broken file never blocks a drill session.
+pub fn load_fsrs_parameters(path: &Path) -> Vec<f32> {
+    match read_fsrs_parameters(path) {
+        Ok(Some(parameters)) => parameters,
+        Ok(None) => DEFAULT_PARAMETERS.to_vec(),
+        Err(err) => {
+            eprintln!(
+                "{} {:#}. {}",
+                Palette::paint(Palette::WARNING, "Warning:"),
+                err,
+                Palette::dim("Using default FSRS parameters."),
+            );
+            DEFAULT_PARAMETERS.to_vec()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retention_bounds_are_inclusive() {
+        assert!(validate_retention(0.70).is_ok());
+        assert!(validate_retention(0.85).is_ok());
+        assert!(validate_retention(0.97).is_ok());
+        assert!(validate_retention(0.69).is_err());
+        assert!(validate_retention(0.98).is_err());
+        assert!(validate_retention(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn learning_steps_parse_and_format() {
+        let steps = parse_learning_steps(" 1m, 10m  1d ").unwrap();
+        assert_eq!(steps, DEFAULT_LEARNING_STEPS.to_vec());
+        assert_eq!(format_learning_steps(&steps), "1m 10m 1d");
+        assert_eq!(
+            format_learning_steps(&parse_learning_steps("90s 1h30m 2d").unwrap()),
+            "90s 90m 2d"
+        );
+
+        assert!(parse_learning_steps("").is_err());
+        assert!(parse_learning_steps("1m 10x").is_err());
+        assert!(parse_learning_steps("1m 0m").is_err());
+        assert!(parse_learning_steps(&"1m ".repeat(MAX_LEARNING_STEPS + 1)).is_err());
+    }
+
+    #[test]
+    fn learn_ahead_accepts_zero() {
+        assert_eq!(parse_learn_ahead("0m").unwrap(), Duration::ZERO);
+        assert_eq!(format_step(parse_learn_ahead("0m").unwrap()), "0m");
+        assert_eq!(format_step(parse_learn_ahead("12h").unwrap()), "12h");
+        assert!(parse_learn_ahead("13h").is_err());
+        assert!(parse_learn_ahead("5x").is_err());
+    }
+
+    #[test]
+    fn fsrs_parameters_round_trip_and_fall_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FSRS_PARAMS_FILE_NAME);
+
+        assert_eq!(read_fsrs_parameters(&path).unwrap(), None);
+        assert_eq!(load_fsrs_parameters(&path), DEFAULT_PARAMETERS.to_vec());
+
+        let custom = DEFAULT_PARAMETERS[..19].to_vec();
+        write_fsrs_parameters(&path, &custom).unwrap();
+        assert_eq!(load_fsrs_parameters(&path), custom);
+
+        assert!(write_fsrs_parameters(&path, &[1.0, 2.0]).is_err());
+
+        fs::write(&path, "[1.0, 2.0, 3.0]").unwrap();
+        assert!(read_fsrs_parameters(&path).is_err());
+        assert_eq!(load_fsrs_parameters(&path), DEFAULT_PARAMETERS.to_vec());
+
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(load_fsrs_parameters(&path), DEFAULT_PARAMETERS.to_vec());
+    }
+}
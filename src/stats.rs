@@ -4,22 +4,67 @@ use std::path::PathBuf;
 
 use crate::card::Card;
 use crate::crud::stats::CardStatsRow;
-use crate::fsrs::LEARN_AHEAD_THRESHOLD_MINS;
+use crate::fsrs::{Performance, ReviewStatus, update_performance};
+use crate::settings::Settings;
+use crate::utils::{start_of_study_day, study_date};
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use clap::ValueEnum;
 use fsrs::{FSRS6_DEFAULT_DECAY, MemoryState, current_retrievability};
 
+/// Days `check` forecasts when no `--horizon` is given, starting today.
+pub const DEFAULT_HORIZON_DAYS: u32 = 7;
+/// Days covered by the projected workload of `check --forecast`.
+pub const FORECAST_DAYS: u32 = 30;
+
+/// A DB row for a card no longer found where it was last seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanedCard {
+    pub card_hash: String,
+    pub file_path: PathBuf,
+    /// The card's first line, counting from 0.
+    pub start_line: i64,
+}
+
 #[derive(Debug, Default)]
 pub struct CardStats {
     pub total_cards_in_db: i64,
     pub num_cards: i64,
+    /// DB rows under the scanned roots that match no registered card, filled
+    /// in from [`DB::orphaned_cards`](crate::crud::DB::orphaned_cards).
+    pub orphans: Vec<OrphanedCard>,
+    /// Registered cards kept out of drills until unsuspended.
+    pub suspended_cards: i64,
+    /// Registered cards flagged for failing too many reviews, suspended or not.
+    pub leech_cards: i64,
+    /// Media links in registered cards whose file is missing.
+    pub broken_media: Vec<BrokenMedia>,
     pub card_lifecycles: HashMap<CardLifeCycle, i64>,
     pub due_cards: i64,
-    pub upcoming_week: BTreeMap<String, usize>,
+    /// Reviews logged since the study day began, counting repeats of the same card.
+    pub reviewed_today: usize,
+    /// The study day `upcoming` starts from.
+    pub today: NaiveDate,
+    /// Cards due per study day (`%Y-%m-%d`), from today through the horizon.
+    pub upcoming: BTreeMap<String, usize>,
     pub upcoming_month: i64,
+    /// Projected reviews per day from [`forecast_workload`], when requested.
+    pub forecast: Option<BTreeMap<String, usize>>,
     pub file_paths: HashMap<PathBuf, usize>,
     pub difficulty_histogram: Histogram<5>,
     pub retrievability_histogram: Histogram<5>,
 }
 
+/// A media link whose file doesn't exist, found by `check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenMedia {
+    pub card_path: PathBuf,
+    /// 1-based line where the card starts.
+    pub line: usize,
+    /// The missing file, resolved against the card's directory.
+    pub media_path: PathBuf,
+}
+
 #[derive(Debug, Clone)]
 pub struct Histogram<const N: usize> {
     pub bins: [u32; N],
@@ -55,17 +100,67 @@ impl<const N: usize> Histogram<N> {
     }
 }
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, ValueEnum)]
 pub enum CardLifeCycle {
+    /// Never reviewed.
     New,
+    /// Reviewed, with an interval of three weeks or less.
     Young,
+    /// Reviewed, with an interval longer than three weeks.
     Mature,
 }
 const MATURE_INTERVAL: f64 = 21.0;
 
+impl CardLifeCycle {
+    pub fn classify(review_count: i64, interval_raw: f64) -> Self {
+        if review_count == 0 {
+            CardLifeCycle::New
+        } else if interval_raw > MATURE_INTERVAL {
+            CardLifeCycle::Mature
+        } else {
+            CardLifeCycle::Young
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CardLifeCycle::New => "new",
+            CardLifeCycle::Young => "young",
+            CardLifeCycle::Mature => "mature",
+        }
+    }
+}
+
+/// Whether a card due at `due_date` belongs in a session started at `now`:
+/// it comes due within the learn-ahead window, or it was scheduled at least a
+/// day out and lands in the hours past midnight that still belong to the
+/// current study day.
+pub fn counts_as_due<Tz: TimeZone>(
+    due_date: DateTime<Utc>,
+    interval_raw: f64,
+    now: &DateTime<Tz>,
+    day_start_hour: u32,
+    learn_ahead: std::time::Duration,
+) -> bool {
+    let learn_ahead = chrono::Duration::from_std(learn_ahead).unwrap_or_default();
+    if due_date <= now.to_utc() + learn_ahead {
+        return true;
+    }
+    let next_day = start_of_study_day(now, day_start_hour) + chrono::Duration::days(1);
+    let midnight = next_day - chrono::Duration::hours(day_start_hour.into());
+    interval_raw >= 1.0 && midnight <= due_date && due_date < next_day
+}
+
 impl CardStats {
     // row is a Record
-    pub fn update(&mut self, card: &Card, row: &CardStatsRow) {
+    pub fn update(
+        &mut self,
+        card: &Card,
+        row: &CardStatsRow,
+        horizon_days: u32,
+        day_start_hour: u32,
+        learn_ahead: std::time::Duration,
+    ) {
         let review_count = row.review_count;
         let due_date = row.due_date;
         let interval = row.interval_raw.unwrap_or_default();
@@ -74,37 +169,34 @@ impl CardStats {
         let last_reviewed_at = row.last_reviewed_at;
 
         let now = chrono::Utc::now();
-        let week_horizon = now + chrono::Duration::days(7);
+        let local_now = now.with_timezone(&Local);
+        let today = study_date(&local_now, day_start_hour);
+        let horizon_end = today + chrono::Days::new(u64::from(horizon_days));
         let month_horizon = now + chrono::Duration::days(30);
         *self.file_paths.entry(card.file_path.clone()).or_insert(0) += 1;
 
-        let lifecycle = if review_count == 0 {
-            CardLifeCycle::New
-        } else if interval > MATURE_INTERVAL {
-            CardLifeCycle::Mature
-        } else {
-            CardLifeCycle::Young
-        };
+        let lifecycle = CardLifeCycle::classify(review_count, interval);
 
         *self.card_lifecycles.entry(lifecycle).or_insert(0) += 1;
 
         match due_date {
             None => {
                 self.due_cards += 1;
-                let day = now.format("%Y-%m-%d").to_string();
-                *self.upcoming_week.entry(day).or_insert(0) += 1;
+                let day = today.format("%Y-%m-%d").to_string();
+                *self.upcoming.entry(day).or_insert(0) += 1;
                 self.upcoming_month += 1;
             }
             Some(due_date) => {
-                if due_date <= now + LEARN_AHEAD_THRESHOLD_MINS {
+                if counts_as_due(due_date, interval, &local_now, day_start_hour, learn_ahead) {
                     self.due_cards += 1;
-                    let day = now.format("%Y-%m-%d").to_string();
-                    *self.upcoming_week.entry(day).or_insert(0) += 1;
+                    let day = today.format("%Y-%m-%d").to_string();
+                    *self.upcoming.entry(day).or_insert(0) += 1;
                     self.upcoming_month += 1;
                 } else {
-                    if due_date <= week_horizon {
-                        let day = due_date.format("%Y-%m-%d").to_string();
-                        *self.upcoming_week.entry(day).or_insert(0) += 1;
+                    let due_day = study_date(&due_date.with_timezone(&Local), day_start_hour);
+                    if due_day < horizon_end {
+                        let day = due_day.format("%Y-%m-%d").to_string();
+                        *self.upcoming.entry(day).or_insert(0) += 1;
                     }
 
                     if due_date <= month_horizon {
@@ -134,10 +226,47 @@ impl CardStats {
     }
 }
 
+/// Estimates how many reviews land on each of the next `days` study days
+/// (`%Y-%m-%d`) if every card is passed the moment it comes due. New and
+/// overdue cards are reviewed right away, and each simulated review schedules
+/// the next one with FSRS, so repeats within the window are counted too.
+pub fn forecast_workload(
+    performances: &[(String, Performance)],
+    settings: &Settings,
+    now: DateTime<Utc>,
+    days: u32,
+) -> Result<BTreeMap<String, usize>> {
+    let day_of = |at: DateTime<Utc>| study_date(&at.with_timezone(&Local), settings.day_start_hour);
+    let end = day_of(now) + chrono::Days::new(u64::from(days));
+    let mut load = BTreeMap::new();
+    for (card_hash, performance) in performances {
+        let mut performance = *performance;
+        let mut review_at = match performance {
+            Performance::New => now,
+            Performance::Reviewed(reviewed) => reviewed.due_date.max(now),
+        };
+        while day_of(review_at) < end {
+            let day = day_of(review_at).format("%Y-%m-%d").to_string();
+            *load.entry(day).or_insert(0) += 1;
+            let reviewed = update_performance(
+                performance,
+                ReviewStatus::Good,
+                review_at,
+                settings,
+                card_hash,
+            )?;
+            review_at = reviewed.due_date;
+            performance = Performance::Reviewed(reviewed);
+        }
+    }
+    Ok(load)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::card::{Card, CardContent};
+    use crate::settings::DEFAULT_LEARN_AHEAD;
     use chrono::{Duration, Utc};
     use std::path::PathBuf;
 
@@ -162,6 +291,8 @@ mod tests {
             difficulty: None,
             stability: None,
             last_reviewed_at: None,
+            suspended: false,
+            leech: false,
         }
     }
 
@@ -172,7 +303,7 @@ mod tests {
         let mut row = default_row();
         row.difficulty = Some(5.0);
 
-        stats.update(&card, &row);
+        stats.update(&card, &row, DEFAULT_HORIZON_DAYS, 0, DEFAULT_LEARN_AHEAD);
 
         assert_eq!(*stats.card_lifecycles.get(&CardLifeCycle::New).unwrap(), 1);
         assert_eq!(stats.due_cards, 1);
@@ -191,7 +322,7 @@ mod tests {
         row.interval_raw = Some(30.0);
         row.due_date = Some(Utc::now() + Duration::days(3));
 
-        stats.update(&card, &row);
+        stats.update(&card, &row, DEFAULT_HORIZON_DAYS, 0, DEFAULT_LEARN_AHEAD);
 
         assert_eq!(
             *stats.card_lifecycles.get(&CardLifeCycle::Mature).unwrap(),
@@ -199,7 +330,31 @@ mod tests {
         );
         assert_eq!(stats.due_cards, 0);
         assert_eq!(stats.upcoming_month, 1);
-        assert_eq!(stats.upcoming_week.values().sum::<usize>(), 1);
+        assert_eq!(stats.upcoming.values().sum::<usize>(), 1);
+    }
+
+    #[test]
+    fn horizon_limits_upcoming_days() {
+        let card = sample_card("deck/file.md");
+        let mut row = default_row();
+        row.review_count = 5;
+        row.interval_raw = Some(12.0);
+        row.due_date = Some(Utc::now() + Duration::days(10));
+
+        let mut week = CardStats::default();
+        week.update(&card, &row, DEFAULT_HORIZON_DAYS, 0, DEFAULT_LEARN_AHEAD);
+        assert!(week.upcoming.is_empty());
+        assert_eq!(week.upcoming_month, 1);
+
+        let mut fortnight = CardStats::default();
+        fortnight.update(&card, &row, 14, 0, DEFAULT_LEARN_AHEAD);
+        let day = row
+            .due_date
+            .unwrap()
+            .with_timezone(&Local)
+            .format("%Y-%m-%d")
+            .to_string();
+        assert_eq!(fortnight.upcoming.get(&day), Some(&1));
     }
 
     #[test]
@@ -212,7 +367,7 @@ mod tests {
         row.stability = Some(5.0);
         row.last_reviewed_at = Some(Utc::now() - Duration::days(4));
 
-        stats.update(&card, &row);
+        stats.update(&card, &row, DEFAULT_HORIZON_DAYS, 0, DEFAULT_LEARN_AHEAD);
 
         let recall = current_retrievability(
             MemoryState {
@@ -226,6 +381,124 @@ mod tests {
         assert_eq!(stats.retrievability_histogram.bins[idx], 1);
     }
 
+    #[test]
+    fn forecast_counts_every_simulated_review_in_the_window() {
+        let now = Utc::now();
+        let settings = Settings::default();
+        let far_off = crate::fsrs::ReviewedPerformance {
+            last_reviewed_at: now - Duration::days(10),
+            stability: 100.0,
+            difficulty: 5.0,
+            interval_raw: 100.0,
+            interval_days: 100,
+            due_date: now + Duration::days(90),
+            review_count: 4,
+        };
+        let later = vec![("later".to_string(), Performance::Reviewed(far_off))];
+        assert!(
+            forecast_workload(&later, &settings, now, FORECAST_DAYS)
+                .unwrap()
+                .is_empty()
+        );
+
+        // a new card is studied today, then keeps coming back through its
+        // learning steps and growing intervals
+        let new = vec![("new".to_string(), Performance::New)];
+        let load = forecast_workload(&new, &settings, now, FORECAST_DAYS).unwrap();
+        let today = now.with_timezone(&Local).format("%Y-%m-%d").to_string();
+        assert!(load[&today] >= 1);
+        assert!(load.values().sum::<usize>() > 1);
+        assert!(load.len() > 1);
+
+        let one_day = forecast_workload(&new, &settings, now, 1).unwrap();
+        assert_eq!(one_day.keys().collect::<Vec<_>>(), vec![&today]);
+    }
+
+    #[test]
+    fn cards_due_before_the_day_start_count_toward_the_current_day() {
+        use chrono::FixedOffset;
+
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let evening = tokyo.with_ymd_and_hms(2024, 3, 10, 22, 0, 0).unwrap();
+        let at = |day, hour, minute| {
+            tokyo
+                .with_ymd_and_hms(2024, 3, day, hour, minute, 0)
+                .unwrap()
+                .to_utc()
+        };
+
+        // with days starting at midnight, only cards due by now are due
+        assert!(counts_as_due(
+            at(10, 22, 10),
+            3.0,
+            &evening,
+            0,
+            DEFAULT_LEARN_AHEAD
+        ));
+        assert!(!counts_as_due(
+            at(11, 1, 0),
+            3.0,
+            &evening,
+            0,
+            DEFAULT_LEARN_AHEAD
+        ));
+
+        // a 4am rollover pulls in review cards due before 4am tomorrow
+        assert!(counts_as_due(
+            at(11, 0, 0),
+            3.0,
+            &evening,
+            4,
+            DEFAULT_LEARN_AHEAD
+        ));
+        assert!(counts_as_due(
+            at(11, 3, 59),
+            3.0,
+            &evening,
+            4,
+            DEFAULT_LEARN_AHEAD
+        ));
+        assert!(!counts_as_due(
+            at(11, 4, 0),
+            3.0,
+            &evening,
+            4,
+            DEFAULT_LEARN_AHEAD
+        ));
+        // but not the rest of this evening, nor learning steps
+        assert!(!counts_as_due(
+            at(10, 23, 30),
+            3.0,
+            &evening,
+            4,
+            DEFAULT_LEARN_AHEAD
+        ));
+        assert!(!counts_as_due(
+            at(11, 1, 0),
+            0.1,
+            &evening,
+            4,
+            DEFAULT_LEARN_AHEAD
+        ));
+
+        // past midnight the same study day is still running
+        let night = tokyo.with_ymd_and_hms(2024, 3, 11, 2, 0, 0).unwrap();
+        assert!(counts_as_due(
+            at(11, 3, 0),
+            3.0,
+            &night,
+            4,
+            DEFAULT_LEARN_AHEAD
+        ));
+        assert!(!counts_as_due(
+            at(11, 3, 0),
+            3.0,
+            &night,
+            0,
+            DEFAULT_LEARN_AHEAD
+        ));
+    }
+
     #[test]
     fn histogram_mean_returns_none_when_empty() {
         let histogram: Histogram<5> = Histogram::default();
@@ -256,7 +529,7 @@ mod tests {
         row.difficulty = Some(7.5);
         row.last_reviewed_at = None; // Card has never been reviewed
 
-        stats.update(&card, &row);
+        stats.update(&card, &row, DEFAULT_HORIZON_DAYS, 0, DEFAULT_LEARN_AHEAD);
 
         // Difficulty histogram should remain empty
         assert_eq!(stats.difficulty_histogram.bins.iter().sum::<u32>(), 0);
@@ -273,7 +546,7 @@ mod tests {
         row.stability = Some(10.0);
         row.last_reviewed_at = Some(Utc::now() - Duration::days(2));
 
-        stats.update(&card, &row);
+        stats.update(&card, &row, DEFAULT_HORIZON_DAYS, 0, DEFAULT_LEARN_AHEAD);
 
         // Difficulty histogram should be updated (7.5 / 10.0 = 0.75)
         let total_count: u32 = stats.difficulty_histogram.bins.iter().sum();
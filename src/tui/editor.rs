@@ -8,6 +8,7 @@ pub struct Editor {
     cursor_col: usize,
     scroll_top: usize,
     pub card_type: CardType,
+    blank: bool,
 }
 
 impl Editor {
@@ -20,8 +21,18 @@ impl Editor {
             cursor_col: 3,
             scroll_top: 0,
             card_type,
+            blank: false,
         }
     }
+
+    /// An editor without the `Q:`/`A:` template, for free text such as a
+    /// typed answer.
+    pub fn blank() -> Self {
+        let mut editor = Self::new();
+        editor.blank = true;
+        editor.clear();
+        editor
+    }
     fn init_lines(card_type: &CardType) -> Vec<String> {
         match card_type {
             CardType::Basic => vec!["Q: ".to_string(), "A: ".to_string()],
@@ -34,9 +45,14 @@ impl Editor {
     }
 
     pub fn clear(&mut self) {
-        self.lines = Self::init_lines(&self.card_type);
+        if self.blank {
+            self.lines = vec![String::new()];
+            self.cursor_col = 0;
+        } else {
+            self.lines = Self::init_lines(&self.card_type);
+            self.cursor_col = 3;
+        }
         self.cursor_row = 0;
-        self.cursor_col = 3;
         self.scroll_top = 0;
     }
 
@@ -72,6 +88,27 @@ impl Editor {
         self.cursor_col += 1;
     }
 
+    /// Inserts `text` at the cursor, splitting it into lines on any line
+    /// ending, and leaves the cursor after the last inserted character.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let column = self.cursor_col;
+        let line = self.current_line_mut();
+        let idx = Self::char_to_byte_index(line, column);
+        let tail = line.split_off(idx);
+
+        let mut segments = text.split('\n');
+        if let Some(first) = segments.next() {
+            line.push_str(first);
+        }
+        for segment in segments {
+            self.cursor_row += 1;
+            self.lines.insert(self.cursor_row, segment.to_string());
+        }
+        self.cursor_col = self.line_len(self.cursor_row);
+        self.current_line_mut().push_str(&tail);
+    }
+
     pub fn insert_newline(&mut self) {
         let column = self.cursor_col;
         let line = self.current_line_mut();
@@ -164,6 +201,42 @@ impl Editor {
         self.cursor_col = min(self.cursor_col, self.line_len(self.cursor_row));
     }
 
+    /// Moves to the start of the previous word, or to the end of the line
+    /// above when already at the start of a line.
+    pub fn move_word_left(&mut self) {
+        if self.cursor_col == 0 {
+            self.move_left();
+            return;
+        }
+        self.cursor_col = word_start_before(&self.lines[self.cursor_row], self.cursor_col);
+    }
+
+    /// Moves to the end of the next word, or to the start of the line below
+    /// when already at the end of a line.
+    pub fn move_word_right(&mut self) {
+        if self.cursor_col >= self.line_len(self.cursor_row) {
+            self.move_right();
+            return;
+        }
+        self.cursor_col = word_end_after(&self.lines[self.cursor_row], self.cursor_col);
+    }
+
+    /// Deletes back to the start of the previous word. At the start of a line
+    /// this joins it to the line above, like `backspace`.
+    pub fn delete_word_back(&mut self) {
+        if self.cursor_col == 0 {
+            self.backspace();
+            return;
+        }
+        let column = self.cursor_col;
+        let target = word_start_before(&self.lines[self.cursor_row], column);
+        let line = self.current_line_mut();
+        let start = Self::char_to_byte_index(line, target);
+        let end = Self::char_to_byte_index(line, column);
+        line.drain(start..end);
+        self.cursor_col = target;
+    }
+
     pub fn move_home(&mut self) {
         self.cursor_col = 0;
     }
@@ -199,3 +272,150 @@ impl Default for Editor {
         Self::new()
     }
 }
+
+/// Words are runs of letters, digits, and `_`; runs of other punctuation
+/// count as words of their own, and whitespace separates both.
+#[derive(PartialEq, Eq)]
+enum CharClass {
+    Space,
+    Word,
+    Punctuation,
+}
+
+impl CharClass {
+    fn of(ch: char) -> Self {
+        if ch.is_whitespace() {
+            CharClass::Space
+        } else if ch.is_alphanumeric() || ch == '_' {
+            CharClass::Word
+        } else {
+            CharClass::Punctuation
+        }
+    }
+}
+
+/// Column where the word ending at or before `column` starts, skipping any
+/// whitespace just before the cursor.
+fn word_start_before(line: &str, column: usize) -> usize {
+    let classes: Vec<CharClass> = line
+        .char_indices()
+        .take(column)
+        .map(|(_, ch)| CharClass::of(ch))
+        .collect();
+    let mut col = classes.len();
+    while col > 0 && classes[col - 1] == CharClass::Space {
+        col -= 1;
+    }
+    if let Some(class) = col.checked_sub(1).map(|prev| &classes[prev]) {
+        while col > 0 && classes[col - 1] == *class {
+            col -= 1;
+        }
+    }
+    col
+}
+
+/// Column just past the word starting at or after `column`, skipping any
+/// whitespace just after the cursor.
+fn word_end_after(line: &str, column: usize) -> usize {
+    let classes: Vec<CharClass> = line
+        .char_indices()
+        .skip(column)
+        .map(|(_, ch)| CharClass::of(ch))
+        .collect();
+    let mut offset = 0;
+    while offset < classes.len() && classes[offset] == CharClass::Space {
+        offset += 1;
+    }
+    if let Some(class) = classes.get(offset) {
+        while offset < classes.len() && classes[offset] == *class {
+            offset += 1;
+        }
+    }
+    column + offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor_with(line: &str) -> Editor {
+        let mut editor = Editor::blank();
+        for ch in line.chars() {
+            editor.insert_char(ch);
+        }
+        editor
+    }
+
+    #[test]
+    fn word_boundaries_skip_space_runs_and_split_on_punctuation() {
+        let line = "Q: foo   bar.baz(qux)";
+        assert_eq!(word_start_before(line, 21), 20);
+        assert_eq!(word_start_before(line, 20), 17);
+        assert_eq!(word_start_before(line, 12), 9);
+        assert_eq!(word_start_before(line, 9), 3);
+        assert_eq!(word_start_before(line, 3), 1);
+        assert_eq!(word_end_after(line, 0), 1);
+        assert_eq!(word_end_after(line, 2), 6);
+        assert_eq!(word_end_after(line, 6), 12);
+        assert_eq!(word_end_after(line, 12), 13);
+    }
+
+    #[test]
+    fn word_movement_handles_unicode_and_crosses_lines() {
+        let mut editor = editor_with("café  naïve");
+        editor.move_word_left();
+        assert_eq!(editor.cursor(), (0, 6));
+        editor.move_word_left();
+        assert_eq!(editor.cursor(), (0, 0));
+        editor.move_word_right();
+        assert_eq!(editor.cursor(), (0, 4));
+
+        editor.move_end();
+        editor.insert_newline();
+        editor.move_word_left();
+        assert_eq!(editor.cursor(), (0, 11));
+        editor.move_word_right();
+        assert_eq!(editor.cursor(), (1, 0));
+    }
+
+    #[test]
+    fn insert_str_splits_lines_and_ends_after_the_text() {
+        let mut editor = Editor::new();
+        editor.insert_str("What is ATP?");
+        assert_eq!(editor.content(), "Q: What is ATP?\nA: ");
+        assert_eq!(editor.cursor(), (0, 15));
+
+        editor.move_down();
+        editor.move_end();
+        editor.insert_str("Energy\r\ncurrency\nof the cell");
+        assert_eq!(
+            editor.content(),
+            "Q: What is ATP?\nA: Energy\ncurrency\nof the cell"
+        );
+        assert_eq!(editor.cursor(), (3, 11));
+
+        let mut editor = editor_with("[] is the capital");
+        editor.move_home();
+        editor.move_right();
+        editor.insert_str("Paris\n");
+        assert_eq!(editor.content(), "[Paris\n] is the capital");
+        assert_eq!(editor.cursor(), (1, 0));
+    }
+
+    #[test]
+    fn delete_word_back_removes_the_previous_word() {
+        let mut editor = editor_with("one two,   three");
+        editor.delete_word_back();
+        assert_eq!(editor.content(), "one two,   ");
+        editor.delete_word_back();
+        assert_eq!(editor.content(), "one two");
+        editor.delete_word_back();
+        assert_eq!(editor.content(), "one ");
+        assert_eq!(editor.cursor(), (0, 4));
+
+        editor.insert_newline();
+        editor.delete_word_back();
+        assert_eq!(editor.content(), "one ");
+        assert_eq!(editor.cursor(), (0, 4));
+    }
+}
@@ -0,0 +1,234 @@
+use std::io::{Stdout, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use crossterm::{cursor::MoveTo, queue};
+use ratatui::{Terminal, backend::CrosstermBackend, layout::Rect};
+
+/// Kitty sends image data in chunks of at most this many base64 bytes.
+const KITTY_CHUNK: usize = 4096;
+/// Kitty image id reused for every card, so drawing a new image replaces the old one.
+const KITTY_IMAGE_ID: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    /// Kitty graphics protocol (kitty, Ghostty). Only PNG files are sent as-is.
+    Kitty,
+    /// iTerm2 inline images (iTerm2, WezTerm). Any format the terminal can decode.
+    Iterm2,
+}
+
+impl GraphicsProtocol {
+    /// Picks a protocol from the environment the terminal advertises. Inside
+    /// tmux or screen the escape codes don't reach the outer terminal, so none.
+    pub fn detect() -> Option<Self> {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let term = var("TERM").unwrap_or_default();
+        if var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+            return None;
+        }
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        if program == "iTerm.app"
+            || program == "WezTerm"
+            || var("LC_TERMINAL").is_some_and(|name| name == "iTerm2")
+        {
+            return Some(GraphicsProtocol::Iterm2);
+        }
+        if term.contains("kitty")
+            || term.contains("ghostty")
+            || program == "ghostty"
+            || var("KITTY_WINDOW_ID").is_some()
+        {
+            return Some(GraphicsProtocol::Kitty);
+        }
+        None
+    }
+
+    pub fn supports(self, path: &Path) -> bool {
+        match self {
+            GraphicsProtocol::Iterm2 => true,
+            GraphicsProtocol::Kitty => path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("png")),
+        }
+    }
+
+    /// Escape sequence that draws `data` at the cursor, fitted inside `area`.
+    fn draw_sequence(self, data: &[u8], area: Rect) -> String {
+        let (cols, rows) = png_dimensions(data)
+            .map(|(width, height)| fit_cells(width, height, area))
+            .unwrap_or((area.width, area.height));
+        let payload = STANDARD.encode(data);
+        match self {
+            GraphicsProtocol::Iterm2 => format!(
+                "\x1b]1337;File=inline=1;size={};width={cols};height={rows};preserveAspectRatio=1:{payload}\x07",
+                data.len()
+            ),
+            GraphicsProtocol::Kitty => {
+                let mut sequence = String::new();
+                let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+                for (index, chunk) in chunks.iter().enumerate() {
+                    let more = u8::from(index + 1 < chunks.len());
+                    let chunk = std::str::from_utf8(chunk).expect("base64 is ascii");
+                    if index == 0 {
+                        sequence.push_str(&format!(
+                            "\x1b_Ga=T,f=100,i={KITTY_IMAGE_ID},q=2,C=1,c={cols},r={rows},m={more};{chunk}\x1b\\"
+                        ));
+                    } else {
+                        sequence.push_str(&format!("\x1b_Gm={more};{chunk}\x1b\\"));
+                    }
+                }
+                sequence
+            }
+        }
+    }
+}
+
+/// Draws card images straight into the terminal on top of the ratatui frame,
+/// remembering what is on screen so each image is only sent once.
+pub struct InlineImages {
+    protocol: GraphicsProtocol,
+    shown: Option<(PathBuf, Rect)>,
+}
+
+impl InlineImages {
+    pub fn detect() -> Option<Self> {
+        GraphicsProtocol::detect().map(|protocol| InlineImages {
+            protocol,
+            shown: None,
+        })
+    }
+
+    pub fn supports(&self, path: &Path) -> bool {
+        self.protocol.supports(path)
+    }
+
+    /// Call after the terminal redraws everything, e.g. when returning from
+    /// another program, since whatever was shown is gone.
+    pub fn forget(&mut self) {
+        self.shown = None;
+    }
+
+    /// Brings the screen in line with `wanted`, the image the last frame left
+    /// room for. Unreadable files are skipped so the drill keeps going.
+    pub fn sync(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+        wanted: Option<(PathBuf, Rect)>,
+    ) -> Result<()> {
+        if self.shown == wanted {
+            return Ok(());
+        }
+        if self.shown.take().is_some() {
+            match self.protocol {
+                GraphicsProtocol::Kitty => {
+                    write!(
+                        terminal.backend_mut(),
+                        "\x1b_Ga=d,d=I,i={KITTY_IMAGE_ID},q=2\x1b\\"
+                    )?;
+                }
+                GraphicsProtocol::Iterm2 => {
+                    // iTerm2 images are ordinary cells that ratatui doesn't know
+                    // about; a full redraw wipes them before the next one is drawn.
+                    terminal.clear().context("failed to clear terminal")?;
+                    return Ok(());
+                }
+            }
+        }
+        if let Some((path, area)) = wanted {
+            if let Ok(data) = std::fs::read(&path) {
+                let backend = terminal.backend_mut();
+                queue!(backend, MoveTo(area.x, area.y))?;
+                write!(backend, "{}", self.protocol.draw_sequence(&data, area))?;
+            }
+            self.shown = Some((path, area));
+        }
+        terminal.backend_mut().flush()?;
+        Ok(())
+    }
+}
+
+/// Pixel size from a PNG's IHDR chunk.
+fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.len() < 24 || !data.starts_with(b"\x89PNG\r\n\x1a\n") || &data[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(data[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(data[20..24].try_into().ok()?);
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+/// Largest columns × rows that fit in `area` while keeping the image's aspect
+/// ratio, assuming terminal cells are twice as tall as they are wide.
+fn fit_cells(width: u32, height: u32, area: Rect) -> (u16, u16) {
+    let (width, height) = (f64::from(width), f64::from(height));
+    let cols_at_full_height = f64::from(area.height) * 2.0 * width / height;
+    if cols_at_full_height <= f64::from(area.width) {
+        ((cols_at_full_height.round() as u16).max(1), area.height)
+    } else {
+        let rows = f64::from(area.width) * height / (2.0 * width);
+        (area.width, (rows.round() as u16).max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_protocol_from_terminal_environment() {
+        let env = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(
+            GraphicsProtocol::from_env(env(&[("TERM", "xterm-kitty")])),
+            Some(GraphicsProtocol::Kitty)
+        );
+        assert_eq!(
+            GraphicsProtocol::from_env(env(&[("TERM_PROGRAM", "iTerm.app")])),
+            Some(GraphicsProtocol::Iterm2)
+        );
+        assert_eq!(
+            GraphicsProtocol::from_env(env(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux")])),
+            None
+        );
+        assert_eq!(
+            GraphicsProtocol::from_env(env(&[("TERM", "xterm-256color")])),
+            None
+        );
+        assert!(GraphicsProtocol::Kitty.supports(Path::new("a/b.PNG")));
+        assert!(!GraphicsProtocol::Kitty.supports(Path::new("a/b.jpg")));
+        assert!(GraphicsProtocol::Iterm2.supports(Path::new("a/b.jpg")));
+    }
+
+    #[test]
+    fn images_keep_their_aspect_ratio_inside_the_area() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend(200u32.to_be_bytes());
+        png.extend(100u32.to_be_bytes());
+        assert_eq!(png_dimensions(&png), Some((200, 100)));
+        assert_eq!(png_dimensions(b"GIF89a"), None);
+
+        let area = Rect::new(0, 0, 40, 10);
+        // 2:1 image at 10 rows needs 40 columns.
+        assert_eq!(fit_cells(200, 100, area), (40, 10));
+        // A wide image is limited by the width instead.
+        assert_eq!(fit_cells(800, 100, area), (40, 3));
+        // A tall image is limited by the height.
+        assert_eq!(fit_cells(100, 400, area), (5, 10));
+
+        let sequence = GraphicsProtocol::Kitty.draw_sequence(&png, area);
+        assert!(sequence.starts_with("\x1b_Ga=T,f=100,i=1,q=2,C=1,c=40,r=10,m=0;"));
+    }
+}
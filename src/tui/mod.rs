@@ -1,5 +1,7 @@
 pub mod editor;
+pub mod graphics;
 pub mod theme;
 
 pub use editor::Editor;
+pub use graphics::InlineImages;
 pub use theme::Theme;
@@ -0,0 +1,158 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Case, punctuation, and spacing are ignored when comparing, the same
+/// differences that don't change a card's hash. Accents count, but `é` typed
+/// as one character or as `e` plus a combining accent is the same answer.
+pub fn normalize_answer(answer: &str) -> String {
+    let mut normalized = String::with_capacity(answer.len());
+    let mut last_was_space = true;
+    for ch in answer.to_lowercase().nfc() {
+        if ch.is_alphanumeric() || (is_combining_mark(ch) && !last_was_space) {
+            normalized.push(ch);
+            last_was_space = false;
+        } else if (ch.is_whitespace() || ch == '-') && !last_was_space {
+            normalized.push(' ');
+            last_was_space = true;
+        }
+    }
+    normalized.truncate(normalized.trim_end().len());
+    normalized
+}
+
+/// Whether a typed answer matches the expected one after normalizing both.
+/// Allows one typo per five characters of the expected answer, up to three.
+pub fn answer_matches(expected: &str, typed: &str) -> bool {
+    let expected = normalize_answer(expected);
+    let typed = normalize_answer(typed);
+    if typed.is_empty() {
+        return expected.is_empty();
+    }
+    let tolerance = (expected.chars().count() / 5).min(3);
+    edit_distance(&expected, &typed) <= tolerance
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_ch) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_ch) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_ch != *b_ch);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffSegment {
+    Same(String),
+    /// In the expected answer but not typed.
+    Missing(String),
+    /// Typed but not in the expected answer.
+    Extra(String),
+}
+
+/// Character diff between the expected and typed answers, ignoring case.
+pub fn diff_answer(expected: &str, typed: &str) -> Vec<DiffSegment> {
+    let expected: Vec<char> = expected.trim().chars().collect();
+    let typed: Vec<char> = typed.trim().chars().collect();
+    let same = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+
+    // lcs[i][j] is the longest common subsequence of expected[i..] and typed[j..]
+    let mut lcs = vec![vec![0usize; typed.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..typed.len()).rev() {
+            lcs[i][j] = if same(expected[i], typed[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut segments = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < typed.len() {
+        let segment = if i < expected.len() && j < typed.len() && same(expected[i], typed[j]) {
+            i += 1;
+            j += 1;
+            DiffSegment::Same(expected[i - 1].to_string())
+        } else if j < typed.len() && (i == expected.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            j += 1;
+            DiffSegment::Extra(typed[j - 1].to_string())
+        } else {
+            i += 1;
+            DiffSegment::Missing(expected[i - 1].to_string())
+        };
+        push_segment(&mut segments, segment);
+    }
+    segments
+}
+
+/// Appends `segment`, merging it into the last one when they are the same kind.
+fn push_segment(segments: &mut Vec<DiffSegment>, segment: DiffSegment) {
+    match (segments.last_mut(), segment) {
+        (Some(DiffSegment::Same(text)), DiffSegment::Same(more))
+        | (Some(DiffSegment::Missing(text)), DiffSegment::Missing(more))
+        | (Some(DiffSegment::Extra(text)), DiffSegment::Extra(more)) => text.push_str(&more),
+        (_, segment) => segments.push(segment),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_ignore_case_punctuation_and_small_typos() {
+        assert_eq!(
+            normalize_answer("  The Mitochondria!  "),
+            "the mitochondria"
+        );
+        assert!(answer_matches("The mitochondria.", "the   MITOCHONDRIA"));
+        assert!(answer_matches("mitochondria", "mitocondria"));
+        assert!(answer_matches("Ribosome-bound", "ribosome bound"));
+        assert!(!answer_matches("cat", "car"));
+        assert!(!answer_matches("mitochondria", "nucleus"));
+        assert!(!answer_matches("Paris", ""));
+    }
+
+    #[test]
+    fn normalizing_keeps_accents_and_collapses_whitespace() {
+        // precomposed and decomposed accents are the same letter
+        assert_eq!(normalize_answer("Café"), "café");
+        assert_eq!(normalize_answer("Cafe\u{301}"), "café");
+        assert!(answer_matches("está", "esta\u{301}"));
+        // an accent still changes a short answer
+        assert!(!answer_matches("papá", "papa"));
+        assert_eq!(normalize_answer("ÉCOLE"), "école");
+
+        assert_eq!(
+            normalize_answer("\tla\u{a0}  casa\n de\r\nPapel "),
+            "la casa de papel"
+        );
+        assert!(answer_matches("la casa", " la\tcasa\n"));
+    }
+
+    #[test]
+    fn diff_marks_missing_and_extra_characters() {
+        assert_eq!(
+            diff_answer("Paris", "pairs"),
+            vec![
+                DiffSegment::Same("Pa".into()),
+                DiffSegment::Extra("i".into()),
+                DiffSegment::Same("r".into()),
+                DiffSegment::Missing("i".into()),
+                DiffSegment::Same("s".into()),
+            ]
+        );
+        assert_eq!(
+            diff_answer("Tokyo", ""),
+            vec![DiffSegment::Missing("Tokyo".into())]
+        );
+    }
+}
@@ -1,16 +1,25 @@
-use std::path::Path;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use anyhow::anyhow;
+use chrono::NaiveDate;
 use dialoguer::Confirm;
 use dialoguer::theme::ColorfulTheme;
 use directories::ProjectDirs;
 
+const MARKDOWN_EXTENSIONS: [&str; 4] = ["md", "markdown", "mdx", "mkd"];
+
 pub fn is_markdown(path: &Path) -> bool {
     path.extension()
         .and_then(|ext| ext.to_str())
-        .map(|ext| ext.eq_ignore_ascii_case("md"))
+        .map(|ext| {
+            MARKDOWN_EXTENSIONS
+                .iter()
+                .any(|markdown| ext.eq_ignore_ascii_case(markdown))
+        })
         .unwrap_or(false)
 }
 
@@ -40,6 +49,115 @@ where
     }
 }
 
+/// Parses a `YYYY-MM-DD` date, as given to `export --since`.
+pub fn parse_date(input: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d")
+        .map_err(|_| anyhow!("invalid date '{input}': expected YYYY-MM-DD"))
+}
+
+/// Parses durations like `15m`, `1h30m`, `1d`, `90s`, or a bare number of minutes.
+pub fn parse_duration(input: &str) -> Result<std::time::Duration> {
+    let duration = parse_duration_or_zero(input)?;
+    if duration.is_zero() {
+        return Err(anyhow!(
+            "invalid duration '{input}': must be greater than zero"
+        ));
+    }
+    Ok(duration)
+}
+
+/// Like [`parse_duration`], but also accepts `0m` and other zero durations.
+pub fn parse_duration_or_zero(input: &str) -> Result<std::time::Duration> {
+    let total_secs = match input.trim().parse::<u64>() {
+        Ok(minutes) => minutes
+            .checked_mul(60)
+            .ok_or_else(|| anyhow!("invalid duration '{input}': too large"))?,
+        Err(_) => parse_duration_units(input)?,
+    };
+    Ok(std::time::Duration::from_secs(total_secs))
+}
+
+/// Seconds in a duration written with units, like `1h30m`.
+fn parse_duration_units(input: &str) -> Result<u64> {
+    let mut total_secs = 0;
+    let mut digits = String::new();
+    for ch in input.trim().chars() {
+        if ch.is_ascii_digit() {
+            digits.push(ch);
+            continue;
+        }
+        let unit_secs = match ch.to_ascii_lowercase() {
+            'd' => 86_400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(anyhow!("invalid duration '{input}': unknown unit '{ch}'")),
+        };
+        let value: u64 = digits
+            .parse()
+            .map_err(|_| anyhow!("invalid duration '{input}': expected a number before '{ch}'"))?;
+        total_secs = value
+            .checked_mul(unit_secs)
+            .and_then(|secs| secs.checked_add(total_secs))
+            .ok_or_else(|| anyhow!("invalid duration '{input}': too large"))?;
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        return Err(anyhow!(
+            "invalid duration '{input}': missing unit after {digits}"
+        ));
+    }
+    Ok(total_secs)
+}
+
+/// When the calendar day containing `now` began in its own time zone, as UTC.
+pub fn start_of_day<Tz: chrono::TimeZone>(
+    now: &chrono::DateTime<Tz>,
+) -> chrono::DateTime<chrono::Utc> {
+    let midnight = now.date_naive().and_time(chrono::NaiveTime::MIN);
+    let timezone = now.timezone();
+    // Days that start with a DST jump have no local midnight; take the first valid instant.
+    timezone
+        .from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(midnight + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .map_or_else(|| now.to_utc(), |start| start.to_utc())
+}
+
+/// The study day `at` falls on when days roll over `day_start_hour` hours
+/// after local midnight, so a review at 1am with a 4am rollover still counts
+/// toward the previous day.
+pub fn study_date<Tz: chrono::TimeZone>(
+    at: &chrono::DateTime<Tz>,
+    day_start_hour: u32,
+) -> chrono::NaiveDate {
+    (at.naive_local() - chrono::Duration::hours(day_start_hour.into())).date()
+}
+
+/// When the study day containing `now` began, as UTC.
+pub fn start_of_study_day<Tz: chrono::TimeZone>(
+    now: &chrono::DateTime<Tz>,
+    day_start_hour: u32,
+) -> chrono::DateTime<chrono::Utc> {
+    let offset = chrono::Duration::hours(day_start_hour.into());
+    start_of_day(&(now.clone() - offset)) + offset
+}
+
+/// Formats a duration as `m:ss`, or `h:mm:ss` past an hour.
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
 pub fn strip_controls_and_escapes(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
     let mut chars = input.chars().peekable();
@@ -77,20 +195,100 @@ pub fn ask_yn(prompt: String) -> bool {
         .unwrap()
 }
 
-pub fn get_data_dir() -> Result<std::path::PathBuf> {
-    let proj_dirs = ProjectDirs::from("", "", "repeater")
-        .ok_or_else(|| anyhow!("Could not determine project directory"))?;
+/// Environment variable that moves the data directory, like `--data-dir`.
+pub const DATA_DIR_ENV: &str = "REPEATER_DATA_DIR";
+
+/// The `--data-dir` flag wins over the environment variable; an empty
+/// variable counts as unset.
+fn data_dir_override(flag: Option<&Path>, env_value: Option<OsString>) -> Option<PathBuf> {
+    flag.map(Path::to_path_buf).or_else(|| {
+        env_value
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    })
+}
 
-    let data_dir = proj_dirs.data_dir();
-    std::fs::create_dir_all(data_dir)?;
+/// Where `cards.db`, `auth.json`, and the other data files live: `flag`
+/// (from `--data-dir`), then `REPEATER_DATA_DIR`, then the platform's data
+/// directory. The directory is created if it is missing.
+pub fn get_data_dir(flag: Option<&Path>) -> Result<PathBuf> {
+    let data_dir = match data_dir_override(flag, env::var_os(DATA_DIR_ENV)) {
+        Some(dir) => dir,
+        None => ProjectDirs::from("", "", "repeater")
+            .ok_or_else(|| anyhow!("Could not determine project directory"))?
+            .data_dir()
+            .to_path_buf(),
+    };
+    std::fs::create_dir_all(&data_dir)
+        .with_context(|| format!("Failed to create the data directory {}", data_dir.display()))?;
 
-    Ok(data_dir.to_path_buf())
+    Ok(data_dir)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn dates_are_read_as_year_month_day() {
+        assert_eq!(
+            parse_date(" 2024-01-31 ").unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()
+        );
+        assert!(parse_date("2024-02-30").is_err());
+        assert!(parse_date("31/01/2024").is_err());
+    }
+
+    #[test]
+    fn data_dir_flag_wins_over_the_environment() {
+        let flag = Path::new("/tmp/work");
+        let env_value = Some(OsString::from("/tmp/personal"));
+        assert_eq!(
+            data_dir_override(Some(flag), env_value.clone()),
+            Some(PathBuf::from("/tmp/work"))
+        );
+        assert_eq!(
+            data_dir_override(None, env_value),
+            Some(PathBuf::from("/tmp/personal"))
+        );
+        assert_eq!(data_dir_override(None, Some(OsString::new())), None);
+        assert_eq!(data_dir_override(None, None), None);
+    }
+
+    #[test]
+    fn start_of_day_uses_the_local_calendar_day() {
+        use chrono::{FixedOffset, TimeZone};
+
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let now = tokyo.with_ymd_and_hms(2024, 3, 10, 1, 30, 0).unwrap();
+        assert_eq!(
+            start_of_day(&now),
+            chrono::Utc.with_ymd_and_hms(2024, 3, 9, 15, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn study_day_rolls_over_at_the_day_start_hour() {
+        use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let before = tokyo.with_ymd_and_hms(2024, 3, 10, 3, 59, 0).unwrap();
+        let after = tokyo.with_ymd_and_hms(2024, 3, 10, 4, 0, 0).unwrap();
+        let march = |day| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+
+        assert_eq!(study_date(&before, 0), march(10));
+        assert_eq!(study_date(&before, 4), march(9));
+        assert_eq!(study_date(&after, 4), march(10));
+        assert_eq!(
+            start_of_study_day(&before, 4),
+            chrono::Utc.with_ymd_and_hms(2024, 3, 8, 19, 0, 0).unwrap()
+        );
+        assert_eq!(
+            start_of_study_day(&after, 4),
+            chrono::Utc.with_ymd_and_hms(2024, 3, 9, 19, 0, 0).unwrap()
+        );
+    }
+
     #[test]
     fn test_strip_controls_and_escapes() {
         let input = "\x1b[1mHello\x1b[0m";
@@ -103,6 +301,18 @@ mod tests {
         assert!(!is_markdown(Path::new("test.txt")));
     }
 
+    #[test]
+    fn test_is_markdown_other_extensions() {
+        assert!(is_markdown(Path::new("notes/test.markdown")));
+        assert!(is_markdown(Path::new("test.mdx")));
+        assert!(is_markdown(Path::new("test.mkd")));
+        assert!(is_markdown(Path::new("TEST.MD")));
+        assert!(is_markdown(Path::new("test.Markdown")));
+        assert!(!is_markdown(Path::new("test.mdown.txt")));
+        assert!(!is_markdown(Path::new("markdown")));
+        assert!(!is_markdown(Path::new("test.mkdn")));
+    }
+
     #[test]
     fn test_pluralize_single() {
         assert_eq!(pluralize("card", 1), "1 card");
@@ -116,6 +326,34 @@ mod tests {
         assert_eq!(pluralize("cloze card", 3), "3 cloze cards");
     }
 
+    #[test]
+    fn test_parse_duration() {
+        use std::time::Duration;
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_duration("15").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86_400));
+        assert!(parse_duration("99999999999999999d").is_err());
+        assert!(parse_duration("999999999999999999").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("0").is_err());
+        assert_eq!(parse_duration_or_zero("0m").unwrap(), Duration::ZERO);
+        assert_eq!(parse_duration_or_zero("0").unwrap(), Duration::ZERO);
+        assert!(parse_duration_or_zero("10x").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("1h30").is_err());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        use std::time::Duration;
+        assert_eq!(format_duration(Duration::from_secs(59)), "0:59");
+        assert_eq!(format_duration(Duration::from_secs(900)), "15:00");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1:02:05");
+    }
+
     #[test]
     fn test_pluralize_zero() {
         assert_eq!(pluralize("card", 0), "0 cards");